@@ -1,44 +1,72 @@
 use crate::cmd::keybindings::KeyBindings;
-use crate::cmd::{get_command, CommandTag};
-use crate::datastructure::generic::{Vec2, Vec2d, Vec2i};
+use crate::cmd::{
+    format::formatter_for,
+    get_command,
+    related::RelatedFiles,
+    reveal::{absolute_path, reveal},
+    shell::{ShellJob, MAX_LINES_PER_POLL},
+    snippets::get_snippet,
+    CommandTag,
+};
+use crate::datastructure::generic::{Vec2d, Vec2i};
 use crate::debugger_catch;
 use crate::debuginfo::DebugInfo;
 use crate::opengl::types::RGBAColor;
 use crate::opengl::{
     polygon_renderer::{PolygonRenderer, TextureMap, TextureType},
     rectangle_renderer::RectRenderer,
+    scissor::{ClipRect, ScissorGuard},
     shaders::{RectShader, TextShader},
     text_renderer::TextRenderer,
 };
 use crate::textbuffer::operations::LineOperation;
-use crate::textbuffer::{buffers::Buffers, CharBuffer};
+use crate::textbuffer::symbols::line_of_list_item;
+use crate::textbuffer::{
+    buffers::{buffer_id_of_list_item, list_item as buffer_list_item, BufferId, Buffers},
+    metadata::Index,
+    CharBuffer,
+};
 use crate::ui::basic::{
-    coordinate::{Coordinate, Layout, PointArithmetic, Size},
+    boundingbox::BoundingBox,
+    coordinate::{Coordinate, Layout, Size, Spacing},
     frame::Frame,
 };
 use crate::ui::eventhandling::event::{key_press, AppAction, InputboxAction, ViewAction};
 use crate::ui::eventhandling::input::KeyboardInputContext;
 use crate::ui::{
-    clipboard::ClipBoard,
+    clipboard::{ClipBoard, ClipEntry},
+    cursor_region::{cursor_region_at, CursorRegion, PanelRegions},
     debug_view::DebugView,
+    delete_guard::DeleteGuard,
     eventhandling::event::{CommandOutput, InputBehavior, InvalidInputElement},
+    file_positions::{file_positions_path, FilePositions},
+    focus_history::FocusHistory,
     font::Font,
+    idle_save::IdleSave,
     inputbox::{InputBox, Mode},
-    panel::{Panel, PanelId},
+    jump_list::JumpList,
+    panel::{split_side_by_side, Direction, Panel, PanelId},
+    peek::{peek_text, Peek, PEEK_CONTEXT_LINES},
     view::{Popup, View, ViewId},
+    view_options::ViewOptions,
     MouseState, Viewable, UID,
 };
+use crate::utils::smart_case_sensitive;
 
 use glfw::{Action, Key, Modifiers, MouseButton, Window};
 
 use std::iter::FromIterator;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::sync::mpsc::Receiver;
 
 pub static TEST_DATA: &str = include_str!("./textbuffer/contiguous/contiguous.rs");
 static INACTIVE_VIEW_BACKGROUND: RGBAColor = RGBAColor { r: 0.021, g: 0.62, b: 0.742123, a: 1.0 };
 static ACTIVE_VIEW_BACKGROUND: RGBAColor = RGBAColor { r: 0.071, g: 0.202, b: 0.3242123, a: 1.0 };
+/// The column paragraphs are rewrapped to, in views without rulers
+const DEFAULT_REFLOW_WIDTH: usize = 80;
+/// The widest a scroll bar can be set to, with the "Scroll bar width" command
+const MAX_SCROLL_BAR_WIDTH: i32 = 60;
 
 fn all_views<'app>(panels: &'app Vec<Panel>) -> impl Iterator<Item = &View> + Clone {
     panels.iter().flat_map(|p| p.children.iter())
@@ -48,9 +76,54 @@ fn all_views_mut<'app>(panels: &'app mut Vec<Panel>) -> impl Iterator<Item = &'a
     panels.iter_mut().flat_map(|p| p.children.iter_mut())
 }
 
+/// The view that cycling the focus goes to from current, among views, given in the order all_views produces them along with
+/// whether they're visible. Hidden views are skipped, and after the last view comes the first. None if current isn't visible
+fn next_in_focus_cycle(views: impl Iterator<Item = (ViewId, bool)> + Clone, current: ViewId) -> Option<ViewId> {
+    let visible = views.filter(|(_, visible)| *visible).map(|(id, _)| id);
+    visible.clone().chain(visible).skip_while(|id| *id != current).nth(1)
+}
+
+/// Maps the view number the user sees (1-based, counting only visible views) to the index of that view,
+/// in the order that all_views produces them. Returns None if there are fewer than `number` visible views
+fn visible_view_index(visibility: impl Iterator<Item = bool>, number: usize) -> Option<usize> {
+    if number == 0 {
+        return None;
+    }
+    visibility
+        .enumerate()
+        .filter(|(_, visible)| *visible)
+        .nth(number - 1)
+        .map(|(index, _)| index)
+}
+
+/// Whether pos is on one of the overlays, given as (visible, overlay). Overlays, like the input box and the popup, are drawn on top
+/// of the views, so what's under a visible one can't be clicked
+fn covered_by_overlay(overlays: &[(bool, &dyn Viewable)], pos: Vec2i) -> bool {
+    overlays.iter().any(|&(visible, overlay)| visible && overlay.contains_point(pos))
+}
+
+/// The title of the window, for the file of the active view. Buffers without a file go by "untitled", and unsaved changes are marked
+/// with a * in front
+fn window_title(file_name: Option<&Path>, dirty: bool) -> String {
+    let name = file_name.map_or_else(|| "untitled".to_string(), |name| name.display().to_string());
+    format!("{}{} \u{2014} cxg", if dirty { "* " } else { "" }, name)
+}
+
+/// The views that closing every view but keep removes, given as (id, pinned, pristine). Pinned views are never closed,
+/// and neither are views with unsaved changes
+fn views_to_close(views: impl Iterator<Item = (ViewId, bool, bool)>, keep: Option<ViewId>) -> Vec<ViewId> {
+    views
+        .filter(|&(id, pinned, pristine)| Some(id) != keep && !pinned && pristine)
+        .map(|(id, ..)| id)
+        .collect()
+}
+
 pub struct Application<'app> {
     /// Window Title
-    _title_bar: String,
+    title_bar: String,
+    /// What the title was made from; the active view, the file of its buffer, the revision of it and the hash of its saved
+    /// contents. The title is only made again, when one of them changes
+    title_made_from: Option<(ViewId, Option<PathBuf>, u64, u64)>,
     /// Window Size
     pub window_size: Size,
     /// Total space of window, that can be occupied by panels (status bar for instance, is *not* counted among this space)
@@ -88,6 +161,8 @@ pub struct Application<'app> {
     pub debug_view: DebugView,
     /// Current mouse state
     mouse_state: MouseState,
+    /// What the mouse cursor was last over, which its shape is set by
+    cursor_region: CursorRegion,
     /// renderer for "animations" such as when we're "moving" a window to a new place. Due to how i've designed the draw command list in PolygonRenderer and RectRenderer, I may very well be able
     /// to compress this into 3 renderers in total, instead of having a bunch of them
     rect_animation_renderer: RectRenderer,
@@ -102,6 +177,31 @@ pub struct Application<'app> {
     pub translate_key_input: bool,
 
     pub input_context: KeyboardInputContext,
+    /// Shell commands that are still running, and the views their output goes into
+    shell_jobs: Vec<(ViewId, ShellJob)>,
+    /// What was last searched for, so that the search can be repeated without opening the input box again
+    last_search: Option<String>,
+    /// Where the cursor was in files, when they were last saved or closed, so that reopening them goes back there
+    file_positions: FilePositions,
+    /// Saves files with unsaved changes when the user stops typing, or leaves the window, if turned on by the "Auto save" command
+    idle_save: IdleSave,
+    /// Where the cursor was before jumps to lines, search matches, definitions and marks, to go back and forth between
+    jumps: JumpList,
+    /// The views, most recently focused first, for cycle_recent_views
+    focus_history: FocusHistory,
+    /// Which files toggle_related switches between
+    related_files: RelatedFiles,
+    /// Text to put on the system clipboard. That takes the window, so it's done at the end of process_all_events
+    system_clipboard: Option<String>,
+    /// Deleting selections larger than this has to be confirmed. Given to every view, set by the "Delete limit" command
+    delete_guard: DeleteGuard,
+    /// The view whose delete is waiting for the user to confirm it in the popup. Other input, clicks and drags that would select
+    /// text included, is ignored until it's settled
+    delete_to_confirm: Option<ViewId>,
+    /// The search match shown in the popup, while searching with the input box. Up and Down go through the matches
+    peek: Option<Peek>,
+    /// The display options views are opened with
+    view_defaults: ViewOptions,
 }
 
 static mut INVALID_INPUT: InvalidInputElement = InvalidInputElement {};
@@ -160,7 +260,7 @@ impl<'app> Application<'app> {
             buffer,
             fonts[0].clone(),
             fonts[1].clone(),
-            tex_map.textures.get(&TextureType::Background(2)).map(|t| *t).unwrap(),
+            tex_map.get(TextureType::Background(2)),
         );
         panels[0].add_view(view);
 
@@ -178,7 +278,7 @@ impl<'app> Application<'app> {
             Buffers::free_buffer(),
             fonts[0].clone(),
             fonts[1].clone(),
-            tex_map.textures.get(&TextureType::Background(2)).map(|t| *t).unwrap(),
+            tex_map.get(TextureType::Background(2)),
         );
 
         popup.set_anchor(Vec2i::new(250, 768 - 250));
@@ -201,12 +301,12 @@ impl<'app> Application<'app> {
             Buffers::free_buffer(),
             fonts[0].clone(),
             fonts[1].clone(),
-            tex_map.textures.get(&TextureType::Background(2)).map(|t| *t).unwrap(),
+            tex_map.get(TextureType::Background(2)),
         );
         debug_view.set_anchor(Vec2i::new(5, 763));
-        debug_view.update(Some(tex_map.textures.get(&TextureType::Background(2)).map(|t| *t).unwrap()));
+        debug_view.update(tex_map.get(TextureType::Background(2)));
         // debug_view.window_renderer.set_color(RGBAColor { r: 0.35, g: 0.7, b: 1.0, a: 0.95 });
-        let debug_view = DebugView::new(debug_view, debug_info, tex_map.textures.get(&TextureType::Background(2)).unwrap().clone());
+        let debug_view = DebugView::new(debug_view, debug_info, tex_map.get(TextureType::Background(2)));
         let ib_border_margin = 2;
         let ib_frame = Frame {
             anchor: Vec2i::new(250, 700),
@@ -237,7 +337,8 @@ impl<'app> Application<'app> {
         }
 
         let mut res = Application {
-            _title_bar: "cxgledit".into(),
+            title_bar: "<cxgledit>".into(),
+            title_made_from: None,
             window_size: Size::new(1024, 768),
             panel_space_size: Size::new(1024, 768),
             fonts,
@@ -256,12 +357,25 @@ impl<'app> Application<'app> {
             input_box,
             debug_view,
             mouse_state: MouseState::None,
+            cursor_region: CursorRegion::Elsewhere,
             rect_animation_renderer,
             tex_map,
             clipboard: ClipBoard::new(),
             key_bindings,
             translate_key_input: true,
             input_context: KeyboardInputContext::TextView,
+            shell_jobs: vec![],
+            last_search: None,
+            file_positions: file_positions_path().map_or_else(FilePositions::default, |path| FilePositions::load(&path)),
+            idle_save: IdleSave::default(),
+            jumps: JumpList::default(),
+            focus_history: FocusHistory::default(),
+            related_files: RelatedFiles::default(),
+            system_clipboard: None,
+            delete_guard: DeleteGuard::default(),
+            delete_to_confirm: None,
+            peek: None,
+            view_defaults: ViewOptions::default(),
         };
         let v = res.panels.last_mut().and_then(|p| p.children.last_mut()).unwrap() as *mut _;
         res.active_keyboard_input = unsafe { &mut (*v) as &'app mut dyn InputBehavior };
@@ -271,9 +385,7 @@ impl<'app> Application<'app> {
 
     pub fn decorate_active_view(&mut self) {
         let view = unsafe { self.active_view.as_mut().unwrap() };
-        view.bg_color = ACTIVE_VIEW_BACKGROUND;
-        view.window_renderer.set_color(ACTIVE_VIEW_BACKGROUND);
-        view.update(None);
+        view.decorate(ACTIVE_VIEW_BACKGROUND, true);
     }
 
     /// Creates a text view and makes that the focused UI element
@@ -291,7 +403,7 @@ impl<'app> Application<'app> {
             let menu_font = self.fonts[1].clone();
             let Size { width, height } = view_size;
             let view_name = view_name.as_ref().map(|name| name.as_ref()).unwrap_or("unnamed view");
-            let view = View::new(
+            let mut view = View::new(
                 view_name,
                 view_id.into(),
                 TextRenderer::create(self.font_shader.clone(), 1024),
@@ -303,14 +415,14 @@ impl<'app> Application<'app> {
                 self.buffers.request_new_buffer(),
                 font,
                 menu_font,
-                self.tex_map.textures.get(&TextureType::Background(2)).map(|t| *t).unwrap(),
+                self.tex_map.get(TextureType::Background(2)),
             );
+            view.options = self.view_defaults.clone();
+            view.delete_guard = self.delete_guard;
             self.active_ui_element = UID::View(*view.id);
             p.add_view(view);
             unsafe {
-                (*self.active_view).bg_color = INACTIVE_VIEW_BACKGROUND;
-                // (*self.active_view).window_renderer.set_color(INACTIVE_VIEW_BACKGROUND);
-                (*self.active_view).update(None);
+                (*self.active_view).decorate(INACTIVE_VIEW_BACKGROUND, false);
             }
             self.active_view = p.get_view(view_id.into()).unwrap() as *mut _;
             self.active_keyboard_input = unsafe { &mut (*self.active_view) as &'app mut dyn InputBehavior };
@@ -319,6 +431,143 @@ impl<'app> Application<'app> {
         }
     }
 
+    /// Opens each file in a view of the active panel, the first one in the active view if that is empty. Files with a line go to
+    /// that line (1-based), the others to where the cursor was when they were last closed. Files that don't exist get empty views named after them
+    pub fn open_files(&mut self, files: Vec<(PathBuf, Option<usize>)>) {
+        for (path, line) in files {
+            let v = self.get_active_view();
+            let reuse_active = v.buffer.empty() && !v.pinned && v.buffer.file_name().is_none();
+            if !reuse_active {
+                let name = path.file_name().and_then(|s| s.to_str()).map(|f| f.to_string());
+                self.open_text_view(self.active_panel(), name, self.window_size);
+            }
+            let v = self.get_active_view();
+            if path.exists() {
+                v.load_file(&path);
+            } else {
+                println!("{} does not exist, it will be created when saved", path.display()); // todo: UI representation
+                v.buffer.set_file_name(&path);
+            }
+            match line {
+                Some(line) => {
+                    v.buffer.goto_line(line.saturating_sub(1));
+                    v.set_view_on_buffer_cursor();
+                }
+                None => self.restore_file_position(),
+            }
+            let v = self.get_active_view();
+            v.set_need_redraw();
+            v.update(None);
+        }
+    }
+
+    /// Focuses the view file is open in, or if it isn't open, opens it
+    pub fn open_or_focus(&mut self, file: PathBuf) {
+        let same_file = |open: &Path| open == file || open.canonicalize().ok().map_or(false, |open| file.canonicalize().ok() == Some(open));
+        let open_in = all_views(&self.panels)
+            .find(|v| v.buffer.file_name().map_or(false, same_file))
+            .map(|v| v.id);
+        match open_in {
+            Some(view) => self.activate_view(view),
+            None => self.open_files(vec![(file, None)]),
+        }
+    }
+
+    /// The absolute path of the file of the active view. None, with a message saying so, if it has no file
+    fn active_file_path(&mut self) -> Option<PathBuf> {
+        let v = unsafe { self.active_view.as_ref().unwrap() };
+        match v.buffer.file_name() {
+            Some(file) => Some(absolute_path(file, &std::env::current_dir().unwrap_or_default())),
+            None => {
+                self.popup.reset();
+                self.popup.view.insert_str("The active view has no file yet. Save it first");
+                self.popup.visible = true;
+                None
+            }
+        }
+    }
+
+    /// Switches to the file related to the active one, like a header to its source (see RelatedFiles), opening it if it isn't
+    /// open. If there are several, they are listed to choose from
+    pub fn toggle_related(&mut self) {
+        let v = unsafe { self.active_view.as_mut().unwrap() };
+        let file = match v.buffer.file_name() {
+            Some(file) => file.to_path_buf(),
+            None => return,
+        };
+        let mut related = self.related_files.existing(&file);
+        match related.len() {
+            0 => {
+                self.popup.reset();
+                self.popup.view.insert_str(&format!("Found no file related to {}", file.display()));
+                self.popup.visible = true;
+            }
+            1 => self.open_or_focus(related.pop().unwrap()),
+            _ => {
+                self.toggle_input_box(Mode::CommandInput(CommandTag::ToggleRelated));
+                self.input_box.set_choices(related.iter().map(|p| p.display().to_string()).collect());
+            }
+        }
+    }
+
+    /// Runs command_line off the UI thread, and opens a read only view in the active panel that its output is streamed into
+    pub fn run_shell_command(&mut self, command_line: &str) {
+        let panel = self.active_panel();
+        self.open_text_view(panel, Some(format!("$ {}", command_line)), self.window_size);
+        let v = self.get_active_view();
+        v.buffer.set_read_only(true);
+        v.options.show_change_markers = false;
+        v.follow_tail = true;
+        let view_id = v.id;
+        self.shell_jobs.push((view_id, ShellJob::spawn(command_line)));
+    }
+
+    /// Moves the output that running shell commands have produced so far, into their views. Jobs whose command has finished are
+    /// dropped, and so are those whose view has been closed, which stops their command
+    fn poll_shell_jobs(&mut self) {
+        let panels = &self.panels;
+        self.shell_jobs.retain(|(view_id, _)| all_views(panels).any(|v| v.id == *view_id));
+        for (view_id, job) in self.shell_jobs.iter_mut() {
+            if let Some(view) = all_views_mut(&mut self.panels).find(|v| v.id == *view_id) {
+                view.append_shell_output(&job.poll(MAX_LINES_PER_POLL));
+            }
+        }
+        self.shell_jobs.retain(|(_, job)| !job.finished());
+    }
+
+    /// Opens the input box for a new search, forgetting the last one
+    pub fn start_search(&mut self) {
+        self.last_search = None;
+        self.get_active_view().set_search_markers(None);
+        self.toggle_input_box(Mode::CommandInput(CommandTag::Find));
+    }
+
+    /// Repeats the last search in the active view, wrapping around the ends of its buffer, without opening the input box
+    pub fn repeat_search(&mut self, forward: bool) {
+        if let Some(find) = self.last_search.clone() {
+            let from = self.cursor_location();
+            let v = self.get_active_view();
+            v.set_search_markers(Some(&find));
+            if v.buffer.search_wrapping(&find, forward) {
+                v.set_view_on_buffer_cursor();
+                v.flash_at_cursor(find.chars().count());
+                v.set_need_redraw();
+                self.remember_jump(from);
+            }
+        } else {
+            println!("No search to repeat"); // todo: UI representation
+        }
+    }
+
+    /// Shows how many times find occurs in the active view, next to the search input, and where along its scroll bar
+    fn show_search_results(&mut self, find: &str) {
+        self.get_active_view().set_search_markers(Some(find));
+        let buffer = &self.get_active_view().buffer;
+        let count = buffer.count_matches(find, smart_case_sensitive(find, buffer.smart_case));
+        self.input_box
+            .set_status(Some(format!("{} {}", count, if count == 1 { "match" } else { "matches" })));
+    }
+
     /// Gets the currently active panel, which always is the parent of the View that is currently active
     pub fn active_panel(&self) -> PanelId {
         unsafe { (*self.active_view).panel_id.unwrap() }
@@ -334,33 +583,251 @@ impl<'app> Application<'app> {
         }
         let id = {
             let view = self.get_active_view();
-            view.bg_color = INACTIVE_VIEW_BACKGROUND;
-            view.window_renderer.set_color(INACTIVE_VIEW_BACKGROUND);
-            view.update(None);
+            view.decorate(INACTIVE_VIEW_BACKGROUND, false);
             view.id
         };
-        {
-            let mut iter = all_views(&self.panels).filter(|v| v.visible).cycle();
-            let mut next = false;
-            while let Some(it) = iter.next() {
-                if next {
-                    self.active_view = it as *const _ as *mut _;
-                    break;
-                }
-                if (*it).id == id {
-                    next = true;
-                }
+        if let Some(next) = next_in_focus_cycle(all_views(&self.panels).map(|v| (v.id, v.visible)), id) {
+            if let Some(view) = all_views_mut(&mut self.panels).find(|v| v.id == next) {
+                self.active_view = view as *mut _;
+            }
+        }
+        let id = unsafe { (*self.active_view).id };
+        self.active_ui_element = UID::View(*id);
+        self.decorate_active_view();
+        self.active_keyboard_input = unsafe { &mut (*self.active_view) as &'app mut dyn InputBehavior };
+        let v = unsafe { self.active_view.as_mut().unwrap() };
+        if !v.visible {
+            v.visible = true;
+        }
+    }
+
+    /// Activates the visible view numbered `number` (1-based), as an alternative to cycling through them one by one.
+    /// If there are fewer visible views than that, this does nothing.
+    pub fn focus_view(&mut self, number: usize) {
+        let index = match visible_view_index(all_views(&self.panels).map(|v| v.visible), number) {
+            Some(index) => index,
+            None => return,
+        };
+        let id = all_views(&self.panels).nth(index).unwrap().id;
+        self.activate_view(id);
+    }
+
+    /// Activates the view that was focused before the active one. Pressed again while Control is held, it goes further back, to the
+    /// view focused before that one, and so on; letting go of Control settles on the view it went to
+    pub fn cycle_recent_views(&mut self) {
+        let open: Vec<ViewId> = all_views(&self.panels).map(|v| v.id).collect();
+        self.focus_history.retain(|view| open.contains(&view));
+        if let Some(view) = self.focus_history.cycle() {
+            self.activate_view(view);
+        }
+    }
+
+    /// The view and the position of the cursor in it, to pass on to remember_jump after the cursor jumps somewhere else
+    fn cursor_location(&mut self) -> (ViewId, Index) {
+        let v = self.get_active_view();
+        (v.id, v.buffer.cursor_abs())
+    }
+
+    /// Remembers from, where the cursor was before a jump, for jump_back to return to. Jumps that went nowhere are left out
+    fn remember_jump(&mut self, from: (ViewId, Index)) {
+        if self.cursor_location() != from {
+            self.jumps.push(from.0, from.1);
+        }
+    }
+
+    /// Goes back to where the cursor was before the last jump, or forward again after going back, activating the view it was in.
+    /// Jumps made in views that have been closed since, are skipped
+    pub fn jump_back(&mut self, back: bool) {
+        let (view, here) = self.cursor_location();
+        loop {
+            let (view_id, index) = match if back { self.jumps.back(view, here) } else { self.jumps.forward() } {
+                Some(jump) => jump,
+                None => return,
+            };
+            if all_views(&self.panels).any(|v| v.id == view_id) {
+                self.activate_view(view_id);
+                let v = self.get_active_view();
+                let index = Index(std::cmp::min(*index, v.buffer.len()));
+                v.buffer.cursor_goto_deselect(index);
+                v.set_view_on_buffer_cursor();
+                v.flash_cursor_line();
+                v.set_need_redraw();
+                return;
+            }
+        }
+    }
+
+    /// Shows the popup, asking the user to confirm the delete that the active view held back, if it held one back
+    fn ask_to_confirm_delete(&mut self) {
+        let v = unsafe { self.active_view.as_mut().unwrap() };
+        if let Some((chars, lines)) = v.held_delete_size() {
+            self.delete_to_confirm = Some(v.id);
+            self.popup.reset();
+            self.popup
+                .view
+                .insert_str(&format!("Delete {} characters, on {} lines?\nEnter deletes them, Escape keeps them", chars, lines));
+            self.popup.visible = true;
+        }
+    }
+
+    /// Makes the delete the user was asked to confirm, if confirmed, and hides the popup
+    fn settle_delete(&mut self, view_id: ViewId, confirmed: bool) {
+        if let Some(view) = all_views_mut(&mut self.panels).find(|v| v.id == view_id) {
+            view.settle_held_delete(confirmed);
+        }
+        self.delete_to_confirm = None;
+        self.popup.visible = false;
+        self.popup.reset();
+    }
+
+    /// Shows the next (or previous, if not forward) match of what's typed in the search input box in the popup, with the lines
+    /// around it, without moving the cursor. Peeking again goes on from the match peeked at last
+    fn peek_search_match(&mut self, forward: bool) {
+        let find: String = self.input_box.input_box.data.iter().collect();
+        if find.is_empty() {
+            return;
+        }
+        let v = unsafe { self.active_view.as_mut().unwrap() };
+        let from = match &self.peek {
+            Some(peek) if peek.view == v.id && peek.find == find => *peek.at,
+            _ => *v.buffer.cursor_abs(),
+        };
+        let found = v.buffer.find_wrapping(&find, from, forward).map(Index);
+        let (at, (text, match_begin)) = match found.and_then(|at| peek_text(&v.buffer, at, PEEK_CONTEXT_LINES).map(|peeked| (at, peeked))) {
+            Some(found) => found,
+            None => {
+                self.input_box.set_status(Some("No matches".to_string()));
+                return;
             }
+        };
+        let line = v.buffer.meta_data().get_line_number_of_buffer_index(at).unwrap_or(0);
+        self.input_box
+            .set_status(Some(format!("Match on line {}, Enter goes there", line + 1)));
+        self.peek = Some(Peek { view: v.id, find: find.clone(), at });
+        self.popup.reset();
+        self.popup.view.insert_str(&text);
+        self.popup.view.buffer.cursor_goto(Index(match_begin));
+        self.popup.view.set_view_on_buffer_cursor();
+        self.popup.view.flash_at_cursor(find.chars().count());
+        self.popup.visible = true;
+    }
 
-            let id = unsafe { (*self.active_view).id };
-            self.active_ui_element = UID::View(*id);
+    /// Hides the popup, if it shows a search match, and returns the match
+    fn close_peek(&mut self) -> Option<Peek> {
+        let peek = self.peek.take();
+        if peek.is_some() {
+            self.popup.visible = false;
+            self.popup.reset();
+        }
+        peek
+    }
+
+    /// Makes the view with view_id the active one, showing it again if it was hidden
+    fn activate_view(&mut self, view_id: ViewId) {
+        let next = match all_views_mut(&mut self.panels).find(|v| v.id == view_id) {
+            Some(view) => view as *mut View,
+            None => return,
+        };
+        if next == self.active_view {
+            return;
         }
+        {
+            let view = unsafe { self.active_view.as_mut().unwrap() };
+            view.decorate(INACTIVE_VIEW_BACKGROUND, false);
+        }
+        self.active_view = next;
+        self.active_ui_element = UID::View(*view_id);
         self.decorate_active_view();
         self.active_keyboard_input = unsafe { &mut (*self.active_view) as &'app mut dyn InputBehavior };
         let v = unsafe { self.active_view.as_mut().unwrap() };
         if !v.visible {
             v.visible = true;
+            for p in self.panels.iter_mut() {
+                p.layout();
+            }
+        }
+    }
+
+    /// The buffers open in views, followed by the ones no view holds, listed for the user to switch between
+    fn open_buffers(&self) -> Vec<String> {
+        all_views(&self.panels)
+            .map(|v| (v.buffer.id, v.buffer.file_name(), !v.buffer.pristine()))
+            .chain(self.buffers.iter())
+            .map(|(id, file_name, dirty)| buffer_list_item(id, file_name, dirty))
+            .collect()
+    }
+
+    /// Activates the view showing the buffer with id. A buffer that no view shows, gets a new view in the active panel
+    fn switch_to_buffer(&mut self, id: BufferId) {
+        let shown_in = all_views(&self.panels).find(|v| v.buffer.id == id).map(|v| v.id);
+        if let Some(view_id) = shown_in {
+            self.activate_view(view_id);
+        } else if let Some(buffer) = self.buffers.take_buffer(id) {
+            let name = buffer
+                .file_name()
+                .and_then(|p| p.file_name())
+                .map(|f| f.to_string_lossy().to_string());
+            self.open_text_view(self.active_panel(), name, self.window_size);
+            let v = self.get_active_view();
+            let requested = std::mem::replace(&mut v.buffer, buffer);
+            v.set_view_on_buffer_cursor();
+            v.set_need_redraw();
+            self.buffers.destroy_buffer(requested);
+        }
+    }
+
+    /// Moves the active view one step in direction, among the views of its panel
+    pub fn move_active_view(&mut self, direction: Direction) {
+        let active_id = self.get_active_view_id();
+        for panel in self.panels.iter_mut() {
+            if let Some(index) = panel.children.iter().position(|v| v.id == active_id) {
+                if let Some(new_index) = panel.move_child(index, direction) {
+                    // the views swapped places in memory, so the pointers to the active view must follow it
+                    self.active_view = &mut panel.children[new_index] as *mut _;
+                    self.active_keyboard_input = cast_ptr_to_input(self.active_view);
+                }
+                return;
+            }
+        }
+    }
+
+    /// Grows or shrinks the active view within its panel, taking space from, or giving it to, its neighbour
+    pub fn resize_active_view(&mut self, direction: Direction) {
+        let active_id = self.get_active_view_id();
+        if let Some(panel) = self.panels.iter_mut().find(|p| p.children.iter().any(|v| v.id == active_id)) {
+            let index = panel.children.iter().position(|v| v.id == active_id).unwrap();
+            panel.resize_child(index, direction);
+        }
+    }
+
+    /// Moves the active view into a new panel, which takes the right half of the space of the panel it was in. A panel always holds
+    /// a view, so a panel left without views gets a new, empty one
+    pub fn detach_active_view(&mut self) {
+        let view_id = self.get_active_view_id();
+        let panel_id = self.active_panel();
+        let source = self.panels.iter().position(|p| p.id == panel_id).unwrap();
+        if self.panels[source].children.len() == 1 {
+            self.open_text_view(panel_id, None, self.window_size);
+        }
+        let detached_id = self.panels.iter().map(|p| *p.id).max().unwrap_or(0) + 1;
+        let panel = &mut self.panels[source];
+        let view = panel.remove_view(view_id).unwrap();
+        let [(kept_anchor, kept_size), (anchor, size)] = split_side_by_side(panel.anchor, panel.size);
+        panel.set_anchor(kept_anchor);
+        panel.resize(kept_size);
+        panel.layout();
+        for v in panel.children.iter_mut() {
+            v.decorate(INACTIVE_VIEW_BACKGROUND, false);
         }
+
+        let mut detached = Panel::new(detached_id, panel.layout, panel.margin, panel.border, size.width, size.height, anchor);
+        detached.add_view(view);
+        self.panels.push(detached);
+        // the view moved in memory, so the pointers to the active view must follow it
+        self.active_view = self.panels.last_mut().unwrap().children.last_mut().unwrap() as *mut _;
+        self.active_keyboard_input = cast_ptr_to_input(self.active_view);
+        self.active_ui_element = UID::View(*view_id);
+        self.decorate_active_view();
     }
 
     #[inline(always)]
@@ -411,12 +878,13 @@ impl<'app> Application<'app> {
         assert_eq!(ax, size_change_factor.x);
         assert_eq!(ay, size_change_factor.y);
 
+        // the edges of the panels are scaled and rounded, not their anchors and sizes, so that panels side by side stay edge to edge
+        let scale = |v: Vec2i| Vec2d::new(v.x as f64 * size_change_factor.x, v.y as f64 * size_change_factor.y);
         for p in self.panels.iter_mut() {
-            let panel_anchor = Vec2d::new(p.anchor.x as _, p.anchor.y as _);
-            let Vec2 { x, y } = panel_anchor * size_change_factor;
-            let new_size = Size::vector_multiply(p.size, size_change_factor);
-            p.set_anchor(Vec2i::new(x.round() as _, y.round() as _));
-            p.resize(new_size);
+            let BoundingBox { min, max } = BoundingBox::from_info(p.anchor, p.size);
+            let snapped = BoundingBox::snap_to_pixel(scale(min), scale(max));
+            p.set_anchor(Vec2i::new(snapped.min.x, snapped.max.y));
+            p.resize(snapped.size());
             p.layout();
         }
 
@@ -436,24 +904,51 @@ impl<'app> Application<'app> {
     }
 
     pub fn process_all_events(&mut self, window: &mut Window, events: &Receiver<(f64, glfw::WindowEvent)>) {
-        for (_, event) in glfw::flush_messages(events) {
+        for (time, event) in glfw::flush_messages(events) {
             match event {
                 glfw::WindowEvent::FramebufferSize(width, height) => {
                     self.handle_resize_event(width, height);
                 }
+                glfw::WindowEvent::Focus(false) => {
+                    if self.idle_save.focus_lost() {
+                        self.save_modified_buffers();
+                    }
+                }
                 glfw::WindowEvent::Char(ch) => {
+                    self.idle_save.input_at(time);
+                    if self.delete_to_confirm.is_some() {
+                        continue;
+                    }
                     self.active_keyboard_input.handle_char(ch);
+                    self.ask_to_confirm_delete();
                     // let v = self.get_active_view();
                     // v.insert_ch(ch);
                 }
                 glfw::WindowEvent::Key(key, _, action, m) => {
+                    self.idle_save.input_at(time);
+                    if let Some(view_id) = self.delete_to_confirm {
+                        if key_press(action) {
+                            match key {
+                                // not Y and N, as the character typed with them would go to the view right after
+                                Key::Enter | Key::KpEnter => self.settle_delete(view_id, true),
+                                Key::Escape => self.settle_delete(view_id, false),
+                                _ => {}
+                            }
+                        }
+                        continue;
+                    }
                     self.handle_key_event(window, key, action, m);
+                    self.ask_to_confirm_delete();
                 }
                 glfw::WindowEvent::MouseButton(mbtn, act, _mods) => {
                     let (x, y) = window.get_cursor_pos();
                     let pos = self.translate_screen_to_application_space(Vec2d::new(x, y));
 
                     if act == glfw::Action::Press {
+                        // a click would move the cursor, and change the selection whose delete is waiting to be confirmed
+                        if self.delete_to_confirm.is_some() {
+                            continue;
+                        }
                         let new_state = MouseState::Click(mbtn, pos);
                         self.handle_mouse_input(new_state);
                     } else {
@@ -462,8 +957,11 @@ impl<'app> Application<'app> {
                 }
                 glfw::WindowEvent::CursorPos(mposx, mposy) => {
                     let new_pos = self.translate_screen_to_application_space(Vec2d::new(mposx, mposy));
+                    if let MouseState::None = self.mouse_state {
+                        self.update_cursor_shape(window, new_pos.to_i32());
+                    }
                     match self.mouse_state {
-                        MouseState::UIElementClicked(view, btn, pos) => {
+                        MouseState::UIElementClicked(view, btn, pos, anchor) => {
                             // If control is pressed, we want to activate the Drag action for the UI element itsef
                             let cv = self.get_view_unchecked(view);
                             if cv.title_frame.to_bb().box_hit_check(pos.to_i32()) {
@@ -471,9 +969,9 @@ impl<'app> Application<'app> {
                             } else {
                                 if window.get_key(glfw::Key::LeftControl) == Action::Press || window.get_key(glfw::Key::RightControl) == Action::Press {
                                     self.mouse_state = MouseState::UIElementDrag(view, btn, new_pos);
-                                } else {
+                                } else if self.delete_to_confirm.is_none() {
                                     // Otherwise, we want to tell the UI element to handle the drag action for us; e.g. for selecting text
-                                    let new_state = MouseState::UIElementDragAction(view, btn, pos, new_pos);
+                                    let new_state = MouseState::UIElementDragAction(view, btn, pos, new_pos, anchor);
                                     self.handle_mouse_input(new_state);
                                 }
                             }
@@ -482,8 +980,8 @@ impl<'app> Application<'app> {
                             // Continue drag, REMEMBER, MUST translate to Application coordinate space
                             self.mouse_state = MouseState::UIElementDrag(view, btn, new_pos)
                         }
-                        MouseState::UIElementDragAction(v, btn, begin, ..) => {
-                            let new_state = MouseState::UIElementDragAction(v, btn, begin, new_pos);
+                        MouseState::UIElementDragAction(v, btn, begin, _, anchor) if self.delete_to_confirm.is_none() => {
+                            let new_state = MouseState::UIElementDragAction(v, btn, begin, new_pos, anchor);
                             self.handle_mouse_input(new_state);
                         }
                         _ => { // Do nothing
@@ -493,6 +991,12 @@ impl<'app> Application<'app> {
                 _ => {}
             }
         }
+        // however the active view got focused; by a click, a command, a jump and so on
+        let active = unsafe { (*self.active_view).id };
+        self.focus_history.focused(active);
+        if let Some(text) = self.system_clipboard.take() {
+            window.set_clipboard_string(&text);
+        }
     }
 
     fn handle_mouse_input(&mut self, new_state: MouseState) {
@@ -501,15 +1005,13 @@ impl<'app> Application<'app> {
                 if btn == glfw::MouseButton::Button1 {
                     let active_id = self.get_active_view_id();
                     let pos = p.to_i32();
-                    let clicked_view = self
-                        .panels
-                        .iter_mut()
-                        .flat_map(|p| p.children.iter_mut())
-                        .find(|v| v.bounding_box().box_hit_check(pos));
+                    let clicked_view = self.view_at(pos).and_then(|id| all_views_mut(&mut self.panels).find(|v| v.id == id));
                     if let Some(clicked_view) = clicked_view {
                         let id = clicked_view.id;
 
                         let de_activate_old = id != active_id;
+                        // where the click landed, before the click moves the cursor there, for a drag to select from
+                        let anchor = clicked_view.coordinate_to_index(pos);
                         clicked_view.mouse_clicked(pos);
                         self.active_view = &mut (*clicked_view) as *mut _;
                         self.active_keyboard_input = cast_ptr_to_input(self.active_view); // unsafe { self.active_view.as_mut().unwrap() as &'app mut dyn Input };
@@ -525,79 +1027,61 @@ impl<'app> Application<'app> {
                                 .find(|v| v.id == active_id)
                             {
                                 // decorate view as an inactive one
-                                v.bg_color = INACTIVE_VIEW_BACKGROUND;
-                                v.set_need_redraw();
-                                v.window_renderer.set_color(INACTIVE_VIEW_BACKGROUND);
-                                v.update(None);
+                                v.decorate(INACTIVE_VIEW_BACKGROUND, false);
                             }
                         }
-                        self.mouse_state = MouseState::UIElementClicked(id, MouseButton::Button1, p);
+                        self.mouse_state = MouseState::UIElementClicked(id, MouseButton::Button1, p, anchor);
                     }
                 }
             }
-            MouseState::UIElementClicked(_view_id, _btn, _pos) => {}
+            MouseState::UIElementClicked(_view_id, _btn, _pos, _anchor) => {}
             MouseState::UIElementDrag(_maybe_view, _btn, _pos) => {}
-            MouseState::UIElementDragAction(_view, _btn, begin, current) => {
-                let pos = begin.to_i32();
+            MouseState::UIElementDragAction(_view, _btn, begin, current, anchor) => {
                 let view_handling_action = self
-                    .panels
-                    .iter_mut()
-                    .flat_map(|p| p.children.iter_mut())
-                    .find(|v| v.bounding_box().box_hit_check(pos));
+                    .view_at(begin.to_i32())
+                    .and_then(|id| all_views_mut(&mut self.panels).find(|v| v.id == id));
                 if let Some(handling_view) = view_handling_action {
-                    handling_view.mouse_dragged(begin.to_i32(), current.to_i32());
+                    handling_view.mouse_dragged(begin.to_i32(), current.to_i32(), anchor);
                 }
                 self.mouse_state = new_state;
             }
             MouseState::Released(_btn, pos) => {
                 match self.mouse_state {
                     MouseState::UIElementDrag(dragged_view_id, _, _) => {
-                        let view_dropped_on = self
-                            .panels
-                            .iter_mut()
-                            .flat_map(|p| p.children.iter_mut())
-                            .find(|v| v.bounding_box().box_hit_check(pos.to_i32()))
-                            .map(|v| v.id);
+                        let view_dropped_on = self.drop_slot(dragged_view_id, pos.to_i32()).map(|(view, _)| view);
                         if let Some(view_dropped_on) = view_dropped_on {
-                            if dragged_view_id != view_dropped_on {
-                                let p_a = self
-                                    .panels
-                                    .iter_mut()
-                                    .position(|p| p.children.iter().any(|f| f.id == dragged_view_id));
-                                let mut panel_a = self.panels.swap_remove(p_a.unwrap());
-                                let va = panel_a.children.iter().position(|v| v.id == dragged_view_id);
-
-                                let coexist = panel_a.children.iter().any(|v| v.id == view_dropped_on);
-                                if coexist {
-                                    let vb = panel_a.children.iter().position(|v| v.id == view_dropped_on);
-                                    panel_a.children.swap(va.unwrap(), vb.unwrap());
-                                    panel_a.layout();
-                                    for v in panel_a.children.iter_mut() {
-                                        if v.id == dragged_view_id {
-                                            v.bg_color = ACTIVE_VIEW_BACKGROUND;
-                                            v.window_renderer.set_color(ACTIVE_VIEW_BACKGROUND);
-                                            v.update(None);
-                                            self.active_view = v as *mut _;
-                                            self.active_keyboard_input = cast_ptr_to_input(self.active_view);
-                                        } else {
-                                            v.bg_color = INACTIVE_VIEW_BACKGROUND;
-                                            v.window_renderer.set_color(INACTIVE_VIEW_BACKGROUND);
-                                            v.update(None);
-                                        }
+                            let p_a = self
+                                .panels
+                                .iter_mut()
+                                .position(|p| p.children.iter().any(|f| f.id == dragged_view_id));
+                            let mut panel_a = self.panels.swap_remove(p_a.unwrap());
+                            let va = panel_a.children.iter().position(|v| v.id == dragged_view_id);
+
+                            let coexist = panel_a.children.iter().any(|v| v.id == view_dropped_on);
+                            if coexist {
+                                let vb = panel_a.children.iter().position(|v| v.id == view_dropped_on);
+                                panel_a.swap_children(va.unwrap(), vb.unwrap());
+                                for v in panel_a.children.iter_mut() {
+                                    if v.id == dragged_view_id {
+                                        v.decorate(ACTIVE_VIEW_BACKGROUND, true);
+                                        self.active_view = v as *mut _;
+                                        self.active_keyboard_input = cast_ptr_to_input(self.active_view);
+                                    } else {
+                                        v.decorate(INACTIVE_VIEW_BACKGROUND, false);
                                     }
-                                    self.panels.insert(p_a.unwrap(), panel_a);
-                                } else {
-                                    let p_b = self
-                                        .panels
-                                        .iter_mut()
-                                        .position(|p| p.children.iter().any(|f| f.id == view_dropped_on));
-                                    let mut panel_b = self.panels.swap_remove(p_b.unwrap());
-
-                                    let vb = panel_b.children.iter().position(|v| v.id == dragged_view_id);
-                                    std::mem::swap(panel_a.children.get_mut(va.unwrap()).unwrap(), panel_b.children.get_mut(vb.unwrap()).unwrap());
-                                    self.panels.insert(p_a.unwrap(), panel_a);
-                                    self.panels.insert(p_b.unwrap(), panel_b);
                                 }
+                                self.panels.insert(p_a.unwrap(), panel_a);
+                            } else {
+                                let p_b = self
+                                    .panels
+                                    .iter_mut()
+                                    .position(|p| p.children.iter().any(|f| f.id == view_dropped_on));
+                                let mut panel_b = self.panels.swap_remove(p_b.unwrap());
+
+                                let vb = panel_b.children.iter().position(|v| v.id == dragged_view_id);
+                                std::mem::swap(panel_a.children.get_mut(va.unwrap()).unwrap(), panel_b.children.get_mut(vb.unwrap()).unwrap());
+                                self.panels.insert(p_a.unwrap(), panel_a);
+                                self.panels.insert(p_b.unwrap(), panel_b);
                             }
                         }
                     }
@@ -611,11 +1095,64 @@ impl<'app> Application<'app> {
         }
     }
 
+    /// Whether pos is on the input box or the popup, which then take clicks there, instead of the views under them
+    fn overlay_at(&self, pos: Vec2i) -> bool {
+        let overlays: [(bool, &dyn Viewable); 2] = [
+            (self.input_box.visible, &self.input_box),
+            (self.popup.visible, &self.popup.view),
+        ];
+        covered_by_overlay(&overlays, pos)
+    }
+
+    /// The view at pos, which clicks there go to. Views are hit in the order they're drawn, so the one on top gets the click.
+    /// Hidden views can't be clicked, and neither can what's under the input box or the popup, which aren't views of a panel
+    pub fn view_at(&self, pos: Vec2i) -> Option<ViewId> {
+        if self.overlay_at(pos) {
+            return None;
+        }
+        self.panels.iter().filter_map(|p| p.view_at(pos)).last()
+    }
+
+    /// Where the dragged view lands if it's dropped at pos, and the bar that marks the slot. Like clicks, drops can't land
+    /// under the input box or the popup
+    pub fn drop_slot(&self, dragged: ViewId, pos: Vec2i) -> Option<(ViewId, BoundingBox)> {
+        if self.overlay_at(pos) {
+            return None;
+        }
+        self.panels.iter().filter_map(|p| p.drop_slot(dragged, pos)).last()
+    }
+
+    /// What's at pos, which the shape of the mouse cursor shows. The popup, drawn above every panel, is classified as a panel of its own
+    pub fn cursor_region_at(&self, pos: Vec2i) -> CursorRegion {
+        let mut panels: Vec<PanelRegions> = self.panels.iter().map(|p| p.regions()).collect();
+        if self.popup.visible {
+            panels.push(PanelRegions {
+                bounding_box: self.popup.view.bounding_box(),
+                layout: Layout::Vertical(Spacing(0)),
+                views: vec![self.popup.view.regions()],
+            });
+        }
+        if self.input_box.visible {
+            panels.push(PanelRegions { bounding_box: self.input_box.bounding_box(), layout: Layout::Vertical(Spacing(0)), views: vec![] });
+        }
+        cursor_region_at(pos, &panels)
+    }
+
+    /// Sets the shape of the mouse cursor, by what it's over. The cursor is only replaced when it's over another kind of region
+    fn update_cursor_shape(&mut self, window: &mut Window, pos: Vec2i) {
+        let region = self.cursor_region_at(pos);
+        if region != self.cursor_region {
+            window.set_cursor(Some(glfw::Cursor::standard(region.standard_cursor())));
+            self.cursor_region = region;
+        }
+    }
+
     fn get_active_view_id(&self) -> ViewId {
         unsafe { self.active_view.as_ref().unwrap().id }
     }
 
     pub fn toggle_input_box(&mut self, mode: Mode) {
+        self.close_peek();
         if self.input_box.visible && mode == self.input_box.mode {
             self.active_keyboard_input = cast_ptr_to_input(self.active_view);
             self.input_box.clear();
@@ -624,7 +1161,7 @@ impl<'app> Application<'app> {
             self.input_context = KeyboardInputContext::TextView;
         } else {
             self.input_box.clear();
-            self.input_box.mode = mode;
+            self.input_box.open(mode, None);
             // self.active_input = &mut self.input_box as &'app mut dyn Input;
             self.active_keyboard_input = unsafe { &mut *(&mut self.input_box as *mut _) as &'app mut dyn InputBehavior };
             self.input_box.visible = true;
@@ -632,6 +1169,117 @@ impl<'app> Application<'app> {
         }
     }
 
+    /// Runs cmd, which takes no input, on the active view
+    fn run_command(&mut self, cmd: CommandTag) {
+        match cmd {
+            CommandTag::ToggleReadOnly => {
+                self.get_active_view().toggle_read_only();
+            }
+            CommandTag::SortLines => {
+                self.get_active_view().sort_lines();
+            }
+            CommandTag::RemoveDuplicateLines => {
+                self.get_active_view().remove_duplicate_lines();
+            }
+            CommandTag::NextChange | CommandTag::PreviousChange => {
+                let from = self.cursor_location();
+                self.get_active_view().goto_modified_region(cmd == CommandTag::NextChange);
+                self.remember_jump(from);
+            }
+            CommandTag::TogglePinned => {
+                self.get_active_view().toggle_pinned();
+            }
+            CommandTag::CloseOtherViews | CommandTag::CloseAllViews => {
+                if cmd == CommandTag::CloseOtherViews {
+                    self.close_other_views();
+                } else {
+                    self.close_all_views();
+                }
+            }
+            CommandTag::ChangeCase(mode) => {
+                self.get_active_view().transform_case(mode);
+            }
+            CommandTag::TrimSelection => {
+                self.get_active_view().trim_selection();
+            }
+            CommandTag::Reflow => {
+                let view = self.get_active_view();
+                let width = view.options.rulers.first().copied().unwrap_or(DEFAULT_REFLOW_WIDTH);
+                view.reflow_paragraph(width);
+            }
+            CommandTag::Format => {
+                let v = unsafe { self.active_view.as_mut().unwrap() };
+                let formatter = v.buffer.file_name().and_then(formatter_for);
+                let failure = match formatter {
+                    Some(formatter) => v.format_document(formatter).err(),
+                    None => Some("There's no formatter for this kind of file".to_string()),
+                };
+                if let Some(failure) = failure {
+                    self.popup.reset();
+                    self.popup.view.insert_str(&format!("Could not format the document:\n{}", failure));
+                    self.popup.visible = true;
+                }
+            }
+            CommandTag::CopyAs(format) => {
+                let v = unsafe { self.active_view.as_mut().unwrap() };
+                if let Some(entry) = ClipEntry::copied_as(&v.buffer, format) {
+                    self.clipboard.take_entry(entry);
+                }
+            }
+            CommandTag::ShowInFolder => {
+                if let Some(Err(failure)) = self.active_file_path().map(|path| reveal(&path)) {
+                    self.popup.reset();
+                    self.popup
+                        .view
+                        .insert_str(&format!("Could not show the file in the file manager:\n{}", failure));
+                    self.popup.visible = true;
+                }
+            }
+            CommandTag::CopyPath => {
+                if let Some(path) = self.active_file_path() {
+                    let path = path.display().to_string();
+                    self.clipboard.copy(&path);
+                    self.system_clipboard = Some(path);
+                }
+            }
+            CommandTag::ToggleViewOption(option) => {
+                self.get_active_view().toggle_option(option);
+            }
+            CommandTag::ApplyOptionsToNewViews => {
+                self.view_defaults = self.get_active_view().options.clone();
+            }
+            CommandTag::ToggleRelated => {
+                self.toggle_related();
+            }
+            // these take input, and are run once it's entered
+            _ => {}
+        }
+    }
+
+    /// Hides the input box, emptied, and hands keyboard input back to the active view
+    fn close_input_box(&mut self) {
+        self.input_box.clear();
+        self.input_box.visible = false;
+        self.input_context = KeyboardInputContext::TextView;
+        self.active_keyboard_input = cast_ptr_to_input(self.active_view);
+    }
+
+    /// Opens the file finder, walking from the directory of the active view's file. Unnamed buffers walk from the current working directory
+    pub fn reveal_in_file_finder(&mut self) {
+        let dir = self
+            .get_active_view()
+            .buffer
+            .file_name()
+            .and_then(|f| f.parent())
+            .filter(|p| !p.as_os_str().is_empty())
+            .map(|p| p.to_path_buf());
+        if !self.input_box.visible || self.input_box.mode != Mode::CommandInput(CommandTag::OpenFile) {
+            self.toggle_input_box(Mode::CommandInput(CommandTag::OpenFile));
+        }
+        self.input_box.clear();
+        self.input_box.open(Mode::CommandInput(CommandTag::OpenFile), dir.as_deref());
+    }
+
     pub fn handle_key_event(&mut self, _window: &mut Window, key: glfw::Key, action: glfw::Action, modifier: glfw::Modifiers) {
         let time = std::time::Instant::now();
         if key == glfw::Key::F2 && action == Action::Press && modifier == glfw::Modifiers::Control {
@@ -645,6 +1293,10 @@ impl<'app> Application<'app> {
             }
         }
 
+        if action == Action::Release && (key == glfw::Key::LeftControl || key == glfw::Key::RightControl) {
+            self.focus_history.settle();
+        }
+
         if key == glfw::Key::F9 && action == Action::Press {
             let v = self.get_active_view();
             v.buffer.debug_print_history();
@@ -688,12 +1340,15 @@ impl<'app> Application<'app> {
                         }
                     },
                     AppAction::OpenFile => self.toggle_input_box(Mode::CommandInput(CommandTag::OpenFile)),
+                    AppAction::RevealInFileFinder => self.reveal_in_file_finder(),
                     AppAction::SaveFile => todo!(),
                     AppAction::SearchInFiles => todo!("Create input box action for searching in all files"),
                     AppAction::GotoLineInFile => self.toggle_input_box(Mode::CommandInput(CommandTag::Goto)),
                     AppAction::CycleFocus => {
                         self.cycle_focus();
                     }
+                    AppAction::FocusView(number) => self.focus_view(number),
+                    AppAction::CycleRecentViews => self.cycle_recent_views(),
                     AppAction::HideFocused => todo!(),
                     AppAction::ShowAll => todo!(),
                     AppAction::ShowDebugInterface => {
@@ -711,6 +1366,12 @@ impl<'app> Application<'app> {
                         self.open_text_view(self.active_panel(), Some("new view".into()), size);
                     }
                     AppAction::ListCommands => self.toggle_input_box(Mode::CommandList),
+                    AppAction::ToggleReadOnly => self.get_active_view().toggle_read_only(),
+                    AppAction::MoveActiveView(direction) => self.move_active_view(direction),
+                    AppAction::ResizeActiveView(direction) => self.resize_active_view(direction),
+                    AppAction::DetachActiveView => self.detach_active_view(),
+                    AppAction::JumpBack => self.jump_back(true),
+                    AppAction::JumpForward => self.jump_back(false),
                 }
             }
         } else {
@@ -723,7 +1384,7 @@ impl<'app> Application<'app> {
                     }
                 }
                 Key::F if key_press(action) && modifier == Modifiers::Control => {
-                    self.toggle_input_box(Mode::CommandInput(CommandTag::Find));
+                    self.start_search();
                 }
                 Key::KpAdd => {}
                 Key::W if modifier.contains(Modifiers::Control) && action == Action::Press => {
@@ -810,7 +1471,7 @@ impl<'app> Application<'app> {
                 _ => match self.active_keyboard_input.handle_key(key, action, modifier) {
                     CommandOutput::OpenFile(path) => {
                         let v = self.get_active_view();
-                        if v.buffer.empty() {
+                        if v.buffer.empty() && !v.pinned {
                             v.load_file(&path);
                             v.set_view_on_buffer_cursor();
                             v.set_need_redraw();
@@ -828,12 +1489,17 @@ impl<'app> Application<'app> {
                             v.update(None);
                             self.input_box.visible = false;
                         }
+                        self.restore_file_position();
                         self.input_box.clear();
                     }
                     CommandOutput::Goto(line) => {
+                        let from = self.cursor_location();
                         let v = self.get_active_view();
                         v.buffer.goto_line(line as usize);
                         v.set_view_on_buffer_cursor();
+                        self.remember_jump(from);
+                        let v = self.get_active_view();
+                        v.flash_cursor_line();
                         v.set_need_redraw();
                         v.update(None);
                         self.active_keyboard_input = unsafe { &mut (*self.active_view) as &'app mut dyn InputBehavior };
@@ -842,10 +1508,18 @@ impl<'app> Application<'app> {
                     }
                     CommandOutput::Find(find) => {
                         // todo: use the regex crate for searching
+                        self.last_search = Some(find.clone()).filter(|find| !find.is_empty());
+                        let from = self.cursor_location();
                         let v = self.get_active_view();
+                        let before = v.buffer.cursor_abs();
                         v.buffer.search_next(&find);
                         v.set_view_on_buffer_cursor();
+                        if v.buffer.cursor_abs() != before {
+                            v.flash_at_cursor(find.chars().count());
+                        }
                         v.set_need_redraw();
+                        self.remember_jump(from);
+                        self.show_search_results(&find);
                     }
                     CommandOutput::SaveFile(file_path) => {
                         if let Some(p) = file_path {
@@ -871,6 +1545,7 @@ impl<'app> Application<'app> {
                                 }
                             }
                         }
+                        self.remember_file_positions(&[self.get_active_view_id()]);
                     }
                     // we discard the ClipboardCopy response, if it did not hold any data, which is why we match exactly on Some(data) here
                     CommandOutput::ClipboardCopy(Some(data)) => {
@@ -905,48 +1580,112 @@ impl<'app> Application<'app> {
         self.window_size.height
     }
 
-    pub fn update_window(&mut self) {
+    /// Draws all the UI elements. time is the time of this frame in seconds, which drives animations
+    pub fn update_window(&mut self, time: f64) {
+        self.poll_shell_jobs();
+        if self.idle_save.due(time) {
+            self.save_modified_buffers();
+        }
         unsafe {
             gl::ClearColor(0.2, 0.3, 0.3, 1.0);
             gl::Clear(gl::COLOR_BUFFER_BIT);
             gl::Viewport(0, 0, self.width() as _, self.height() as _);
         }
 
+        // every element clips what it draws to itself, with a guard of its own, which restores this when it's dropped
+        let _window_clip = ScissorGuard::new(ClipRect::new(0, 0, self.width(), self.height()));
+
         // TODO: when z-indexing will become a thing, sort these first by that said z-index, back to front, before drawing
         for v in self.panels.iter_mut().flat_map(|p| p.children.iter_mut()) {
-            v.draw();
-        }
-        unsafe {
-            gl::Scissor(0, 0, self.width(), self.height());
+            v.draw(time);
         }
 
         if self.popup.visible {
-            self.popup.view.draw();
-        }
-        unsafe {
-            gl::Scissor(0, 0, self.width(), self.height());
+            self.popup.view.draw(time);
         }
 
         self.input_box.draw();
-        unsafe {
-            gl::Scissor(0, 0, self.width(), self.height());
-        }
         self.debug_view.draw();
-        unsafe {
-            gl::Scissor(0, 0, self.width(), self.height());
-        }
-        if let MouseState::UIElementDrag(.., pos) = self.mouse_state {
+        if let MouseState::UIElementDrag(dragged, _, pos) = self.mouse_state {
             let v = unsafe { self.active_view.as_mut().unwrap() };
             let mut bb = v.bounding_box();
             bb.center_align_around(pos.to_i32());
             self.rect_animation_renderer
                 .set_rect(bb, RGBAColor { r: 0.75, g: 0.75, b: 0.75, a: 0.25 });
+            if let Some((_, bar)) = self.drop_slot(dragged, pos.to_i32()) {
+                self.rect_animation_renderer
+                    .add_rect(bar, RGBAColor { r: 0.35, g: 0.6, b: 0.95, a: 0.9 });
+            }
             self.rect_animation_renderer.draw();
         } else {
             self.rect_animation_renderer.clear_data();
         }
     }
 
+    /// Shows the file of the active view in the title of the window, and whether it has unsaved changes
+    pub fn update_window_title(&mut self, window: &mut Window) {
+        let v = unsafe { self.active_view.as_ref().unwrap() };
+        let md = v.buffer.meta_data();
+        let made_from = (v.id, v.buffer.file_name().map(Path::to_path_buf), md.revision(), md.get_pristine_hash());
+        if self.title_made_from.as_ref() == Some(&made_from) {
+            return;
+        }
+        self.title_made_from = Some(made_from);
+        let title = window_title(v.buffer.file_name(), !v.buffer.pristine());
+        if title != self.title_bar {
+            window.set_title(&title);
+            self.title_bar = title;
+        }
+    }
+
+    /// Remembers where the cursor is in the files of views, and writes that to disk
+    fn remember_file_positions(&mut self, views: &[ViewId]) {
+        let positions: Vec<_> = all_views(&self.panels)
+            .filter(|v| views.contains(&v.id))
+            .filter_map(|v| {
+                v.buffer
+                    .file_name()
+                    .map(|file| (file.to_path_buf(), v.file_position(), v.buffer.marks()))
+            })
+            .collect();
+        if positions.is_empty() {
+            return;
+        }
+        for (file, position, marks) in positions {
+            self.file_positions.remember(&file, position);
+            self.file_positions.remember_marks(&file, marks);
+        }
+        if let Some(Err(e)) = file_positions_path().map(|path| self.file_positions.save(&path)) {
+            println!("Failed to write file positions: {}", e); // todo: UI representation
+        }
+    }
+
+    /// Saves the buffers of views that have a file, and changes that aren't saved yet. Buffers without a file are left be, as
+    /// they'd need the user to pick one
+    fn save_modified_buffers(&mut self) {
+        let mut saved = vec![];
+        for v in all_views_mut(&mut self.panels).filter(|v| !v.buffer.read_only() && !v.buffer.pristine()) {
+            if let Some(p) = v.buffer.file_name().map(Path::to_path_buf) {
+                v.buffer.save_file(&p);
+                saved.push(v.id);
+            }
+        }
+        self.remember_file_positions(&saved);
+    }
+
+    /// Moves the cursor of the active view to where it was, and sets the marks that were set, when its file was last saved or closed
+    fn restore_file_position(&mut self) {
+        let file = match self.get_active_view().buffer.file_name().map(Path::to_path_buf) {
+            Some(file) => file,
+            None => return,
+        };
+        let marks = self.file_positions.recall_marks(&file).to_vec();
+        self.get_active_view().buffer.restore_marks(&marks);
+        if let Some(position) = self.file_positions.recall(&file) {
+            self.get_active_view().restore_file_position(position);
+        }
+    }
+
     pub fn close_active_view(&mut self, force_close: bool) {
         // we never detroy the popup window until the application is exited. So hitting "ctrl+w" or whatever keybinding we might have,
         // is just going to cancel the popup and hide it again
@@ -962,12 +1701,13 @@ impl<'app> Application<'app> {
         if view.buffer.pristine() || force_close {
             let view_id = view.id;
             let panel_id = view.panel_id.unwrap();
+            self.remember_file_positions(&[view_id]);
 
-            if self.panels.last().unwrap().children.len() == 1 {
+            if self.panels.iter().find(|p| p.id == panel_id).unwrap().children.len() == 1 {
                 self.open_text_view(panel_id, None, self.window_size);
             }
 
-            let panel = self.panels.get_mut(*panel_id as usize).unwrap();
+            let panel = self.panels.iter_mut().find(|p| p.id == panel_id).unwrap();
 
             let v = panel.remove_view(view_id);
             drop(v);
@@ -981,6 +1721,67 @@ impl<'app> Application<'app> {
         }
     }
 
+    /// Closes every view but the active one. Pinned views, and views with unsaved changes, are left open
+    pub fn close_other_views(&mut self) {
+        let keep = self.get_active_view_id();
+        self.close_views(Some(keep));
+    }
+
+    /// Closes every view, except pinned views and views with unsaved changes. Panels left without views get a new, empty one
+    pub fn close_all_views(&mut self) {
+        self.close_views(None);
+    }
+
+    fn close_views(&mut self, keep: Option<ViewId>) {
+        let active_id = self.get_active_view_id();
+        let active_panel = self.active_panel();
+        let closing = views_to_close(all_views(&self.panels).map(|v| (v.id, v.pinned, v.buffer.pristine())), keep);
+        let unsaved = all_views(&self.panels)
+            .filter(|v| Some(v.id) != keep && !v.pinned && !v.buffer.pristine())
+            .count();
+        if unsaved > 0 {
+            println!("{} views with unsaved changes were left open", unsaved); // todo: UI representation
+        }
+        if closing.is_empty() {
+            return;
+        }
+        // a panel always holds at least one view
+        let emptied: Vec<PanelId> = self
+            .panels
+            .iter()
+            .filter(|p| p.children.iter().all(|v| closing.contains(&v.id)))
+            .map(|p| p.id)
+            .collect();
+        self.remember_file_positions(&closing);
+        for panel_id in emptied {
+            self.open_text_view(panel_id, None, self.window_size);
+        }
+        for panel in self.panels.iter_mut() {
+            for id in closing.iter() {
+                drop(panel.remove_view(*id));
+            }
+            panel.layout();
+        }
+        // removing views moves the remaining ones around in memory, so the active view has to be looked up again
+        let active = if closing.contains(&active_id) {
+            self.panels
+                .iter()
+                .find(|p| p.id == active_panel)
+                .and_then(|p| p.children.last())
+                .unwrap()
+        } else {
+            all_views(&self.panels).find(|v| v.id == active_id).unwrap()
+        };
+        self.active_view = active as *const View as *mut View;
+        for v in all_views_mut(&mut self.panels) {
+            v.decorate(INACTIVE_VIEW_BACKGROUND, false);
+        }
+        self.active_keyboard_input = unsafe { &mut (*self.active_view) as &'app mut dyn InputBehavior };
+        self.decorate_active_view();
+        let id = unsafe { (*self.active_view).id };
+        self.active_ui_element = UID::View(*id);
+    }
+
     pub fn set_debug(&mut self, set: bool) {
         self.debug = set;
     }
@@ -1012,23 +1813,20 @@ impl<'app> Application<'app> {
             ViewAction::Cut => {
                 // let v = self.get_active_view();
 
-                if let Some(data) = self.get_active_view().buffer.cut_range_or_line() {
-                    self.clipboard.take(data);
+                if let Some(entry) = ClipEntry::cut_from(&mut self.get_active_view().buffer) {
+                    self.clipboard.take_entry(entry);
                 }
                 self.get_active_view().set_need_redraw();
             }
             ViewAction::Copy => {
                 let v = self.get_active_view();
-                if let Some(data) = v.buffer.copy_range_or_line() {
-                    self.clipboard.take(data);
+                if let Some(entry) = ClipEntry::copied_from(&v.buffer) {
+                    self.clipboard.take_entry(entry);
                 }
             }
             ViewAction::Paste => {
-                if let Some(data) = self.clipboard.give().cloned() {
-                    let v = self.get_active_view();
-                    for c in data.chars() {
-                        v.insert_ch(c);
-                    }
+                if let Some(entry) = self.clipboard.give_entry().cloned() {
+                    self.get_active_view().paste(&entry);
                 }
             }
             ViewAction::Undo => {
@@ -1044,10 +1842,8 @@ impl<'app> Application<'app> {
             ViewAction::LineOperation(ref lineop) => {
                 let v = self.get_active_view();
                 if let Some((begin, end)) = v.buffer.get_selection() {
-                    let md = v.buffer.meta_data();
-                    let a = unsafe { md.get_line_number_of_buffer_index(begin).unwrap_unchecked() };
-                    let b_inclusive = unsafe { md.get_line_number_of_buffer_index(end).unwrap_unchecked() };
-                    v.buffer.line_operation(a..=b_inclusive, lineop);
+                    let lines = v.buffer.meta_data().lines_spanning(begin, end);
+                    v.buffer.line_operation(lines, lineop);
                     v.set_need_redraw();
                 } else {
                     let line = *v.buffer.cursor_row();
@@ -1083,9 +1879,22 @@ impl<'app> Application<'app> {
                         }
                     }
                 }
+                self.remember_file_positions(&[self.get_active_view_id()]);
             }
             ViewAction::OpenFile => self.toggle_input_box(Mode::CommandInput(CommandTag::OpenFile)),
-            ViewAction::Find => self.toggle_input_box(Mode::CommandInput(CommandTag::Find)),
+            ViewAction::Find => self.start_search(),
+            ViewAction::FindNext => self.repeat_search(true),
+            ViewAction::FindPrevious => self.repeat_search(false),
+            ViewAction::GotoDefinition => {
+                let from = self.cursor_location();
+                let definitions = self.get_active_view().goto_definition_of_word();
+                self.remember_jump(from);
+                if definitions.len() > 1 {
+                    self.toggle_input_box(Mode::CommandInput(CommandTag::GotoSymbol));
+                    self.input_box
+                        .set_choices(definitions.iter().map(|symbol| symbol.list_item()).collect());
+                }
+            }
             ViewAction::Goto => {
                 self.toggle_input_box(Mode::CommandInput(CommandTag::Goto));
             }
@@ -1093,16 +1902,34 @@ impl<'app> Application<'app> {
                 let v = self.get_active_view();
                 v.insert_ch('\n');
             }
+            ViewAction::DuplicateSelection => {
+                let v = self.get_active_view();
+                v.duplicate_selection();
+            }
+            ViewAction::ExpandSelection => {
+                let v = self.get_active_view();
+                v.expand_selection();
+            }
+            ViewAction::ShrinkSelection => {
+                let v = self.get_active_view();
+                v.shrink_selection();
+            }
+            ViewAction::SelectInsideBlock => {
+                let v = self.get_active_view();
+                v.select_inside_block();
+            }
+            ViewAction::SelectAroundBlock => {
+                let v = self.get_active_view();
+                v.select_around_block();
+            }
         }
     }
 
     pub fn handle_input_for_inputbox(&mut self, translation: InputboxAction) {
         match translation {
             InputboxAction::Cancel => {
-                self.input_box.clear();
-                self.input_box.visible = false;
-                self.input_context = KeyboardInputContext::TextView;
-                self.active_keyboard_input = unsafe { &mut (*self.active_view) as &mut dyn InputBehavior };
+                self.close_peek();
+                self.close_input_box();
             }
             InputboxAction::MovecursorLeft => {
                 self.input_box.input_box.cursor = self.input_box.input_box.cursor.saturating_sub(1);
@@ -1110,6 +1937,12 @@ impl<'app> Application<'app> {
             InputboxAction::MovecursorRight => {
                 self.input_box.input_box.cursor = (self.input_box.input_box.cursor + 1).clamp(0, self.input_box.input_box.data.len());
             }
+            InputboxAction::ScrollSelectionUp if self.input_box.mode == Mode::CommandInput(CommandTag::Find) => {
+                self.peek_search_match(false);
+            }
+            InputboxAction::ScrollSelectionDown if self.input_box.mode == Mode::CommandInput(CommandTag::Find) => {
+                self.peek_search_match(true);
+            }
             InputboxAction::ScrollSelectionUp => {
                 self.input_box.selection_list.scroll_selection_up();
             }
@@ -1129,23 +1962,37 @@ impl<'app> Application<'app> {
                 Mode::CommandInput(cmd) => match cmd {
                     CommandTag::Goto => {
                         if let Ok(line) = self.input_box.input_box.data.iter().collect::<String>().parse::<usize>() {
+                            let from = self.cursor_location();
                             let v = self.get_active_view();
                             v.buffer.goto_line(line);
                             v.set_view_on_buffer_cursor();
+                            self.remember_jump(from);
+                            let v = self.get_active_view();
+                            v.flash_cursor_line();
                             v.set_need_redraw();
                             v.update(None);
-                            self.active_keyboard_input = unsafe { &mut (*self.active_view) as &mut dyn InputBehavior };
-                            self.input_box.visible = false;
-                            self.input_box.clear();
-                            self.input_context = KeyboardInputContext::TextView;
+                            self.close_input_box();
                         }
                     }
                     CommandTag::Find => {
                         let input_data = &self.input_box.input_box.data.iter().collect::<String>();
+                        self.last_search = Some(input_data.clone()).filter(|find| !find.is_empty());
+                        let peeked = self.close_peek().filter(|peek| &peek.find == input_data);
+                        let from = self.cursor_location();
                         let v = self.get_active_view();
-                        v.buffer.search_next(&input_data);
+                        let before = v.buffer.cursor_abs();
+                        match peeked {
+                            // the match peeked at is the one gone to
+                            Some(peek) if peek.view == v.id => v.buffer.cursor_goto(Index(std::cmp::min(*peek.at, v.buffer.len()))),
+                            _ => v.buffer.search_next(&input_data),
+                        }
                         v.set_view_on_buffer_cursor();
+                        if v.buffer.cursor_abs() != before {
+                            v.flash_at_cursor(input_data.chars().count());
+                        }
                         v.set_need_redraw();
+                        self.remember_jump(from);
+                        self.show_search_results(&input_data);
                     }
                     CommandTag::GotoInFile => todo!(),
                     CommandTag::OpenFile => {
@@ -1153,21 +2000,14 @@ impl<'app> Application<'app> {
                             let name = String::from_iter(&item);
                             let p = Path::new(&name).to_path_buf();
                             if p.is_dir() {
-                                self.input_box.input_box.data = item;
-                                self.input_box.input_box.data.push('/');
-                                self.input_box.input_box.cursor = self.input_box.input_box.data.len();
-                                self.input_box.selection_list.selection = None;
-                                self.input_box.update_list_of_files();
-                                self.input_box.needs_update = true;
+                                self.input_box.descend_into(item);
                             } else {
                                 if p.exists() {
                                     let v = self.get_active_view();
-                                    if v.buffer.empty() {
+                                    if v.buffer.empty() && !v.pinned {
                                         v.buffer.load_file(&p);
                                         v.set_need_redraw();
                                         v.update(None);
-                                        self.active_keyboard_input = unsafe { &mut (*self.active_view) as &mut dyn InputBehavior };
-                                        self.input_box.visible = false;
                                     } else {
                                         let p_id = self.get_active_view().panel_id;
                                         let f_name = p.file_name();
@@ -1177,21 +2017,173 @@ impl<'app> Application<'app> {
                                         v.buffer.load_file(&p);
                                         v.set_need_redraw();
                                         v.update(None);
-                                        self.input_box.visible = false;
                                     }
-                                    self.input_box.clear();
-                                    self.input_context = KeyboardInputContext::TextView;
+                                    self.restore_file_position();
+                                    self.close_input_box();
+                                }
+                            }
+                        }
+                    }
+                    CommandTag::InsertFile => {
+                        if let Some(item) = self.input_box.selection_list.pop_selected() {
+                            let p = PathBuf::from(String::from_iter(&item));
+                            if p.is_dir() {
+                                self.input_box.descend_into(item);
+                            } else {
+                                self.close_input_box();
+                                let v = unsafe { self.active_view.as_mut().unwrap() };
+                                if let Err(err) = v.insert_file(&p) {
+                                    self.popup.reset();
+                                    self.popup.view.insert_str(&format!("Could not insert {}:\n{}", p.display(), err));
+                                    self.popup.visible = true;
                                 }
                             }
                         }
                     }
                     CommandTag::SaveFile => todo!(),
+                    CommandTag::AutoSave => {
+                        let input = self.input_box.input_box.data.iter().collect::<String>();
+                        if self.idle_save.configure(&input) {
+                            self.close_input_box();
+                        } else {
+                            self.input_box.set_status(Some(format!("Not a number of seconds: {}", input.trim())));
+                        }
+                    }
+                    CommandTag::DeleteLimit => {
+                        let input = self.input_box.input_box.data.iter().collect::<String>();
+                        if self.delete_guard.configure(&input) {
+                            let guard = self.delete_guard;
+                            all_views_mut(&mut self.panels).for_each(|v| v.delete_guard = guard);
+                            self.close_input_box();
+                        } else {
+                            self.input_box
+                                .set_status(Some(format!("Not a number of characters, or of lines: {}", input.trim())));
+                        }
+                    }
+                    CommandTag::ScrollBarWidth => {
+                        let input = self.input_box.input_box.data.iter().collect::<String>();
+                        match input.trim().parse::<i32>() {
+                            Ok(width) if (0..=MAX_SCROLL_BAR_WIDTH).contains(&width) => {
+                                let v = unsafe { self.active_view.as_mut().unwrap() };
+                                v.options.scroll_bar_width = width;
+                                v.set_need_redraw();
+                                self.close_input_box();
+                            }
+                            _ => self
+                                .input_box
+                                .set_status(Some(format!("Not a width between 0 and {}: {}", MAX_SCROLL_BAR_WIDTH, input.trim()))),
+                        }
+                    }
+                    CommandTag::SetMark | CommandTag::JumpToMark => {
+                        let input = self.input_box.input_box.data.iter().collect::<String>();
+                        let mut names = input.trim().chars();
+                        match (names.next(), names.next()) {
+                            (Some(name), None) => {
+                                let found = if cmd == CommandTag::SetMark {
+                                    self.get_active_view().set_mark(name);
+                                    true
+                                } else {
+                                    let from = self.cursor_location();
+                                    let found = self.get_active_view().goto_mark(name);
+                                    self.remember_jump(from);
+                                    found
+                                };
+                                if found {
+                                    self.close_input_box();
+                                } else {
+                                    self.input_box.set_status(Some(format!("No mark named {}", name)));
+                                }
+                            }
+                            _ => self
+                                .input_box
+                                .set_status(Some(format!("A mark is named by one character, not: {}", input.trim()))),
+                        }
+                    }
+                    CommandTag::SetViewColor => {
+                        let input = self.input_box.input_box.data.iter().collect::<String>();
+                        let color = RGBAColor::from_hex(&input);
+                        if color.is_some() || input.trim().is_empty() {
+                            self.close_input_box();
+                            if let Some(color) = color {
+                                self.get_active_view().set_bg_color(color);
+                            } else {
+                                self.get_active_view().clear_bg_color();
+                                self.decorate_active_view();
+                            }
+                        } else {
+                            self.input_box.set_status(Some(format!("Not a color: {}", input.trim())));
+                        }
+                    }
+                    CommandTag::RunShell => {
+                        let command_line = self.input_box.input_box.data.iter().collect::<String>();
+                        if !command_line.trim().is_empty() {
+                            self.close_input_box();
+                            self.run_shell_command(&command_line);
+                        }
+                    }
+                    CommandTag::GotoSymbol => {
+                        if let Some(line) = self
+                            .input_box
+                            .selection_list
+                            .pop_selected()
+                            .and_then(|item| line_of_list_item(&item.iter().collect::<String>()))
+                        {
+                            self.close_input_box();
+                            let from = self.cursor_location();
+                            let v = self.get_active_view();
+                            v.buffer.goto_line(line);
+                            v.set_view_on_buffer_cursor();
+                            v.flash_cursor_line();
+                            v.set_need_redraw();
+                            self.remember_jump(from);
+                        }
+                    }
+                    CommandTag::SwitchBuffer => {
+                        if let Some(id) = self
+                            .input_box
+                            .selection_list
+                            .pop_selected()
+                            .and_then(|item| buffer_id_of_list_item(&item.iter().collect::<String>()))
+                        {
+                            self.close_input_box();
+                            self.switch_to_buffer(id);
+                        }
+                    }
+                    CommandTag::ToggleRelated => {
+                        if let Some(item) = self.input_box.selection_list.pop_selected() {
+                            self.close_input_box();
+                            self.open_or_focus(PathBuf::from(item.iter().collect::<String>()));
+                        }
+                    }
+                    CommandTag::InsertSnippet => {
+                        if let Some(snippet) = self
+                            .input_box
+                            .selection_list
+                            .pop_selected()
+                            .and_then(|item| get_snippet(&item.iter().collect::<String>()))
+                        {
+                            self.close_input_box();
+                            let v = self.get_active_view();
+                            v.insert_snippet(&snippet.expand());
+                            v.set_need_redraw();
+                        }
+                    }
+                    // the rest take no input; they're run as they're selected from the command list
+                    _ => {}
                 },
                 Mode::CommandList => {
                     if let Some(item) = self.input_box.selection_list.pop_selected() {
                         let name = item.iter().collect::<String>();
-                        if let Some(cmd) = get_command(&name) {
-                            self.toggle_input_box(Mode::CommandInput(*cmd));
+                        if let Some(&cmd) = get_command(&name) {
+                            if self.input_box.select_command(cmd) {
+                                if cmd == CommandTag::SwitchBuffer {
+                                    let open_buffers = self.open_buffers();
+                                    self.input_box.set_choices(open_buffers);
+                                }
+                            } else {
+                                self.close_input_box();
+                                self.run_command(cmd);
+                            }
                         } else {
                             println!("Found no command by name: {}", name);
                         }
@@ -1299,3 +2291,86 @@ where
 {
     unsafe { &mut (*t) as &'app mut dyn InputBehavior }
 }
+
+#[cfg(test)]
+mod app_tests {
+    use super::{covered_by_overlay, next_in_focus_cycle, views_to_close, visible_view_index, window_title};
+    use crate::datastructure::generic::Vec2i;
+    use crate::textbuffer::metadata::Index;
+    use crate::ui::basic::boundingbox::BoundingBox;
+    use crate::ui::basic::coordinate::Size;
+    use crate::ui::view::ViewId;
+    use crate::ui::Viewable;
+
+    /// An overlay that's only good for hit testing
+    struct Overlay(BoundingBox);
+
+    impl Viewable for Overlay {
+        fn resize(&mut self, _size: Size) {}
+        fn set_anchor(&mut self, _anchor: Vec2i) {}
+        fn bounding_box(&self) -> BoundingBox {
+            self.0.clone()
+        }
+        fn mouse_clicked(&mut self, _screen_coordinate: Vec2i) {}
+        fn mouse_dragged(&mut self, _begin_coordinate: Vec2i, _current_coordinated: Vec2i, _anchor: Option<Index>) {}
+    }
+
+    #[test]
+    fn view_number_maps_to_visible_views_only() {
+        let visibility = [true, false, true, true, false];
+        assert_eq!(visible_view_index(visibility.iter().copied(), 1), Some(0));
+        assert_eq!(visible_view_index(visibility.iter().copied(), 2), Some(2));
+        assert_eq!(visible_view_index(visibility.iter().copied(), 3), Some(3));
+        assert_eq!(visible_view_index(visibility.iter().copied(), 4), None);
+        assert_eq!(visible_view_index(visibility.iter().copied(), 0), None);
+    }
+
+    #[test]
+    fn view_number_without_visible_views() {
+        let visibility = [false, false];
+        assert_eq!(visible_view_index(visibility.iter().copied(), 1), None);
+        assert_eq!(visible_view_index(std::iter::empty(), 1), None);
+    }
+
+    #[test]
+    fn close_others_leaves_pinned_views_open() {
+        // (id, pinned, pristine)
+        let views = [
+            (ViewId(1), false, true),
+            (ViewId(2), true, true),
+            (ViewId(3), false, true),
+            (ViewId(4), false, false),
+            (ViewId(5), true, false),
+        ];
+        assert_eq!(views_to_close(views.iter().copied(), Some(ViewId(3))), vec![ViewId(1)]);
+        assert_eq!(views_to_close(views.iter().copied(), None), vec![ViewId(1), ViewId(3)]);
+    }
+
+    #[test]
+    fn cycling_focus_skips_hidden_views_and_wraps_around() {
+        let views = [(ViewId(1), true), (ViewId(2), false), (ViewId(3), true)];
+        assert_eq!(next_in_focus_cycle(views.iter().copied(), ViewId(1)), Some(ViewId(3)));
+        assert_eq!(next_in_focus_cycle(views.iter().copied(), ViewId(3)), Some(ViewId(1)));
+        // a hidden view isn't in the cycle, so there's no next view to go to from it
+        assert_eq!(next_in_focus_cycle(views.iter().copied(), ViewId(2)), None);
+        assert_eq!(next_in_focus_cycle([(ViewId(1), true)].iter().copied(), ViewId(1)), Some(ViewId(1)));
+    }
+
+    #[test]
+    fn window_title_shows_the_file_and_unsaved_changes() {
+        let file = std::path::Path::new("src/app.rs");
+        assert_eq!(window_title(Some(file), false), "src/app.rs \u{2014} cxg");
+        assert_eq!(window_title(Some(file), true), "* src/app.rs \u{2014} cxg");
+        assert_eq!(window_title(None, false), "untitled \u{2014} cxg");
+        assert_eq!(window_title(None, true), "* untitled \u{2014} cxg");
+    }
+
+    #[test]
+    fn clicks_on_a_visible_input_box_do_not_reach_views() {
+        let input_box: &dyn Viewable = &Overlay(BoundingBox::new(Vec2i::new(100, 300), Vec2i::new(600, 700)));
+        let inside = Vec2i::new(200, 500);
+        assert!(covered_by_overlay(&[(true, input_box)], inside));
+        assert!(!covered_by_overlay(&[(false, input_box)], inside), "hidden overlays let clicks through");
+        assert!(!covered_by_overlay(&[(true, input_box)], Vec2i::new(50, 500)));
+    }
+}
@@ -0,0 +1,54 @@
+use std::path::PathBuf;
+
+/// Files to open, given as command line arguments. A file may be followed by `:line` (1-based) to start out on that line,
+/// like `cxgledit src/app.rs:42 src/main.rs`
+pub fn parse_file_arguments<I: IntoIterator<Item = String>>(args: I) -> Vec<(PathBuf, Option<usize>)> {
+    args.into_iter()
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| match arg.rfind(':') {
+            Some(pos) if pos > 0 => match arg[pos + 1..].parse::<usize>() {
+                Ok(line) => (PathBuf::from(&arg[..pos]), Some(line)),
+                Err(_) => (PathBuf::from(arg), None),
+            },
+            _ => (PathBuf::from(arg), None),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod args_tests {
+    use super::parse_file_arguments;
+    use std::path::PathBuf;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn files_with_and_without_lines() {
+        let files = parse_file_arguments(args(&["src/app.rs:42", "src/main.rs", "notes.txt:1"]));
+        assert_eq!(
+            files,
+            vec![
+                (PathBuf::from("src/app.rs"), Some(42)),
+                (PathBuf::from("src/main.rs"), None),
+                (PathBuf::from("notes.txt"), Some(1))
+            ]
+        );
+        assert!(parse_file_arguments(args(&[])).is_empty());
+    }
+
+    #[test]
+    fn colons_that_are_not_line_suffixes() {
+        let files = parse_file_arguments(args(&["C:\\code\\main.rs", "file:", ":12", "a:b:7"]));
+        assert_eq!(
+            files,
+            vec![
+                (PathBuf::from("C:\\code\\main.rs"), None),
+                (PathBuf::from("file:"), None),
+                (PathBuf::from(":12"), None),
+                (PathBuf::from("a:b"), Some(7))
+            ]
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use super::shell::shell_command;
+use crate::textbuffer::contiguous::contiguous::ContiguousBuffer;
+
+use std::io::Write;
+use std::path::Path;
+use std::process::Stdio;
+use std::thread;
+
+/// The formatter the Format document command runs, for files with each extension. A formatter reads the text from stdin, and writes
+/// the formatted text to stdout
+pub const FORMATTERS: &[(&str, &str)] = &[
+    ("rs", "rustfmt --edition 2018"),
+    ("c", "clang-format"),
+    ("h", "clang-format"),
+    ("cpp", "clang-format"),
+    ("hpp", "clang-format"),
+    ("go", "gofmt"),
+];
+
+/// The formatter for file, going by its extension
+pub fn formatter_for(file: &Path) -> Option<&'static str> {
+    let extension = file.extension()?.to_str()?;
+    FORMATTERS.iter().find(|(ext, _)| *ext == extension).map(|(_, formatter)| *formatter)
+}
+
+/// Runs command_line through the shell, with input written to its stdin. Returns what it wrote to stdout, if it succeeded, or else
+/// what it wrote to stderr, or why it couldn't be run
+pub fn pipe_through(command_line: &str, input: &str) -> Result<String, String> {
+    let mut child = shell_command(command_line)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("failed to run {}: {}", command_line, err))?;
+    // written from a thread of its own, or a command that writes out before it has read all of its input, could block both ends
+    let mut stdin = child.stdin.take().unwrap();
+    let input = input.to_string();
+    let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child.wait_with_output().map_err(|err| err.to_string())?;
+    let _ = writer.join();
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim_end().to_string();
+        return Err(match output.status.code() {
+            _ if !stderr.is_empty() => stderr,
+            Some(code) => format!("{} exited with code {}", command_line, code),
+            None => format!("{} was terminated by a signal", command_line),
+        });
+    }
+    String::from_utf8(output.stdout).map_err(|_| format!("{} wrote output that isn't UTF-8", command_line))
+}
+
+/// Replaces the text of buffer with what formatter makes of it, as one step in the history, keeping the cursor where it was as
+/// best as it can. If formatter fails, the buffer is left as it was. Returns whether the text changed
+pub fn format_buffer(buffer: &mut ContiguousBuffer, formatter: &str) -> Result<bool, String> {
+    let text: String = buffer.iter().collect();
+    let formatted: Vec<char> = pipe_through(formatter, &text)?.chars().collect();
+    Ok(buffer.replace_all_content(&formatted))
+}
+
+#[cfg(all(test, unix))]
+mod format_tests {
+    use super::format_buffer;
+    use crate::textbuffer::{contiguous::contiguous::ContiguousBuffer, metadata::Index, CharBuffer};
+
+    #[test]
+    fn formatted_output_replaces_the_text() {
+        let text = |b: &ContiguousBuffer| b.iter().collect::<String>();
+        let mut buffer = ContiguousBuffer::new(0, 1024);
+        buffer.insert_slice(&"let a = 1;\nlet b = 2;\n".chars().collect::<Vec<char>>());
+        buffer.cursor_goto(Index(14));
+        assert_eq!(format_buffer(&mut buffer, "tr '[:lower:]' '[:upper:]'"), Ok(true));
+        assert_eq!(text(&buffer), "LET A = 1;\nLET B = 2;\n");
+        assert_eq!(buffer.cursor_abs(), Index(14));
+        assert_eq!(format_buffer(&mut buffer, "cat"), Ok(false), "already formatted");
+        buffer.undo();
+        assert_eq!(text(&buffer), "let a = 1;\nlet b = 2;\n");
+    }
+
+    #[test]
+    fn a_failing_formatter_leaves_the_buffer_be() {
+        let mut buffer = ContiguousBuffer::new(0, 1024);
+        buffer.insert_slice(&"fn main( {}\n".chars().collect::<Vec<char>>());
+        assert_eq!(format_buffer(&mut buffer, "cat > /dev/null; echo 'expected `)`' >&2; exit 1"), Err("expected `)`".to_string()));
+        assert_eq!(format_buffer(&mut buffer, "cat > /dev/null; exit 3"), Err("cat > /dev/null; exit 3 exited with code 3".to_string()));
+        assert_eq!(buffer.iter().collect::<String>(), "fn main( {}\n");
+    }
+}
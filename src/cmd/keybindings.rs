@@ -5,7 +5,10 @@ use crate::{
     textbuffer::{operations::LineOperation, Movement, TextKind},
     // AppAction, InputboxAction and ViewAction are currently the three contexts which can receive keyboard input (at least configurable
     // keyboard input)
-    ui::eventhandling::event::{AppAction, InputboxAction, ViewAction},
+    ui::{
+        eventhandling::event::{AppAction, InputboxAction, ViewAction},
+        panel::Direction,
+    },
 };
 use serde::{de::Visitor, Deserialize, Deserializer, Serialize, Serializer};
 
@@ -277,6 +280,10 @@ pub fn tv_default() -> HashMap<BindingRequirement, TextViewKeyBinding> {
     m.insert(BindingRequirement(K::Up, M::SHIFT), B::held(A::TextSelect(Movement::Backward(TextKind::Line, 1))));
     m.insert(BindingRequirement(K::Down, M::empty()), B::held(A::Movement(Movement::Forward(TextKind::Line, 1))));
     m.insert(BindingRequirement(K::Down, M::SHIFT), B::held(A::TextSelect(Movement::Forward(TextKind::Line, 1))));
+    m.insert(BindingRequirement(K::Up, M::CONTROL), B::held(A::Movement(Movement::Backward(TextKind::Paragraph, 1))));
+    m.insert(BindingRequirement(K::Up, M::CONTROL | M::SHIFT), B::held(A::TextSelect(Movement::Backward(TextKind::Paragraph, 1))));
+    m.insert(BindingRequirement(K::Down, M::CONTROL), B::held(A::Movement(Movement::Forward(TextKind::Paragraph, 1))));
+    m.insert(BindingRequirement(K::Down, M::CONTROL | M::SHIFT), B::held(A::TextSelect(Movement::Forward(TextKind::Paragraph, 1))));
 
     m.insert(BindingRequirement(K::M, M::SHIFT | M::CONTROL), B::press(A::InsertStr("fn main() {\n    println!(\"hello world!\");\n}".into())));
 
@@ -295,6 +302,9 @@ pub fn tv_default() -> HashMap<BindingRequirement, TextViewKeyBinding> {
     m.insert(BindingRequirement(K::End, M::SHIFT | M::CONTROL), B::held(A::TextSelect(Movement::End(TextKind::File))));
 
     m.insert(BindingRequirement(K::F, M::CONTROL), B::press(A::Find));
+    m.insert(BindingRequirement(K::F3, M::empty()), B::held(A::FindNext));
+    m.insert(BindingRequirement(K::F3, M::SHIFT), B::held(A::FindPrevious));
+    m.insert(BindingRequirement(K::F12, M::empty()), B::press(A::GotoDefinition));
     m.insert(BindingRequirement(K::G, M::CONTROL), B::press(A::Goto));
     m.insert(BindingRequirement(K::Delete, M::empty()), B::held(A::Delete(Movement::Forward(TextKind::Char, 1))));
     m.insert(BindingRequirement(K::Delete, M::CONTROL), B::held(A::Delete(Movement::Forward(TextKind::Word, 1))));
@@ -308,6 +318,12 @@ pub fn tv_default() -> HashMap<BindingRequirement, TextViewKeyBinding> {
     m.insert(BindingRequirement(K::Tab, M::empty()), B::press(A::LineOperation(LineOperation::ShiftRight { shift_by: 4 })));
     m.insert(BindingRequirement(K::Tab, M::SHIFT), B::press(A::LineOperation(LineOperation::ShiftLeft { shift_by: 4 })));
 
+    m.insert(BindingRequirement(K::D, M::CONTROL | M::SHIFT), B::press(A::DuplicateSelection));
+    m.insert(BindingRequirement(K::Up, M::ALT), B::press(A::ExpandSelection));
+    m.insert(BindingRequirement(K::Down, M::ALT), B::press(A::ShrinkSelection));
+    m.insert(BindingRequirement(K::B, M::CONTROL), B::press(A::SelectInsideBlock));
+    m.insert(BindingRequirement(K::B, M::CONTROL | M::SHIFT), B::press(A::SelectAroundBlock));
+
     m.insert(BindingRequirement(K::Z, M::CONTROL), B::held(A::Undo));
     m.insert(BindingRequirement(K::Z, M::CONTROL | M::SHIFT), B::held(A::Redo));
     m
@@ -362,15 +378,45 @@ pub fn app_default() -> HashMap<BindingRequirement, AppBinding> {
     map.insert(BindingRequirement(K::Escape, M::empty()), B::press(A::Cancel));
     map.insert(BindingRequirement(K::O, M::CONTROL), B::press(A::OpenFile));
     map.insert(BindingRequirement(K::I, M::CONTROL | M::SHIFT), B::press(A::OpenFile));
+    map.insert(BindingRequirement(K::O, M::CONTROL | M::SHIFT), B::press(A::RevealInFileFinder));
     map.insert(BindingRequirement(K::S, M::CONTROL), B::press(A::SaveFile));
     map.insert(BindingRequirement(K::F, M::CONTROL | M::SHIFT), B::press(A::SearchInFiles));
     map.insert(BindingRequirement(K::G, M::CONTROL | M::SHIFT), B::press(A::GotoLineInFile));
     map.insert(BindingRequirement(K::Tab, M::CONTROL), B::press(A::CycleFocus));
+    map.insert(BindingRequirement(K::GraveAccent, M::CONTROL), B::press(A::CycleRecentViews));
+    let view_number_keys = [
+        K::Num1,
+        K::Num2,
+        K::Num3,
+        K::Num4,
+        K::Num5,
+        K::Num6,
+        K::Num7,
+        K::Num8,
+        K::Num9,
+    ];
+    for (number, key) in view_number_keys.iter().enumerate() {
+        map.insert(BindingRequirement(*key, M::ALT), B::press(A::FocusView(number + 1)));
+    }
     map.insert(BindingRequirement(K::D, M::CONTROL), B::press(A::ShowDebugInterface));
     map.insert(BindingRequirement(K::W, M::CONTROL), B::press(A::CloseActiveView(false)));
     map.insert(BindingRequirement(K::W, M::CONTROL | M::SHIFT), B::press(A::CloseActiveView(true)));
     map.insert(BindingRequirement(K::Q, M::CONTROL), B::press(A::Quit));
     map.insert(BindingRequirement(K::N, M::CONTROL), B::press(A::OpenNewView));
     map.insert(BindingRequirement(K::P, M::CONTROL | M::SHIFT), B::press(A::ListCommands));
+    map.insert(BindingRequirement(K::R, M::CONTROL | M::SHIFT), B::press(A::ToggleReadOnly));
+    map.insert(BindingRequirement(K::D, M::CONTROL | M::ALT), B::press(A::DetachActiveView));
+    map.insert(BindingRequirement(K::Left, M::ALT), B::press(A::JumpBack));
+    map.insert(BindingRequirement(K::Right, M::ALT), B::press(A::JumpForward));
+    let arrows = [
+        (K::Left, Direction::Left),
+        (K::Right, Direction::Right),
+        (K::Up, Direction::Up),
+        (K::Down, Direction::Down),
+    ];
+    for (key, direction) in arrows.iter() {
+        map.insert(BindingRequirement(*key, M::CONTROL | M::ALT), B::press(A::MoveActiveView(*direction)));
+        map.insert(BindingRequirement(*key, M::CONTROL | M::ALT | M::SHIFT), B::held(A::ResizeActiveView(*direction)));
+    }
     map
 }
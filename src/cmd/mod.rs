@@ -1,8 +1,20 @@
+pub mod args;
+pub mod format;
 pub mod keybindings;
 #[rustfmt::skip]
 pub mod keyimpl;
+pub mod related;
+pub mod reveal;
+pub mod shell;
+pub mod snippets;
 pub mod translation;
 
+use crate::{
+    textbuffer::operations::CaseMode,
+    ui::{clipboard::CopyFormat, view_options::ViewOption},
+    utils::smart_case_sensitive,
+};
+
 // todo(feature): add SymbolList, for when we want to Go to Symbol
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum CommandTag {
@@ -11,6 +23,44 @@ pub enum CommandTag {
     Find,
     OpenFile,
     SaveFile,
+    ToggleReadOnly,
+    InsertSnippet,
+    InsertFile,
+    SortLines,
+    RemoveDuplicateLines,
+    NextChange,
+    PreviousChange,
+    RunShell,
+    TogglePinned,
+    CloseOtherViews,
+    CloseAllViews,
+    ChangeCase(CaseMode),
+    TrimSelection,
+    /// Rewrap the paragraph at the cursor, to the first ruler of the view
+    Reflow,
+    /// Run the text of the active view through the formatter for its file type, see format::FORMATTERS
+    Format,
+    /// Choose between symbols, listed by the application, see InputBox::set_choices
+    GotoSymbol,
+    SwitchBuffer,
+    /// Switch between the active file and its companion; the file related to it, like a header to its source, see
+    /// related::RELATED_FILES. Listed as "Toggle companion", since "Toggle related" would turn up among the matches of "gt" (go to)
+    ToggleRelated,
+    /// Copy the selection, or the line of the cursor, dressed up for pasting outside the editor
+    CopyAs(CopyFormat),
+    /// Show the file of the active view in the file manager
+    ShowInFolder,
+    /// Copy the absolute path of the file of the active view
+    CopyPath,
+    SetViewColor,
+    AutoSave,
+    DeleteLimit,
+    ScrollBarWidth,
+    SetMark,
+    JumpToMark,
+    ToggleViewOption(ViewOption),
+    /// Makes the display options of the active view, those that views opened from then on get
+    ApplyOptionsToNewViews,
 }
 
 pub const COMMAND_NAMES: &[(&'static str, &'static CommandTag)] = &[
@@ -19,6 +69,44 @@ pub const COMMAND_NAMES: &[(&'static str, &'static CommandTag)] = &[
     ("FIND", &CommandTag::Find),
     ("OPENFILE", &CommandTag::OpenFile),
     ("SAVEFILE", &CommandTag::SaveFile),
+    ("TOGGLEREADONLY", &CommandTag::ToggleReadOnly),
+    ("INSERTSNIPPET", &CommandTag::InsertSnippet),
+    ("INSERTFILE", &CommandTag::InsertFile),
+    ("SORTLINES", &CommandTag::SortLines),
+    ("REMOVEDUPLICATELINES", &CommandTag::RemoveDuplicateLines),
+    ("NEXTCHANGE", &CommandTag::NextChange),
+    ("PREVIOUSCHANGE", &CommandTag::PreviousChange),
+    ("RUNSHELL", &CommandTag::RunShell),
+    ("TOGGLEPINNED", &CommandTag::TogglePinned),
+    ("CLOSEOTHERVIEWS", &CommandTag::CloseOtherViews),
+    ("CLOSEALLVIEWS", &CommandTag::CloseAllViews),
+    ("UPPERCASE", &CommandTag::ChangeCase(CaseMode::Upper)),
+    ("LOWERCASE", &CommandTag::ChangeCase(CaseMode::Lower)),
+    ("TITLECASE", &CommandTag::ChangeCase(CaseMode::Title)),
+    ("TOGGLECASE", &CommandTag::ChangeCase(CaseMode::Toggle)),
+    ("TRIMSELECTION", &CommandTag::TrimSelection),
+    ("WRAPPARAGRAPH", &CommandTag::Reflow),
+    ("FORMATDOCUMENT", &CommandTag::Format),
+    ("SWITCHBUFFER", &CommandTag::SwitchBuffer),
+    ("TOGGLECOMPANION", &CommandTag::ToggleRelated),
+    ("COPYWITHLINENUMBERS", &CommandTag::CopyAs(CopyFormat::LineNumbers)),
+    ("COPYASCODEBLOCK", &CommandTag::CopyAs(CopyFormat::CodeBlock)),
+    ("SHOWINFOLDER", &CommandTag::ShowInFolder),
+    ("COPYPATH", &CommandTag::CopyPath),
+    ("SETVIEWCOLOR", &CommandTag::SetViewColor),
+    ("AUTOSAVE", &CommandTag::AutoSave),
+    ("DELETELIMIT", &CommandTag::DeleteLimit),
+    ("SCROLLBARWIDTH", &CommandTag::ScrollBarWidth),
+    ("SETMARK", &CommandTag::SetMark),
+    ("JUMPTOMARK", &CommandTag::JumpToMark),
+    ("SHOWWHITESPACE", &CommandTag::ToggleViewOption(ViewOption::Whitespace)),
+    ("SHOWINDENTGUIDES", &CommandTag::ToggleViewOption(ViewOption::IndentGuides)),
+    ("SHOWCHANGEMARKERS", &CommandTag::ToggleViewOption(ViewOption::ChangeMarkers)),
+    ("SHOWCURSORLINE", &CommandTag::ToggleViewOption(ViewOption::CurrentLine)),
+    ("SHOWWORDMATCHES", &CommandTag::ToggleViewOption(ViewOption::WordMatches)),
+    ("AUTOHIDESCROLLBAR", &CommandTag::ToggleViewOption(ViewOption::AutoHideScrollBar)),
+    ("SHOWBACKGROUNDLOGO", &CommandTag::ToggleViewOption(ViewOption::EmptyBackground)),
+    ("APPLYOPTIONSTONEWVIEWS", &CommandTag::ApplyOptionsToNewViews),
 ];
 
 impl CommandTag {
@@ -29,6 +117,35 @@ impl CommandTag {
             CommandTag::GotoInFile => "Insert file:line to go to:",
             CommandTag::OpenFile => "Open file:",
             CommandTag::SaveFile => "Save file:",
+            CommandTag::ToggleReadOnly => "Toggle read only state of the active view",
+            CommandTag::InsertSnippet => "Insert snippet:",
+            CommandTag::InsertFile => "Insert the contents of file:",
+            CommandTag::SortLines => "Sort the selected lines, or all lines of the active view",
+            CommandTag::RemoveDuplicateLines => "Remove repeated lines from the selection, or from all lines of the active view",
+            CommandTag::NextChange => "Go to the next edited region of the active view",
+            CommandTag::PreviousChange => "Go to the previous edited region of the active view",
+            CommandTag::RunShell => "Shell command to run:",
+            CommandTag::TogglePinned => "Pin or unpin the active view, pinned views are not closed by close other/all views",
+            CommandTag::CloseOtherViews => "Close all views, except the active one and pinned views",
+            CommandTag::CloseAllViews => "Close all views, except pinned views",
+            CommandTag::ChangeCase(_) => "Change the case of the selection, or of the word at the cursor",
+            CommandTag::TrimSelection => "Shrink the selection to leave out surrounding whitespace",
+            CommandTag::Reflow => "Rewrap the paragraph at the cursor to the ruler column",
+            CommandTag::Format => "Format the active view with the formatter for its file type",
+            CommandTag::GotoSymbol => "Go to definition:",
+            CommandTag::SwitchBuffer => "Switch to buffer:",
+            CommandTag::ToggleRelated => "Switch to related file:",
+            CommandTag::CopyAs(_) => "Copy the selection, or the line of the cursor, for pasting into a chat or an issue",
+            CommandTag::ShowInFolder => "Show the file of the active view in the file manager",
+            CommandTag::CopyPath => "Copy the absolute path of the file of the active view",
+            CommandTag::SetViewColor => "Background color of the active view, as #rrggbb (leave empty for the default):",
+            CommandTag::AutoSave => "Seconds without typing, after which files with changes are saved (leave empty or off to turn off):",
+            CommandTag::DeleteLimit => "Characters (or N lines) a delete may remove without asking first (leave empty or off to never ask):",
+            CommandTag::ScrollBarWidth => "Width of the scroll bar of the active view, in pixels:",
+            CommandTag::SetMark => "Name the cursor position, with a character:",
+            CommandTag::JumpToMark => "Jump to the position named:",
+            CommandTag::ToggleViewOption(_) => "Turn a display option of the active view on or off",
+            CommandTag::ApplyOptionsToNewViews => "Open new views with the display options of the active view",
         }
     }
 
@@ -39,19 +156,90 @@ impl CommandTag {
             CommandTag::Find => "Find",
             CommandTag::OpenFile => "Open file",
             CommandTag::SaveFile => "Save file",
+            CommandTag::ToggleReadOnly => "Toggle read only",
+            CommandTag::InsertSnippet => "Insert snippet",
+            CommandTag::InsertFile => "Insert file",
+            CommandTag::SortLines => "Sort lines",
+            CommandTag::RemoveDuplicateLines => "Remove duplicate lines",
+            CommandTag::NextChange => "Next change",
+            CommandTag::PreviousChange => "Previous change",
+            CommandTag::RunShell => "Run shell command",
+            CommandTag::TogglePinned => "Toggle pinned",
+            CommandTag::CloseOtherViews => "Close other views",
+            CommandTag::CloseAllViews => "Close all views",
+            CommandTag::ChangeCase(CaseMode::Upper) => "Upper case",
+            CommandTag::ChangeCase(CaseMode::Lower) => "Lower case",
+            CommandTag::ChangeCase(CaseMode::Title) => "Title case",
+            CommandTag::ChangeCase(CaseMode::Toggle) => "Toggle case",
+            CommandTag::TrimSelection => "Trim selection",
+            CommandTag::Reflow => "Wrap paragraph",
+            CommandTag::Format => "Format document",
+            CommandTag::GotoSymbol => "Go to symbol",
+            CommandTag::SwitchBuffer => "Switch buffer",
+            CommandTag::ToggleRelated => "Toggle companion",
+            CommandTag::CopyAs(CopyFormat::LineNumbers) => "Copy with line numbers",
+            CommandTag::CopyAs(CopyFormat::CodeBlock) => "Copy as code block",
+            CommandTag::ShowInFolder => "Show in folder",
+            CommandTag::CopyPath => "Copy path",
+            CommandTag::SetViewColor => "Set view color",
+            CommandTag::AutoSave => "Auto save",
+            CommandTag::DeleteLimit => "Delete limit",
+            CommandTag::ScrollBarWidth => "Scroll bar width",
+            CommandTag::SetMark => "Set mark",
+            CommandTag::JumpToMark => "Jump to mark",
+            CommandTag::ToggleViewOption(ViewOption::Whitespace) => "Show whitespace",
+            CommandTag::ToggleViewOption(ViewOption::IndentGuides) => "Show indent guides",
+            CommandTag::ToggleViewOption(ViewOption::ChangeMarkers) => "Show change markers",
+            CommandTag::ToggleViewOption(ViewOption::CurrentLine) => "Show cursor line",
+            CommandTag::ToggleViewOption(ViewOption::WordMatches) => "Show word matches",
+            CommandTag::ToggleViewOption(ViewOption::AutoHideScrollBar) => "Auto hide scroll bar",
+            CommandTag::ToggleViewOption(ViewOption::EmptyBackground) => "Show background logo",
+            CommandTag::ApplyOptionsToNewViews => "Apply options to new views",
         }
     }
+
+    /// Whether the command needs input (text, or a choice from a list) after being selected from the command list. Those that
+    /// don't are run right away; switching to a related file only asks which one, when there are several
+    pub fn takes_input(&self) -> bool {
+        matches!(
+            self,
+            CommandTag::Goto
+                | CommandTag::GotoInFile
+                | CommandTag::Find
+                | CommandTag::OpenFile
+                | CommandTag::SaveFile
+                | CommandTag::InsertSnippet
+                | CommandTag::InsertFile
+                | CommandTag::RunShell
+                | CommandTag::GotoSymbol
+                | CommandTag::SwitchBuffer
+                | CommandTag::SetViewColor
+                | CommandTag::AutoSave
+                | CommandTag::DeleteLimit
+                | CommandTag::ScrollBarWidth
+                | CommandTag::SetMark
+                | CommandTag::JumpToMark
+        )
+    }
 }
 
 /// Matches user input against existing commands based on a rank search
 pub fn commands_matching(input: &str) -> Option<Vec<&CommandTag>> {
     let mut result = Vec::with_capacity(COMMAND_NAMES.len());
 
+    // smart case; the all caps command identifiers carry no case, so case sensitive input is matched against the displayed names instead
+    let case_sensitive = smart_case_sensitive(input, true);
+    let needle = if case_sensitive { input.to_string() } else { input.to_uppercase() };
     for (cmd_name, tag) in COMMAND_NAMES {
+        let cmd_name: String = if case_sensitive {
+            CommandTag::name(**tag).chars().filter(|c| !c.is_whitespace()).collect()
+        } else {
+            cmd_name.to_string()
+        };
         if input.len() <= cmd_name.len() {
             let mut current_pos = 0;
             let mut matched = false;
-            for c in input.to_uppercase().chars().filter(|c| !c.is_whitespace()) {
+            for c in needle.chars().filter(|c| !c.is_whitespace()) {
                 if let Some(p) = cmd_name[current_pos..].find(c) {
                     current_pos = p;
                     matched = true;
@@ -98,8 +286,8 @@ pub mod command_tests {
 
         assert_eq!(gmatches.len(), 2, "Length did not match!");
         assert_eq!(gmatches2.len(), 1, "Length did not match!");
-        assert_eq!(fmatches.len(), 2, "Length did not match!");
-        assert_eq!(fi_matches.len(), 4, "Length did not match!");
+        assert_eq!(fmatches.len(), 3, "Length did not match!");
+        assert_eq!(fi_matches.len(), 5, "Length did not match!");
 
         // gt matches against Go To and Go To in file
         assert!(gmatches.contains(&&CommandTag::Goto), "Go to was not found in result");
@@ -108,14 +296,26 @@ pub mod command_tests {
         // but gtf only matches against Go To in File
         assert!(gmatches2.contains(&&CommandTag::GotoInFile), "Go to in File was not found in result!");
 
-        // ef matches against opEn File and savE File
+        // ef matches against opEn File, savE File and insErt File
         assert!(fmatches.contains(&&CommandTag::SaveFile), "Save File was not found in result!");
         assert!(fmatches.contains(&&CommandTag::OpenFile), "Open File was not found in result!");
+        assert!(fmatches.contains(&&CommandTag::InsertFile), "Insert File was not found in result!");
 
-        // fi matches against open FIle, save FIle, go to in FIle and FInd
+        // fi matches against open FIle, save FIle, go to in FIle, insert FIle and FInd
         assert!(fi_matches.contains(&&CommandTag::Find), "Save File was not found in result!");
         assert!(fi_matches.contains(&&CommandTag::OpenFile), "Open File was not found in result!");
         assert!(fi_matches.contains(&&CommandTag::SaveFile), "Save File was not found in result!");
         assert!(fi_matches.contains(&&CommandTag::GotoInFile), "Open File was not found in result!");
+        assert!(fi_matches.contains(&&CommandTag::InsertFile), "Insert File was not found in result!");
+    }
+
+    #[test]
+    fn smart_case_matches() {
+        // lower case input ignores case, upper case input is matched against the displayed name, case sensitively
+        let insensitive = commands_matching("fi").unwrap();
+        let sensitive = commands_matching("Fi").unwrap();
+        assert_eq!(insensitive.len(), 5, "Length did not match!");
+        assert_eq!(sensitive.len(), 1, "Length did not match!");
+        assert!(sensitive.contains(&&CommandTag::Find), "Find was not found in result");
     }
 }
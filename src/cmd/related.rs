@@ -0,0 +1,93 @@
+use std::path::{Path, PathBuf};
+
+/// Endings of file names that go together, like those of a header and its source. A file whose name ends with the first of a
+/// pair, is related to the files in its directory that are named the same, but end with one of the second instead
+pub const RELATED_FILES: &[(&str, &[&str])] = &[
+    (".h", &[".c", ".cpp", ".cc"]),
+    (".hpp", &[".cpp", ".cc"]),
+    (".c", &[".h"]),
+    (".cpp", &[".hpp", ".h"]),
+    (".cc", &[".hpp", ".h"]),
+    (".rs", &["_tests.rs"]),
+    ("_tests.rs", &[".rs"]),
+    (".vs.glsl", &[".fs.glsl"]),
+    (".fs.glsl", &[".vs.glsl"]),
+];
+
+/// Finds the files related to a file, going by the endings of their names, as they're paired up in RELATED_FILES
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelatedFiles {
+    pairs: Vec<(String, Vec<String>)>,
+}
+
+impl Default for RelatedFiles {
+    fn default() -> Self {
+        RelatedFiles::new(RELATED_FILES)
+    }
+}
+
+impl RelatedFiles {
+    fn new(pairs: &[(&str, &[&str])]) -> RelatedFiles {
+        let pairs = pairs
+            .iter()
+            .map(|(ending, related)| (ending.to_string(), related.iter().map(|r| r.to_string()).collect()))
+            .collect();
+        RelatedFiles { pairs }
+    }
+
+    /// The paths the files related to file would have, whether they exist or not, in the order they're paired up in. Where more
+    /// than one ending matches the name of file, the longest one decides
+    pub fn candidates(&self, file: &Path) -> Vec<PathBuf> {
+        let name = match file.file_name().and_then(|name| name.to_str()) {
+            Some(name) => name,
+            None => return vec![],
+        };
+        self.pairs
+            .iter()
+            .filter(|(ending, _)| name.len() > ending.len() && name.ends_with(ending.as_str()))
+            .max_by_key(|(ending, _)| ending.len())
+            .map_or(vec![], |(ending, related)| {
+                let stem = &name[..name.len() - ending.len()];
+                related.iter().map(|r| file.with_file_name(format!("{}{}", stem, r))).collect()
+            })
+    }
+
+    /// The files related to file, that exist
+    pub fn existing(&self, file: &Path) -> Vec<PathBuf> {
+        self.candidates(file).into_iter().filter(|candidate| candidate.is_file()).collect()
+    }
+}
+
+#[cfg(test)]
+mod related_tests {
+    use super::RelatedFiles;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn candidates_are_named_like_the_file() {
+        let related = RelatedFiles::default();
+        let candidates = |file: &str| related.candidates(Path::new(file));
+        assert_eq!(
+            candidates("src/list.h"),
+            vec![
+                PathBuf::from("src/list.c"),
+                "src/list.cpp".into(),
+                "src/list.cc".into()
+            ]
+        );
+        assert_eq!(candidates("list.cpp"), vec![PathBuf::from("list.hpp"), "list.h".into()]);
+        assert_eq!(candidates("src/app.rs"), vec![PathBuf::from("src/app_tests.rs")]);
+        assert_eq!(candidates("src/app_tests.rs"), vec![PathBuf::from("src/app.rs")], "the longest ending decides");
+        assert_eq!(candidates("assets/text.vs.glsl"), vec![PathBuf::from("assets/text.fs.glsl")]);
+        assert_eq!(candidates("README.md"), Vec::<PathBuf>::new());
+        assert_eq!(candidates(".h"), Vec::<PathBuf>::new(), "a name that is all ending");
+    }
+
+    #[test]
+    fn only_the_given_pairs_relate_files() {
+        let related = RelatedFiles::new(&[(".ts", &[".spec.ts", ".css"]), (".spec.ts", &[".ts"])]);
+        assert_eq!(related.candidates(Path::new("app/view.ts")), vec![PathBuf::from("app/view.spec.ts"), "app/view.css".into()]);
+        assert_eq!(related.candidates(Path::new("app/view.spec.ts")), vec![PathBuf::from("app/view.ts")]);
+        assert_eq!(related.candidates(Path::new("list.h")), Vec::<PathBuf>::new());
+    }
+}
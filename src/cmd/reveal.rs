@@ -0,0 +1,78 @@
+use std::ffi::OsString;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use std::thread;
+
+/// file, made absolute by joining it onto working_dir, if it's relative, with the . and .. in it resolved. Symbolic links are
+/// left as they are, and the file doesn't have to exist
+pub fn absolute_path(file: &Path, working_dir: &Path) -> PathBuf {
+    let mut absolute = PathBuf::new();
+    for component in working_dir.join(file).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                absolute.pop();
+            }
+            component => absolute.push(component.as_os_str()),
+        }
+    }
+    absolute
+}
+
+/// The program, and the arguments to it, that show file in the file manager of os (as in std::env::consts::OS). Where the file
+/// manager can't be asked to select the file, the folder it's in is opened
+pub fn reveal_command(os: &str, file: &Path) -> (&'static str, Vec<OsString>) {
+    match os {
+        "macos" => ("open", vec!["-R".into(), file.into()]),
+        "windows" => {
+            let mut select = OsString::from("/select,");
+            select.push(file);
+            ("explorer", vec![select])
+        }
+        _ => {
+            let folder = file
+                .parent()
+                .filter(|folder| !folder.as_os_str().is_empty())
+                .unwrap_or(Path::new("."));
+            ("xdg-open", vec![folder.into()])
+        }
+    }
+}
+
+/// Shows file in the file manager, without waiting for it
+pub fn reveal(file: &Path) -> Result<(), String> {
+    let (program, args) = reveal_command(std::env::consts::OS, file);
+    let mut child = Command::new(program)
+        .args(&args)
+        .spawn()
+        .map_err(|err| format!("failed to run {}: {}", program, err))?;
+    // waited for off the UI thread, so that it doesn't linger as a zombie once the file manager is up
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[cfg(test)]
+mod reveal_tests {
+    use super::{absolute_path, reveal_command};
+    use std::ffi::OsString;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn relative_paths_are_made_absolute() {
+        let working_dir = Path::new("/home/user/cxgledit");
+        assert_eq!(absolute_path(Path::new("src/app.rs"), working_dir), PathBuf::from("/home/user/cxgledit/src/app.rs"));
+        assert_eq!(absolute_path(Path::new("./src/../README.md"), working_dir), PathBuf::from("/home/user/cxgledit/README.md"));
+        assert_eq!(absolute_path(Path::new("../notes.txt"), working_dir), PathBuf::from("/home/user/notes.txt"));
+        assert_eq!(absolute_path(Path::new("/etc/hosts"), working_dir), PathBuf::from("/etc/hosts"));
+    }
+
+    #[test]
+    fn each_platform_has_its_file_manager() {
+        let file = Path::new("/home/user/cxgledit/src/app.rs");
+        let args = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+        assert_eq!(reveal_command("macos", file), ("open", args(&["-R", "/home/user/cxgledit/src/app.rs"])));
+        assert_eq!(reveal_command("windows", file), ("explorer", args(&["/select,/home/user/cxgledit/src/app.rs"])));
+        assert_eq!(reveal_command("linux", file), ("xdg-open", args(&["/home/user/cxgledit/src"])));
+        assert_eq!(reveal_command("freebsd", Path::new("app.rs")), ("xdg-open", args(&["."])));
+    }
+}
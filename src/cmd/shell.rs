@@ -0,0 +1,218 @@
+use crate::textbuffer::contiguous::contiguous::ContiguousBuffer;
+
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+
+/// The most output lines taken from a running command each frame, so that a command spewing output can't stall the UI thread
+pub const MAX_LINES_PER_POLL: usize = 1024;
+
+/// What a shell command, running on its own thread, reports back to the UI thread
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ShellOutput {
+    /// A line of output, from either stdout or stderr, without its line break
+    Line(String),
+    /// The command could not be started
+    Failed(String),
+    /// The command has exited, with its exit code, if it had one
+    Finished(Option<i32>),
+}
+
+/// A shell command running off the UI thread. Its output is streamed back and picked up with poll. Dropping the job stops the
+/// command, if it's still running
+pub struct ShellJob {
+    pub command_line: String,
+    receiver: Receiver<ShellOutput>,
+    /// The process running the command, until it has exited. None for jobs that stream a reader
+    child: Option<Child>,
+    finished: bool,
+}
+
+impl ShellJob {
+    /// Runs command_line through the platform's shell
+    pub fn spawn(command_line: &str) -> ShellJob {
+        let mut command = shell_command(command_line);
+        command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped());
+
+        let (sender, receiver) = mpsc::channel();
+        let child = match command.spawn() {
+            Ok(mut child) => {
+                // the output is read on threads of its own, how the command exited is picked up by poll once all of it is read
+                if let Some(out) = child.stdout.take() {
+                    forward_lines(out, sender.clone());
+                }
+                if let Some(err) = child.stderr.take() {
+                    forward_lines(err, sender.clone());
+                }
+                Some(child)
+            }
+            Err(err) => {
+                let _ = sender.send(ShellOutput::Failed(err.to_string()));
+                None
+            }
+        };
+        ShellJob { command_line: command_line.to_string(), receiver, child, finished: false }
+    }
+
+    /// Streams the lines of reader, as if it was the output of a command named command_line
+    pub fn from_reader<R: Read + Send + 'static>(command_line: &str, reader: R) -> ShellJob {
+        let (sender, receiver) = mpsc::channel();
+        let forwarder = forward_lines(reader, sender.clone());
+        thread::spawn(move || {
+            let _ = forwarder.join();
+            let _ = sender.send(ShellOutput::Finished(Some(0)));
+        });
+        ShellJob { command_line: command_line.to_string(), receiver, child: None, finished: false }
+    }
+
+    /// Takes the output that has arrived since the last poll, at most max_lines of it. Never blocks
+    pub fn poll(&mut self, max_lines: usize) -> Vec<ShellOutput> {
+        let mut output = vec![];
+        while output.len() < max_lines {
+            match self.receiver.try_recv() {
+                Ok(out) => {
+                    if let ShellOutput::Finished(_) | ShellOutput::Failed(_) = out {
+                        self.finished = true;
+                    }
+                    output.push(out);
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    // all of the output has been read, what's left is how the command exited
+                    match self.child.as_mut().map(Child::try_wait) {
+                        // it has closed its output, but not exited yet
+                        Some(Ok(None)) => {}
+                        Some(exited) => {
+                            let code = exited.ok().flatten().and_then(|status| status.code());
+                            output.push(ShellOutput::Finished(code));
+                            self.child = None;
+                            self.finished = true;
+                        }
+                        None => self.finished = true,
+                    }
+                    break;
+                }
+            }
+        }
+        output
+    }
+
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+}
+
+impl Drop for ShellJob {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            // nobody is going to see its output anymore, the view showing it has been closed
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// A command that runs command_line through the platform's shell
+pub fn shell_command(command_line: &str) -> Command {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.arg("/C");
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.arg("-c");
+        command
+    };
+    command.arg(command_line);
+    command
+}
+
+fn forward_lines<R: Read + Send + 'static>(reader: R, sender: Sender<ShellOutput>) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        for line in BufReader::new(reader).lines() {
+            match line {
+                Ok(line) => {
+                    if sender.send(ShellOutput::Line(line)).is_err() {
+                        // nobody is listening anymore, the view showing the output has been closed
+                        break;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+    })
+}
+
+/// The text that output is shown as, in the view of the command
+pub fn output_text(output: &[ShellOutput]) -> String {
+    let mut text = String::new();
+    for out in output {
+        match out {
+            ShellOutput::Line(line) => {
+                text.push_str(line);
+                text.push('\n');
+            }
+            ShellOutput::Failed(err) => text.push_str(&format!("[failed to run command: {}]\n", err)),
+            ShellOutput::Finished(Some(code)) => text.push_str(&format!("[exited with code {}]\n", code)),
+            ShellOutput::Finished(None) => text.push_str("[terminated by signal]\n"),
+        }
+    }
+    text
+}
+
+/// Appends output to the end of buffer, even if it's read only, which the views showing command output are. It's not an edit, so
+/// it's left out of the history. The cursor is left at the end of the buffer
+pub fn append_output(buffer: &mut ContiguousBuffer, output: &[ShellOutput]) {
+    let text: Vec<char> = output_text(output).chars().collect();
+    if !text.is_empty() {
+        buffer.append_unrecorded(&text);
+    }
+}
+
+#[cfg(test)]
+mod shell_tests {
+    use super::{append_output, ShellJob, ShellOutput, MAX_LINES_PER_POLL};
+    use crate::textbuffer::{contiguous::contiguous::ContiguousBuffer, CharBuffer};
+    use std::io::Cursor;
+
+    #[test]
+    fn command_output_is_appended_to_buffer() {
+        let mut job = ShellJob::from_reader("mocked", Cursor::new("first line\nsecond line\n"));
+        let mut buffer = ContiguousBuffer::new(0, 1024);
+        buffer.set_read_only(true);
+        let mut received = vec![];
+        while !job.finished() {
+            let output = job.poll(MAX_LINES_PER_POLL);
+            append_output(&mut buffer, &output);
+            received.extend(output);
+            std::thread::yield_now();
+        }
+        assert_eq!(
+            received,
+            vec![
+                ShellOutput::Line("first line".into()),
+                ShellOutput::Line("second line".into()),
+                ShellOutput::Finished(Some(0))
+            ]
+        );
+        assert_eq!(buffer.data.iter().collect::<String>(), "first line\nsecond line\n[exited with code 0]\n");
+        assert!(buffer.read_only());
+        assert_eq!(buffer.meta_data().line_count(), 4);
+        // the output can't be undone, not even once the view is made writable
+        buffer.set_read_only(false);
+        buffer.undo();
+        assert_eq!(buffer.data.iter().collect::<String>(), "first line\nsecond line\n[exited with code 0]\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn dropping_a_job_stops_its_command() {
+        let mut job = ShellJob::spawn("sleep 10");
+        assert!(job.poll(MAX_LINES_PER_POLL).is_empty());
+        let pid = job.child.as_ref().unwrap().id().to_string();
+        drop(job);
+        let running = std::process::Command::new("kill").args(&["-0", &pid]).status().unwrap();
+        assert!(!running.success(), "the command is still running");
+    }
+}
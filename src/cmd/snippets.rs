@@ -0,0 +1,122 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Where the cursor ends up, after a snippet has been inserted. If a snippet has no placeholder, the cursor is placed after it
+pub const CURSOR_PLACEHOLDER: &str = "$CURSOR";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Snippet {
+    /// Today's date, YYYY-MM-DD (UTC)
+    Date,
+    /// A random (version 4) UUID
+    Uuid,
+    /// Text inserted as is, except for the cursor placeholder
+    Text(&'static str),
+}
+
+// todo(feature): read user defined snippets from the configuration, instead of this map
+pub const SNIPPETS: &[(&'static str, Snippet)] = &[
+    ("date", Snippet::Date),
+    ("uuid", Snippet::Uuid),
+    ("main", Snippet::Text("fn main() {\n    $CURSOR\n}\n")),
+    ("test", Snippet::Text("#[test]\nfn $CURSOR() {\n\n}\n")),
+    ("todo", Snippet::Text("// todo: $CURSOR")),
+];
+
+impl Snippet {
+    pub fn expand(&self) -> String {
+        match self {
+            Snippet::Date => {
+                let days = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() / 86400);
+                let (year, month, day) = civil_from_days(days as i64);
+                format!("{:04}-{:02}-{:02}", year, month, day)
+            }
+            Snippet::Uuid => random_uuid(),
+            Snippet::Text(text) => text.to_string(),
+        }
+    }
+}
+
+pub fn get_snippet(name: &str) -> Option<Snippet> {
+    SNIPPETS.iter().find(|(n, ..)| *n == name).map(|(.., snippet)| *snippet)
+}
+
+/// Names of the snippets that contain input
+pub fn snippets_matching(input: &str) -> Vec<&'static str> {
+    let input = input.to_lowercase();
+    SNIPPETS
+        .iter()
+        .map(|(name, ..)| *name)
+        .filter(|name| name.contains(&input))
+        .collect()
+}
+
+/// Removes the (first) cursor placeholder from an expanded snippet. Returns the text to insert, and the offset in chars into that text,
+/// where the cursor should be placed, if it had a placeholder
+pub fn resolve_cursor_placeholder(expansion: &str) -> (String, Option<usize>) {
+    if let Some(byte_pos) = expansion.find(CURSOR_PLACEHOLDER) {
+        let offset = expansion[..byte_pos].chars().count();
+        (expansion.replacen(CURSOR_PLACEHOLDER, "", 1), Some(offset))
+    } else {
+        (expansion.to_string(), None)
+    }
+}
+
+/// Converts days since 1970-01-01 to a (year, month, day) date. See http://howardhinnant.github.io/date_algorithms.html
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Not cryptographically secure by any means, but random enough for inserting an id into a text
+fn random_uuid() -> String {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos());
+    let state = RandomState::new();
+    let mut hasher = state.build_hasher();
+    hasher.write_u128(nanos);
+    let high = hasher.finish();
+    hasher.write_u64(high);
+    let low = hasher.finish();
+    // version 4, variant 1
+    let high = (high & !0xf000) | 0x4000;
+    let low = (low & !(0xc << 60)) | (0x8 << 60);
+    format!("{:08x}-{:04x}-{:04x}-{:04x}-{:012x}", high >> 32, (high >> 16) & 0xffff, high & 0xffff, low >> 48, low & 0xffff_ffff_ffff)
+}
+
+#[cfg(test)]
+mod snippet_tests {
+    use super::{civil_from_days, get_snippet, resolve_cursor_placeholder, Snippet};
+
+    #[test]
+    fn expand_built_in_snippets() {
+        let date = get_snippet("date").unwrap().expand();
+        assert_eq!(date.len(), "YYYY-MM-DD".len());
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19723), (2024, 1, 1));
+        assert_eq!(civil_from_days(19782), (2024, 2, 29));
+
+        let uuid = get_snippet("uuid").unwrap().expand();
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(uuid.chars().nth(14), Some('4'));
+        assert_eq!(get_snippet("does not exist"), None);
+    }
+
+    #[test]
+    fn cursor_placeholder() {
+        let (text, cursor) = resolve_cursor_placeholder(&Snippet::Text("fn main() {\n    $CURSOR\n}\n").expand());
+        assert_eq!(text, "fn main() {\n    \n}\n");
+        assert_eq!(cursor, Some(16));
+        let (text, cursor) = resolve_cursor_placeholder("no placeholder");
+        assert_eq!(text, "no placeholder");
+        assert_eq!(cursor, None);
+    }
+}
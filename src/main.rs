@@ -33,13 +33,16 @@ use std::{path::Path, rc::Rc};
 use crate::{debuginfo::DebugInfo, utils::get_sys_error};
 
 use self::glfw::Context;
-use opengl::glinit;
+use opengl::{
+    glinit,
+    shaders::{source, ShaderError},
+};
 pub use utils::macros::*;
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub enum MainInitError {
     GLFW(glfw::InitError),
-    Shader(String),
+    Shader(ShaderError),
 }
 
 impl From<glfw::InitError> for MainInitError {
@@ -48,6 +51,12 @@ impl From<glfw::InitError> for MainInitError {
     }
 }
 
+impl From<ShaderError> for MainInitError {
+    fn from(item: ShaderError) -> MainInitError {
+        MainInitError::Shader(item)
+    }
+}
+
 type Main = Result<(), MainInitError>;
 static mut TRAP_HANDLER: fn(i32) = |_| {};
 
@@ -77,9 +86,6 @@ fn main() -> Main {
     let debug_info = DebugInfo::begin_recording();
     let width = 1024;
     let height = 768;
-    let font_path = std::path::Path::new("fonts/SourceCodePro-Regular.ttf");
-    let menu_font_path = std::path::Path::new("fonts/SourceCodePro-Light.ttf");
-    assert_eq!(font_path.exists(), true, "Path to font does not exist {}", font_path.display());
     let mut glfw_handle = glfw::init(glfw::FAIL_ON_ERRORS)?;
     foo();
 
@@ -97,6 +103,7 @@ fn main() -> Main {
     window.set_mouse_button_polling(true);
     window.set_scroll_polling(true);
     window.set_cursor_pos_polling(true);
+    window.set_focus_polling(true); // for saving files when leaving the window, see IdleSave
 
     // glfw_handle.set_swap_interval(glfw::SwapInterval::Sync(1));
     glfw_handle.set_swap_interval(glfw::SwapInterval::None);
@@ -104,9 +111,23 @@ fn main() -> Main {
     unsafe {
         glinit::init_gl();
     };
-    let font_program = opengl::shaders::TextShader::new();
-    let rectangle_program = opengl::shaders::RectShader::new(Path::new("./src/assets/round_rect.vs.glsl"), Path::new("./src/assets/round_rect.fs.glsl"));
-    let poly_program = opengl::shaders::RectShader::new(Path::new("./src/assets/rectangle.vs.glsl"), Path::new("./src/assets/rectangle.fs.glsl"));
+    let shader = |file_name: &str, embedded: &'static str| source::with_override(Path::new(source::OVERRIDE_DIRECTORY), file_name, embedded);
+    let shaders = opengl::shaders::TextShader::new(&shader("text.vs.glsl", source::TEXT_VERTEX_SHADER), &shader("text.fs.glsl", source::TEXT_FRAGMENT_SHADER))
+        .and_then(|text_shader| {
+            let rect_shader = opengl::shaders::RectShader::new(
+                &shader("round_rect.vs.glsl", source::ROUND_RECT_VERTEX_SHADER),
+                &shader("round_rect.fs.glsl", source::ROUND_RECT_FRAGMENT_SHADER),
+            )?;
+            let poly_shader = opengl::shaders::RectShader::new(
+                &shader("rectangle.vs.glsl", source::RECTANGLE_VERTEX_SHADER),
+                &shader("rectangle.fs.glsl", source::RECTANGLE_FRAGMENT_SHADER),
+            )?;
+            Ok((text_shader, rect_shader, poly_shader))
+        });
+    let (font_program, rectangle_program, poly_program) = shaders.map_err(|e| {
+        println!("Failed to create the shaders, exiting. {}", e);
+        MainInitError::from(e)
+    })?;
 
     font_program.bind();
     // let char_range = (0..=0x0F028u32).filter_map(|c| std::char::from_u32(c)).collect();
@@ -114,15 +135,16 @@ fn main() -> Main {
     // let char_range: Vec<char> = (0..=1000u32).filter_map(std::char::from_u32).chain((0x2264..=0x2265).filter_map(std::char::from_u32)).collect();
     let char_range: Vec<char> = (0..=0x0f8u32)
         .filter_map(std::char::from_u32)
-        .chain(crate::utils::convert_vec_of_u32_utf(&vec![0x2260, 0x2264, 0x2265]))
+        .chain(crate::utils::convert_vec_of_u32_utf(&vec![0x2260, 0x2264, 0x2265, 0x2192]))
         .collect();
 
-    let font = ui::font::Font::new(font_path, 14, &char_range).expect("Failed to create font");
-    let menu_font = ui::font::Font::new(menu_font_path, 14, &char_range).expect("Failed to create font");
+    let font = ui::font::Font::from_bytes(ui::font::SOURCE_CODE_PRO_REGULAR, 14, &char_range).expect("Failed to create font");
+    let menu_font = ui::font::Font::from_bytes(ui::font::SOURCE_CODE_PRO_LIGHT, 14, &char_range).expect("Failed to create font");
     let fonts = vec![Rc::new(font), Rc::new(menu_font)];
 
     // let mut text_renderer = opengl::text::TextRenderer::create(font_program.clone(), &fonts[], 64 * 1024 * 100).expect("Failed to create TextRenderer");
     let mut app = app::Application::create(fonts, font_program, rectangle_program, poly_program, debug_info);
+    app.open_files(cmd::args::parse_file_arguments(std::env::args().skip(1)));
     let mut last_update = glfw_handle.get_time();
     let mut frame_counter = 0.0;
     let mut once_a_second_update = 60.0;
@@ -148,7 +170,8 @@ fn main() -> Main {
             app.debug_view.do_update_view(fps, frame_time);
         }
         app.process_all_events(&mut window, &events);
-        app.update_window();
+        app.update_window_title(&mut window);
+        app.update_window(glfw_handle.get_time());
         window.swap_buffers();
         glfw_handle.wait_events_timeout(1.0 / 125.0);
         // glfw_handle.poll_events();
@@ -0,0 +1,79 @@
+/// The CPU side data a renderer builds what it draws from: vertices, indices and draw commands. The data is rebuilt whenever what's
+/// drawn changes, which while scrolling is every frame. Clearing keeps the allocations, and the arena remembers the most quads a
+/// frame has had, so that the next frame has room for as many up front, instead of reallocating as it grows
+pub struct RenderArena<V, C> {
+    pub vertices: Vec<V>,
+    pub indices: Vec<u32>,
+    pub commands: Vec<C>,
+    peak_quads: usize,
+}
+
+impl<V, C> RenderArena<V, C> {
+    pub fn with_capacity(quads: usize) -> RenderArena<V, C> {
+        let mut arena = RenderArena { vertices: vec![], indices: vec![], commands: vec![], peak_quads: 0 };
+        arena.reserve_for(quads);
+        arena
+    }
+
+    /// The number of quads in the arena
+    pub fn quads(&self) -> usize {
+        self.indices.len() / 6
+    }
+
+    /// The most quads the arena has held, between any two clears
+    pub fn peak_quads(&self) -> usize {
+        std::cmp::max(self.peak_quads, self.quads())
+    }
+
+    /// Makes room for quads more quads, one draw command each, at most
+    pub fn reserve_for(&mut self, quads: usize) {
+        self.vertices.reserve(quads * 4);
+        self.indices.reserve(quads * 6);
+        self.commands.reserve(quads);
+    }
+
+    /// Clears the data, keeping the allocations, which are grown to fit the largest frame so far, if they aren't already
+    pub fn clear(&mut self) {
+        self.peak_quads = self.peak_quads();
+        self.vertices.clear();
+        self.indices.clear();
+        self.commands.clear();
+        self.reserve_for(self.peak_quads);
+    }
+}
+
+#[cfg(test)]
+mod arena_tests {
+    use super::RenderArena;
+
+    fn push_quads(arena: &mut RenderArena<[f32; 4], usize>, quads: usize) {
+        for quad in 0..quads {
+            let vtx = arena.vertices.len() as u32;
+            arena.vertices.extend_from_slice(&[[0.0; 4]; 4]);
+            arena.indices.extend_from_slice(&[vtx, vtx + 1, vtx + 2, vtx, vtx + 2, vtx + 3]);
+            arena.commands.push(quad);
+        }
+    }
+
+    #[test]
+    fn smaller_frames_reuse_the_allocation_of_a_large_one() {
+        let mut arena = RenderArena::with_capacity(10);
+        push_quads(&mut arena, 1000);
+        let capacities = (arena.vertices.capacity(), arena.indices.capacity(), arena.commands.capacity());
+        let allocations = (arena.vertices.as_ptr(), arena.indices.as_ptr(), arena.commands.as_ptr());
+
+        arena.clear();
+        assert_eq!(arena.quads(), 0);
+        assert_eq!(arena.peak_quads(), 1000);
+        push_quads(&mut arena, 20);
+        assert_eq!(arena.quads(), 20);
+        assert_eq!((arena.vertices.capacity(), arena.indices.capacity(), arena.commands.capacity()), capacities);
+        assert_eq!((arena.vertices.as_ptr(), arena.indices.as_ptr(), arena.commands.as_ptr()), allocations);
+
+        // a frame as large as the largest one so far, doesn't have to grow the arena either
+        arena.clear();
+        push_quads(&mut arena, 1000);
+        assert_eq!((arena.vertices.as_ptr(), arena.indices.as_ptr(), arena.commands.as_ptr()), allocations);
+        assert_eq!(arena.peak_quads(), 1000);
+    }
+}
@@ -1,5 +1,5 @@
+use super::shaders::{info_log_text, ShaderError, ShaderStage};
 use super::types::{Matrix, Vec4f};
-use crate::MainInitError;
 
 use gl::{CompileShader, CreateProgram, GetProgramInfoLog, GetProgramiv, GetShaderInfoLog, GetShaderiv, ShaderSource};
 use std::ffi::CString;
@@ -11,6 +11,10 @@ pub struct OpenGLHandle {
 
 impl Drop for OpenGLHandle {
     fn drop(&mut self) {
+        // zero names no object, like the handles of renderers made without a GL context
+        if self.vao == 0 && self.vbo == 0 && self.ebo == 0 {
+            return;
+        }
         unsafe {
             gl::DeleteVertexArrays(1, &self.vao as _);
             gl::DeleteBuffers(1, &self.vbo as _);
@@ -51,57 +55,63 @@ pub fn screen_projection_matrix(width: u32, height: u32, scrolled: i32) -> Matri
     Matrix { data: [a, b, c, d] }
 }
 
-pub fn create_shader_program(vertex_source: &str, frag_source: &str) -> Result<gl::types::GLuint, MainInitError> {
-    let program = unsafe {
-        let vertex_shader = gl::CreateShader(gl::VERTEX_SHADER);
-        let v_src = CString::new(vertex_source.as_bytes()).unwrap();
-        ShaderSource(vertex_shader, 1, &v_src.as_ptr(), std::ptr::null());
-        CompileShader(vertex_shader);
-
-        let mut ok = gl::FALSE as gl::types::GLint;
-        let mut log = Vec::with_capacity(512);
-
-        log.set_len(512 - 1);
-        GetShaderiv(vertex_shader, gl::COMPILE_STATUS, &mut ok);
-        if ok != gl::TRUE as gl::types::GLint {
-            GetShaderInfoLog(vertex_shader, 512, std::ptr::null_mut(), log.as_mut_ptr() as *mut gl::types::GLchar);
-            println!("Compilation of vertex shader failed:\n{}", log.iter().map(|v| *v as char).collect::<String>());
-            return Err(MainInitError::Shader(log.iter().map(|v| *v as char).collect::<String>()));
-        }
-        log.clear();
-
-        let frag_shader = gl::CreateShader(gl::FRAGMENT_SHADER);
-        let f_src = CString::new(frag_source.as_bytes()).unwrap();
-        ShaderSource(frag_shader, 1, &f_src.as_ptr(), std::ptr::null());
-        CompileShader(frag_shader);
-
-        log.set_len(512 - 1);
-        GetShaderiv(frag_shader, gl::COMPILE_STATUS, &mut ok);
-        if ok != gl::TRUE as gl::types::GLint {
-            GetShaderInfoLog(frag_shader, 512, std::ptr::null_mut(), log.as_mut_ptr() as *mut gl::types::GLchar);
-            println!("Compilation of fragment shader failed:\n{}", log.iter().map(|v| *v as char).collect::<String>());
-            return Err(MainInitError::Shader(log.iter().map(|v| *v as char).collect::<String>()));
-        }
-        log.clear();
+pub fn create_shader_program(vertex_source: &str, frag_source: &str) -> Result<gl::types::GLuint, ShaderError> {
+    unsafe {
+        let vertex_shader = compile_shader(gl::VERTEX_SHADER, ShaderStage::Vertex, vertex_source)?;
+        let frag_shader = match compile_shader(gl::FRAGMENT_SHADER, ShaderStage::Fragment, frag_source) {
+            Ok(shader) => shader,
+            Err(e) => {
+                gl::DeleteShader(vertex_shader);
+                return Err(e);
+            }
+        };
 
         let shader_program = CreateProgram();
         gl::AttachShader(shader_program, vertex_shader);
         gl::AttachShader(shader_program, frag_shader);
         gl::LinkProgram(shader_program);
+        gl::DeleteShader(vertex_shader);
+        gl::DeleteShader(frag_shader);
 
+        let mut ok = gl::FALSE as gl::types::GLint;
         GetProgramiv(shader_program, gl::LINK_STATUS, &mut ok);
-
         if ok != gl::TRUE as gl::types::GLint {
-            GetProgramInfoLog(shader_program, 512, std::ptr::null_mut(), log.as_mut_ptr() as *mut gl::types::GLchar);
-            println!("Linking of shader program failed:\n{}", std::str::from_utf8(&log).unwrap_or("Failed to retrieve error message from OpenGL"));
+            let log = info_log(shader_program, GetProgramiv, GetProgramInfoLog);
+            gl::DeleteProgram(shader_program);
+            return Err(ShaderError::Link(log));
         }
+        Ok(shader_program)
+    }
+}
 
-        gl::DeleteShader(vertex_shader);
-        gl::DeleteShader(frag_shader);
-        shader_program
-    };
+unsafe fn compile_shader(kind: gl::types::GLenum, stage: ShaderStage, source: &str) -> Result<gl::types::GLuint, ShaderError> {
+    let src = CString::new(source.as_bytes()).map_err(|_| ShaderError::InvalidSource(stage))?;
+    let shader = gl::CreateShader(kind);
+    ShaderSource(shader, 1, &src.as_ptr(), std::ptr::null());
+    CompileShader(shader);
+
+    let mut ok = gl::FALSE as gl::types::GLint;
+    GetShaderiv(shader, gl::COMPILE_STATUS, &mut ok);
+    if ok != gl::TRUE as gl::types::GLint {
+        let log = info_log(shader, GetShaderiv, GetShaderInfoLog);
+        gl::DeleteShader(shader);
+        return Err(ShaderError::Compile(stage, log));
+    }
+    Ok(shader)
+}
 
-    Ok(program)
+/// Reads the info log of a shader, or a program, with the pair of GL functions for that kind of object
+unsafe fn info_log(
+    object: gl::types::GLuint, get_iv: unsafe fn(gl::types::GLuint, gl::types::GLenum, *mut gl::types::GLint),
+    get_log: unsafe fn(gl::types::GLuint, gl::types::GLsizei, *mut gl::types::GLsizei, *mut gl::types::GLchar),
+) -> String {
+    let mut len = 0;
+    get_iv(object, gl::INFO_LOG_LENGTH, &mut len);
+    let mut log = vec![0u8; std::cmp::max(len, 1) as usize];
+    let mut written = 0;
+    get_log(object, log.len() as gl::types::GLsizei, &mut written, log.as_mut_ptr() as *mut gl::types::GLchar);
+    log.truncate(std::cmp::max(written, 0) as usize);
+    info_log_text(&log)
 }
 
 pub extern "system" fn gl_debug_output(
@@ -1,8 +1,12 @@
 pub mod shaders;
 pub mod types;
 
+/// The vertex, index and draw command data renderers build each frame, kept allocated between frames
+pub mod arena;
 pub mod polygon_renderer;
 pub mod renderer;
+/// Clipping of what's drawn, to the rectangle of the UI element being drawn
+pub mod scissor;
 
 /// Rect renderer module. Renders simple rectangles, such as windows/borders and cursors
 pub mod rectangle_renderer;
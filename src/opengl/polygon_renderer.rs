@@ -7,6 +7,7 @@ use crate::{
 };
 
 use super::{
+    arena::RenderArena,
     glinit::OpenGLHandle,
     shaders::RectShader,
     text_renderer::BufferIndex,
@@ -25,6 +26,21 @@ pub struct Texture {
 }
 
 impl Texture {
+    /// Uploads the RGBA pixels of an image of dimensions to the GPU
+    pub fn upload(pixels: &[u8], dimensions: Vec2i) -> Texture {
+        let mut id = 0;
+        unsafe {
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_2D, id);
+            // gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGBA as i32, dimensions.x, dimensions.y, 0, gl::RGBA, gl::UNSIGNED_BYTE, pixels.as_ptr() as *const _);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as _);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as _);
+            // gl::GenerateMipmap(gl::TEXTURE_2D);
+        }
+        Texture { id, dimensions }
+    }
+
     pub fn bind(&self) {
         unsafe { gl::BindTexture(gl::TEXTURE_2D, self.id) }
     }
@@ -46,28 +62,41 @@ impl TextureMap {
         let mut textures = HashMap::new();
 
         for (p, tex_type) in paths {
-            let decoder = png::Decoder::new(std::fs::File::open(p).unwrap());
-            let (info, mut reader) = decoder.read_info().unwrap();
-            let mut buf = vec![0; reader.output_buffer_size()];
-            reader.next_frame(&mut buf).unwrap();
-            let dimensions = Vec2i::new(info.width as _, info.height as _);
-            let mut id = 0;
-            unsafe {
-                gl::GenTextures(1, &mut id);
-                gl::BindTexture(gl::TEXTURE_2D, id);
-                // gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
-                gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGBA as i32, dimensions.x, dimensions.y, 0, gl::RGBA, gl::UNSIGNED_BYTE, buf.as_ptr() as *const _);
-                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as _);
-                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as _);
-                // gl::GenerateMipmap(gl::TEXTURE_2D);
-            }
-
+            let (pixels, dimensions) = image_or_fallback(p);
             assert!(!textures.contains_key(&tex_type));
-            textures.insert(tex_type, Texture { id, dimensions });
+            textures.insert(tex_type, Texture::upload(&pixels, dimensions));
         }
 
         TextureMap { textures }
     }
+
+    /// The texture of type. None if the map wasn't made with one
+    pub fn get(&self, tex_type: TextureType) -> Option<Texture> {
+        self.textures.get(&tex_type).copied()
+    }
+}
+
+/// The pixel a texture is made of, in place of an image that couldn't be loaded. It's transparent, so that what's drawn with the
+/// texture looks like it's drawn without one
+pub const FALLBACK_PIXEL: [u8; 4] = [0, 0, 0, 0];
+
+/// The pixels and the dimensions of the PNG image at path, or of a single FALLBACK_PIXEL if it's missing or can't be decoded
+fn image_or_fallback(path: &Path) -> (Vec<u8>, Vec2i) {
+    load_png(path).unwrap_or_else(|err| {
+        // todo: UI representation
+        println!("Warning: could not load the image {}, using a blank texture instead: {}", path.display(), err);
+        (FALLBACK_PIXEL.to_vec(), Vec2i::new(1, 1))
+    })
+}
+
+/// Reads the PNG image at path, returning its pixels and its dimensions
+fn load_png(path: &Path) -> Result<(Vec<u8>, Vec2i), String> {
+    let file = std::fs::File::open(path).map_err(|err| err.to_string())?;
+    let decoder = png::Decoder::new(file);
+    let (info, mut reader) = decoder.read_info().map_err(|err| err.to_string())?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    reader.next_frame(&mut buf).map_err(|err| err.to_string())?;
+    Ok((buf, Vec2i::new(info.width as _, info.height as _)))
 }
 
 pub enum PolygonType {
@@ -125,13 +154,11 @@ pub enum PolygonDrawCommand {
 
 pub struct PolygonRenderer {
     gl_handle: OpenGLHandle,
-    vtx_data: Vec<RectangleVertex>,
-    indices: Vec<u32>,
+    data: RenderArena<RectangleVertex, PolygonDrawCommand>,
     pub shader: RectShader,
     reserved_vertex_count: isize,
     reserved_index_count: isize,
     pub needs_update: bool,
-    pub draw_commands: Vec<PolygonDrawCommand>,
 }
 
 impl PolygonRenderer {
@@ -142,7 +169,6 @@ impl PolygonRenderer {
         let (vertices_count, reserved_indices) = reserve_primitive.request_reserve();
         let reserved_vtx_bytes = vertices_count.bytes_len();
         let reserved_indices_bytes = reserved_indices.bytes_len();
-        let indices = Vec::with_capacity(reserved_indices.value());
 
         let (mut vao, mut vbo, mut ebo) = (0, 0, 0);
         unsafe {
@@ -170,16 +196,32 @@ impl PolygonRenderer {
 
         PolygonRenderer {
             gl_handle,
-            vtx_data: Vec::with_capacity(vertices_count.value()),
-            indices,
+            data: RenderArena::with_capacity(reserve_quads as _),
             shader,
             reserved_vertex_count: vertices_count.value() as _,
             reserved_index_count: reserved_indices.value() as _,
             // color: RGBAColor {r: 0.3,g: 0.34,b: 0.48,a: 1.0,},
             needs_update: true,
-            draw_commands: Vec::with_capacity(10),
         }
     }
+
+    /// A renderer that lays out polygons, but has no buffers to upload them to, since tests have no OpenGL context
+    #[cfg(test)]
+    pub fn without_buffers(shader: RectShader) -> PolygonRenderer {
+        PolygonRenderer {
+            gl_handle: OpenGLHandle { vao: 0, vbo: 0, ebo: 0 },
+            data: RenderArena::with_capacity(0),
+            shader,
+            reserved_vertex_count: 0,
+            reserved_index_count: 0,
+            needs_update: true,
+        }
+    }
+
+    /// Makes room for quads more rectangles to be pushed, without reallocating. Bordered rectangles take two
+    pub fn reserve_for(&mut self, quads: usize) {
+        self.data.reserve_for(quads);
+    }
     /// Binds the Vertex Array Object, it's related Vertex Buffer Objects and the Element Buffer Object and the Shader that this
     /// renderer uses.
     pub fn bind(&self) {
@@ -187,18 +229,16 @@ impl PolygonRenderer {
         self.shader.bind();
     }
 
-    /// Clears all rendering data, stored on the CPU side
+    /// Clears all rendering data, stored on the CPU side. The memory is kept, for the data of the next frame
     pub fn clear_data(&mut self) {
-        self.vtx_data.clear();
-        self.indices.clear();
-        self.draw_commands.clear();
+        self.data.clear();
         self.needs_update = true;
     }
 
     /// Changes the vertex color data
     pub fn set_color(&mut self, color: RGBAColor) {
         let RGBAColor { r, g, b, .. } = color;
-        for v in self.vtx_data.iter_mut() {
+        for v in self.data.vertices.iter_mut() {
             v.r = r;
             v.g = g;
             v.b = b;
@@ -215,24 +255,26 @@ impl PolygonRenderer {
         match poly_type {
             PolygonType::Undecorated => {
                 let indices = self.make_vertex_data(rect, color);
-                self.draw_commands.push(PolygonDrawCommand::Undecorated { indices });
+                self.data.commands.push(PolygonDrawCommand::Undecorated { indices });
             }
             PolygonType::Decorated { texture } => {
                 let indices = self.make_vertex_data(rect, color);
-                self.draw_commands.push(PolygonDrawCommand::Decorated { indices, texture });
+                self.data.commands.push(PolygonDrawCommand::Decorated { indices, texture });
             }
             PolygonType::RoundedUndecorated { corner_radius } => {
                 let rect_size = rect.size_f32();
                 let bl_rect_screen_pos = rect.min.to_f32();
                 let indices = self.make_vertex_data(rect, color);
-                self.draw_commands
+                self.data
+                    .commands
                     .push(PolygonDrawCommand::RoundedUndecorated { indices, corner_radius, rect_size, bl_rect_screen_pos });
             }
             PolygonType::RoundedDecorated { corner_radius, texture } => {
                 let rect_size = rect.size_f32();
                 let bl_rect_screen_pos = rect.min.to_f32();
                 let indices = self.make_vertex_data(rect, color);
-                self.draw_commands
+                self.data
+                    .commands
                     .push(PolygonDrawCommand::RoundedDecorated { indices, corner_radius, rect_size, bl_rect_screen_pos, texture });
             }
         }
@@ -246,17 +288,21 @@ impl PolygonRenderer {
     pub fn make_vertex_data(&mut self, rect: BoundingBox, color: RGBAColor) -> BufferIndex {
         let BoundingBox { min, max } = rect;
         let RGBAColor { r, g, b, a } = color;
-        let ebo_idx = self.indices.len();
-        let vtx_index = self.vtx_data.len() as u32;
-        self.vtx_data
+        let ebo_idx = self.data.indices.len();
+        let vtx_index = self.data.vertices.len() as u32;
+        self.data
+            .vertices
             .push(RectangleVertex::new(min.x as f32, max.y as f32, 0.0, 1.0, r, g, b, a));
-        self.vtx_data
+        self.data
+            .vertices
             .push(RectangleVertex::new(min.x as f32, min.y as f32, 0.0, 0.0, r, g, b, a));
-        self.vtx_data
+        self.data
+            .vertices
             .push(RectangleVertex::new(max.x as f32, min.y as f32, 1.0, 0.0, r, g, b, a));
-        self.vtx_data
+        self.data
+            .vertices
             .push(RectangleVertex::new(max.x as f32, max.y as f32, 1.0, 1.0, r, g, b, a));
-        self.indices.extend_from_slice(&[
+        self.data.indices.extend_from_slice(&[
             vtx_index,
             vtx_index + 1,
             vtx_index + 2,
@@ -265,7 +311,7 @@ impl PolygonRenderer {
             vtx_index + 3,
         ]);
         self.needs_update = true;
-        let elem_count = self.indices.len() - ebo_idx;
+        let elem_count = self.data.indices.len() - ebo_idx;
         BufferIndex::new(ebo_idx, elem_count)
     }
 
@@ -297,7 +343,7 @@ impl PolygonRenderer {
             self.upload_cpu_data();
             self.needs_update = false;
         }
-        for dc in self.draw_commands.iter() {
+        for dc in self.data.commands.iter() {
             let indices = match dc {
                 PolygonDrawCommand::Undecorated { indices } => {
                     Texture::unbind_textures();
@@ -340,25 +386,54 @@ impl PolygonRenderer {
 impl PolygonRenderer {
     fn upload_cpu_data(&mut self) {
         unsafe {
-            gl::BufferSubData(gl::ARRAY_BUFFER, 0, (self.vtx_data.len() * std::mem::size_of::<RectangleVertex>()) as _, self.vtx_data.as_ptr() as _);
-            gl::BufferSubData(gl::ELEMENT_ARRAY_BUFFER, 0, (self.indices.len() * std::mem::size_of::<u32>()) as _, self.indices.as_ptr() as _);
+            gl::BufferSubData(gl::ARRAY_BUFFER, 0, (self.data.vertices.len() * std::mem::size_of::<RectangleVertex>()) as _, self.data.vertices.as_ptr() as _);
+            gl::BufferSubData(gl::ELEMENT_ARRAY_BUFFER, 0, (self.data.indices.len() * std::mem::size_of::<u32>()) as _, self.data.indices.as_ptr() as _);
         }
         self.needs_update = false;
     }
 
     fn reserve_gpu_memory_if_needed(&mut self) {
-        if self.reserved_vertex_count <= self.vtx_data.len() as _ {
-            self.reserved_vertex_count = self.vtx_data.capacity() as _;
+        if self.reserved_vertex_count <= self.data.vertices.len() as _ {
+            self.reserved_vertex_count = self.data.vertices.capacity() as _;
             unsafe {
-                gl::BufferData(gl::ARRAY_BUFFER, (std::mem::size_of::<RectangleVertex>() * self.vtx_data.capacity()) as _, std::ptr::null(), gl::DYNAMIC_DRAW);
+                gl::BufferData(
+                    gl::ARRAY_BUFFER,
+                    (std::mem::size_of::<RectangleVertex>() * self.data.vertices.capacity()) as _,
+                    std::ptr::null(),
+                    gl::DYNAMIC_DRAW,
+                );
             }
         }
 
-        if self.reserved_index_count <= self.indices.len() as _ {
-            self.reserved_index_count = self.indices.capacity() as _;
+        if self.reserved_index_count <= self.data.indices.len() as _ {
+            self.reserved_index_count = self.data.indices.capacity() as _;
             unsafe {
-                gl::BufferData(gl::ELEMENT_ARRAY_BUFFER, (std::mem::size_of::<u32>() * self.indices.capacity()) as _, std::ptr::null(), gl::DYNAMIC_DRAW);
+                gl::BufferData(gl::ELEMENT_ARRAY_BUFFER, (std::mem::size_of::<u32>() * self.data.indices.capacity()) as _, std::ptr::null(), gl::DYNAMIC_DRAW);
             }
         }
     }
 }
+
+#[cfg(test)]
+mod polygon_renderer_tests {
+    use super::{image_or_fallback, load_png, FALLBACK_PIXEL};
+    use crate::datastructure::generic::Vec2i;
+    use crate::utils::temp_dir::TempDir;
+    use std::path::Path;
+
+    #[test]
+    fn images_that_cant_be_loaded_fall_back_to_a_blank_pixel() {
+        let missing = Path::new("./no_such_logo.png");
+        assert!(load_png(missing).is_err());
+        assert_eq!(image_or_fallback(missing), (FALLBACK_PIXEL.to_vec(), Vec2i::new(1, 1)));
+
+        let directory = TempDir::new("corrupt_png");
+        let corrupt = directory.join("corrupt.png");
+        std::fs::write(&corrupt, b"\x89PNG, or so it says").unwrap();
+        assert!(load_png(&corrupt).is_err());
+
+        let (pixels, dimensions) = image_or_fallback(Path::new("./logo.png"));
+        assert!(dimensions.x > 1 && dimensions.y > 1);
+        assert!(pixels.len() >= (dimensions.x * dimensions.y) as usize);
+    }
+}
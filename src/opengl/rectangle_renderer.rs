@@ -88,6 +88,21 @@ impl RectRenderer {
         }
     }
 
+    /// A renderer that lays out rectangles, but has no buffers to upload them to, since tests have no OpenGL context
+    #[cfg(test)]
+    pub fn without_buffers(shader: RectShader) -> RectRenderer {
+        RectRenderer {
+            gl_handle: OpenGLHandle { vao: 0, vbo: 0, ebo: 0 },
+            vtx_data: vec![],
+            indices: vec![],
+            shader,
+            reserved_vertex_count: 0,
+            reserved_index_count: 0,
+            needs_update: true,
+            draw_commands: vec![],
+        }
+    }
+
     pub fn bind(&self) {
         self.gl_handle.bind();
         self.shader.bind();
@@ -0,0 +1,118 @@
+use std::cell::Cell;
+
+use crate::{datastructure::generic::Vec2i, ui::basic::frame::Frame};
+
+/// A rectangle of the window, outside of which nothing is drawn. In the window coordinates of OpenGL, so x, y is the bottom left corner
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ClipRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl ClipRect {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> ClipRect {
+        ClipRect { x, y, width, height }
+    }
+
+    /// The clip rectangle covering frame, which is anchored at its top left corner
+    pub fn from_frame(frame: &Frame) -> ClipRect {
+        let Frame { anchor: Vec2i { x, y }, size } = *frame;
+        ClipRect::new(x, y - size.height, size.width, size.height)
+    }
+}
+
+thread_local! {
+    /// The clip rectangle of the innermost live ScissorGuard. None when nothing is clipped
+    static CURRENT_CLIP: Cell<Option<ClipRect>> = Cell::new(None);
+}
+
+/// The clip rectangle drawing is currently restricted to, if any
+pub fn current_clip() -> Option<ClipRect> {
+    CURRENT_CLIP.with(|current| current.get())
+}
+
+fn apply_to_gl(clip: Option<ClipRect>) {
+    unsafe {
+        match clip {
+            Some(ClipRect { x, y, width, height }) => {
+                gl::Enable(gl::SCISSOR_TEST);
+                gl::Scissor(x, y, width, height);
+            }
+            None => gl::Disable(gl::SCISSOR_TEST),
+        }
+    }
+}
+
+/// Clips drawing to a rectangle, for as long as the guard lives. Dropping it restores the clipping that was in effect before
+/// it, so guards nest, and no element has to reset the scissor state for the next one to be drawn
+pub struct ScissorGuard {
+    previous: Option<ClipRect>,
+    apply: fn(Option<ClipRect>),
+}
+
+impl ScissorGuard {
+    pub fn new(clip: ClipRect) -> ScissorGuard {
+        ScissorGuard::with_apply(clip, apply_to_gl)
+    }
+
+    /// A guard that sets the scissor state with apply, which is what tests, without an OpenGL context, need
+    fn with_apply(clip: ClipRect, apply: fn(Option<ClipRect>)) -> ScissorGuard {
+        let previous = CURRENT_CLIP.with(|current| current.replace(Some(clip)));
+        apply(Some(clip));
+        ScissorGuard { previous, apply }
+    }
+}
+
+impl Drop for ScissorGuard {
+    fn drop(&mut self) {
+        CURRENT_CLIP.with(|current| current.set(self.previous));
+        (self.apply)(self.previous);
+    }
+}
+
+#[cfg(test)]
+mod scissor_tests {
+    use super::{current_clip, ClipRect, ScissorGuard};
+    use std::cell::RefCell;
+
+    thread_local! {
+        static APPLIED: RefCell<Vec<Option<ClipRect>>> = RefCell::new(vec![]);
+    }
+
+    fn record(clip: Option<ClipRect>) {
+        APPLIED.with(|applied| applied.borrow_mut().push(clip));
+    }
+
+    #[test]
+    fn dropped_guards_restore_the_prior_clip() {
+        let window = ClipRect::new(0, 0, 1024, 768);
+        let view = ClipRect::new(10, 20, 300, 400);
+        let cursor = ClipRect::new(12, 20, 296, 380);
+        assert_eq!(current_clip(), None);
+        {
+            let _window = ScissorGuard::with_apply(window, record);
+            {
+                let _view = ScissorGuard::with_apply(view, record);
+                assert_eq!(current_clip(), Some(view));
+            }
+            assert_eq!(current_clip(), Some(window));
+            let _cursor = ScissorGuard::with_apply(cursor, record);
+            assert_eq!(current_clip(), Some(cursor));
+        }
+        assert_eq!(current_clip(), None);
+        let applied = APPLIED.with(|applied| applied.borrow().clone());
+        assert_eq!(
+            applied,
+            vec![
+                Some(window),
+                Some(view),
+                Some(window),
+                Some(cursor),
+                Some(window),
+                None
+            ]
+        );
+    }
+}
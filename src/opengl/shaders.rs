@@ -1,13 +1,71 @@
-use std::{io::Read, path::Path};
-
 use crate::datastructure::generic::{Vec2, Vec2f};
+use std::fmt::{Display, Formatter};
 
 /// Default shader sources, compiled into the binary
 pub mod source {
-    // pub const RECT_VERTEX_SHADER: &str = include_str!("../assets/rect.vs.glsl");
-    // pub const RECT_FRAGMENT_SHADER: &str = include_str!("../assets/rect.fs.glsl");
+    use std::borrow::Cow;
+    use std::path::Path;
+
     pub const TEXT_VERTEX_SHADER: &str = include_str!("../assets/text.vs.glsl");
     pub const TEXT_FRAGMENT_SHADER: &str = include_str!("../assets/text.fs.glsl");
+    pub const ROUND_RECT_VERTEX_SHADER: &str = include_str!("../assets/round_rect.vs.glsl");
+    pub const ROUND_RECT_FRAGMENT_SHADER: &str = include_str!("../assets/round_rect.fs.glsl");
+    pub const RECTANGLE_VERTEX_SHADER: &str = include_str!("../assets/rectangle.vs.glsl");
+    pub const RECTANGLE_FRAGMENT_SHADER: &str = include_str!("../assets/rectangle.fs.glsl");
+
+    /// Where the user can put shader files (named like the ones in src/assets), to use instead of the embedded ones
+    pub const OVERRIDE_DIRECTORY: &str = "./shaders";
+
+    /// The source of the shader file_name in override_directory, if the user put one there, otherwise the embedded source
+    pub fn with_override(override_directory: &Path, file_name: &str, embedded: &'static str) -> Cow<'static, str> {
+        let path = override_directory.join(file_name);
+        if !path.exists() {
+            return Cow::Borrowed(embedded);
+        }
+        match std::fs::read_to_string(&path) {
+            Ok(source) => Cow::Owned(source),
+            Err(e) => {
+                println!("Failed to read shader {}, using the default one: {}", path.display(), e); // todo: UI representation
+                Cow::Borrowed(embedded)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment,
+}
+
+/// Why a shader program couldn't be created. Compile and link errors carry the info log of the GL, which says what's wrong where
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum ShaderError {
+    Compile(ShaderStage, String),
+    Link(String),
+    /// The source contains a nul character, so it can't be handed to the GL
+    InvalidSource(ShaderStage),
+}
+
+impl Display for ShaderError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let stage_name = |stage: &ShaderStage| match stage {
+            ShaderStage::Vertex => "vertex",
+            ShaderStage::Fragment => "fragment",
+        };
+        match self {
+            ShaderError::Compile(stage, log) => write!(f, "Compilation of {} shader failed:\n{}", stage_name(stage), log),
+            ShaderError::Link(log) => write!(f, "Linking of shader program failed:\n{}", log),
+            ShaderError::InvalidSource(stage) => write!(f, "The {} shader source contains a nul character", stage_name(stage)),
+        }
+    }
+}
+
+/// The text of an info log, as the GL wrote it, without the nul terminator and trailing line breaks
+pub fn info_log_text(raw: &[u8]) -> String {
+    String::from_utf8_lossy(raw)
+        .trim_end_matches(|c: char| c == '\0' || c.is_whitespace())
+        .to_string()
 }
 
 #[derive(Clone)]
@@ -17,20 +75,20 @@ pub struct TextShader {
 }
 
 impl TextShader {
-    pub fn new() -> TextShader {
-        let font_program = match super::glinit::create_shader_program(source::TEXT_VERTEX_SHADER, source::TEXT_FRAGMENT_SHADER) {
-            Ok(program) => program,
-            Err(e) => {
-                println!("Error creating Rectangle shader program. Exiting application. {:?}", e);
-                std::process::exit(1);
-            }
-        };
+    /// A shader that names no program, for renderers in tests, which have no OpenGL context to link one in
+    #[cfg(test)]
+    pub fn unlinked() -> TextShader {
+        TextShader { id: 0, projection_uniform: -1 }
+    }
+
+    pub fn new(vertex_source: &str, fragment_source: &str) -> Result<TextShader, ShaderError> {
+        let font_program = super::glinit::create_shader_program(vertex_source, fragment_source)?;
         let projection_uniform = unsafe {
             let uniform_name = std::ffi::CString::new("projection").expect("Failed to create CString");
             gl::GetUniformLocation(font_program, uniform_name.as_ptr())
         };
         assert_ne!(projection_uniform, -1);
-        TextShader { id: font_program, projection_uniform }
+        Ok(TextShader { id: font_program, projection_uniform })
     }
 
     pub fn bind(&self) {
@@ -59,6 +117,12 @@ pub struct RectShader {
 }
 
 impl RectShader {
+    /// A shader that names no program, for renderers in tests, which have no OpenGL context to link one in
+    #[cfg(test)]
+    pub fn unlinked() -> RectShader {
+        RectShader { id: 0, u_projection: -1, u_radius: -1, u_rect_size: -1, u_rect_pos: -1, u_use_texture: -1 }
+    }
+
     pub fn panic_if_not_ok(&self, err_msg: &'static str) {
         assert!(self.u_projection >= 0, "{}: projection uniform ID invalid: {}", err_msg, self.u_projection);
         assert!(self.u_radius >= 0, "{}: radius uniform ID invalid: {}", err_msg, self.u_radius);
@@ -68,26 +132,8 @@ impl RectShader {
         println!("Validated shader uniforms & locations; {:#?}", self);
     }
 
-    pub fn new(vs_path: &Path, fs_path: &Path) -> RectShader {
-        let rvs = std::fs::File::open(vs_path).and_then(|mut f| {
-            let mut s = String::new();
-            f.read_to_string(&mut s)?;
-            Ok(s)
-        });
-
-        let rfs = std::fs::File::open(fs_path).and_then(|mut f| {
-            let mut s = String::new();
-            f.read_to_string(&mut s)?;
-            Ok(s)
-        });
-
-        let font_program = match super::glinit::create_shader_program(&rvs.expect("failed to read RVS code"), &rfs.expect("failed to read RFS code")) {
-            Ok(program) => program,
-            Err(_) => {
-                println!("Error creating Rectangle shader program. Exiting application.");
-                std::process::exit(1);
-            }
-        };
+    pub fn new(vertex_source: &str, fragment_source: &str) -> Result<RectShader, ShaderError> {
+        let font_program = super::glinit::create_shader_program(vertex_source, fragment_source)?;
         let (projection_uniform, radius, rect_size, rect_pos, use_texture) = unsafe {
             let projection_uniform_name = std::ffi::CString::new("projection").expect("Failed to create CString");
             let radius = std::ffi::CString::new("radius").expect("Failed to create CString");
@@ -104,14 +150,14 @@ impl RectShader {
         };
 
         assert_ne!(projection_uniform, -1);
-        RectShader {
+        Ok(RectShader {
             id: font_program,
             u_projection: projection_uniform,
             u_radius: radius,
             u_rect_size: rect_size,
             u_rect_pos: rect_pos,
             u_use_texture: use_texture,
-        }
+        })
     }
 
     pub fn bind(&self) {
@@ -157,3 +203,52 @@ impl RectShader {
         }
     }
 }
+
+#[cfg(test)]
+mod shaders_tests {
+    use super::{info_log_text, source, ShaderError, ShaderStage};
+    use crate::utils::temp_dir::TempDir;
+    use std::path::Path;
+
+    #[test]
+    fn embedded_sources_are_complete_shaders() {
+        let sources = [
+            source::TEXT_VERTEX_SHADER,
+            source::TEXT_FRAGMENT_SHADER,
+            source::ROUND_RECT_VERTEX_SHADER,
+            source::ROUND_RECT_FRAGMENT_SHADER,
+            source::RECTANGLE_VERTEX_SHADER,
+            source::RECTANGLE_FRAGMENT_SHADER,
+        ];
+        for shader in sources.iter() {
+            assert!(shader.starts_with("#version 430 core"));
+            assert!(shader.contains("void main()"));
+            // they're handed to OpenGL as C strings
+            assert!(std::ffi::CString::new(*shader).is_ok());
+        }
+    }
+
+    #[test]
+    fn shader_overrides_replace_embedded_sources() {
+        let directory = TempDir::new("shaders");
+        std::fs::write(directory.join("text.fs.glsl"), "#version 430 core\nvoid main() {}\n").unwrap();
+        let overridden = source::with_override(directory.path(), "text.fs.glsl", source::TEXT_FRAGMENT_SHADER);
+        let embedded = source::with_override(directory.path(), "text.vs.glsl", source::TEXT_VERTEX_SHADER);
+        assert_eq!(overridden, "#version 430 core\nvoid main() {}\n");
+        assert_eq!(embedded, source::TEXT_VERTEX_SHADER);
+        assert_eq!(source::with_override(Path::new("does/not/exist"), "text.vs.glsl", source::TEXT_VERTEX_SHADER), source::TEXT_VERTEX_SHADER);
+    }
+
+    #[test]
+    fn shader_errors_carry_the_gl_log() {
+        // what Mesa reports for a fragment shader missing a semicolon, as the GL hands it over: nul terminated
+        let raw = b"0:4(2): error: syntax error, unexpected '}', expecting ',' or ';'\n\0";
+        let error = ShaderError::Compile(ShaderStage::Fragment, info_log_text(raw));
+        assert_eq!(error.to_string(), "Compilation of fragment shader failed:\n0:4(2): error: syntax error, unexpected '}', expecting ',' or ';'");
+        assert_eq!(info_log_text(b""), "");
+        assert_eq!(
+            ShaderError::Link(info_log_text(b"error: vertex shader lacks `main'\0")).to_string(),
+            "Linking of shader program failed:\nerror: vertex shader lacks `main'"
+        );
+    }
+}
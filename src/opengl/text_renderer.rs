@@ -1,13 +1,12 @@
 use std::rc::Rc;
 
 use super::{
+    arena::RenderArena,
+    scissor::{ClipRect, ScissorGuard},
     types::{RGBColor, TextVertex as TVertex},
     Primitive,
 };
-use crate::{
-    datastructure::generic::Vec2i,
-    ui::{basic::coordinate::Size, basic::frame::Frame, font::Font},
-};
+use crate::ui::{basic::boundingbox::BoundingBox, basic::coordinate::Size, basic::frame::Frame, font::Font};
 
 #[derive(PartialEq, Clone, Copy, Eq, Hash, PartialOrd, Ord, Debug)]
 pub struct RendererId(pub u32);
@@ -37,6 +36,24 @@ impl BufferIndex {
     }
 }
 
+/// Where text goes horizontally, within the rect given to TextRenderer::push_aligned
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// The x at which text width wide begins, to be aligned within rect. Text wider than rect sticks out of its right edge when left
+/// aligned, out of its left edge when right aligned, and out of both when centered
+pub fn aligned_x(width: i32, rect: &BoundingBox, align: Align) -> i32 {
+    match align {
+        Align::Left => rect.min.x,
+        Align::Center => rect.min.x + (rect.width() - width) / 2,
+        Align::Right => rect.max.x - width,
+    }
+}
+
 pub struct TextDrawCommand {
     font: Rc<Font>,
     data_indices: BufferIndex,
@@ -48,15 +65,36 @@ impl TextDrawCommand {
     }
 }
 
+/// Text laid out as character quads, and the draw commands that draw it
+impl RenderArena<TVertex, TextDrawCommand> {
+    pub fn push_draw_command(&mut self, text: impl Iterator<Item = char>, color: RGBColor, x: i32, y: i32, font: Rc<Font>) {
+        use TextDrawCommand as DC;
+        let ebo_idx = self.indices.len();
+        push_text_quads(&mut self.vertices, &mut self.indices, text, color, x, y, &font);
+        let elem_count = self.indices.len() - ebo_idx;
+        self.commands.push(DC::new(font, BufferIndex::new(ebo_idx, elem_count)));
+    }
+
+    /// Like push_draw_command, for text in a string. Room for the text is reserved up front
+    pub fn push_str(&mut self, text: &str, color: RGBColor, x: i32, y: i32, font: Rc<Font>) {
+        self.reserve_for(text.chars().count());
+        self.push_draw_command(text.chars(), color, x, y, font);
+    }
+
+    /// Like push_draw_command, for text in a slice of characters. Room for the text is reserved up front
+    pub fn push_chars(&mut self, text: &[char], color: RGBColor, x: i32, y: i32, font: Rc<Font>) {
+        self.reserve_for(text.len());
+        self.push_draw_command(text.iter().copied(), color, x, y, font);
+    }
+}
+
 pub struct TextRenderer {
     gl_handle: super::glinit::OpenGLHandle,
     pub pristine: bool,
-    vtx_data: Vec<TVertex>,
-    indices: Vec<u32>,
+    quads: RenderArena<TVertex, TextDrawCommand>,
     pub shader: super::shaders::TextShader,
     reserved_vertex_count: isize,
     reserved_index_count: isize,
-    pub draw_commands: Vec<TextDrawCommand>,
 }
 
 /// Public interface
@@ -73,7 +111,6 @@ impl TextRenderer {
 
         // in the buffer of TVertices, each color attribute is 16 bytes in, namely 4 * sizeof(float) = 4 * 4
         let (mut vao, mut vbo, mut ebo) = (0, 0, 0);
-        let indices = Vec::with_capacity(reserved_indices.value());
         unsafe {
             gl::GenVertexArrays(1, &mut vao);
             gl::GenBuffers(1, &mut vbo);
@@ -102,91 +139,55 @@ impl TextRenderer {
             gl_handle,
             shader,
             pristine: false,
-            vtx_data: Vec::with_capacity(vertices_count.value()),
-            indices,
+            quads: RenderArena::with_capacity(reserve_quads),
             reserved_vertex_count: vertices_count.value() as _,
             reserved_index_count: reserved_indices.value() as _,
-            draw_commands: Vec::with_capacity(10),
         };
         tdb
     }
 
+    /// A renderer that lays out text, but has no buffers to upload it to, since tests have no OpenGL context
+    #[cfg(test)]
+    pub fn without_buffers(shader: super::shaders::TextShader) -> TextRenderer {
+        TextRenderer {
+            gl_handle: super::glinit::OpenGLHandle { vao: 0, vbo: 0, ebo: 0 },
+            shader,
+            pristine: false,
+            quads: RenderArena::with_capacity(0),
+            reserved_vertex_count: 0,
+            reserved_index_count: 0,
+        }
+    }
+
     pub fn bind(&self) {
         self.gl_handle.bind();
         self.shader.bind();
     }
 
     pub fn push_draw_command(&mut self, text: impl Iterator<Item = char>, color: RGBColor, x: i32, y: i32, font: Rc<Font>) {
-        use TextDrawCommand as DC;
-        let mut current_x = x;
-        let mut current_y = y - font.row_height();
-        // we need to be able to peek ahead
-        let mut text = text.peekable();
-        let ebo_idx = self.indices.len();
-        while let Some(c) = text.next() {
-            if c == '\n' {
-                current_x = x;
-                current_y -= font.row_height();
-                continue;
-            }
-
-            let c = {
-                let resulting_unicode = match text.peek() {
-                    Some('=') => match c {
-                        '<' => unsafe { std::char::from_u32_unchecked(0x2264) },
-                        '>' => unsafe { std::char::from_u32_unchecked(0x2265) },
-                        '!' => unsafe { std::char::from_u32_unchecked(0x2260) },
-                        _ => c,
-                    },
-                    _ => c,
-                };
-                if resulting_unicode != c {
-                    text.next();
-                }
-                resulting_unicode
-            };
+        self.quads.push_draw_command(text, color, x, y, font);
+        self.pristine = false;
+    }
 
-            if let Some(g) = font.get_glyph(c) {
-                let RGBColor { r: red, g: green, b: blue } = color;
-                let xpos = current_x as f32 + g.bearing.x as f32;
-                let ypos = current_y as f32 - (g.size.y - g.bearing.y) as f32;
-                let x0 = g.x0 as f32 / font.texture_width() as f32;
-                let x1 = g.x1 as f32 / font.texture_width() as f32;
-                let y0 = g.y0 as f32 / font.texture_height() as f32;
-                let y1 = g.y1 as f32 / font.texture_height() as f32;
-
-                let w = g.width();
-                let h = g.height();
-
-                let vtx_index = self.vtx_data.len() as u32;
-                // Todo(optimization, avx, simd): TVertex has been padded with an extra float, (sizeof TVertex == 8 * 4 bytes == 128 bit. Should be *extremely* friendly for SIMD purposes now)
-
-                self.vtx_data.push(TVertex::new(xpos, ypos + h, x0, y0, red, green, blue));
-                self.vtx_data.push(TVertex::new(xpos, ypos, x0, y1, red, green, blue));
-                self.vtx_data.push(TVertex::new(xpos + w, ypos, x1, y1, red, green, blue));
-                self.vtx_data.push(TVertex::new(xpos + w, ypos + h, x1, y0, red, green, blue));
-
-                self.indices.extend_from_slice(&[
-                    vtx_index,
-                    vtx_index + 1,
-                    vtx_index + 2,
-                    vtx_index,
-                    vtx_index + 2,
-                    vtx_index + 3,
-                ]);
-                current_x += g.advance;
-            } else {
-                let mut buf = [0; 4];
-                c.encode_utf16(&mut buf);
-                panic!("Could not find glyph for {}, {:?}", c, buf);
-            }
-        }
+    /// Like push_draw_command, for text in a string. Room for the text is reserved up front
+    pub fn push_str(&mut self, text: &str, color: RGBColor, x: i32, y: i32, font: Rc<Font>) {
+        self.quads.push_str(text, color, x, y, font);
+        self.pristine = false;
+    }
 
-        let elem_count = self.indices.len() - ebo_idx;
-        self.draw_commands.push(DC::new(font, BufferIndex::new(ebo_idx, elem_count)));
+    /// Like push_draw_command, for text in a slice of characters. Room for the text is reserved up front
+    pub fn push_chars(&mut self, text: &[char], color: RGBColor, x: i32, y: i32, font: Rc<Font>) {
+        self.quads.push_chars(text, color, x, y, font);
         self.pristine = false;
     }
 
+    /// Like push_str, with the text aligned within rect, hanging from its top. Text of several lines is aligned as a block, by its
+    /// widest line
+    pub fn push_aligned(&mut self, text: &str, color: RGBColor, rect: BoundingBox, align: Align, font: Rc<Font>) {
+        let width = calculate_text_dimensions_iter(text, &font).width;
+        self.push_str(text, color, aligned_x(width, &rect, align), rect.max.y, font);
+    }
+
     pub fn execute_draw_list(&mut self) {
         self.gl_handle.bind();
         if !self.pristine {
@@ -196,7 +197,7 @@ impl TextRenderer {
         }
         self.shader.bind();
         // todo(optimization): this means we can smash together consecutive DrawCommands that use the same settings & configurations, thus reducing the draw calls
-        for TextDrawCommand { font, data_indices: BufferIndex { idx_buffer_idx, idx_count }, .. } in self.draw_commands.iter() {
+        for TextDrawCommand { font, data_indices: BufferIndex { idx_buffer_idx, idx_count }, .. } in self.quads.commands.iter() {
             font.bind();
             unsafe {
                 gl::DrawElements(gl::TRIANGLES, (*idx_count) as _, gl::UNSIGNED_INT, (std::mem::size_of::<u32>() * *idx_buffer_idx) as _);
@@ -205,15 +206,8 @@ impl TextRenderer {
     }
 
     pub fn draw_clipped_list(&mut self, clip_frame: Frame) {
-        let Frame { anchor: Vec2i { x: top_x, y: top_y }, size } = clip_frame;
-        unsafe {
-            gl::Enable(gl::SCISSOR_TEST);
-            gl::Scissor(top_x, top_y - size.height, size.width, size.height);
-        }
+        let _clip = ScissorGuard::new(ClipRect::from_frame(&clip_frame));
         self.execute_draw_list();
-        unsafe {
-            gl::Disable(gl::SCISSOR_TEST);
-        }
     }
 }
 
@@ -221,42 +215,117 @@ impl TextRenderer {
 impl TextRenderer {
     fn upload_cpu_data(&self) {
         unsafe {
-            gl::BufferSubData(gl::ARRAY_BUFFER, 0, (self.vtx_data.len() * std::mem::size_of::<TVertex>()) as _, self.vtx_data.as_ptr() as _);
-            gl::BufferSubData(gl::ELEMENT_ARRAY_BUFFER, 0, (self.indices.len() * std::mem::size_of::<u32>()) as _, self.indices.as_ptr() as _);
+            let RenderArena { vertices, indices, .. } = &self.quads;
+            gl::BufferSubData(gl::ARRAY_BUFFER, 0, (vertices.len() * std::mem::size_of::<TVertex>()) as _, vertices.as_ptr() as _);
+            gl::BufferSubData(gl::ELEMENT_ARRAY_BUFFER, 0, (indices.len() * std::mem::size_of::<u32>()) as _, indices.as_ptr() as _);
         }
     }
 
     pub fn clear_data(&mut self) {
-        self.vtx_data.clear();
-        self.indices.clear();
-        self.draw_commands.clear();
+        self.quads.clear();
     }
 
     fn reserve_gpu_memory_if_needed(&mut self) {
-        if self.reserved_vertex_count <= self.vtx_data.len() as _ {
-            self.reserved_vertex_count = self.vtx_data.capacity() as _;
+        let RenderArena { vertices, indices, .. } = &self.quads;
+        if self.reserved_vertex_count <= vertices.len() as _ {
+            self.reserved_vertex_count = vertices.capacity() as _;
             unsafe {
-                gl::BufferData(gl::ARRAY_BUFFER, (std::mem::size_of::<TVertex>() * self.vtx_data.capacity()) as _, std::ptr::null(), gl::DYNAMIC_DRAW);
+                gl::BufferData(gl::ARRAY_BUFFER, (std::mem::size_of::<TVertex>() * vertices.capacity()) as _, std::ptr::null(), gl::DYNAMIC_DRAW);
             }
         }
 
-        if self.reserved_index_count <= self.indices.len() as _ {
-            self.reserved_index_count = self.indices.capacity() as _;
+        if self.reserved_index_count <= indices.len() as _ {
+            self.reserved_index_count = indices.capacity() as _;
             unsafe {
-                gl::BufferData(gl::ELEMENT_ARRAY_BUFFER, (std::mem::size_of::<u32>() * self.indices.capacity()) as _, std::ptr::null(), gl::DYNAMIC_DRAW);
+                gl::BufferData(gl::ELEMENT_ARRAY_BUFFER, (std::mem::size_of::<u32>() * indices.capacity()) as _, std::ptr::null(), gl::DYNAMIC_DRAW);
             }
         }
     }
 }
 
+/// Lays out text, with its top left corner at x, y, as one quad (4 vertices and 6 indices) per character
+fn push_text_quads(vtx_data: &mut Vec<TVertex>, indices: &mut Vec<u32>, text: impl Iterator<Item = char>, color: RGBColor, x: i32, y: i32, font: &Font) {
+    let mut current_x = x;
+    let mut current_y = y - font.row_height();
+    // we need to be able to peek ahead
+    let mut text = text.peekable();
+    let mut previous = None;
+    while let Some(c) = text.next() {
+        if c == '\n' {
+            current_x = x;
+            current_y -= font.row_height();
+            previous = None;
+            continue;
+        }
+
+        let c = {
+            let resulting_unicode = match text.peek() {
+                Some('=') => match c {
+                    '<' => unsafe { std::char::from_u32_unchecked(0x2264) },
+                    '>' => unsafe { std::char::from_u32_unchecked(0x2265) },
+                    '!' => unsafe { std::char::from_u32_unchecked(0x2260) },
+                    _ => c,
+                },
+                _ => c,
+            };
+            if resulting_unicode != c {
+                text.next();
+            }
+            resulting_unicode
+        };
+
+        if let Some(prev) = previous {
+            current_x += font.kerning(prev, c);
+        }
+        previous = Some(c);
+
+        if let Some(g) = font.get_glyph(c) {
+            let RGBColor { r: red, g: green, b: blue } = color;
+            let xpos = current_x as f32 + g.bearing.x as f32;
+            let ypos = current_y as f32 - (g.size.y - g.bearing.y) as f32;
+            let x0 = g.x0 as f32 / font.texture_width() as f32;
+            let x1 = g.x1 as f32 / font.texture_width() as f32;
+            let y0 = g.y0 as f32 / font.texture_height() as f32;
+            let y1 = g.y1 as f32 / font.texture_height() as f32;
+
+            let w = g.width();
+            let h = g.height();
+
+            let vtx_index = vtx_data.len() as u32;
+            // Todo(optimization, avx, simd): TVertex has been padded with an extra float, (sizeof TVertex == 8 * 4 bytes == 128 bit. Should be *extremely* friendly for SIMD purposes now)
+
+            vtx_data.push(TVertex::new(xpos, ypos + h, x0, y0, red, green, blue));
+            vtx_data.push(TVertex::new(xpos, ypos, x0, y1, red, green, blue));
+            vtx_data.push(TVertex::new(xpos + w, ypos, x1, y1, red, green, blue));
+            vtx_data.push(TVertex::new(xpos + w, ypos + h, x1, y0, red, green, blue));
+
+            indices.extend_from_slice(&[
+                vtx_index,
+                vtx_index + 1,
+                vtx_index + 2,
+                vtx_index,
+                vtx_index + 2,
+                vtx_index + 3,
+            ]);
+            current_x += g.advance;
+        } else {
+            let mut buf = [0; 4];
+            c.encode_utf16(&mut buf);
+            panic!("Could not find glyph for {}, {:?}", c, buf);
+        }
+    }
+}
+
 // Calculates the size required for the bounding box to cover to be able to hold this text
 pub fn calculate_text_dimensions(text: &[char], font: &Font) -> Size {
     let mut size = Size { width: 0, height: font.row_height() };
     let mut max_x = 0;
+    let mut previous = None;
     for (index, &c) in text.iter().enumerate() {
         if c == '\n' {
             size.height += font.row_height();
             size.width = 0;
+            previous = None;
         } else {
             let c = if c == '<' || c == '>' || c == '!' {
                 if let Some('=') = text.get(index + 1) {
@@ -275,12 +344,18 @@ pub fn calculate_text_dimensions(text: &[char], font: &Font) -> Size {
                 c
             };
             if c == '=' {
-                size.width += match text.get(index - 1) {
+                let glyph = match text.get(index - 1) {
                     Some('<') | Some('>') | Some('!') => None,
                     _ => font.get_glyph(c),
+                };
+                if glyph.is_some() {
+                    size.width += previous.map_or(0, |prev| font.kerning(prev, c));
+                    previous = Some(c);
                 }
-                .map_or(0, |g| g.advance);
+                size.width += glyph.map_or(0, |g| g.advance);
             } else {
+                size.width += previous.map_or(0, |prev| font.kerning(prev, c));
+                previous = Some(c);
                 size.width += font.get_glyph(c).unwrap().advance;
             }
         }
@@ -295,10 +370,12 @@ pub fn calculate_text_dimensions_iter(text: &str, font: &Font) -> Size {
     let mut size = Size { width: 0, height: font.row_height() };
     let mut max_x = 0;
 
+    let mut previous = None;
     for (index, c) in text.chars().enumerate() {
         if c == '\n' {
             size.height += font.row_height();
             size.width = 0;
+            previous = None;
         } else {
             let c = if c == '<' || c == '>' || c == '!' {
                 if let Some("=") = text.get(index + 1..index + 2) {
@@ -317,12 +394,18 @@ pub fn calculate_text_dimensions_iter(text: &str, font: &Font) -> Size {
                 c
             };
             if c == '=' {
-                size.width += match text.get(index - 1..index) {
+                let glyph = match text.get(index - 1..index) {
                     Some("<") | Some(">") | Some("!") => None,
                     _ => font.get_glyph(c),
+                };
+                if glyph.is_some() {
+                    size.width += previous.map_or(0, |prev| font.kerning(prev, c));
+                    previous = Some(c);
                 }
-                .map_or(0, |g| g.advance);
+                size.width += glyph.map_or(0, |g| g.advance);
             } else {
+                size.width += previous.map_or(0, |prev| font.kerning(prev, c));
+                previous = Some(c);
                 size.width += font.get_glyph(c).unwrap().advance;
             }
         }
@@ -332,3 +415,61 @@ pub fn calculate_text_dimensions_iter(text: &str, font: &Font) -> Size {
     size.width = max_x;
     size
 }
+
+#[cfg(test)]
+mod text_renderer_tests {
+    use super::{aligned_x, calculate_text_dimensions_iter, Align};
+    use crate::datastructure::generic::Vec2i;
+    use crate::opengl::arena::RenderArena;
+    use crate::opengl::types::RGBColor;
+    use crate::ui::basic::boundingbox::BoundingBox;
+    use crate::ui::font::{Font, SOURCE_CODE_PRO_REGULAR};
+    use std::rc::Rc;
+
+    #[test]
+    fn str_and_chars_produce_the_same_quads() {
+        let characters: Vec<char> = (' '..='~').chain(std::iter::once('\u{2264}')).collect();
+        let font = Rc::new(Font::without_texture(SOURCE_CODE_PRO_REGULAR, 14, &characters));
+        let text = "let a <= b;\nfoo";
+        let chars: Vec<char> = text.chars().collect();
+
+        let mut from_str = RenderArena::with_capacity(0);
+        from_str.push_str(text, RGBColor::white(), 0, 100, font.clone());
+        from_str.push_str("x", RGBColor::black(), 50, 50, font.clone());
+        let mut from_chars = RenderArena::with_capacity(0);
+        from_chars.push_chars(&chars, RGBColor::white(), 0, 100, font.clone());
+        from_chars.push_chars(&['x'], RGBColor::black(), 50, 50, font);
+
+        // the new line takes no quad, and <= is drawn as the single character ≤
+        assert_eq!(from_str.quads(), 13 + 1);
+        assert_eq!(from_chars.vertices, from_str.vertices);
+        assert_eq!(from_chars.indices, from_str.indices);
+        let commands = |quads: &RenderArena<_, super::TextDrawCommand>| {
+            quads
+                .commands
+                .iter()
+                .map(|dc| (dc.data_indices.idx_buffer_idx, dc.data_indices.idx_count))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(commands(&from_str), vec![(0, 13 * 6), (13 * 6, 6)]);
+        assert_eq!(commands(&from_chars), commands(&from_str));
+    }
+
+    #[test]
+    fn aligned_text_lines_up_with_the_rect() {
+        let characters: Vec<char> = (' '..='~').collect();
+        let font = Font::without_texture(SOURCE_CODE_PRO_REGULAR, 14, &characters);
+        let rect = BoundingBox::new(Vec2i::new(100, 0), Vec2i::new(301, 20));
+        let width = calculate_text_dimensions_iter("42", &font).width;
+        assert!(width > 0 && width < rect.width());
+
+        assert_eq!(aligned_x(width, &rect, Align::Left), 100);
+        assert_eq!(aligned_x(width, &rect, Align::Right) + width, 301, "right aligned text ends at the right edge");
+        let x = aligned_x(width, &rect, Align::Center);
+        let (left_gap, right_gap) = (x - rect.min.x, rect.max.x - (x + width));
+        assert!((left_gap - right_gap).abs() <= 1, "centered with {} to the left, {} to the right", left_gap, right_gap);
+
+        // too wide to fit, it sticks out evenly on both sides
+        assert_eq!(aligned_x(rect.width() + 40, &rect, Align::Center), 80);
+    }
+}
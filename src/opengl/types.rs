@@ -7,7 +7,7 @@ pub struct UVCoordinates {
     pub v: glfloat,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct RGBColor {
     pub r: glfloat,
     pub g: glfloat,
@@ -27,6 +27,12 @@ impl RGBColor {
         RGBColor { r, g, b }
     }
 
+    /// Parses a color written like #rrggbb, with or without the #
+    pub fn from_hex(hex: &str) -> Option<RGBColor> {
+        let digits = hex.trim().trim_start_matches('#').len();
+        RGBAColor::from_hex(hex).filter(|_| digits == 6).map(RGBAColor::to_rgb)
+    }
+
     pub fn black() -> RGBColor {
         RGBColor { r: 0.0, g: 0.0, b: 0.0 }
     }
@@ -57,7 +63,7 @@ impl RGBColor {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct RGBAColor {
     pub r: glfloat,
     pub g: glfloat,
@@ -75,6 +81,18 @@ impl RGBAColor {
         RGBAColor { r, g, b, a }
     }
 
+    /// Parses a color written like #rrggbb, or #rrggbbaa, with or without the #
+    pub fn from_hex(hex: &str) -> Option<RGBAColor> {
+        let hex = hex.trim();
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        if !(hex.len() == 6 || hex.len() == 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let channel = |at: usize| u8::from_str_radix(&hex[at..at + 2], 16).ok().map(|c| c as glfloat / 255.0);
+        let a = if hex.len() == 8 { channel(6)? } else { 1.0 };
+        Some(RGBAColor { r: channel(0)?, g: channel(2)?, b: channel(4)?, a })
+    }
+
     pub fn black() -> RGBAColor {
         RGBAColor { r: 0.0, g: 0.0, b: 0.0, a: 1.0 }
     }
@@ -103,6 +121,23 @@ impl RGBAColor {
         let &RGBAColor { r, g, b, a } = self;
         Self::new(r + value, g + value, b + value, a)
     }
+
+    /// This color, drawn on top of bg. What's left of bg shining through is weighted by how transparent this color is
+    pub fn over(self, bg: RGBAColor) -> RGBAColor {
+        let a = self.a + bg.a * (1.0 - self.a);
+        if a <= 0.0 {
+            return RGBAColor::new(0.0, 0.0, 0.0, 0.0);
+        }
+        let channel = |fg: glfloat, bg_channel: glfloat| (fg * self.a + bg_channel * bg.a * (1.0 - self.a)) / a;
+        RGBAColor::new(channel(self.r, bg.r), channel(self.g, bg.g), channel(self.b, bg.b), a)
+    }
+
+    /// The color t of the way from this color to other, alpha included. t is clamped to 0.0 ..= 1.0
+    pub fn lerp(self, other: RGBAColor, t: f32) -> RGBAColor {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |from: glfloat, to: glfloat| from + (to - from) * t;
+        RGBAColor::new(mix(self.r, other.r), mix(self.g, other.g), mix(self.b, other.b), mix(self.a, other.a))
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -136,7 +171,7 @@ impl RectVertex {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct TextVertex {
     pub x: glfloat,
     pub y: glfloat,
@@ -184,3 +219,51 @@ impl Matrix {
         &self.data[0].a as *const _
     }
 }
+
+#[cfg(test)]
+mod types_tests {
+    use super::{RGBAColor, RGBColor};
+
+    #[test]
+    fn parse_hex_colors() {
+        assert_eq!(RGBAColor::from_hex("#ff8000"), Some(RGBAColor::new(1.0, 128.0 / 255.0, 0.0, 1.0)));
+        assert_eq!(RGBAColor::from_hex("FF800000"), Some(RGBAColor::new(1.0, 128.0 / 255.0, 0.0, 0.0)));
+        assert_eq!(RGBColor::from_hex(" #000000 "), Some(RGBColor::black()));
+        assert_eq!(RGBColor::from_hex("#ffffff80"), None);
+        for invalid in [
+            "",
+            "#",
+            "#fff",
+            "#ff800",
+            "#ff80000",
+            "#gg8000",
+            "##ff8000",
+            "#ff8000ff00",
+        ]
+        .iter()
+        {
+            assert_eq!(RGBAColor::from_hex(invalid), None, "{} is not a color", invalid);
+        }
+    }
+
+    #[test]
+    fn blend_over_background() {
+        let bg = RGBAColor::new(0.0, 0.0, 1.0, 1.0);
+        assert_eq!(RGBAColor::red().over(bg), RGBAColor::red());
+        assert_eq!(RGBAColor::new(1.0, 0.0, 0.0, 0.0).over(bg), bg);
+        assert_eq!(RGBAColor::new(1.0, 0.0, 0.0, 0.5).over(bg), RGBAColor::new(0.5, 0.0, 0.5, 1.0));
+        // on a transparent background, the color stays what it is
+        let half_red = RGBAColor::new(1.0, 0.0, 0.0, 0.5);
+        assert_eq!(half_red.over(RGBAColor::new(0.0, 0.0, 0.0, 0.0)), half_red);
+    }
+
+    #[test]
+    fn interpolate_between_colors() {
+        let (from, to) = (RGBAColor::black(), RGBAColor::new(1.0, 0.5, 0.25, 0.0));
+        assert_eq!(from.lerp(to, 0.0), from);
+        assert_eq!(from.lerp(to, 1.0), to);
+        assert_eq!(from.lerp(to, 0.5), RGBAColor::new(0.5, 0.25, 0.125, 0.5));
+        assert_eq!(from.lerp(to, -1.0), from);
+        assert_eq!(from.lerp(to, 2.0), to);
+    }
+}
@@ -1,4 +1,9 @@
 use super::contiguous::contiguous::ContiguousBuffer;
+use super::CharBuffer;
+use std::path::Path;
+
+/// The id of a buffer, unique among the buffers requested from Buffers
+pub type BufferId = u32;
 
 pub struct Buffers {
     buffers: Vec<Box<ContiguousBuffer>>,
@@ -6,11 +11,13 @@ pub struct Buffers {
     /// necessarily as many that are in buffers, so not buffers.len(), since a View might request a buffer
     /// and the view will take ownership and store the Box inside itself, then hand it back, if it wants to switch to editing another buffer for instace
     live_buffer_ids: Vec<u32>,
+    /// The id the next requested buffer gets. Only ever goes up, so that an id isn't handed out again after its buffer is destroyed
+    next_buffer_id: u32,
 }
 
 impl Buffers {
     pub fn new() -> Self {
-        Buffers { buffers: vec![], live_buffer_ids: vec![] }
+        Buffers { buffers: vec![], live_buffer_ids: vec![], next_buffer_id: 0 }
     }
 
     /// Creates an un-managed text buffer. Useful for text views that do not have multiple buffers, or have some buffer managing logic of it's own
@@ -19,9 +26,15 @@ impl Buffers {
     }
 
     pub fn request_new_buffer(&mut self) -> Box<ContiguousBuffer> {
-        let buf = ContiguousBuffer::new(self.live_buffer_ids.len() as _, 1024);
-        self.live_buffer_ids.push(self.live_buffer_ids.len() as _);
-        Box::new(buf)
+        let id = self.next_buffer_id;
+        self.next_buffer_id += 1;
+        self.live_buffer_ids.push(id);
+        Box::new(ContiguousBuffer::new(id, 1024))
+    }
+
+    /// The buffers held here, that no view has taken, as (id, file name, dirty). Dirty buffers have changes that are not saved
+    pub fn iter(&self) -> impl Iterator<Item = (BufferId, Option<&Path>, bool)> {
+        self.buffers.iter().map(|b| (b.id, b.file_name(), !b.pristine()))
     }
 
     pub fn take_buffer(&mut self, id: u32) -> Option<Box<ContiguousBuffer>> {
@@ -42,3 +55,80 @@ impl Buffers {
         drop(buffer);
     }
 }
+
+/// How a buffer is listed in the input box, for the user to switch to
+pub fn list_item(id: BufferId, file_name: Option<&Path>, dirty: bool) -> String {
+    let name = file_name.map_or("unnamed buffer".to_string(), |p| p.display().to_string());
+    format!("{}: {}{}", id, name, if dirty { " [modified]" } else { "" })
+}
+
+/// The id of a buffer, listed with list_item
+pub fn buffer_id_of_list_item(item: &str) -> Option<BufferId> {
+    item.split(':').next().and_then(|id| id.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod buffers_tests {
+    use super::{buffer_id_of_list_item, list_item, Buffers};
+    use crate::textbuffer::CharBuffer;
+    use std::path::Path;
+
+    #[test]
+    fn iterate_buffers_with_name_and_dirty_state() {
+        let mut buffers = Buffers::new();
+        let mut named = buffers.request_new_buffer();
+        named.set_file_name(Path::new("src/main.rs"));
+        let mut edited = buffers.request_new_buffer();
+        edited.insert('a', true);
+        let untouched = buffers.request_new_buffer();
+        let ids = (named.id, edited.id, untouched.id);
+        buffers.give_back_buffer(named);
+        buffers.give_back_buffer(edited);
+        buffers.give_back_buffer(untouched);
+
+        let listed: Vec<_> = buffers
+            .iter()
+            .map(|(id, path, dirty)| (id, path.map(|p| p.to_path_buf()), dirty))
+            .collect();
+        // the file name is part of the checksum, so a name not yet saved to is a change as well
+        assert_eq!(
+            listed,
+            vec![
+                (ids.0, Some(Path::new("src/main.rs").to_path_buf()), true),
+                (ids.1, None, true),
+                (ids.2, None, false)
+            ]
+        );
+
+        let taken = buffers.take_buffer(ids.1).unwrap();
+        buffers.destroy_buffer(taken);
+        assert_eq!(buffers.iter().count(), 2);
+    }
+
+    #[test]
+    fn ids_are_never_handed_out_twice() {
+        let mut buffers = Buffers::new();
+        let first = buffers.request_new_buffer();
+        let last = buffers.request_new_buffer();
+        let (first_id, last_id) = (first.id, last.id);
+        buffers.give_back_buffer(first);
+
+        // destroying the buffer with the highest id doesn't make its id free to take
+        buffers.destroy_buffer(last);
+        let next = buffers.request_new_buffer();
+        assert_ne!(next.id, last_id);
+        assert_ne!(next.id, first_id);
+        let taken = buffers.take_buffer(first_id).unwrap();
+        buffers.destroy_buffer(taken);
+        assert!(buffers.request_new_buffer().id > next.id);
+    }
+
+    #[test]
+    fn list_items_carry_the_buffer_id() {
+        let item = list_item(7, Some(Path::new("README.md")), true);
+        assert_eq!(item, "7: README.md [modified]");
+        assert_eq!(buffer_id_of_list_item(&item), Some(7));
+        assert_eq!(buffer_id_of_list_item(&list_item(0, None, false)), Some(0));
+        assert_eq!(buffer_id_of_list_item("README.md"), None);
+    }
+}
@@ -7,15 +7,20 @@ use std::{
 };
 
 use super::super::{cursor::BufferCursor, CharBuffer, Movement};
+use super::transaction::Transaction;
 use crate::{
     debugger_catch, only_in_debug,
     textbuffer::{
         cursor::MetaCursor,
+        indent::{self, IndentPolicy},
+        marks::Marks,
         metadata::{self, calculate_hash},
-        operations::{History, OperationParameter},
+        operations::{self, CaseMode, CoalescePolicy, History, Operation, OperationParameter},
+        reflow,
+        symbols::{definitions_of, scan_symbols, Symbol},
         LineOperation, TextKind,
     },
-    utils::{copy_slice_to, AsUsize},
+    utils::{chars_match, copy_slice_to, smart_case_sensitive, AsUsize},
 };
 
 #[cfg(debug_assertions)]
@@ -27,8 +32,21 @@ pub struct ContiguousBuffer {
     edit_cursor: BufferCursor,
     pub meta_cursor: Option<MetaCursor>,
     history: History,
+    /// Named positions, moved along with every edit
+    marks: Marks,
     size: usize,
     meta_data: metadata::MetaData,
+    /// When set, all edits to the buffer are ignored. Cursor movement and selection still works as normal
+    read_only: bool,
+    /// Search all lower case needles case insensitively, see utils::smart_case_sensitive
+    pub smart_case: bool,
+    /// Cursor and meta cursor from before each expand_selection, so that shrink_selection can step back through them
+    expansions: Vec<(metadata::Index, Option<MetaCursor>)>,
+    /// The selection the last expand_selection made. If the selection has changed since, the expansions no longer apply
+    expanded_to: Option<(metadata::Index, metadata::Index)>,
+    /// Where the last move up or down left the cursor, the revision of the text then, and the column the moves aim for, see
+    /// goal_column
+    goal: Option<(metadata::Index, u64, metadata::Column)>,
 }
 
 impl std::hash::Hash for ContiguousBuffer {
@@ -39,15 +57,83 @@ impl std::hash::Hash for ContiguousBuffer {
 
 impl ContiguousBuffer {
     pub fn new(id: u32, capacity: usize) -> ContiguousBuffer {
-        ContiguousBuffer {
+        let mut buffer = ContiguousBuffer {
             id,
             data: Vec::with_capacity(capacity),
             edit_cursor: BufferCursor::default(),
             meta_cursor: None,
             history: History::new(),
+            marks: Marks::default(),
             size: 0,
             meta_data: metadata::MetaData::new(None),
-        }
+            read_only: false,
+            smart_case: true,
+            expansions: vec![],
+            expanded_to: None,
+            goal: None,
+        };
+        // a new buffer has no changes to save
+        let hash = calculate_hash(&buffer);
+        buffer.meta_data.set_checksum(hash);
+        buffer.meta_data.set_pristine_hash(hash);
+        buffer
+    }
+
+    /// Whether or not edits to this buffer are currently suppressed
+    pub fn read_only(&self) -> bool {
+        self.read_only
+    }
+
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    /// Names the buffer after a file that doesn't exist (yet), so that it's saved there without asking for a file name
+    pub fn set_file_name(&mut self, path: &Path) {
+        self.meta_data.file_name = Some(path.to_path_buf());
+    }
+
+    /// Sets how many operations can be undone at most. Older operations are forgotten
+    pub fn set_history_limit(&mut self, max_operations: usize) {
+        self.history.set_max_operations(max_operations);
+    }
+
+    /// Sets how consecutive deletes are grouped into undo steps
+    pub fn set_coalesce_policy(&mut self, policy: CoalescePolicy) {
+        self.history.set_coalesce_policy(policy);
+    }
+
+    /// Edits made within interval_ms of each other are undone together. None undoes them one by one
+    pub fn set_undo_group_interval(&mut self, interval_ms: Option<u64>) {
+        self.history.set_group_interval(interval_ms);
+    }
+
+    /// Records the insertion of ch at in the history, moving the marks along with it
+    fn record_insert(&mut self, at: metadata::Index, ch: char) {
+        self.marks.inserted(at, 1);
+        self.history.push_insert(at, ch);
+    }
+
+    /// Records the deletion of ch at in the history, moving the marks along with it
+    fn record_delete(&mut self, at: metadata::Index, ch: char) {
+        self.marks.deleted(at, 1);
+        self.history.push_delete(at, ch);
+    }
+
+    fn record_insert_range(&mut self, at: metadata::Index, text: String) {
+        self.marks.inserted(at, text.chars().count());
+        self.history.push_insert_range(at, text);
+    }
+
+    fn record_delete_range(&mut self, at: metadata::Index, text: String) {
+        self.marks.deleted(at, text.chars().count());
+        self.history.push_delete_range(at, text);
+    }
+
+    /// Records operations as one undo step, moving the marks along with each of them in turn
+    fn record_group(&mut self, operations: Vec<Operation>) {
+        operations.iter().for_each(|operation| self.marks.follow(operation));
+        self.history.push_group(operations);
     }
 
     pub fn debug_print_history(&self) {
@@ -82,6 +168,14 @@ impl ContiguousBuffer {
         unsafe { &self.data.get_unchecked(range.clone()) }
     }
 
+    /// Returns the contents of range as a String. The range is clamped to the buffer, so a range reaching past the end
+    /// (like a view range that hasn't caught up with a deletion yet) only returns what's actually there
+    pub fn text_in_range(&self, range: std::ops::Range<usize>) -> String {
+        let end = min(range.end, self.len());
+        let begin = min(range.start, end);
+        String::from_iter(self.get_slice(begin..end))
+    }
+
     pub fn get_lines_as_slices(&self, first: metadata::Line, last: metadata::Line) -> Vec<&[char]> {
         debug_assert!(first < last, "Last line must come after first line");
         let mut res = Vec::with_capacity(*(last - first));
@@ -103,11 +197,22 @@ impl ContiguousBuffer {
         })
     }
 
+    /// The spaces and tabs that line begins with. Empty if the line isn't indented, or doesn't exist
+    pub fn line_indent(&self, line: metadata::Line) -> &[char] {
+        match self.meta_data.get_line_info(line) {
+            Some((begin, length)) => indent::leading_whitespace(&self.data[*begin..*begin + *length]),
+            None => &[],
+        }
+    }
+
     pub fn get_cursor(&self) -> &BufferCursor {
         &self.edit_cursor
     }
 
     pub fn insert_slice(&mut self, slice: &[char]) {
+        if self.read_only {
+            return;
+        }
         if let Some(mc) = &self.meta_cursor {
             match *mc {
                 MetaCursor::Absolute(marker) => {
@@ -117,6 +222,7 @@ impl ContiguousBuffer {
                         (*self.edit_cursor.pos, *marker)
                     };
                     self.data.drain(erase_from..=erase_to);
+                    self.marks.deleted(metadata::Index(erase_from), erase_to + 1 - erase_from);
                     self.meta_cursor = None;
                     self.size = self.data.len();
                     self.rebuild_metadata();
@@ -142,8 +248,8 @@ impl ContiguousBuffer {
                 let new_abs_cursor_pos = metadata::Index(abs as usize + slice.len());
                 self.size = v.len();
                 self.data = v;
-                self.rebuild_metadata();
-                self.meta_data.set_buffer_size(self.size);
+                self.text_inserted(metadata::Index(abs as usize), slice.len());
+                self.marks.inserted(metadata::Index(abs as usize), slice.len());
                 self.edit_cursor = self.cursor_from_metadata(new_abs_cursor_pos).unwrap();
             }
         } else {
@@ -182,27 +288,15 @@ impl ContiguousBuffer {
                 if count == 1 {
                     if let Some(&c) = self.get(self.edit_cursor.absolute()) {
                         if c.is_alphanumeric() {
-                            self.edit_cursor = self.find_next(|c| c.is_whitespace()).unwrap_or(BufferCursor {
-                                pos: metadata::Index(self.len()),
-                                row: metadata::Line(self.meta_data.line_count() - 1),
-                                col: metadata::Column(
-                                    self.meta_data
-                                        .get_line_start_index(metadata::Line(self.meta_data.line_count() - 1))
-                                        .map(|v| self.len() - *v)
-                                        .unwrap(),
-                                ),
-                            });
+                            self.edit_cursor = self
+                                .find_next(|c| c.is_whitespace())
+                                .or_else(|| self.cursor_from_metadata(metadata::Index(self.len())))
+                                .unwrap_or_default();
                         } else if c.is_whitespace() {
-                            self.edit_cursor = self.find_next(|c| c.is_alphanumeric()).unwrap_or(BufferCursor {
-                                pos: metadata::Index(self.len()),
-                                row: metadata::Line(self.meta_data.line_count() - 1),
-                                col: metadata::Column(
-                                    self.meta_data
-                                        .get_line_start_index(metadata::Line(self.meta_data.line_count() - 1))
-                                        .map(|v| self.len() - *v)
-                                        .unwrap(),
-                                ),
-                            });
+                            self.edit_cursor = self
+                                .find_next(|c| c.is_alphanumeric())
+                                .or_else(|| self.cursor_from_metadata(metadata::Index(self.len())))
+                                .unwrap_or_default();
                         }
                     }
                 } else {
@@ -219,6 +313,23 @@ impl ContiguousBuffer {
                     self.move_cursor(Movement::End(TextKind::Block));
                 }
             }
+            TextKind::Paragraph => {
+                for _ in 0..count {
+                    let (row, last) = (self.cursor_row(), self.meta_data.last_line());
+                    let mut line = row;
+                    while line < last && self.is_blank_line(line) {
+                        line = line.offset(1);
+                    }
+                    while line < last && !self.is_blank_line(line) {
+                        line = line.offset(1);
+                    }
+                    match self.meta_data.get_line_start_index(line) {
+                        Some(start) if line > row && self.is_blank_line(line) => self.cursor_goto(start),
+                        _ => self.cursor_goto(metadata::Index(self.len())),
+                    }
+                }
+            }
+            TextKind::File => self.cursor_goto(metadata::Index(self.len())),
             _ => {
                 todo!("TextKind::{:?} not yet implemented", kind)
             }
@@ -276,6 +387,23 @@ impl ContiguousBuffer {
                     self.move_cursor(Movement::Begin(TextKind::Block));
                 }
             }
+            TextKind::Paragraph => {
+                for _ in 0..count {
+                    let row = self.cursor_row();
+                    let mut line = row;
+                    while *line > 0 && self.is_blank_line(line) {
+                        line = line.offset(-1);
+                    }
+                    while *line > 0 && !self.is_blank_line(line) {
+                        line = line.offset(-1);
+                    }
+                    match self.meta_data.get_line_start_index(line) {
+                        Some(start) if line < row && self.is_blank_line(line) => self.cursor_goto(start),
+                        _ => self.cursor_goto(metadata::Index(0)),
+                    }
+                }
+            }
+            TextKind::File => self.cursor_goto(metadata::Index(0)),
             _ => {
                 todo!("TextKind::{:?} not yet implemented", kind)
             }
@@ -301,19 +429,40 @@ impl ContiguousBuffer {
                 MetaCursor::LineRange { column, begin, end } => todo!(),
             }
         } else {
-            let row = self.edit_cursor.row;
-            self.meta_data
-                .get_line_start_index(row)
-                .zip(
-                    self.meta_data
-                        .get_line_start_index(row.offset(1))
-                        .or_else(|| Some(metadata::Index(self.len()))),
-                )
-                .map(|(begin, end)| String::from_iter(self.get_slice(*begin..*end)))
+            self.cursor_line_range().map(|line| String::from_iter(self.get_slice(line)))
+        }
+    }
+
+    /// The range of the line the cursor is on, from its beginning up to the beginning of the next line, so including its new line
+    /// character. The last line ends at the end of the buffer
+    fn cursor_line_range(&self) -> Option<std::ops::Range<usize>> {
+        let row = self.edit_cursor.row;
+        let begin = self.meta_data.get_line_start_index(row)?;
+        let end = self
+            .meta_data
+            .get_line_start_index(row.offset(1))
+            .unwrap_or(metadata::Index(self.len()));
+        Some(*begin..*end)
+    }
+
+    /// Selects the line the cursor is on, along with its new line character, so that line operations can work on the selection
+    /// like on any other. Returns false if there's nothing to select; the cursor is on an empty last line
+    pub fn select_line(&mut self) -> bool {
+        match self.cursor_line_range().filter(|line| !line.is_empty()) {
+            Some(line) => {
+                self.set_absolute_meta_cursor(metadata::Index(line.start));
+                self.cursor_goto(metadata::Index(line.end - 1));
+                true
+            }
+            None => false,
         }
     }
 
     pub fn cut_range_or_line(&mut self) -> Option<String> {
+        if self.read_only {
+            // we can't remove anything from a read only buffer, so cutting degrades to copying
+            return self.copy_range_or_line();
+        }
         if let Some(meta_cursor) = &self.meta_cursor {
             match *meta_cursor {
                 MetaCursor::Absolute(meta_cursor) => {
@@ -322,12 +471,12 @@ impl ContiguousBuffer {
                     } else {
                         if meta_cursor < self.edit_cursor.pos {
                             let res: String = self.data.drain(*meta_cursor..*self.edit_cursor.pos.offset(1)).collect();
-                            self.history.push_delete_range(meta_cursor, res.clone());
+                            self.record_delete_range(meta_cursor, res.clone());
                             self.rebuild_metadata();
                             Some(res)
                         } else {
                             let res: String = self.data.drain(*self.edit_cursor.pos..*meta_cursor.offset(1)).collect();
-                            self.history.push_delete_range(meta_cursor, res.clone());
+                            self.record_delete_range(meta_cursor, res.clone());
                             self.rebuild_metadata();
                             Some(res)
                         }
@@ -337,21 +486,471 @@ impl ContiguousBuffer {
                 MetaCursor::LineRange { column, begin, end } => todo!(),
             }
         } else {
-            let row = self.edit_cursor.row;
-            self.meta_data
-                .get_line_start_index(row)
-                .zip(
-                    self.meta_data
-                        .get_line_start_index(row.offset(1))
-                        .or_else(|| Some(metadata::Index(self.len()))),
-                )
-                .map(|(begin, end)| {
-                    let res: String = self.data.drain(*begin..*end).collect();
-                    self.history.push_delete_range(begin, res.clone());
-                    self.rebuild_metadata();
-                    res
-                })
+            self.cursor_line_range().map(|line| {
+                let res: String = self.data.drain(line.clone()).collect();
+                self.record_delete_range(metadata::Index(line.start), res.clone());
+                self.rebuild_metadata();
+                res
+            })
+        }
+    }
+
+    /// Duplicates the selected text by inserting a copy of it directly after the selection, and then selects the copy.
+    /// If the selection spans whole lines, this means the copy ends up below the selected lines. If nothing is selected
+    /// the line the cursor is on gets duplicated below it instead. Either way, it's recorded as one operation in the history
+    pub fn duplicate_selection_or_line(&mut self) {
+        use metadata::Index;
+        if self.read_only || self.empty() {
+            return;
+        }
+        if let Some((begin, end)) = self.get_selection() {
+            let end = Index(min(*end, self.len() - 1));
+            if begin > end {
+                return;
+            }
+            let copy: Vec<char> = self.data[*begin..=*end].to_vec();
+            let insert_at = end.offset(1);
+            self.data.splice(*insert_at..*insert_at, copy.iter().copied());
+            self.record_insert_range(insert_at, copy.iter().collect());
+            self.size = self.data.len();
+            self.rebuild_metadata();
+            self.meta_data.set_buffer_size(self.size);
+            self.cursor_goto(insert_at.offset(copy.len() as isize - 1));
+            self.set_absolute_meta_cursor(insert_at);
+        } else {
+            let row = self.cursor_row();
+            let col = self.cursor_col();
+            let line_begin = self.meta_data.get_line_start_index(row).unwrap_or(Index(0));
+            let line_end = self.meta_data.get_line_start_index(row.offset(1)).unwrap_or(Index(self.len()));
+            let mut copy: Vec<char> = self.data[*line_begin..*line_end].to_vec();
+            // the last line has no trailing new line, so the copy has to begin with one instead
+            let new_line_begin = if copy.last() == Some(&'\n') {
+                line_end
+            } else {
+                copy.insert(0, '\n');
+                line_end.offset(1)
+            };
+            self.data.splice(*line_end..*line_end, copy.iter().copied());
+            self.record_insert_range(line_end, copy.iter().collect());
+            self.size = self.data.len();
+            self.rebuild_metadata();
+            self.meta_data.set_buffer_size(self.size);
+            self.cursor_goto(new_line_begin.offset(*col as _));
+        }
+    }
+
+    /// Appends text to the end of the buffer, without recording it in the history, whether the buffer is read only or not; for text
+    /// that's streamed into a view, like the output of a command, rather than typed. The selection is dropped and the cursor is
+    /// placed at the end
+    pub fn append_unrecorded(&mut self, text: &[char]) {
+        let end = self.len();
+        self.data.extend_from_slice(text);
+        self.size = self.data.len();
+        self.marks.inserted(metadata::Index(end), text.len());
+        // the lines begun in text come after all of the lines there were
+        for (i, _) in text.iter().enumerate().filter(|(_, c)| **c == '\n') {
+            self.meta_data.push_new_line_begin(metadata::Index(end + i + 1));
+        }
+        self.meta_data.set_buffer_size(self.size);
+        self.meta_cursor = None;
+        self.cursor_goto(metadata::Index(self.size));
+    }
+
+    /// Inserts text as whole lines, above the line of the cursor, keeping the cursor where it was in the text. A trailing
+    /// new line is added if text doesn't have one, so that the text always ends up on lines of its own
+    pub fn insert_lines_above_cursor(&mut self, text: &str) {
+        use metadata::Index;
+        if self.read_only || text.is_empty() {
+            return;
+        }
+        let mut lines: Vec<char> = text.chars().collect();
+        if lines.last() != Some(&'\n') {
+            lines.push('\n');
+        }
+        let col = self.cursor_col();
+        let line_begin = self.meta_data.get_line_start_index(self.cursor_row()).unwrap_or(Index(0));
+        self.data.splice(*line_begin..*line_begin, lines.iter().copied());
+        self.record_insert_range(line_begin, lines.iter().collect());
+        self.size = self.data.len();
+        self.rebuild_metadata();
+        self.meta_data.set_buffer_size(self.size);
+        self.cursor_goto(line_begin.offset((lines.len() + *col) as _));
+    }
+
+    /// Inserts each line of text at the cursor's column, on consecutive lines starting at the cursor's line. Lines too short to
+    /// reach the column are padded with spaces, and new lines are added at the end of the buffer if needed. Undone in one step
+    pub fn insert_block(&mut self, text: &str) {
+        use metadata::Index;
+        if self.read_only || text.is_empty() {
+            return;
+        }
+        let origin = self.cursor_abs();
+        let col = *self.cursor_col();
+        let first_line = self.meta_data.get_line_start_index(self.cursor_row()).unwrap_or(Index(0));
+        self.transaction(|edits| {
+            // where the line that the next line of text goes on begins, None once past the last line of the buffer
+            let mut line_begin = Some(*first_line);
+            for line in text.strip_suffix('\n').unwrap_or(text).split('\n') {
+                let mut insertion = vec![];
+                let insert_at = match line_begin {
+                    Some(begin) => {
+                        let length = edits.text()[begin..]
+                            .iter()
+                            .position(|&c| c == '\n')
+                            .unwrap_or(edits.text().len() - begin);
+                        insertion.extend(std::iter::repeat(' ').take(col.saturating_sub(length)));
+                        begin + min(col, length)
+                    }
+                    None => {
+                        insertion.push('\n');
+                        insertion.extend(std::iter::repeat(' ').take(col));
+                        edits.text().len()
+                    }
+                };
+                insertion.extend(line.chars());
+                edits.insert(Index(insert_at), &insertion);
+                let inserted_end = insert_at + insertion.len();
+                line_begin = edits.text()[inserted_end..]
+                    .iter()
+                    .position(|&c| c == '\n')
+                    .map(|newline| inserted_end + newline + 1);
+            }
+        });
+        self.cursor_goto(origin);
+    }
+
+    /// Wraps the selected text in the pair `open` and `close`, keeping the (now enclosed) text selected.
+    /// Recorded as one operation in the history. Returns false, without doing anything, if nothing is selected
+    pub fn wrap_selection(&mut self, open: char, close: char) -> bool {
+        use metadata::Index;
+        if self.read_only || self.empty() {
+            return false;
+        }
+        if let Some((begin, end)) = self.get_selection() {
+            let end = Index(min(*end, self.len() - 1));
+            if begin > end {
+                return false;
+            }
+            let cursor_at_begin = self.edit_cursor.pos == begin;
+            self.data.insert(*end + 1, close);
+            self.data.insert(*begin, open);
+            self.record_group(vec![
+                Operation::Insert(begin, OperationParameter::Char(open)),
+                Operation::Insert(end.offset(2), OperationParameter::Char(close)),
+            ]);
+            self.size = self.data.len();
+            self.rebuild_metadata();
+            self.meta_data.set_buffer_size(self.size);
+            let (inner_begin, inner_end) = (begin.offset(1), end.offset(1));
+            if cursor_at_begin {
+                self.cursor_goto(inner_begin);
+                self.set_absolute_meta_cursor(inner_end);
+            } else {
+                self.cursor_goto(inner_end);
+                self.set_absolute_meta_cursor(inner_begin);
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    /// The lines spanned by the selection (see MetaData::lines_spanning), or all lines in the buffer, if nothing is selected
+    pub fn selected_lines_or_all(&self) -> std::ops::Range<usize> {
+        match self.get_selection() {
+            Some((begin, end)) => self.meta_data.lines_spanning(begin, end),
+            None => 0..self.meta_data.line_count(),
+        }
+    }
+
+    /// Sorts the lines in the range. Whether the last sorted line ends with a new line or not is kept as it was, so sorting the last lines
+    /// of a buffer without a trailing new line, doesn't add one. Undone in one step
+    pub fn sort_lines(&mut self, lines: std::ops::Range<usize>, descending: bool, case_insensitive: bool) {
+        self.rewrite_lines(lines, |lines| {
+            if case_insensitive {
+                lines.sort_by_cached_key(|line| line.to_lowercase());
+            } else {
+                lines.sort();
+            }
+            if descending {
+                lines.reverse();
+            }
+        });
+    }
+
+    /// Removes lines in the range that are duplicates of an earlier line, or only those that duplicate the line right before them
+    /// if adjacent_only is set (like uniq does). Returns the number of lines removed. Undone in one step
+    pub fn dedup_lines(&mut self, lines: std::ops::Range<usize>, adjacent_only: bool) -> usize {
+        self.rewrite_lines(lines, |lines| {
+            if adjacent_only {
+                lines.dedup();
+            } else {
+                let mut seen = std::collections::HashSet::new();
+                lines.retain(|line| seen.insert(*line));
+            }
+        })
+    }
+
+    /// Replaces the lines in the range, with what rewrite makes of them, recording it as one step in the history. Returns how many
+    /// fewer lines there are afterwards. The new line of the last line in the range is left out of what rewrite gets, and is kept as is
+    fn rewrite_lines<F: FnOnce(&mut Vec<&str>)>(&mut self, lines: std::ops::Range<usize>, rewrite: F) -> usize {
+        use metadata::{Index, Line};
+        let lines = lines.start..min(lines.end, self.meta_data.line_count());
+        if self.read_only || lines.len() < 2 {
+            return 0;
+        }
+        let (begin, end) = self.meta_data.get_byte_indices_of_lines(Line(lines.start), Line(lines.end));
+        let original: String = self.data[*begin..*end].iter().collect();
+        let (block, trailing_newline) = match original.strip_suffix('\n') {
+            Some(block) => (block, "\n"),
+            None => (original.as_str(), ""),
+        };
+        let mut rewritten: Vec<&str> = block.split('\n').collect();
+        let line_count = rewritten.len();
+        rewrite(&mut rewritten);
+        let removed = line_count - rewritten.len();
+        let rewritten = rewritten.join("\n") + trailing_newline;
+        if rewritten == original {
+            return removed;
+        }
+        let cursor = *self.edit_cursor.pos;
+        self.replace_range(begin..end, &rewritten.chars().collect::<Vec<_>>());
+        if removed > 0 {
+            // the selection no longer covers the same lines
+            self.meta_cursor = None;
+        }
+        self.cursor_goto(Index(min(cursor, self.len())));
+        removed
+    }
+
+    /// Replaces the text in range with `with`, recorded as a delete and an insert that are undone in one step. The cursor is
+    /// placed after the inserted text. Does nothing if the range is not within the buffer
+    pub fn replace_range(&mut self, range: std::ops::Range<metadata::Index>, with: &[char]) {
+        if self.read_only || range.start > range.end || *range.end > self.len() {
+            return;
+        }
+        let begin = range.start;
+        self.transaction(|edits| edits.replace_range(range, with));
+        self.cursor_goto(begin.offset(with.len() as _));
+    }
+
+    /// Makes the edits that `edits` makes to the transaction it's given, recorded as one step in the history, and rebuilds the
+    /// metadata once, after all of them. If `edits` panics, the buffer is left as it was. Returns what `edits` returns, or None if
+    /// the buffer is read only
+    pub fn transaction<R, F: FnOnce(&mut Transaction) -> R>(&mut self, edits: F) -> Option<R> {
+        if self.read_only {
+            return None;
         }
+        let mut transaction = Transaction::new(&mut self.data);
+        let result = edits(&mut transaction);
+        let operations = transaction.finish();
+        if !operations.is_empty() {
+            self.size = self.data.len();
+            // an insertion of a single range, like that of a file, moves the line begins after it along, instead of finding them all anew
+            match &operations[..] {
+                [Operation::Insert(at, OperationParameter::Range(text))] => self.text_inserted(*at, text.chars().count()),
+                _ => self.rebuild_metadata(),
+            }
+            self.record_group(operations);
+            if let Some(MetaCursor::Absolute(selection_begin)) = self.meta_cursor {
+                if *selection_begin > self.len() {
+                    self.meta_cursor = None;
+                }
+            }
+            self.cursor_goto(metadata::Index(min(*self.edit_cursor.pos, self.len())));
+        }
+        Some(result)
+    }
+
+    /// Updates the metadata for length characters, that are in the text already, having been inserted at at
+    fn text_inserted(&mut self, at: metadata::Index, length: usize) {
+        let new_line_begins: Vec<metadata::Index> = self.data[*at..*at + length]
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| **c == '\n')
+            .map(|(i, _)| at.offset(i as isize + 1))
+            .collect();
+        self.meta_data.text_inserted(at, length, &new_line_begins);
+        self.meta_data.set_buffer_size(self.size);
+        let cs = calculate_hash(self);
+        self.meta_data.set_checksum(cs);
+    }
+
+    /// Replaces all of the text with new, as one step in the history; how the output of a formatter is taken in. The cursor stays on
+    /// the line and column it was on, or as close to them as the new text allows. Returns false, leaving the buffer be, if it's read
+    /// only or new is the text it already has
+    pub fn replace_all_content(&mut self, new: &[char]) -> bool {
+        if self.read_only || self.data[..] == *new {
+            return false;
+        }
+        let (row, col) = (*self.edit_cursor.row, *self.edit_cursor.col);
+        let len = self.len();
+        self.transaction(|edits| edits.replace_range(metadata::Index(0)..metadata::Index(len), new));
+        self.meta_cursor = None;
+        let line = metadata::Line(min(row, self.meta_data.line_count() - 1));
+        let (begin, length) = self.meta_data.get_line_info(line).unwrap_or_default();
+        let line_end = *begin + *length;
+        let text_end = if line_end > *begin && self.data[line_end - 1] == '\n' { line_end - 1 } else { line_end };
+        self.cursor_goto(metadata::Index(min(*begin + col, text_end)));
+        true
+    }
+
+    /// Inserts the contents of the file at path at the cursor, in one go, and as one step in the history, and places the cursor after
+    /// them. Returns how many characters were inserted. A file that can't be read, or isn't UTF-8, leaves the buffer as it was
+    pub fn insert_file(&mut self, path: &Path) -> std::io::Result<usize> {
+        let contents: Vec<char> = std::fs::read_to_string(path)?.chars().collect();
+        let at = metadata::Index(min(*self.edit_cursor.pos, self.len()));
+        self.transaction(|edits| edits.insert(at, &contents))
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::PermissionDenied, "the buffer is read only"))?;
+        self.meta_cursor = None;
+        self.cursor_goto(at.offset(contents.len() as _));
+        Ok(contents.len())
+    }
+
+    /// Shrinks the selection so that it neither begins nor ends with whitespace, keeping the direction it was made in. Returns false
+    /// if nothing is selected, the selection is all whitespace, or there was nothing to trim
+    pub fn trim_selection(&mut self) -> bool {
+        let anchor = match self.meta_cursor {
+            Some(MetaCursor::Absolute(anchor)) => anchor,
+            _ => return false,
+        };
+        let (begin, end) = match self.get_selection() {
+            Some(selection) => selection,
+            None => return false,
+        };
+        let end = min(*end, self.len().saturating_sub(1));
+        let selected = match self.data.get(*begin..=end) {
+            Some(selected) => selected,
+            None => return false,
+        };
+        let (first, last) = match (selected.iter().position(|c| !c.is_whitespace()), selected.iter().rposition(|c| !c.is_whitespace())) {
+            (Some(first), Some(last)) => (*begin + first, *begin + last),
+            _ => return false,
+        };
+        if first == *begin && last == end {
+            return false;
+        }
+        let (anchor, cursor) = if anchor <= self.edit_cursor.pos { (first, last) } else { (last, first) };
+        self.set_absolute_meta_cursor(metadata::Index(anchor));
+        self.cursor_goto(metadata::Index(cursor));
+        true
+    }
+
+    /// Changes the case of the selection, or of the word at the cursor if nothing is selected, undone in one step. The selection
+    /// is kept, covering the transformed text. Returns false if there was nothing to transform, or nothing changed
+    pub fn transform_selection_case(&mut self, mode: CaseMode) -> bool {
+        if self.read_only {
+            return false;
+        }
+        let selected = self
+            .get_selection()
+            .map(|(begin, end)| (begin, metadata::Index(min(*end + 1, self.len()))));
+        let (begin, end) = match selected.or_else(|| self.word_at_cursor().map(|(_, begin, end)| (begin, end))) {
+            Some(range) => range,
+            None => return false,
+        };
+        let transformed = mode.apply(&self.data[*begin..*end]);
+        if transformed[..] == self.data[*begin..*end] {
+            return false;
+        }
+        let cursor = self.edit_cursor.pos;
+        self.replace_range(begin..end, &transformed);
+        if selected.is_some() && !transformed.is_empty() {
+            self.set_absolute_meta_cursor(begin);
+            self.cursor_goto(begin.offset(transformed.len() as isize - 1));
+        } else {
+            self.cursor_goto(metadata::Index(min(*cursor, self.len())));
+        }
+        true
+    }
+
+    /// Rewraps the paragraph that line at is in, so that its lines are no longer than width, where that can be helped. Every line
+    /// begins with the indentation (and comment marker) of line at; a paragraph ends at a blank line, or at a line that begins
+    /// differently. Undone in one step. Returns false if line at is blank, or nothing changed
+    pub fn reflow_paragraph(&mut self, at: metadata::Line, width: usize) -> bool {
+        if self.read_only {
+            return false;
+        }
+        let text: String = self.data.iter().collect();
+        let lines: Vec<&str> = text.split('\n').collect();
+        let at = *at;
+        let prefix = match lines.get(at) {
+            Some(line) => reflow::line_prefix(line),
+            None => return false,
+        };
+        let in_paragraph = |line: &str| reflow::line_prefix(line) == prefix && !line[prefix.len()..].trim().is_empty();
+        if !in_paragraph(lines[at]) {
+            return false;
+        }
+        let first = (0..at).rev().take_while(|&line| in_paragraph(lines[line])).last().unwrap_or(at);
+        let end = (at..lines.len()).take_while(|&line| in_paragraph(lines[line])).count() + at;
+        let words: Vec<&str> = lines[first..end]
+            .iter()
+            .flat_map(|line| line[prefix.len()..].split_whitespace())
+            .collect();
+        let original = lines[first..end].join("\n");
+        let wrapped = reflow::fill(&words, prefix, width).join("\n");
+        if wrapped == original {
+            return false;
+        }
+        let begin: usize = lines[..first].iter().map(|line| line.chars().count() + 1).sum();
+        let end = begin + original.chars().count();
+        self.replace_range(metadata::Index(begin)..metadata::Index(end), &wrapped.chars().collect::<Vec<_>>());
+        true
+    }
+
+    /// Inserts ch at the cursor, indented according to policy. A newline gets the indentation the policy decides on, or splits a pair
+    /// of brackets the cursor is between, and a '}' typed as the first non-whitespace on its line, gets lined up with its opening
+    /// brace. The indentation and ch are undone in one step.
+    /// Returns false if the policy has nothing to say about ch (or there's a selection), in which case ch has not been inserted
+    pub fn insert_indented(&mut self, ch: char, policy: IndentPolicy) -> bool {
+        use metadata::Index;
+        if self.read_only || !policy.enabled() || self.meta_cursor.is_some() {
+            return false;
+        }
+        let cursor = *self.edit_cursor.pos;
+        let line_begin = cursor - *self.edit_cursor.col;
+        let line_before_cursor = &self.data[line_begin..cursor];
+        // where the cursor goes, from replace_from, if not after the replacement
+        let mut cursor_offset = None;
+        let (replace_from, replacement) = match ch {
+            '\n' if policy.split_brackets && indent::between_brackets(&self.data, cursor) => {
+                let outer = if policy.keep_indent { indent::leading_whitespace(line_before_cursor) } else { &[] };
+                let inner: String = outer.iter().copied().chain(std::iter::repeat(' ').take(policy.tab_width)).collect();
+                cursor_offset = Some(1 + inner.chars().count());
+                (cursor, format!("\n{}\n{}", inner, outer.iter().collect::<String>()))
+            }
+            '\n' => {
+                let indent = policy.indent_after_newline(line_before_cursor);
+                if indent.is_empty() {
+                    return false;
+                }
+                (cursor, std::iter::once('\n').chain(indent).collect::<String>())
+            }
+            '}' if policy.braces && line_before_cursor.iter().all(|c| c.is_whitespace()) => {
+                if let Some(open) = indent::matching_bracket(&self.data, cursor, '{', '}') {
+                    let open_line = self.meta_data.get_line_number_of_buffer_index(Index(open)).unwrap_or(0);
+                    let indent = self.line_indent(metadata::Line(open_line));
+                    (line_begin, indent.iter().chain(std::iter::once(&'}')).collect::<String>())
+                } else {
+                    return false;
+                }
+            }
+            _ => return false,
+        };
+        let replaced: String = self.data.splice(replace_from..cursor, replacement.chars()).collect();
+        if replaced.is_empty() {
+            self.record_insert_range(Index(replace_from), replacement.clone());
+        } else {
+            self.record_group(vec![
+                Operation::Delete(Index(replace_from), OperationParameter::Range(replaced)),
+                Operation::Insert(Index(replace_from), OperationParameter::Range(replacement.clone())),
+            ]);
+        }
+        self.size = self.data.len();
+        self.rebuild_metadata();
+        self.cursor_goto(Index(replace_from + cursor_offset.unwrap_or_else(|| replacement.chars().count())));
+        true
     }
 
     /// Returns the (possibly) selected range. This always makes sure to return begin .. end, since the meta cursor can be both behind and in front
@@ -386,6 +985,13 @@ impl ContiguousBuffer {
     /// They explicitly only deal with absolute positions/indices, and before returning, calls this function
     /// to return an Option of a well formed BufferCursor
 
+    /// A line that's empty, or only holds whitespace. These separate paragraphs
+    fn is_blank_line(&self, line: metadata::Line) -> bool {
+        self.meta_data
+            .get_line_info(line)
+            .map_or(false, |(begin, length)| self.data[*begin..*begin + *length].iter().all(|c| c.is_whitespace()))
+    }
+
     fn find_index_of_prev_from(&self, start_position: metadata::Index, f: fn(char) -> bool) -> Option<metadata::Index> {
         self.data.get(0..=(*start_position)).and_then(|range| {
             range
@@ -423,7 +1029,7 @@ impl ContiguousBuffer {
     fn find_prev_newline_pos_from(&self, abs_pos: metadata::Index) -> Option<metadata::Index> {
         let abs_pos = *abs_pos;
         if abs_pos >= self.data.len() {
-            self.meta_data.line_begin_indices.last().map(|v| *v)
+            Some(self.meta_data.last_line_start_index())
         } else {
             let reversed_abs_position = self.data.len() - abs_pos;
             self.iter()
@@ -479,7 +1085,28 @@ impl ContiguousBuffer {
         }
     }
 
+    /// The column moves up and down aim for; the column the cursor was on when a run of them began, so that passing a short line
+    /// doesn't pull the cursor over to the left for good. Moving the cursor any other way, or editing the text, ends the run.
+    /// Home and End end it too, even when they leave the cursor where it was
+    fn goal_column(&self) -> metadata::Column {
+        match self.goal {
+            Some((at, revision, column)) if at == self.edit_cursor.pos && revision == self.meta_data.revision() => column,
+            _ => self.edit_cursor.col,
+        }
+    }
+
+    /// Makes the column the cursor is on, the one moves up and down aim for
+    pub fn reset_goal_column(&mut self) {
+        self.goal = None;
+    }
+
+    /// Keeps aiming for column, from where the cursor is now
+    fn keep_goal_column(&mut self, column: metadata::Column) {
+        self.goal = Some((self.edit_cursor.pos, self.meta_data.revision(), column));
+    }
+
     fn cursor_move_up(&mut self) {
+        let goal = self.goal_column();
         if self.cursor_row() == metadata::Line(0) {
             self.cursor_goto(metadata::Index(0));
         } else {
@@ -491,16 +1118,18 @@ impl ContiguousBuffer {
                     self.meta_data
                         .get_line_length_of(prior_line)
                         .map(|prior_line_len| {
-                            let pos = index.offset(min(prior_line_len.offset(-1).as_usize() as _, self.cursor_col().as_usize() as _));
+                            let pos = index.offset(min(prior_line_len.offset(-1).as_usize() as _, goal.as_usize() as _));
                             self.cursor_from_metadata(pos)
                         })
                         .unwrap_or(self.cursor_from_metadata(index))
                 })
                 .unwrap_or(BufferCursor::default())
         }
+        self.keep_goal_column(goal);
     }
 
     fn cursor_move_down(&mut self) {
+        let goal = self.goal_column();
         // This is all the lines up until the 3rd to last - normal behavior, 2nd to last means we are moving into the last, other behavior applies in the else branch
         let next_line_index = self.cursor_row().offset(1);
 
@@ -515,99 +1144,385 @@ impl ContiguousBuffer {
             .map(|l| l.as_column())
             .and_then(|next_line_length| {
                 if let Some(line_begin) = self.meta_data.get(self.edit_cursor.row.offset(1)) {
-                    let new_buffer_index = line_begin.offset(if self.cursor_col() <= next_line_length.offset(-1) {
-                        *self.cursor_col() as _
-                    } else {
-                        *(next_line_length.offset(-1)) as _
-                    });
+                    let new_buffer_index =
+                        line_begin.offset(if goal <= next_line_length.offset(-1) { *goal as _ } else { *(next_line_length.offset(-1)) as _ });
                     self.cursor_from_metadata(new_buffer_index)
                 } else {
                     None
                 }
             });
         self.set_cursor(new_cursor.unwrap_or(self.edit_cursor));
+        self.keep_goal_column(goal);
     }
 
-    pub fn search_next(&mut self, find: &str) {
-        let v: Vec<char> = find.chars().collect();
-        let mut idx = *self.edit_cursor.pos + 1;
-        while idx < self.len() {
-            if self.data[idx] == v[0] {
-                if let Some(sub_ref_slice) = &self.data.get(idx..idx + v.len()) {
-                    if sub_ref_slice[v.len() - 1] == v[v.len() - 1] {
-                        if sub_ref_slice[..] == v[..] {
-                            println!("Found {} at {} ({:?})", find, idx, &self.data[idx..(idx + v.len())]);
-                            self.cursor_goto(metadata::Index(idx));
-                            return;
-                        } else {
-                            idx += v.len();
-                        }
-                    } else {
-                        idx += v.len();
-                    }
-                } else {
-                    println!("could not find __{}__", find);
-                    return;
-                }
-            } else {
-                idx += 1;
+    /// The lines added or modified since the buffer was last saved (or loaded), sorted by line
+    pub fn line_changes_since_save(&self) -> Vec<(usize, operations::LineChange)> {
+        operations::line_changes(self.history.operations_since_save(), &self.meta_data)
+    }
+
+    /// Moves the cursor to the beginning of the next (or previous, if not forward) region of lines edited since the file was loaded.
+    /// Returns false if there is no such region
+    pub fn goto_modified_region(&mut self, forward: bool) -> bool {
+        let regions = operations::region_starts(&operations::modified_lines(self.history.operations(), &self.meta_data));
+        let row = *self.cursor_row();
+        let target = if forward {
+            regions.into_iter().find(|&line| line > row)
+        } else {
+            regions.into_iter().rev().find(|&line| line < row)
+        };
+        match target.and_then(|line| self.meta_data.get_line_start_index(metadata::Line(line))) {
+            Some(line_begin) => {
+                self.meta_cursor = None;
+                self.cursor_goto(line_begin);
+                true
             }
+            None => false,
         }
-        println!("could not find {}", find);
     }
-}
-
-/// Trait implementation definitions for SimpleBuffer
 
-impl std::ops::Index<usize> for ContiguousBuffer {
-    type Output = char;
-    #[inline(always)]
-    fn index(&self, index: usize) -> &Self::Output {
-        unsafe { self.data.get_unchecked(index) }
+    /// Names the position of the cursor, replacing the mark of that name if there was one
+    pub fn set_mark(&mut self, name: char) {
+        let at = self.edit_cursor.pos;
+        self.marks.set(name, at);
     }
-}
 
-impl std::ops::IndexMut<usize> for ContiguousBuffer {
-    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
-        unsafe { self.data.get_unchecked_mut(index) }
+    /// Where mark name is, after the edits made since it was set. None if there's no mark by that name
+    pub fn mark(&self, name: char) -> Option<BufferCursor> {
+        let at = self.marks.get(name)?;
+        self.cursor_from_metadata(metadata::Index(min(*at, self.len())))
     }
-}
 
-impl std::ops::Index<std::ops::Range<usize>> for ContiguousBuffer {
-    type Output = [char];
-    #[inline(always)]
-    fn index(&self, index: std::ops::Range<usize>) -> &Self::Output {
-        unsafe { self.data.get_unchecked(index) }
+    /// The marks of the buffer, with their positions worked out, to be remembered between sessions
+    pub fn marks(&self) -> Vec<(char, BufferCursor)> {
+        self.marks
+            .iter()
+            .filter_map(|(name, _)| self.mark(name).map(|cursor| (name, cursor)))
+            .collect()
     }
-}
 
-impl std::ops::IndexMut<std::ops::Range<usize>> for ContiguousBuffer {
-    fn index_mut(&mut self, index: std::ops::Range<usize>) -> &mut Self::Output {
-        unsafe { self.data.get_unchecked_mut(index) }
+    /// Sets marks remembered from an earlier session. The file may have been changed since, so only their absolute positions are
+    /// used, kept within the buffer
+    pub fn restore_marks(&mut self, marks: &[(char, BufferCursor)]) {
+        let len = self.len();
+        for (name, cursor) in marks {
+            self.marks.set(*name, metadata::Index(min(*cursor.pos, len)));
+        }
     }
-}
 
-impl<'a> CharBuffer<'a> for ContiguousBuffer {
-    type ItemIterator = std::slice::Iter<'a, char>;
+    /// Moves the cursor to the first character of its line that isn't a space or a tab, or to the end of the line if it's blank.
+    /// Returns false if the cursor already was there
+    pub fn cursor_to_first_non_blank(&mut self) -> bool {
+        let line_begin = match self.meta_data.get(self.cursor_row()) {
+            Some(begin) => *begin,
+            None => return false,
+        };
+        let first_non_blank = line_begin + self.line_indent(self.cursor_row()).len();
+        self.meta_cursor = None;
+        self.reset_goal_column();
+        if first_non_blank == *self.edit_cursor.pos {
+            return false;
+        }
+        self.cursor_goto(metadata::Index(first_non_blank));
+        true
+    }
 
-    fn file_name(&self) -> Option<&Path> {
-        self.meta_data.file_name.as_ref().map(|pb| pb.as_path())
+    /// Moves the cursor to index, dropping the selection, like every movement of the cursor without Shift held does
+    pub fn cursor_goto_deselect(&mut self, index: metadata::Index) {
+        self.meta_cursor = None;
+        self.cursor_goto(index);
     }
 
-    fn clear(&mut self) {
-        self.data.clear();
-        self.edit_cursor = BufferCursor::default();
-        self.meta_data.clear_line_index_metadata();
+    /// Moves the cursor to index, extending the selection, or selecting from where the cursor was, if nothing was selected
+    pub fn select_to(&mut self, index: metadata::Index) {
+        let anchor = match self.meta_cursor {
+            Some(MetaCursor::Absolute(anchor)) => anchor,
+            _ => self.edit_cursor.pos,
+        };
+        self.cursor_goto(index);
+        self.set_absolute_meta_cursor(anchor);
     }
 
-    #[inline(always)]
-    fn cursor_row(&self) -> metadata::Line {
-        self.edit_cursor.row
+    /// The identifier under the cursor, and where it begins and ends (exclusive). At the end of a line (or the buffer), the word
+    /// right before the cursor counts as under it. None if the cursor is on anything else than a word
+    pub fn word_at_cursor(&self) -> Option<(String, metadata::Index, metadata::Index)> {
+        let cursor = *self.edit_cursor.pos;
+        let at = match self.data.get(cursor) {
+            Some(&c) if is_word_char(c) => cursor,
+            None | Some('\n') if cursor > 0 && self.data.get(cursor - 1).map_or(false, |&c| is_word_char(c)) => cursor - 1,
+            _ => return None,
+        };
+        let begin = self.data[..at].iter().rposition(|&c| !is_word_char(c)).map_or(0, |i| i + 1);
+        let end = self.data[at..].iter().position(|&c| !is_word_char(c)).map_or(self.len(), |i| at + i);
+        Some((self.data[begin..end].iter().collect(), metadata::Index(begin), metadata::Index(end)))
     }
 
-    #[inline(always)]
-    fn cursor_col(&self) -> metadata::Column {
-        self.edit_cursor.col
+    /// Grows the selection, or the cursor if nothing is selected, to the smallest unit around it: the word, the line(s), the
+    /// contents of the enclosing block, the block including its braces, and lastly the entire buffer. Returns false if it can't grow
+    pub fn expand_selection(&mut self) -> bool {
+        let selection = self.get_selection();
+        if selection != self.expanded_to {
+            self.expansions.clear();
+        }
+        let (begin, end) = selection.map_or((*self.edit_cursor.pos, *self.edit_cursor.pos), |(b, e)| (*b, *e + 1));
+        let expanded = self
+            .selection_units(begin, end)
+            .into_iter()
+            .filter(|&(b, e)| b <= begin && e >= end && e - b > end - begin)
+            .min_by_key(|&(b, e)| e - b);
+        if let Some((b, e)) = expanded {
+            self.expansions.push((self.edit_cursor.pos, self.meta_cursor));
+            self.cursor_goto(metadata::Index(e - 1));
+            self.set_absolute_meta_cursor(metadata::Index(b));
+            self.expanded_to = self.get_selection();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Selects what's inside the innermost {...} block around the cursor, or if not inside, the block along with its braces. Returns
+    /// false if the cursor isn't in a block, or there's nothing inside it to select
+    pub fn select_block(&mut self, inside: bool) -> bool {
+        let (begin, end) = match indent::enclosing_block(&self.data, *self.edit_cursor.pos) {
+            Some((open, close)) if inside && close > open + 1 => (open + 1, close - 1),
+            Some((open, close)) if !inside => (open, close),
+            _ => return false,
+        };
+        self.cursor_goto(metadata::Index(end));
+        self.set_absolute_meta_cursor(metadata::Index(begin));
+        true
+    }
+
+    /// Reverses the last expand_selection, as long as the selection hasn't been changed in between. Returns false if there's nothing to undo
+    pub fn shrink_selection(&mut self) -> bool {
+        if self.get_selection() != self.expanded_to {
+            self.expansions.clear();
+            self.expanded_to = None;
+            return false;
+        }
+        if let Some((cursor, meta_cursor)) = self.expansions.pop() {
+            self.cursor_goto(cursor);
+            self.meta_cursor = meta_cursor;
+            self.expanded_to = if self.expansions.is_empty() { None } else { self.get_selection() };
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Ranges (begin, end exclusive) of the units the range begin..end may be expanded to
+    fn selection_units(&self, begin: usize, end: usize) -> Vec<(usize, usize)> {
+        let mut units = vec![];
+        if let Some((_, b, e)) = self.word_at_cursor().filter(|_| begin == end) {
+            units.push((*b, *e));
+        }
+        let line_begin = self.data[..begin].iter().rposition(|&c| c == '\n').map_or(0, |i| i + 1);
+        let line_end = self.data[end..].iter().position(|&c| c == '\n').map_or(self.len(), |i| end + i);
+        units.push((line_begin, line_end));
+
+        let mut search_from = begin;
+        while let Some(open) = indent::matching_bracket(&self.data, search_from, '{', '}') {
+            if let Some(close) = indent::matching_close_bracket(&self.data, open, '{', '}').filter(|&close| close >= end) {
+                units.push((open + 1, close));
+                units.push((open, close + 1));
+                break;
+            }
+            search_from = open;
+        }
+        units.push((0, self.len()));
+        units
+    }
+
+    /// Moves the cursor to the line where the word at the cursor is defined, if it's defined exactly once in this buffer.
+    /// Returns the definitions found, so that the caller can let the user choose between them, if there's more than one
+    pub fn goto_definition_of_word(&mut self) -> Vec<Symbol> {
+        let word = match self.word_at_cursor() {
+            Some((word, ..)) => word,
+            None => return vec![],
+        };
+        let definitions = definitions_of(scan_symbols(&self.data), &word);
+        if let [definition] = &definitions[..] {
+            self.meta_cursor = None;
+            self.goto_line(definition.line);
+        }
+        definitions
+    }
+
+    /// Moves the cursor to the next occurrence of find, after the cursor
+    pub fn search_next(&mut self, find: &str) {
+        let found = self.find_from(*self.edit_cursor.pos + 1, find);
+        self.goto_search_result(found);
+    }
+
+    /// Replaces the next occurrence of find, at or after the cursor, with replacement, and places the cursor after it.
+    /// Returns false if there is no such occurrence
+    pub fn replace_next(&mut self, find: &str, replacement: &str) -> bool {
+        if self.read_only {
+            return false;
+        }
+        if let Some(idx) = self.find_from(*self.edit_cursor.pos, find) {
+            let end = idx + find.chars().count();
+            self.replace_range(metadata::Index(idx)..metadata::Index(end), &replacement.chars().collect::<Vec<_>>());
+            true
+        } else {
+            false
+        }
+    }
+
+    /// The index of the first occurrence of find, at or after from
+    fn find_from(&self, from: usize, find: &str) -> Option<usize> {
+        let needle: Vec<char> = find.chars().collect();
+        let case_sensitive = smart_case_sensitive(find, self.smart_case);
+        (from..self.len()).find(|&idx| self.matches_at(idx, &needle, case_sensitive))
+    }
+
+    /// How many times needle occurs in the buffer, in a single pass. Occurrences don't overlap; after a match, counting continues
+    /// after its end, so "aa" occurs twice in "aaaaa", like it would be found by replacing one occurrence after the other
+    pub fn count_matches(&self, needle: &str, case_sensitive: bool) -> usize {
+        let needle: Vec<char> = needle.chars().collect();
+        self.match_starts(&needle, case_sensitive).count()
+    }
+
+    /// Where each occurrence of needle begins, counted the same way as in count_matches
+    pub fn find_all(&self, needle: &str, case_sensitive: bool) -> Vec<metadata::Index> {
+        let needle: Vec<char> = needle.chars().collect();
+        self.match_starts(&needle, case_sensitive).map(metadata::Index).collect()
+    }
+
+    /// Where word occurs on its own, and not as a part of a longer word, within range of the buffer. Case sensitive
+    pub fn whole_word_matches(&self, word: &str, range: std::ops::Range<usize>) -> Vec<metadata::Index> {
+        let needle: Vec<char> = word.chars().collect();
+        let end = min(range.end, self.len());
+        if needle.is_empty() || range.start + needle.len() > end {
+            return vec![];
+        }
+        let not_word = |idx: Option<usize>| idx.and_then(|idx| self.data.get(idx)).map_or(true, |&c| !is_word_char(c));
+        (range.start..=end - needle.len())
+            .filter(|&idx| self.matches_at(idx, &needle, true) && not_word(idx.checked_sub(1)) && not_word(Some(idx + needle.len())))
+            .map(metadata::Index)
+            .collect()
+    }
+
+    fn match_starts<'a>(&'a self, needle: &'a [char], case_sensitive: bool) -> impl Iterator<Item = usize> + 'a {
+        let mut idx = 0;
+        std::iter::from_fn(move || {
+            while !needle.is_empty() && idx + needle.len() <= self.len() {
+                if self.matches_at(idx, needle, case_sensitive) {
+                    idx += needle.len();
+                    return Some(idx - needle.len());
+                }
+                idx += 1;
+            }
+            None
+        })
+    }
+
+    /// Moves the cursor to the previous occurrence of find, before the cursor
+    pub fn search_prev(&mut self, find: &str) {
+        let needle: Vec<char> = find.chars().collect();
+        let case_sensitive = smart_case_sensitive(find, self.smart_case);
+        let found = (0..*self.edit_cursor.pos)
+            .rev()
+            .find(|&idx| self.matches_at(idx, &needle, case_sensitive));
+        self.goto_search_result(found);
+    }
+
+    /// Moves the cursor to the next (or previous, if not forward) occurrence of find, continuing from the other end of the buffer
+    /// when there are no more occurrences in that direction. Returns false if find does not occur anywhere in the buffer
+    pub fn search_wrapping(&mut self, find: &str, forward: bool) -> bool {
+        let found = self.find_wrapping(find, *self.edit_cursor.pos, forward);
+        self.goto_search_result(found);
+        found.is_some()
+    }
+
+    /// The next (or previous, if not forward) occurrence of find from, but not at, from, continuing from the other end of the
+    /// buffer when there are no more occurrences in that direction
+    pub fn find_wrapping(&self, find: &str, from: usize, forward: bool) -> Option<usize> {
+        let needle: Vec<char> = find.chars().collect();
+        let case_sensitive = smart_case_sensitive(find, self.smart_case);
+        let from = min(from, self.len());
+        if forward {
+            (from + 1..self.len())
+                .chain(0..=from)
+                .find(|&idx| self.matches_at(idx, &needle, case_sensitive))
+        } else {
+            (0..from)
+                .rev()
+                .chain((from..self.len()).rev())
+                .find(|&idx| self.matches_at(idx, &needle, case_sensitive))
+        }
+    }
+
+    fn matches_at(&self, idx: usize, needle: &[char], case_sensitive: bool) -> bool {
+        !needle.is_empty()
+            && self
+                .data
+                .get(idx..idx + needle.len())
+                .map_or(false, |s| s.iter().zip(needle).all(|(a, b)| chars_match(*a, *b, case_sensitive)))
+    }
+
+    /// Moves the cursor to where a search found what it looked for. Not finding it leaves the cursor be
+    fn goto_search_result(&mut self, found: Option<usize>) {
+        if let Some(idx) = found {
+            self.cursor_goto(metadata::Index(idx));
+        }
+    }
+}
+
+/// Trait implementation definitions for SimpleBuffer
+
+impl std::ops::Index<usize> for ContiguousBuffer {
+    type Output = char;
+    #[inline(always)]
+    fn index(&self, index: usize) -> &Self::Output {
+        unsafe { self.data.get_unchecked(index) }
+    }
+}
+
+impl std::ops::IndexMut<usize> for ContiguousBuffer {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        unsafe { self.data.get_unchecked_mut(index) }
+    }
+}
+
+impl std::ops::Index<std::ops::Range<usize>> for ContiguousBuffer {
+    type Output = [char];
+    #[inline(always)]
+    fn index(&self, index: std::ops::Range<usize>) -> &Self::Output {
+        unsafe { self.data.get_unchecked(index) }
+    }
+}
+
+impl std::ops::IndexMut<std::ops::Range<usize>> for ContiguousBuffer {
+    fn index_mut(&mut self, index: std::ops::Range<usize>) -> &mut Self::Output {
+        unsafe { self.data.get_unchecked_mut(index) }
+    }
+}
+
+impl<'a> CharBuffer<'a> for ContiguousBuffer {
+    type ItemIterator = std::slice::Iter<'a, char>;
+
+    fn file_name(&self) -> Option<&Path> {
+        self.meta_data.file_name.as_ref().map(|pb| pb.as_path())
+    }
+
+    fn clear(&mut self) {
+        self.data.clear();
+        self.marks.clear();
+        self.edit_cursor = BufferCursor::default();
+        self.size = 0;
+        self.meta_data.clear_line_index_metadata();
+        self.meta_data.set_buffer_size(0);
+    }
+
+    #[inline(always)]
+    fn cursor_row(&self) -> metadata::Line {
+        self.edit_cursor.row
+    }
+
+    #[inline(always)]
+    fn cursor_col(&self) -> metadata::Column {
+        self.edit_cursor.col
     }
 
     #[inline(always)]
@@ -617,6 +1532,9 @@ impl<'a> CharBuffer<'a> for ContiguousBuffer {
 
     fn insert(&mut self, ch: char, register_history: bool) {
         use metadata::{Column as Col, Index};
+        if self.read_only {
+            return;
+        }
         let pos = self.edit_cursor.absolute();
         debug_assert!(self.edit_cursor.absolute() <= Index(self.len()), "You can't insert something outside of the range of [0..len()]");
         if let Some(marker) = &self.meta_cursor {
@@ -628,6 +1546,7 @@ impl<'a> CharBuffer<'a> for ContiguousBuffer {
                         (*self.edit_cursor.pos, *marker)
                     };
                     self.data.drain(erase_from..=erase_to);
+                    self.marks.deleted(metadata::Index(erase_from), erase_to + 1 - erase_from);
                     self.meta_cursor = None;
                     self.size = self.data.len();
                     self.rebuild_metadata();
@@ -653,13 +1572,13 @@ impl<'a> CharBuffer<'a> for ContiguousBuffer {
         self.size += 1;
         self.meta_data.set_buffer_size(self.size);
         if register_history {
-            self.history.push_insert(pos, ch);
+            self.record_insert(pos, ch);
         }
     }
 
     fn delete_if_selection(&mut self) -> bool {
         use metadata::Index;
-        if self.empty() {
+        if self.empty() || self.read_only {
             false
         } else {
             self.meta_cursor
@@ -672,6 +1591,7 @@ impl<'a> CharBuffer<'a> for ContiguousBuffer {
                         };
 
                         let begin = Index(erase_from);
+                        self.marks.deleted(begin, erase_to + 1 - erase_from);
                         for (offset, c) in self.data.drain(erase_from..=erase_to).enumerate() {
                             self.history.push_delete(begin.offset(offset as isize), c);
                         }
@@ -684,6 +1604,7 @@ impl<'a> CharBuffer<'a> for ContiguousBuffer {
                     &MetaCursor::LineRange { begin, end, .. } => {
                         let md = self.meta_data();
                         if let Some((begin, end)) = md.get(begin).zip(md.get(end.offset(1))).map(|(b, e)| (b, e.offset(-1))) {
+                            self.marks.deleted(begin, *end + 1 - *begin);
                             for (offset, c) in self.data.drain(*begin..=*end).enumerate() {
                                 self.history.push_delete(begin.offset(offset as isize), c);
                             }
@@ -704,7 +1625,7 @@ impl<'a> CharBuffer<'a> for ContiguousBuffer {
     // todo(optimization): don't do the expensive rebuild of meta data after each delete. It's a pretty costly operation.
     fn delete(&mut self, dir: Movement) {
         use metadata::Index;
-        if self.empty() {
+        if self.empty() || self.read_only {
             return;
         }
         if !self.delete_if_selection() {
@@ -720,19 +1641,21 @@ impl<'a> CharBuffer<'a> for ContiguousBuffer {
 
                         for _ in 0..count {
                             let c = self.data.remove(*self.edit_cursor.absolute());
-                            self.history.push_delete(self.edit_cursor.absolute(), c);
+                            self.record_delete(self.edit_cursor.absolute(), c);
                         }
                     }
                     TextKind::Word => {
                         if let Some(c) = self.get(self.cursor_abs()) {
                             if c.is_whitespace() {
                                 if let Some(Index(p)) = self.find_next(|c| !c.is_whitespace()).map(|c| c.pos) {
+                                    self.marks.deleted(self.cursor_abs(), p - *self.cursor_abs());
                                     for ch in self.data.drain(*self.cursor_abs()..p) {
                                         self.history.push_delete(self.edit_cursor.absolute(), ch);
                                     }
                                 }
                             } else if c.is_alphanumeric() {
                                 if let Some(Index(p)) = self.find_next(|c| !c.is_alphanumeric()).map(|c| c.pos) {
+                                    self.marks.deleted(self.cursor_abs(), p - *self.cursor_abs());
                                     for ch in self.data.drain(*self.cursor_abs()..p) {
                                         self.history.push_delete(self.edit_cursor.absolute(), ch);
                                     }
@@ -740,7 +1663,7 @@ impl<'a> CharBuffer<'a> for ContiguousBuffer {
                             } else {
                                 // If we are standing on, say +-/_* (non-alphanumerics) just delete one character at a time
                                 let ch = self.data.remove(*self.cursor_abs());
-                                self.history.push_delete(self.edit_cursor.absolute(), ch);
+                                self.record_delete(self.edit_cursor.absolute(), ch);
                             }
                         }
                     }
@@ -759,7 +1682,7 @@ impl<'a> CharBuffer<'a> for ContiguousBuffer {
                         self.cursor_move_backward(TextKind::Char, count);
                         for _ in 0..count {
                             let c = self.data.remove(*self.edit_cursor.absolute());
-                            self.history.push_delete(self.edit_cursor.absolute(), c);
+                            self.record_delete(self.edit_cursor.absolute(), c);
                         }
                     }
                     TextKind::Word => {
@@ -768,7 +1691,7 @@ impl<'a> CharBuffer<'a> for ContiguousBuffer {
                         let len = *(idx_pos - self.edit_cursor.pos);
                         for _ in 0..len {
                             let c = self.data.remove(*self.edit_cursor.absolute());
-                            self.history.push_delete(self.edit_cursor.absolute(), c);
+                            self.record_delete(self.edit_cursor.absolute(), c);
                         }
                     }
                     _ => {
@@ -802,6 +1725,7 @@ impl<'a> CharBuffer<'a> for ContiguousBuffer {
                 self.meta_data.push_new_line_begin(metadata::Index(i + 1));
             }
         }
+        self.meta_data.set_buffer_size(self.data.len());
         let cs = calculate_hash(self);
         self.meta_data.set_checksum(cs);
     }
@@ -871,12 +1795,22 @@ impl<'a> CharBuffer<'a> for ContiguousBuffer {
                     if let Some(start) = self.meta_data.get(self.cursor_row()) {
                         self.cursor_goto(start);
                     }
+                    self.reset_goal_column();
                 }
                 TextKind::Block => {
                     if let Some(block_begin) = self.find_index_of_prev_from(self.edit_cursor.pos.offset(-1), |f| f == '{') {
                         self.cursor_goto(block_begin);
                     }
                 }
+                TextKind::Paragraph => {
+                    let mut line = self.cursor_row();
+                    while *line > 0 && !self.is_blank_line(line.offset(-1)) {
+                        line = line.offset(-1);
+                    }
+                    if let Some(start) = self.meta_data.get_line_start_index(line) {
+                        self.cursor_goto(start);
+                    }
+                }
                 TextKind::File => {
                     self.cursor_goto(Index(0));
                 }
@@ -901,12 +1835,24 @@ impl<'a> CharBuffer<'a> for ContiguousBuffer {
                         .get(self.cursor_row().offset(1))
                         .map_or(Index(self.len()), |Index(start)| Index(start - 1));
                     self.cursor_goto(end);
+                    self.reset_goal_column();
                 }
                 TextKind::Block => {
                     if let Some(block_begin) = self.find_index_of_next_from(self.edit_cursor.pos.offset(1), |f| f == '}') {
                         self.cursor_goto(block_begin);
                     }
                 }
+                TextKind::Paragraph => {
+                    let (mut line, last) = (self.cursor_row(), self.meta_data.last_line());
+                    while line < last && !self.is_blank_line(line.offset(1)) {
+                        line = line.offset(1);
+                    }
+                    let end = self
+                        .meta_data
+                        .get(line.offset(1))
+                        .map_or(Index(self.len()), |Index(start)| Index(start - 1));
+                    self.cursor_goto(end);
+                }
                 TextKind::File => self.cursor_goto(metadata::Index(self.len()).offset(-1)),
                 _ => {
                     todo!("TextKind::{:?} not yet implemented", kind)
@@ -961,6 +1907,7 @@ impl<'a> CharBuffer<'a> for ContiguousBuffer {
                         self.meta_data.set_checksum(checksum);
                         self.meta_data.set_pristine_hash(checksum);
                         self.meta_data.file_name = Some(path.to_path_buf());
+                        self.history.mark_saved();
                     }
                     Err(_err) => {}
                 },
@@ -989,6 +1936,9 @@ impl<'a> CharBuffer<'a> for ContiguousBuffer {
     where
         T: std::ops::RangeBounds<usize> + std::slice::SliceIndex<[metadata::Index], Output = [metadata::Index]> + Clone + std::ops::RangeBounds<usize>,
     {
+        if self.read_only {
+            return;
+        }
         let a = match lines_range.start_bound() {
             Bound::Included(a) => *a,
             Bound::Excluded(a) => *a,
@@ -996,6 +1946,8 @@ impl<'a> CharBuffer<'a> for ContiguousBuffer {
         };
 
         let mut shift_tracking = 0;
+        // the whitespace removed or added on each line, undone together as one step
+        let mut operations = vec![];
         match op {
             LineOperation::ShiftLeft { shift_by } => {
                 if let Some(lines) = self.meta_data.get_lines(lines_range.clone()).or(self.meta_data.get_lines(a..)) {
@@ -1012,7 +1964,8 @@ impl<'a> CharBuffer<'a> for ContiguousBuffer {
                                 let drain = lb..lb + shiftable;
                                 let cnt = drain.len();
                                 assert_eq!(cnt, shiftable);
-                                self.data.drain(drain);
+                                let removed: String = self.data.drain(drain).collect();
+                                operations.push(Operation::Delete(metadata::Index(lb), OperationParameter::Range(removed)));
                                 shift_tracking -= cnt as i32;
                             }
                         } else {
@@ -1026,7 +1979,8 @@ impl<'a> CharBuffer<'a> for ContiguousBuffer {
                                 let drain = lb..lb + shiftable;
                                 let cnt = drain.len();
                                 assert_eq!(cnt, shiftable);
-                                self.data.drain(drain);
+                                let removed: String = self.data.drain(drain).collect();
+                                operations.push(Operation::Delete(metadata::Index(lb), OperationParameter::Range(removed)));
                                 shift_tracking -= cnt as i32;
                             }
                         }
@@ -1040,14 +1994,17 @@ impl<'a> CharBuffer<'a> for ContiguousBuffer {
                     for &lb in lines.iter() {
                         let lb = lb.offset(shift_tracking as _);
                         self.data.splice(*lb..*lb, data.iter().copied());
-                        self.history.push_insert_range(lb, data.iter().collect());
+                        operations.push(Operation::Insert(lb, OperationParameter::Range(data.iter().collect())));
                         shift_tracking += *shift_by as i32;
                     }
                 }
             }
             LineOperation::PasteAt { insertion } => todo!(),
         }
-
+        if !operations.is_empty() {
+            self.record_group(operations);
+        }
+        self.size = self.data.len();
         self.rebuild_metadata();
         match self.meta_cursor {
             Some(MetaCursor::Absolute(ref mut i)) => {
@@ -1071,63 +2028,102 @@ impl<'a> CharBuffer<'a> for ContiguousBuffer {
 
     fn delete_range(&mut self, begin: metadata::Index, end: metadata::Index) {
         self.data.drain(*begin..*end);
+        self.size = self.data.len();
         self.cursor_goto(begin);
         self.rebuild_metadata();
     }
 
     fn undo(&mut self) {
+        if self.read_only {
+            return;
+        }
         self.meta_cursor = None;
         if let Some(undo) = self.history.undo().cloned() {
-            match undo {
-                crate::textbuffer::operations::Operation::Insert(i, op) => match op {
-                    crate::textbuffer::operations::OperationParameter::Char(..) => self.delete_at(i),
-                    crate::textbuffer::operations::OperationParameter::Range(d) => self.delete_range(i, i.offset(d.len() as _)),
-                },
-                crate::textbuffer::operations::Operation::Delete(i, op) => match op {
-                    crate::textbuffer::operations::OperationParameter::Char(c) => {
-                        self.cursor_goto(i);
-                        self.insert(c, false);
-                    }
-                    crate::textbuffer::operations::OperationParameter::Range(d) => {
-                        self.meta_cursor = None;
-                        self.cursor_goto(i);
-                        for c in d.chars() {
-                            self.insert(c, false);
-                        }
-                    }
-                },
-            }
+            self.marks.follow_undone(&undo);
+            self.revert_operation(undo);
         }
     }
 
     fn redo(&mut self) {
+        if self.read_only {
+            return;
+        }
+        self.meta_cursor = None;
         if let Some(redo) = self.history.redo().cloned() {
-            match redo {
-                crate::textbuffer::operations::Operation::Insert(i, o) => {
+            self.marks.follow(&redo);
+            self.apply_operation(redo);
+        }
+    }
+}
+
+/// History replay
+impl ContiguousBuffer {
+    /// Performs the inverse of `operation`, without registering anything in the history
+    fn revert_operation(&mut self, operation: Operation) {
+        match operation {
+            Operation::Insert(i, op) => match op {
+                OperationParameter::Char(..) => self.delete_at(i),
+                OperationParameter::Range(d) => self.delete_range(i, i.offset(d.chars().count() as _)),
+            },
+            Operation::Delete(i, op) => match op {
+                OperationParameter::Char(c) => {
                     self.cursor_goto(i);
-                    match o {
-                        crate::textbuffer::operations::OperationParameter::Char(c) => self.insert(c, false),
-                        crate::textbuffer::operations::OperationParameter::Range(d) => {
-                            for c in d.chars() {
-                                self.insert(c, false);
-                            }
-                        }
-                    }
+                    self.insert(c, false);
                 }
-                crate::textbuffer::operations::Operation::Delete(i, o) => {
+                OperationParameter::Range(d) => {
+                    self.meta_cursor = None;
                     self.cursor_goto(i);
-                    match o {
-                        crate::textbuffer::operations::OperationParameter::Char(_) => {
-                            self.delete_at(i);
+                    for c in d.chars() {
+                        self.insert(c, false);
+                    }
+                }
+            },
+            Operation::Group(operations) => {
+                for op in operations.into_iter().rev() {
+                    self.revert_operation(op);
+                }
+            }
+        }
+    }
+
+    /// Performs `operation` again, without registering anything in the history
+    fn apply_operation(&mut self, operation: Operation) {
+        match operation {
+            Operation::Insert(i, o) => {
+                self.cursor_goto(i);
+                match o {
+                    OperationParameter::Char(c) => self.insert(c, false),
+                    OperationParameter::Range(d) => {
+                        for c in d.chars() {
+                            self.insert(c, false);
                         }
-                        crate::textbuffer::operations::OperationParameter::Range(d) => self.delete_range(i, i.offset(d.len() as _)),
                     }
                 }
             }
+            Operation::Delete(i, o) => {
+                self.cursor_goto(i);
+                match o {
+                    OperationParameter::Char(_) => {
+                        self.delete_at(i);
+                    }
+                    OperationParameter::Range(d) => self.delete_range(i, i.offset(d.chars().count() as _)),
+                }
+            }
+            Operation::Group(operations) => {
+                for op in operations {
+                    self.apply_operation(op);
+                }
+            }
         }
     }
 }
 
+/// Characters that make up identifiers
+#[inline(always)]
+pub fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
 #[inline(always)]
 pub fn predicate_generate(c: &char) -> fn(char) -> bool {
     if c.is_whitespace() {
@@ -1147,7 +2143,8 @@ mod buffer_tests {
     extern crate test;
 
     use super::ContiguousBuffer;
-    use crate::textbuffer::{metadata as md, CharBuffer, LineOperation, Movement, TextKind};
+    use crate::textbuffer::{indent::IndentPolicy, metadata as md, CharBuffer, LineOperation, Movement, TextKind};
+    use crate::utils::temp_dir::TempDir;
 
     #[test]
     fn cursor_move_in_empty() {
@@ -1168,6 +2165,30 @@ mod buffer_tests {
         assert_eq!(b.edit_cursor.pos, md::Index(0));
     }
 
+    #[test]
+    fn every_movement_in_empty_buffer() {
+        // Page movements are turned into line movements by the view, which knows the page size, before they reach the buffer
+        let kinds = [TextKind::Char, TextKind::Word, TextKind::Line, TextKind::Block, TextKind::Paragraph, TextKind::File];
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        for emptied in [false, true].iter() {
+            if *emptied {
+                b.insert_slice(&"fn main() {\n    {}\n}\n".chars().collect::<Vec<char>>());
+                b.select_move_cursor_absolute(Movement::Begin(TextKind::File));
+                b.delete_if_selection();
+                assert!(b.empty());
+            }
+            assert_eq!(b.meta_data().line_count(), 1);
+            for kind in kinds.iter() {
+                for movement in [Movement::Forward(*kind, 1), Movement::Backward(*kind, 1), Movement::Begin(*kind), Movement::End(*kind)].iter() {
+                    b.move_cursor(*movement);
+                    assert_eq!((b.edit_cursor.pos, b.edit_cursor.row, b.edit_cursor.col), (md::Index(0), md::Line(0), md::Column(0)), "{:?}", movement);
+                }
+            }
+        }
+        b.insert('a', true);
+        assert_eq!(b.cursor_abs(), md::Index(1));
+    }
+
     #[test]
     fn length_checks() {
         let v: Vec<char> = "Hello test world".chars().collect();
@@ -1298,6 +2319,30 @@ fn main() {{
         assert_eq!(assert_str, res);
     }
 
+    #[test]
+    fn undo_shifted_lines() {
+        let original = "fn main() {\n  let a = 1;\n\tlet b = 2;\n}";
+        let mut sb = ContiguousBuffer::new(0, 1024);
+        sb.insert_slice(&original.chars().collect::<Vec<char>>());
+        let text = |sb: &ContiguousBuffer| sb.data.iter().collect::<String>();
+
+        sb.cursor_goto(md::Index(0));
+        sb.line_operation(0..4, &LineOperation::ShiftRight { shift_by: 4 });
+        assert_eq!(text(&sb), "    fn main() {\n      let a = 1;\n    \tlet b = 2;\n    }");
+        sb.undo();
+        assert_eq!(text(&sb), original);
+        sb.redo();
+        assert_eq!(text(&sb), "    fn main() {\n      let a = 1;\n    \tlet b = 2;\n    }");
+        sb.undo();
+
+        // shifting left removes what whitespace there is, up to shift_by, and undoing puts back exactly that
+        sb.line_operation(0..4, &LineOperation::ShiftLeft { shift_by: 4 });
+        assert_eq!(text(&sb), "fn main() {\nlet a = 1;\nlet b = 2;\n}");
+        sb.undo();
+        assert_eq!(text(&sb), original);
+        assert_eq!(sb.len(), original.len());
+    }
+
     #[test]
     fn test_shift_should_not_alter() {
         // this tests shifting by four, it also tests shifting lines with
@@ -1326,6 +2371,554 @@ fn main() {{
         assert_eq!(assert_str, res);
     }
 
+    #[test]
+    fn read_only_suppresses_edits() {
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        for c in "hello".chars() {
+            b.insert(c, true);
+        }
+        b.set_read_only(true);
+        assert!(b.read_only());
+        b.insert('!', true);
+        b.insert_slice(&[' ', 'w', 'o', 'r', 'l', 'd']);
+        b.delete(Movement::Backward(TextKind::Char, 1));
+        b.undo();
+        assert_eq!(b.data.iter().collect::<String>(), "hello");
+        assert_eq!(b.cursor_abs(), md::Index(5));
+
+        b.set_read_only(false);
+        assert!(!b.read_only());
+        b.insert('!', true);
+        assert_eq!(b.data.iter().collect::<String>(), "hello!");
+        b.delete(Movement::Backward(TextKind::Char, 1));
+        assert_eq!(b.data.iter().collect::<String>(), "hello");
+    }
+
+    #[test]
+    fn duplicate_selection_on_same_line() {
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        for c in "let x = foo;".chars() {
+            b.insert(c, true);
+        }
+        b.cursor_goto(md::Index(8));
+        b.select_move_cursor_absolute(Movement::Forward(TextKind::Char, 2));
+        assert_eq!(b.copy_range_or_line(), Some("foo".into()));
+        b.duplicate_selection_or_line();
+        assert_eq!(b.data.iter().collect::<String>(), "let x = foofoo;");
+        assert_eq!(b.get_selection(), Some((md::Index(11), md::Index(13))));
+        b.undo();
+        assert_eq!(b.data.iter().collect::<String>(), "let x = foo;");
+    }
+
+    #[test]
+    fn duplicate_selection_spanning_lines() {
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        for c in "one\ntwo\nthree".chars() {
+            b.insert(c, true);
+        }
+        b.cursor_goto(md::Index(0));
+        b.select_move_cursor_absolute(Movement::Forward(TextKind::Char, 7));
+        b.duplicate_selection_or_line();
+        assert_eq!(b.data.iter().collect::<String>(), "one\ntwo\none\ntwo\nthree");
+        assert_eq!(b.get_selection(), Some((md::Index(8), md::Index(15))));
+        assert_eq!(b.meta_data().line_count(), 5);
+    }
+
+    #[test]
+    fn duplicate_line_without_selection() {
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        for c in "one\ntwo".chars() {
+            b.insert(c, true);
+        }
+        b.cursor_goto(md::Index(5));
+        b.duplicate_selection_or_line();
+        assert_eq!(b.data.iter().collect::<String>(), "one\ntwo\ntwo");
+        assert_eq!(b.cursor_row(), md::Line(2));
+        assert_eq!(b.cursor_col(), md::Column(1));
+    }
+
+    #[test]
+    fn wrap_selected_word_in_parentheses() {
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        for c in "call foo now".chars() {
+            b.insert(c, true);
+        }
+        b.cursor_goto(md::Index(5));
+        b.select_move_cursor_absolute(Movement::Forward(TextKind::Char, 2));
+        assert!(b.wrap_selection('(', ')'));
+        assert_eq!(b.data.iter().collect::<String>(), "call (foo) now");
+        assert_eq!(b.get_selection(), Some((md::Index(6), md::Index(8))));
+        assert_eq!(b.copy_range_or_line(), Some("foo".into()));
+        // the wrapping is undone in one step
+        b.undo();
+        assert_eq!(b.data.iter().collect::<String>(), "call foo now");
+        b.redo();
+        assert_eq!(b.data.iter().collect::<String>(), "call (foo) now");
+    }
+
+    #[test]
+    fn wrap_selected_word_in_quotes() {
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        for c in "say hello".chars() {
+            b.insert(c, true);
+        }
+        // select backwards, from the end of the buffer
+        b.cursor_goto(md::Index(8));
+        b.select_move_cursor_absolute(Movement::Backward(TextKind::Char, 4));
+        assert!(b.wrap_selection('"', '"'));
+        assert_eq!(b.data.iter().collect::<String>(), "say \"hello\"");
+        assert_eq!(b.get_selection(), Some((md::Index(5), md::Index(9))));
+        assert_eq!(b.cursor_abs(), md::Index(5));
+        // without a selection, nothing gets wrapped
+        b.move_cursor(Movement::End(TextKind::Line));
+        assert!(!b.wrap_selection('(', ')'));
+    }
+
+    #[test]
+    fn cursor_at_end_of_empty_buffer() {
+        let b = Box::new(ContiguousBuffer::new(0, 1024));
+        let cursor = b.cursor_from_metadata(md::Index(b.len())).unwrap();
+        assert_eq!(cursor.pos, md::Index(0));
+        assert_eq!(cursor.row, md::Line(0));
+        assert_eq!(cursor.col, md::Column(0));
+    }
+
+    #[test]
+    fn cursor_at_end_of_single_line_buffer() {
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        for c in "hello".chars() {
+            b.insert(c, true);
+        }
+        let cursor = b.cursor_from_metadata(md::Index(b.len())).unwrap();
+        assert_eq!(cursor.pos, md::Index(5));
+        assert_eq!(cursor.row, md::Line(0));
+        assert_eq!(cursor.col, md::Column(5));
+    }
+
+    #[test]
+    fn cursor_at_end_of_multi_line_buffer() {
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        for c in "hello\nworld\nfoo".chars() {
+            b.insert(c, true);
+        }
+        let cursor = b.cursor_from_metadata(md::Index(b.len())).unwrap();
+        assert_eq!(cursor.pos, md::Index(15));
+        assert_eq!(cursor.row, md::Line(2));
+        assert_eq!(cursor.col, md::Column(3));
+
+        // moving a word forward in the last word of the buffer, lands at len()
+        b.cursor_goto(md::Index(12));
+        b.move_cursor(Movement::Forward(TextKind::Word, 1));
+        assert_eq!(b.cursor_abs(), md::Index(15));
+        assert_eq!(b.cursor_row(), md::Line(2));
+        assert_eq!(b.cursor_col(), md::Column(3));
+
+        // ending with a new line means the cursor sits at the beginning of an empty last line
+        b.move_cursor(Movement::End(TextKind::Line));
+        b.insert('\n', true);
+        let cursor = b.cursor_from_metadata(md::Index(b.len())).unwrap();
+        assert_eq!(cursor.row, md::Line(3));
+        assert_eq!(cursor.col, md::Column(0));
+    }
+
+    #[test]
+    fn smart_case_lower_case_needle_ignores_case() {
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        b.insert_slice(&"x Fn fn FN".chars().collect::<Vec<char>>());
+        b.cursor_goto(md::Index(0));
+        b.search_next("fn");
+        assert_eq!(b.cursor_abs(), md::Index(2));
+        b.search_next("fn");
+        assert_eq!(b.cursor_abs(), md::Index(5));
+        b.search_prev("fn");
+        assert_eq!(b.cursor_abs(), md::Index(2));
+    }
+
+    #[test]
+    fn smart_case_upper_case_needle_matches_case() {
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        b.insert_slice(&"x fn Fn fn".chars().collect::<Vec<char>>());
+        b.cursor_goto(md::Index(0));
+        b.search_next("Fn");
+        assert_eq!(b.cursor_abs(), md::Index(5));
+        // no more matches, cursor stays put
+        b.search_next("Fn");
+        assert_eq!(b.cursor_abs(), md::Index(5));
+        b.smart_case = false;
+        b.search_prev("fn");
+        assert_eq!(b.cursor_abs(), md::Index(2));
+    }
+
+    #[test]
+    fn replace_multi_line_range() {
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        b.insert_slice(&"abc\ndef\nghi".chars().collect::<Vec<char>>());
+
+        // shorter, joining all three lines into one
+        b.replace_range(md::Index(2)..md::Index(9), &['X']);
+        assert_eq!(b.data.iter().collect::<String>(), "abXhi");
+        assert_eq!(b.meta_data().line_count(), 1);
+        assert_eq!(b.cursor_abs(), md::Index(3));
+        b.undo();
+        assert_eq!(b.data.iter().collect::<String>(), "abc\ndef\nghi");
+        assert_eq!(b.meta_data().line_count(), 3);
+
+        // longer, splitting the middle line into three
+        b.replace_range(md::Index(4)..md::Index(7), &"1\n2\n3".chars().collect::<Vec<char>>());
+        assert_eq!(b.data.iter().collect::<String>(), "abc\n1\n2\n3\nghi");
+        assert_eq!(b.meta_data().line_count(), 5);
+        assert_eq!(b.meta_data().get_line_start_index(md::Line(4)), Some(md::Index(10)));
+        assert_eq!(b.cursor_abs(), md::Index(9));
+        assert_eq!(b.cursor_row(), md::Line(3));
+        b.undo();
+        assert_eq!(b.data.iter().collect::<String>(), "abc\ndef\nghi");
+        b.redo();
+        assert_eq!(b.data.iter().collect::<String>(), "abc\n1\n2\n3\nghi");
+
+        // out of bounds ranges are left alone
+        b.replace_range(md::Index(10)..md::Index(100), &['X']);
+        assert_eq!(b.data.iter().collect::<String>(), "abc\n1\n2\n3\nghi");
+    }
+
+    #[test]
+    fn word_under_cursor() {
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        b.insert_slice(&"let snake_case = 1;\nend".chars().collect::<Vec<char>>());
+        let snake = Some(("snake_case".to_string(), md::Index(4), md::Index(14)));
+        // start, middle and end of the word
+        for &cursor in [4, 9, 13].iter() {
+            b.cursor_goto(md::Index(cursor));
+            assert_eq!(b.word_at_cursor(), snake);
+        }
+        // on a space, and on punctuation
+        b.cursor_goto(md::Index(3));
+        assert_eq!(b.word_at_cursor(), None);
+        b.cursor_goto(md::Index(15));
+        assert_eq!(b.word_at_cursor(), None);
+        // at the end of a line, the word before the cursor is used, but not if it isn't a word
+        b.cursor_goto(md::Index(19));
+        assert_eq!(b.word_at_cursor(), None);
+        b.cursor_goto(md::Index(18));
+        assert_eq!(b.word_at_cursor(), None);
+        b.cursor_goto(md::Index(17));
+        assert_eq!(b.word_at_cursor(), Some(("1".to_string(), md::Index(17), md::Index(18))));
+        b.cursor_goto(md::Index(b.len()));
+        assert_eq!(b.word_at_cursor(), Some(("end".to_string(), md::Index(20), md::Index(23))));
+    }
+
+    #[test]
+    fn count_non_overlapping_matches() {
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        b.insert_slice(&"Foo foo\nfoO bar aaaaa".chars().collect::<Vec<char>>());
+        assert_eq!(b.count_matches("foo", false), 3);
+        assert_eq!(b.count_matches("foo", true), 1);
+        assert_eq!(b.count_matches("bar", true), 1);
+        assert_eq!(b.count_matches("baz", false), 0);
+        assert_eq!(b.count_matches("", false), 0);
+        // overlapping occurrences count once; "aaaaa" holds two "aa" and one "aaa"
+        assert_eq!(b.count_matches("aa", true), 2);
+        assert_eq!(b.count_matches("aaa", true), 1);
+        assert_eq!(b.count_matches("a", true), 6);
+        assert_eq!(b.find_all("aa", true), vec![md::Index(16), md::Index(18)]);
+        assert_eq!(b.find_all("foo", false), vec![md::Index(0), md::Index(4), md::Index(8)]);
+    }
+
+    #[test]
+    fn goto_definition_from_call_site() {
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        b.insert_slice(&"fn main() {\n    helper(1);\n}\n\nfn helper(n: i32) {}\n".chars().collect::<Vec<char>>());
+        b.cursor_goto(md::Index(18));
+        let definitions = b.goto_definition_of_word();
+        assert_eq!(definitions.len(), 1);
+        assert_eq!(b.cursor_row(), md::Line(4));
+        assert_eq!(b.cursor_col(), md::Column(0));
+
+        // not a definition anywhere in the buffer
+        b.cursor_goto(md::Index(0));
+        assert!(b.goto_definition_of_word().is_empty());
+        assert_eq!(b.cursor_abs(), md::Index(0));
+    }
+
+    #[test]
+    fn trim_selection_whitespace() {
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        b.insert_slice(&"x  foo  y".chars().collect::<Vec<char>>());
+        b.cursor_goto(md::Index(7));
+        b.set_absolute_meta_cursor(md::Index(1));
+        assert!(b.trim_selection());
+        assert_eq!(b.get_selection(), Some((md::Index(3), md::Index(5))));
+        assert_eq!(b.copy_range_or_line(), Some("foo".to_string()));
+        assert!(!b.trim_selection());
+
+        // made backwards, the cursor stays at the beginning
+        b.cursor_goto(md::Index(1));
+        b.set_absolute_meta_cursor(md::Index(7));
+        assert!(b.trim_selection());
+        assert_eq!(b.cursor_abs(), md::Index(3));
+        assert_eq!(b.get_selection(), Some((md::Index(3), md::Index(5))));
+
+        // nothing selected, or only whitespace
+        b.meta_cursor = None;
+        assert!(!b.trim_selection());
+        b.cursor_goto(md::Index(2));
+        b.set_absolute_meta_cursor(md::Index(1));
+        assert!(!b.trim_selection());
+        assert_eq!(b.get_selection(), Some((md::Index(1), md::Index(2))));
+    }
+
+    #[test]
+    fn transform_case() {
+        use crate::textbuffer::operations::CaseMode;
+        let cases = [
+            (CaseMode::Upper, "HELLO WORLD"),
+            (CaseMode::Lower, "hello world"),
+            (CaseMode::Title, "Hello World"),
+            (CaseMode::Toggle, "hELLO wORLD"),
+        ];
+        for &(mode, expected) in cases.iter() {
+            let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+            b.insert_slice(&"Hello World".chars().collect::<Vec<char>>());
+            b.cursor_goto(md::Index(0));
+            b.set_absolute_meta_cursor(md::Index(10));
+            let changed = b.transform_selection_case(mode);
+            assert_eq!(changed, expected != "Hello World");
+            assert_eq!(b.data.iter().collect::<String>(), expected);
+            assert_eq!(b.get_selection(), Some((md::Index(0), md::Index(10))));
+            if changed {
+                b.undo();
+                assert_eq!(b.data.iter().collect::<String>(), "Hello World");
+            }
+        }
+
+        // without a selection, the word at the cursor is transformed, and the cursor stays put
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        b.insert_slice(&"Hello World straße".chars().collect::<Vec<char>>());
+        b.cursor_goto(md::Index(8));
+        assert!(b.transform_selection_case(CaseMode::Upper));
+        assert_eq!(b.data.iter().collect::<String>(), "Hello WORLD straße");
+        assert_eq!(b.cursor_abs(), md::Index(8));
+        assert!(!b.transform_selection_case(CaseMode::Upper));
+        // upper casing 'ß' makes the text longer
+        b.cursor_goto(md::Index(14));
+        assert!(b.transform_selection_case(CaseMode::Upper));
+        assert_eq!(b.data.iter().collect::<String>(), "Hello WORLD STRASSE");
+        assert_eq!(b.len(), 19);
+    }
+
+    #[test]
+    fn expand_and_shrink_selection() {
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        b.insert_slice(&"fn main() {\n    let foo = 1;\n}\n".chars().collect::<Vec<char>>());
+        b.cursor_goto(md::Index(21));
+        let steps = [(20, 22), (12, 27), (11, 28), (10, 29), (0, 29), (0, 30)];
+        for &(begin, end) in steps.iter() {
+            assert!(b.expand_selection());
+            assert_eq!(b.get_selection(), Some((md::Index(begin), md::Index(end))));
+        }
+        assert!(!b.expand_selection());
+        for &(begin, end) in steps.iter().rev().skip(1) {
+            assert!(b.shrink_selection());
+            assert_eq!(b.get_selection(), Some((md::Index(begin), md::Index(end))));
+        }
+        assert!(b.shrink_selection());
+        assert_eq!(b.get_selection(), None);
+        assert_eq!(b.cursor_abs(), md::Index(21));
+        assert!(!b.shrink_selection());
+    }
+
+    #[test]
+    fn replace_next_occurrence() {
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        b.insert_slice(&"foo bar foo".chars().collect::<Vec<char>>());
+        b.cursor_goto(md::Index(1));
+        assert!(b.replace_next("foo", "baz"));
+        assert_eq!(b.data.iter().collect::<String>(), "foo bar baz");
+        assert!(!b.replace_next("foo", "baz"));
+        b.cursor_goto(md::Index(0));
+        assert!(b.replace_next("foo", ""));
+        assert_eq!(b.data.iter().collect::<String>(), " bar baz");
+        assert_eq!(b.cursor_abs(), md::Index(0));
+    }
+
+    #[test]
+    fn repeated_search_cycles_through_matches_and_wraps() {
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        b.insert_slice(&"ab x ab\nab".chars().collect::<Vec<char>>());
+        b.cursor_goto(md::Index(0));
+        let mut visited = vec![];
+        for _ in 0..4 {
+            assert!(b.search_wrapping("ab", true));
+            visited.push(*b.cursor_abs());
+        }
+        assert_eq!(visited, vec![5, 8, 0, 5]);
+        assert!(b.search_wrapping("ab", false));
+        assert_eq!(b.cursor_abs(), md::Index(0));
+        assert!(b.search_wrapping("ab", false));
+        assert_eq!(b.cursor_abs(), md::Index(8));
+        assert!(!b.search_wrapping("nothing", true));
+        assert_eq!(b.cursor_abs(), md::Index(8));
+    }
+
+    #[test]
+    fn move_forward_and_backward_by_paragraph() {
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        b.insert_slice(&"one\ntwo\n\nthree\nfour\n  \nfive".chars().collect::<Vec<char>>());
+        b.cursor_goto(md::Index(0));
+        b.move_cursor(Movement::Forward(TextKind::Paragraph, 1));
+        assert_eq!(b.cursor_abs(), md::Index(8));
+        // a line with only whitespace is a paragraph boundary as well
+        b.move_cursor(Movement::Forward(TextKind::Paragraph, 1));
+        assert_eq!(b.cursor_abs(), md::Index(20));
+        assert_eq!(b.cursor_row(), md::Line(5));
+        // no more blank lines, so we end up at the end of the buffer
+        b.move_cursor(Movement::Forward(TextKind::Paragraph, 1));
+        assert_eq!(b.cursor_abs(), md::Index(b.len()));
+
+        b.move_cursor(Movement::Backward(TextKind::Paragraph, 1));
+        assert_eq!(b.cursor_abs(), md::Index(20));
+        b.move_cursor(Movement::Backward(TextKind::Paragraph, 2));
+        assert_eq!(b.cursor_abs(), md::Index(0));
+    }
+
+    #[test]
+    fn move_to_begin_and_end_of_paragraph() {
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        b.insert_slice(&"one\n\nthree\nfour\n\nfive".chars().collect::<Vec<char>>());
+        // in the middle of "four"
+        b.cursor_goto(md::Index(13));
+        b.move_cursor(Movement::Begin(TextKind::Paragraph));
+        assert_eq!(b.cursor_abs(), md::Index(5));
+        b.move_cursor(Movement::End(TextKind::Paragraph));
+        // on the newline ending "four"
+        assert_eq!(b.cursor_abs(), md::Index(15));
+        assert_eq!(b.cursor_row(), md::Line(3));
+    }
+
+    #[test]
+    fn text_in_scrolled_view_range() {
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        let contents: String = (0..10).map(|l| format!("line {}\n", l)).collect();
+        b.insert_slice(&contents.chars().collect::<Vec<char>>());
+        // a view showing 3 rows, scrolled down to line 4
+        let (begin, end) = b.meta_data().get_byte_indices_of_lines(md::Line(4), md::Line(7));
+        assert_eq!(b.text_in_range(*begin..*end), "line 4\nline 5\nline 6\n");
+        // scrolled past the end of the buffer
+        let (begin, end) = b.meta_data().get_byte_indices_of_lines(md::Line(8), md::Line(11));
+        assert_eq!(b.text_in_range(*begin..*end), "line 8\nline 9\n");
+        assert_eq!(b.text_in_range(*begin..b.len() + 10), "line 8\nline 9\n");
+        assert_eq!(b.text_in_range(b.len() + 5..b.len() + 10), "");
+    }
+
+    fn type_indented(b: &mut ContiguousBuffer, text: &str) {
+        for c in text.chars() {
+            if !b.insert_indented(c, IndentPolicy::default()) {
+                b.insert(c, true);
+            }
+        }
+    }
+
+    #[test]
+    fn indent_after_opening_brace() {
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        type_indented(&mut b, "fn main() {\nlet a = 1;\n");
+        assert_eq!(b.data.iter().collect::<String>(), "fn main() {\n    let a = 1;\n    ");
+        assert_eq!(b.cursor_col(), md::Column(4));
+        // the indentation is undone together with the newline
+        b.undo();
+        assert_eq!(b.data.iter().collect::<String>(), "fn main() {\n    let a = 1;");
+    }
+
+    #[test]
+    fn newline_between_brackets_splits_them() {
+        for (open, close) in [('{', '}'), ('[', ']'), ('(', ')')].iter() {
+            let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+            type_indented(&mut b, &format!("    f{}{}", open, close));
+            b.cursor_goto(md::Index(6));
+            assert!(b.insert_indented('\n', IndentPolicy::default()));
+            assert_eq!(b.data.iter().collect::<String>(), format!("    f{}\n        \n    {}", open, close));
+            assert_eq!((b.cursor_row(), b.cursor_col()), (md::Line(1), md::Column(8)));
+            b.undo();
+            assert_eq!(b.data.iter().collect::<String>(), format!("    f{}{}", open, close));
+        }
+
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        type_indented(&mut b, "    f(]");
+        b.cursor_goto(md::Index(6));
+        type_indented(&mut b, "\n");
+        assert_eq!(b.data.iter().collect::<String>(), "    f(\n    ]", "not a pair");
+        let policy = IndentPolicy { split_brackets: false, ..IndentPolicy::default() };
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        type_indented(&mut b, "    f()");
+        b.cursor_goto(md::Index(6));
+        assert!(b.insert_indented('\n', policy));
+        assert_eq!(b.data.iter().collect::<String>(), "    f(\n    )");
+    }
+
+    #[test]
+    fn dedent_closing_brace() {
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        type_indented(&mut b, "fn main() {\nlet a = 1;\n}");
+        assert_eq!(b.data.iter().collect::<String>(), "fn main() {\n    let a = 1;\n}");
+        assert_eq!(b.cursor_abs(), md::Index(b.len()));
+        b.undo();
+        assert_eq!(b.data.iter().collect::<String>(), "fn main() {\n    let a = 1;\n    ");
+    }
+
+    #[test]
+    fn indent_nested_braces() {
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        type_indented(&mut b, "impl A {\nfn a() {\nif b {\nc();\n}\n}\n}");
+        assert_eq!(
+            b.data.iter().collect::<String>(),
+            "impl A {\n    fn a() {\n        if b {\n            c();\n        }\n    }\n}"
+        );
+        // a brace that isn't the first thing on its line is left alone
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        type_indented(&mut b, "if a {\nb(); }");
+        assert_eq!(b.data.iter().collect::<String>(), "if a {\n    b(); }");
+    }
+
+    #[test]
+    fn sort_lines_ascending_and_descending() {
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        b.insert_slice(&"header\ndelta\nalpha\ncharlie\nbravo\nfooter".chars().collect::<Vec<_>>());
+        b.sort_lines(1..5, false, false);
+        assert_eq!(b.data.iter().collect::<String>(), "header\nalpha\nbravo\ncharlie\ndelta\nfooter");
+        b.undo();
+        assert_eq!(b.data.iter().collect::<String>(), "header\ndelta\nalpha\ncharlie\nbravo\nfooter");
+        b.sort_lines(1..5, true, false);
+        assert_eq!(b.data.iter().collect::<String>(), "header\ndelta\ncharlie\nbravo\nalpha\nfooter");
+    }
+
+    #[test]
+    fn sort_lines_case_insensitive() {
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        b.insert_slice(&"b\nA\nc\nB".chars().collect::<Vec<_>>());
+        let all = b.selected_lines_or_all();
+        assert_eq!(all, 0..4);
+        b.sort_lines(all.clone(), false, false);
+        assert_eq!(b.data.iter().collect::<String>(), "A\nB\nb\nc");
+        b.undo();
+        // equal lines keep their order, and no trailing new line is added
+        b.sort_lines(all, false, true);
+        assert_eq!(b.data.iter().collect::<String>(), "A\nb\nB\nc");
+    }
+
+    #[test]
+    fn dedup_adjacent_and_global() {
+        let text = "a\na\nb\na\nc\nb\nb\n";
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        b.insert_slice(&text.chars().collect::<Vec<_>>());
+        let all = b.selected_lines_or_all();
+        assert_eq!(b.dedup_lines(all.clone(), true), 2);
+        assert_eq!(b.data.iter().collect::<String>(), "a\nb\na\nc\nb\n");
+        b.undo();
+        assert_eq!(b.data.iter().collect::<String>(), text);
+        assert_eq!(b.dedup_lines(all, false), 4);
+        assert_eq!(b.data.iter().collect::<String>(), "a\nb\nc\n");
+        assert_eq!(b.dedup_lines(0..3, false), 0);
+    }
+
     #[bench]
     fn copy_paste_per_char(b: &mut test::Bencher) {
         let text_data = include_str!("contiguous.rs");
@@ -1338,4 +2931,291 @@ fn main() {{
         let text_data = include_str!("contiguous.rs");
         let mut b = Box::new(ContiguousBuffer::new(0, 1024));
     }
+
+    #[test]
+    fn cursor_to_first_non_blank_of_indented_line() {
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        b.insert_slice(&"fn main() {\n    \tlet a = 1;\n    \n}".chars().collect::<Vec<char>>());
+        b.cursor_goto(md::Index(20));
+        // home toggles between the first non-blank and the beginning of the line, like the view does it
+        assert!(b.cursor_to_first_non_blank());
+        assert_eq!(b.edit_cursor.pos, md::Index(17));
+        assert!(!b.cursor_to_first_non_blank());
+        b.move_cursor(Movement::Begin(TextKind::Line));
+        assert_eq!(b.edit_cursor.pos, md::Index(12));
+        assert!(b.cursor_to_first_non_blank());
+        assert_eq!(b.edit_cursor.pos, md::Index(17));
+        // on a blank line, the first non-blank is the end of the line
+        b.move_cursor(Movement::Forward(TextKind::Line, 1));
+        b.move_cursor(Movement::Begin(TextKind::Line));
+        assert!(b.cursor_to_first_non_blank());
+        assert_eq!(b.edit_cursor.pos, md::Index(32));
+        // without indentation, it's the beginning of the line
+        b.cursor_goto(md::Index(3));
+        assert!(b.cursor_to_first_non_blank());
+        assert_eq!(b.edit_cursor.pos, md::Index(0));
+        assert!(!b.cursor_to_first_non_blank());
+    }
+
+    #[test]
+    fn navigating_without_shift_drops_the_selection() {
+        let mut b = Box::new(ContiguousBuffer::new(0, 1024));
+        b.insert_slice(&"first line\nsecond line\nthird".chars().collect::<Vec<char>>());
+        b.cursor_goto(md::Index(3));
+        b.select_move_cursor_absolute(Movement::Forward(TextKind::Char, 4));
+        assert_eq!(b.get_selection(), Some((md::Index(3), md::Index(7))));
+        // with shift, going to the end of the buffer (ctrl + shift + end) extends the selection
+        b.select_to(md::Index(b.len()));
+        assert_eq!(b.get_selection(), Some((md::Index(3), md::Index(b.len()))));
+        // without, going to the beginning (ctrl + home) drops it
+        b.cursor_goto_deselect(md::Index(0));
+        assert_eq!(b.get_selection(), None);
+        assert_eq!(b.edit_cursor.pos, md::Index(0));
+        // and with nothing selected, selecting starts from the cursor
+        b.select_to(md::Index(5));
+        assert_eq!(b.get_selection(), Some((md::Index(0), md::Index(5))));
+        b.move_cursor(Movement::Forward(TextKind::Line, 1));
+        assert_eq!(b.get_selection(), None);
+    }
+
+    #[test]
+    fn reflow_wraps_a_long_line_to_the_width() {
+        let text = |b: &ContiguousBuffer| b.data.iter().collect::<String>();
+        let mut b = ContiguousBuffer::new(0, 1024);
+        b.insert_slice(&"    The quick brown fox jumps over the lazy dog, and then it runs away into the forest again.\nfn main() {}\n".chars().collect::<Vec<char>>());
+        assert!(b.reflow_paragraph(md::Line(0), 40));
+        assert_eq!(text(&b), "    The quick brown fox jumps over the\n    lazy dog, and then it runs away into\n    the forest again.\nfn main() {}\n");
+        assert!(text(&b).lines().all(|line| line.chars().count() <= 40));
+        assert!(!b.reflow_paragraph(md::Line(1), 40), "already fits");
+
+        b.undo();
+        assert_eq!(text(&b), "    The quick brown fox jumps over the lazy dog, and then it runs away into the forest again.\nfn main() {}\n", "undone in one step");
+    }
+
+    #[test]
+    fn reflow_rewraps_a_wrapped_comment() {
+        let text = |b: &ContiguousBuffer| b.data.iter().collect::<String>();
+        let mut b = ContiguousBuffer::new(0, 1024);
+        b.insert_slice(&"let a = 1;\n\n  // one two\n  // three four five six\n  // seven\n\n// not this one\n".chars().collect::<Vec<char>>());
+        assert!(b.reflow_paragraph(md::Line(3), 20));
+        assert_eq!(text(&b), "let a = 1;\n\n  // one two three\n  // four five six\n  // seven\n\n// not this one\n");
+        assert!(!b.reflow_paragraph(md::Line(2), 20), "rewrapping twice changes nothing");
+        assert!(!b.reflow_paragraph(md::Line(1), 20), "blank lines are not paragraphs");
+    }
+
+    #[test]
+    fn marks_follow_edits_above_them() {
+        let mut b = ContiguousBuffer::new(0, 1024);
+        b.insert_slice(&"fn main() {\n    let a = 1;\n}\n".chars().collect::<Vec<char>>());
+        b.cursor_goto(md::Index(16));
+        b.set_mark('a');
+        assert!(b.mark('b').is_none());
+
+        b.cursor_goto(md::Index(0));
+        b.insert_lines_above_cursor("// the entry point");
+        let mark = b.mark('a').unwrap();
+        assert_eq!((mark.pos, mark.row, mark.col), (md::Index(35), md::Line(2), md::Column(4)));
+        assert_eq!(b.data[*mark.pos], 'l');
+
+        b.undo();
+        assert_eq!(b.mark('a').unwrap().pos, md::Index(16), "undoing the edit moves the mark back");
+        b.redo();
+        b.cursor_goto(md::Index(39));
+        b.insert_slice(&"mut ".chars().collect::<Vec<char>>());
+        assert_eq!(b.mark('a').unwrap().pos, md::Index(35), "edits after the mark leave it be");
+    }
+
+    #[test]
+    fn marks_follow_unrecorded_text_and_deleted_selections() {
+        let mut b = ContiguousBuffer::new(0, 1024);
+        b.insert_slice(&"abc\ndef\n".chars().collect::<Vec<char>>());
+        b.set_mark('e');
+        b.cursor_goto(md::Index(4));
+        b.set_mark('d');
+
+        b.append_unrecorded(&"ghi\n".chars().collect::<Vec<char>>());
+        assert_eq!(b.mark('e').unwrap().pos, md::Index(12), "text appended at the mark comes before it");
+        assert_eq!(b.mark('d').unwrap().pos, md::Index(4));
+
+        b.cursor_goto(md::Index(3));
+        b.meta_cursor = Some(crate::textbuffer::cursor::MetaCursor::Absolute(md::Index(0)));
+        b.delete(Movement::Forward(TextKind::Char, 1));
+        assert_eq!(b.data.iter().collect::<String>(), "def\nghi\n");
+        assert_eq!((b.mark('d').unwrap().pos, b.mark('e').unwrap().pos), (md::Index(0), md::Index(8)));
+
+        // pasted in one go, and typed over
+        b.cursor_goto(md::Index(0));
+        b.insert_slice(&['x'; 200]);
+        assert_eq!((b.mark('d').unwrap().pos, b.mark('e').unwrap().pos), (md::Index(200), md::Index(208)));
+        b.meta_cursor = Some(crate::textbuffer::cursor::MetaCursor::Absolute(md::Index(0)));
+        b.cursor_goto(md::Index(199));
+        b.insert_slice(&['y']);
+        assert_eq!(b.data.iter().collect::<String>(), "ydef\nghi\n");
+        assert_eq!((b.mark('d').unwrap().pos, b.mark('e').unwrap().pos), (md::Index(1), md::Index(9)));
+    }
+
+    #[test]
+    fn whole_word_matches_skip_longer_words() {
+        let mut b = ContiguousBuffer::new(0, 1024);
+        b.insert_slice(&"foo(foobar, foo);\nlet foo_2 = barfoo + foo;\nfoo".chars().collect::<Vec<char>>());
+        assert_eq!(b.whole_word_matches("foo", 0..b.len()), vec![md::Index(0), md::Index(12), md::Index(39), md::Index(44)]);
+        assert_eq!(b.whole_word_matches("foo", 5..40), vec![md::Index(12)], "matches have to fit in the range");
+        assert!(b.whole_word_matches("Foo", 0..b.len()).is_empty());
+        assert!(b.whole_word_matches("", 0..b.len()).is_empty());
+    }
+
+    #[test]
+    fn select_line_takes_the_new_line_along() {
+        let mut b = ContiguousBuffer::new(0, 1024);
+        b.insert_slice(&"first\nsecond\nlast".chars().collect::<Vec<char>>());
+        let select_line_at = |b: &mut ContiguousBuffer, at: usize| {
+            b.cursor_goto_deselect(md::Index(at));
+            assert!(b.select_line());
+            (b.get_selection(), b.copy_range_or_line())
+        };
+        assert_eq!(select_line_at(&mut b, 2), (Some((md::Index(0), md::Index(5))), Some("first\n".to_string())));
+        assert_eq!(select_line_at(&mut b, 9), (Some((md::Index(6), md::Index(12))), Some("second\n".to_string())));
+        assert_eq!(select_line_at(&mut b, 17), (Some((md::Index(13), md::Index(16))), Some("last".to_string())), "the last line has no new line");
+
+        b.cursor_goto_deselect(md::Index(17));
+        b.insert_slice(&"\n".chars().collect::<Vec<char>>());
+        assert!(!b.select_line(), "the empty last line has nothing to select");
+    }
+
+    #[test]
+    fn inserting_a_file_is_one_undo_step() {
+        let directory = TempDir::new("insert_file");
+        let path = directory.join("inserted.txt");
+        let contents = "// inserted, with ünicode\nfn inserted() {}\n".repeat(20);
+        std::fs::write(&path, &contents).unwrap();
+        let mut b = ContiguousBuffer::new(0, 1024);
+        b.insert_slice(&"fn main() {}\n".chars().collect::<Vec<char>>());
+        b.cursor_goto(md::Index(0));
+
+        assert_eq!(b.insert_file(&path).unwrap(), contents.chars().count());
+        assert_eq!(b.len(), 13 + contents.chars().count());
+        assert_eq!(*b.cursor_abs(), contents.chars().count(), "the cursor goes after what was inserted");
+        b.undo();
+        assert_eq!(b.iter().collect::<String>(), "fn main() {}\n");
+
+        std::fs::write(&path, [0x66, 0x6e, 0xff, 0xfe]).unwrap();
+        assert!(b.insert_file(&path).is_err(), "not UTF-8");
+        assert_eq!(b.iter().collect::<String>(), "fn main() {}\n");
+    }
+
+    #[test]
+    fn select_inside_and_around_nested_blocks() {
+        let mut b = ContiguousBuffer::new(0, 1024);
+        b.insert_slice(&"{ a { b } c }".chars().collect::<Vec<char>>());
+        let mut select_block = |at: usize, inside: bool| {
+            b.cursor_goto_deselect(md::Index(at));
+            if b.select_block(inside) { b.get_selection().map(|(begin, end)| (*begin, *end)) } else { None }
+        };
+        assert_eq!(select_block(6, true), Some((5, 7)));
+        assert_eq!(select_block(6, false), Some((4, 8)));
+        assert_eq!(select_block(2, true), Some((1, 11)));
+        assert_eq!(select_block(10, false), Some((0, 12)));
+        // on a brace, the block it opens or closes
+        assert_eq!(select_block(4, true), Some((5, 7)));
+        assert_eq!(select_block(8, false), Some((4, 8)));
+        assert_eq!(select_block(0, true), Some((1, 11)));
+        assert_eq!(select_block(12, false), Some((0, 12)));
+
+        let mut b = ContiguousBuffer::new(0, 1024);
+        b.insert_slice(&"fn f() {}".chars().collect::<Vec<char>>());
+        b.cursor_goto(md::Index(2));
+        assert!(!b.select_block(false), "not in a block");
+        b.cursor_goto(md::Index(7));
+        assert!(!b.select_block(true), "nothing inside");
+        assert!(b.select_block(false));
+    }
+
+    #[test]
+    fn bulk_inserts_update_line_begins_like_a_rebuild() {
+        let mut b = ContiguousBuffer::new(0, 1024);
+        let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut random = |below: usize| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed as usize % below
+        };
+        for _ in 0..200 {
+            let text: String = (0..129 + random(200)).map(|_| if random(6) == 0 { '\n' } else { 'x' }).collect();
+            let at = random(b.len() + 1);
+            b.cursor_goto_deselect(md::Index(at));
+            b.insert_slice(&text.chars().collect::<Vec<char>>());
+            let mut rebuilt = ContiguousBuffer::new(1, 0);
+            rebuilt.data = b.data.clone();
+            rebuilt.rebuild_metadata();
+            assert_eq!(b.meta_data().get_lines(..), rebuilt.meta_data().get_lines(..), "after inserting {} characters at {}", text.len(), at);
+            assert_eq!(b.cursor_abs(), md::Index(at + text.len()));
+        }
+    }
+
+    #[test]
+    fn line_indent_is_the_leading_spaces_and_tabs() {
+        let mut b = ContiguousBuffer::new(0, 1024);
+        b.insert_slice(&"\t\tlet a = 1;\n    let b = 2;\n\nlet c = 3;\n  \t ".chars().collect::<Vec<char>>());
+        let indent = |line: usize| b.line_indent(md::Line(line)).iter().collect::<String>();
+        assert_eq!(indent(0), "\t\t");
+        assert_eq!(indent(1), "    ");
+        assert_eq!(indent(2), "", "an empty line");
+        assert_eq!(indent(3), "", "a line that isn't indented");
+        assert_eq!(indent(4), "  \t ", "a line of only whitespace");
+        assert_eq!(indent(5), "", "past the last line");
+    }
+
+    #[test]
+    fn replacing_all_content_keeps_the_cursor_and_undoes_in_one_step() {
+        let text = |b: &ContiguousBuffer| b.iter().collect::<String>();
+        let original = "fn main() {\nlet a = 1;\nlet bb = 2;\n}\n";
+        let mut b = ContiguousBuffer::new(0, 1024);
+        b.insert_slice(&original.chars().collect::<Vec<char>>());
+        b.cursor_goto(md::Index(3));
+        let formatted: Vec<char> = "fn main() {\n    let a = 1;\n    let bb = 2;\n}\n".chars().collect();
+        assert!(b.replace_all_content(&formatted));
+        assert_eq!((b.cursor_row(), b.cursor_col()), (md::Line(0), md::Column(3)), "an unchanged line keeps the cursor");
+        assert!(!b.replace_all_content(&formatted));
+
+        b.cursor_goto(md::Index(42));
+        assert_eq!((b.cursor_row(), b.cursor_col()), (md::Line(2), md::Column(15)));
+        assert!(b.replace_all_content(&"fn main() {\n    let a = 1;\n}\n".chars().collect::<Vec<char>>()));
+        assert_eq!((b.cursor_row(), b.cursor_col()), (md::Line(2), md::Column(1)), "held within a shorter line");
+
+        b.undo();
+        b.undo();
+        assert_eq!(text(&b), original);
+    }
+
+    #[test]
+    fn moving_up_and_down_keeps_aiming_for_the_column_home_and_end_landed_on() {
+        let mut b = ContiguousBuffer::new(0, 1024);
+        b.insert_slice(&"let a = 1;\n\nlet b = 2;\nc;\n    let d = 4;\n".chars().collect::<Vec<char>>());
+        let go = |b: &mut ContiguousBuffer, movement: Movement| {
+            b.move_cursor(movement);
+            (*b.cursor_row(), *b.cursor_col())
+        };
+        go(&mut b, Movement::Begin(TextKind::File));
+        go(&mut b, Movement::Forward(TextKind::Char, 6));
+        assert_eq!(go(&mut b, Movement::Forward(TextKind::Line, 1)), (1, 0));
+        assert_eq!(go(&mut b, Movement::Forward(TextKind::Line, 1)), (2, 6), "the goal column is kept past a short line");
+        assert_eq!(go(&mut b, Movement::Backward(TextKind::Line, 2)), (0, 6));
+
+        // Home on an empty line leaves the cursor where it was, but still ends the aiming for column 6
+        go(&mut b, Movement::Forward(TextKind::Line, 1));
+        assert_eq!(go(&mut b, Movement::Begin(TextKind::Line)), (1, 0));
+        assert_eq!(go(&mut b, Movement::Forward(TextKind::Line, 1)), (2, 0), "Home sets the goal column");
+
+        go(&mut b, Movement::End(TextKind::Line));
+        assert_eq!(go(&mut b, Movement::Forward(TextKind::Line, 1)), (3, 2));
+        assert_eq!(go(&mut b, Movement::End(TextKind::Line)), (3, 2));
+        assert_eq!(go(&mut b, Movement::Forward(TextKind::Line, 1)), (4, 2), "End sets the goal column, to where it landed");
+
+        go(&mut b, Movement::Backward(TextKind::Line, 2));
+        go(&mut b, Movement::Forward(TextKind::Char, 8));
+        assert_eq!(go(&mut b, Movement::Forward(TextKind::Line, 1)), (3, 2));
+        b.insert('x', true);
+        assert_eq!(go(&mut b, Movement::Forward(TextKind::Line, 1)), (4, 3), "editing ends it as well");
+    }
 }
@@ -1 +1,2 @@
 pub mod contiguous;
+pub mod transaction;
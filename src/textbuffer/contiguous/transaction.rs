@@ -0,0 +1,138 @@
+use crate::textbuffer::{
+    metadata::Index,
+    operations::{Operation, OperationParameter},
+};
+use std::ops::Range;
+
+/// Edits made together, through ContiguousBuffer::transaction. They are made to the buffer's text right away, and recorded, so
+/// that if making them panics, the recorded edits are undone on the way out and the buffer is left as it was. The indices of each
+/// edit are into the text as the edits before it left it
+pub struct Transaction<'a> {
+    data: &'a mut Vec<char>,
+    operations: Vec<Operation>,
+    finished: bool,
+}
+
+impl<'a> Transaction<'a> {
+    pub(super) fn new(data: &'a mut Vec<char>) -> Transaction<'a> {
+        Transaction { data, operations: vec![], finished: false }
+    }
+
+    /// The text, with the edits made so far
+    pub fn text(&self) -> &[char] {
+        self.data
+    }
+
+    /// Replaces the text in range with `with`. Returns false, and does nothing, if the range is not within the text
+    pub fn replace_range(&mut self, range: Range<Index>, with: &[char]) -> bool {
+        if range.start > range.end || *range.end > self.data.len() {
+            return false;
+        }
+        if self.data[*range.start..*range.end] != *with {
+            let removed: String = self.data.splice(*range.start..*range.end, with.iter().copied()).collect();
+            if !removed.is_empty() {
+                self.operations
+                    .push(Operation::Delete(range.start, OperationParameter::Range(removed)));
+            }
+            if !with.is_empty() {
+                self.operations
+                    .push(Operation::Insert(range.start, OperationParameter::Range(with.iter().collect())));
+            }
+        }
+        true
+    }
+
+    pub fn insert(&mut self, at: Index, text: &[char]) -> bool {
+        self.replace_range(at..at, text)
+    }
+
+    pub fn delete(&mut self, range: Range<Index>) -> bool {
+        self.replace_range(range, &[])
+    }
+
+    /// The edits that were made, in the order they were made. They're kept from here on
+    pub(super) fn finish(mut self) -> Vec<Operation> {
+        self.finished = true;
+        std::mem::take(&mut self.operations)
+    }
+}
+
+impl Drop for Transaction<'_> {
+    /// A transaction that wasn't finished was cut short by a panic; its edits are undone, latest first
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        for operation in self.operations.drain(..).rev() {
+            match operation {
+                Operation::Insert(at, OperationParameter::Range(text)) => {
+                    self.data.drain(*at..*at + text.chars().count());
+                }
+                Operation::Delete(at, OperationParameter::Range(text)) => {
+                    self.data.splice(*at..*at, text.chars());
+                }
+                // a transaction only records ranges
+                _ => unreachable!(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod transaction_tests {
+    use crate::textbuffer::{contiguous::contiguous::ContiguousBuffer, metadata::Index, CharBuffer};
+
+    fn chars(text: &str) -> Vec<char> {
+        text.chars().collect()
+    }
+
+    #[test]
+    fn edits_of_a_transaction_undo_in_one_step() {
+        let text = |b: &ContiguousBuffer| b.iter().collect::<String>();
+        let mut b = ContiguousBuffer::new(0, 1024);
+        b.insert_slice(&chars("let a = 1;\nlet b = 2;\n"));
+        let edits = b.transaction(|edits| {
+            edits.replace_range(Index(4)..Index(5), &chars("first"));
+            edits.insert(Index(0), &chars("// values\n"));
+            edits.delete(Index(25)..Index(36));
+            edits.text().len()
+        });
+        assert_eq!(edits, Some(25));
+        assert_eq!(text(&b), "// values\nlet first = 1;\n");
+        assert_eq!(b.meta_data().line_count(), 3);
+
+        b.undo();
+        assert_eq!(text(&b), "let a = 1;\nlet b = 2;\n");
+        b.redo();
+        assert_eq!(text(&b), "// values\nlet first = 1;\n");
+    }
+
+    #[test]
+    fn a_panic_in_a_transaction_leaves_the_buffer_be() {
+        let text = |b: &ContiguousBuffer| b.iter().collect::<String>();
+        let mut b = ContiguousBuffer::new(0, 1024);
+        b.insert_slice(&chars("fn main() {}\n"));
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            b.transaction(|edits| {
+                edits.insert(Index(0), &chars("pub "));
+                edits.replace_range(Index(7)..Index(11), &chars("start"));
+                edits.delete(Index(14)..Index(18));
+                panic!("halfway through");
+            })
+        }));
+        assert!(panicked.is_err());
+        assert_eq!(text(&b), "fn main() {}\n");
+        assert_eq!(b.meta_data().line_count(), 2);
+    }
+
+    #[test]
+    fn an_inserted_range_moves_the_lines_after_it_along() {
+        let mut b = ContiguousBuffer::new(0, 1024);
+        b.insert_slice(&chars("fn a() {}\n\nfn b() {}\n"));
+        b.transaction(|edits| edits.insert(Index(10), &chars("// one\n// two\n")));
+        let mut rebuilt = ContiguousBuffer::new(1, 1024);
+        rebuilt.insert_slice(&chars("fn a() {}\n// one\n// two\n\nfn b() {}\n"));
+        assert_eq!(b.iter().collect::<String>(), rebuilt.iter().collect::<String>());
+        assert_eq!(b.meta_data().get_lines(..), rebuilt.meta_data().get_lines(..));
+    }
+}
@@ -1,4 +1,5 @@
 use super::metadata::{Column, Index, Line};
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 
 pub enum CursorType {
@@ -6,7 +7,7 @@ pub enum CursorType {
     LineRange,
 }
 
-#[derive(Default, Debug, Copy, Clone)]
+#[derive(Default, Debug, Copy, Clone, Serialize, Deserialize)]
 pub struct BufferCursor {
     /// Absolute index into buffer
     pub pos: Index,
@@ -0,0 +1,134 @@
+/// Decides what indentation new lines, and closing braces, get when typed. It is language agnostic and only looks at braces.
+/// Both behaviors can be turned on and off individually; with both off, text is inserted as typed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndentPolicy {
+    /// Copy the indentation of the line being broken, to the new line
+    pub keep_indent: bool,
+    /// Indent one level after a line ending with '{', and line up a '}' typed as the first non-whitespace on a line, with its opening brace
+    pub braces: bool,
+    /// Break a line between a pair of brackets, like {}, onto three lines; the close bracket gets its own line, with the indentation
+    /// of the line broken, and the cursor ends up on an indented line in between
+    pub split_brackets: bool,
+    /// Width of one indentation level, in spaces
+    pub tab_width: usize,
+}
+
+impl Default for IndentPolicy {
+    fn default() -> Self {
+        IndentPolicy { keep_indent: true, braces: true, split_brackets: true, tab_width: 4 }
+    }
+}
+
+impl IndentPolicy {
+    pub fn enabled(&self) -> bool {
+        self.keep_indent || self.braces || self.split_brackets
+    }
+
+    /// The indentation a new line should get, when breaking a line whose contents up until the cursor is line_before_cursor
+    pub fn indent_after_newline(&self, line_before_cursor: &[char]) -> Vec<char> {
+        let mut indent = if self.keep_indent { leading_whitespace(line_before_cursor).to_vec() } else { vec![] };
+        if self.braces && line_before_cursor.iter().rev().find(|c| !c.is_whitespace()) == Some(&'{') {
+            indent.extend(std::iter::repeat(' ').take(self.tab_width));
+        }
+        indent
+    }
+}
+
+/// The indentation (spaces and tabs) at the beginning of line
+pub fn leading_whitespace(line: &[char]) -> &[char] {
+    let len = line.iter().take_while(|&&c| c == ' ' || c == '\t').count();
+    &line[..len]
+}
+
+/// Finds the position of the open bracket, that a close bracket at close_position would match. The character at close_position
+/// itself is never looked at, so this works for closing brackets that are about to be typed, as well
+pub fn matching_bracket(data: &[char], close_position: usize, open: char, close: char) -> Option<usize> {
+    let mut depth = 0;
+    for (i, &c) in data[..close_position.min(data.len())].iter().enumerate().rev() {
+        if c == close {
+            depth += 1;
+        } else if c == open {
+            if depth == 0 {
+                return Some(i);
+            }
+            depth -= 1;
+        }
+    }
+    None
+}
+
+/// Finds the position of the close bracket, that matches the open bracket at open_position
+pub fn matching_close_bracket(data: &[char], open_position: usize, open: char, close: char) -> Option<usize> {
+    let mut depth = 0;
+    for (i, &c) in data.iter().enumerate().skip(open_position + 1) {
+        if c == open {
+            depth += 1;
+        } else if c == close {
+            if depth == 0 {
+                return Some(i);
+            }
+            depth -= 1;
+        }
+    }
+    None
+}
+
+/// The positions of the open and the close brace of the innermost {...} block around at. A brace at at, is taken to be in the
+/// block it opens or closes
+pub fn enclosing_block(data: &[char], at: usize) -> Option<(usize, usize)> {
+    let open = match data.get(at) {
+        Some('{') => at,
+        _ => matching_bracket(data, at, '{', '}')?,
+    };
+    matching_close_bracket(data, open, '{', '}').map(|close| (open, close))
+}
+
+/// The pairs of brackets that bracket_pair_at looks for
+const BRACKETS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+/// Whether the cursor sits between an open bracket and its close bracket, like in (|)
+pub fn between_brackets(data: &[char], cursor: usize) -> bool {
+    match (cursor.checked_sub(1).and_then(|before| data.get(before)), data.get(cursor)) {
+        (Some(&before), Some(&at)) => BRACKETS.contains(&(before, at)),
+        _ => false,
+    }
+}
+
+/// A bracket at the cursor, and the bracket it pairs up with, if it has one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BracketPair {
+    pub bracket: usize,
+    pub partner: Option<usize>,
+}
+
+/// Finds the bracket the cursor is on, or else the one right before it, and the bracket matching it
+pub fn bracket_pair_at(data: &[char], cursor: usize) -> Option<BracketPair> {
+    let candidates = std::iter::once(cursor).chain(cursor.checked_sub(1));
+    candidates.filter_map(|pos| data.get(pos).map(|&c| (pos, c))).find_map(|(pos, c)| {
+        BRACKETS.iter().find_map(|&(open, close)| {
+            if c == open {
+                Some(BracketPair { bracket: pos, partner: matching_close_bracket(data, pos, open, close) })
+            } else if c == close {
+                Some(BracketPair { bracket: pos, partner: matching_bracket(data, pos, open, close) })
+            } else {
+                None
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod indent_tests {
+    use super::{bracket_pair_at, BracketPair};
+
+    #[test]
+    fn brackets_next_to_the_cursor_are_paired() {
+        let data: Vec<char> = "f(a[0]) }".chars().collect();
+        // right after the '('
+        assert_eq!(bracket_pair_at(&data, 2), Some(BracketPair { bracket: 1, partner: Some(6) }));
+        // on the ')'
+        assert_eq!(bracket_pair_at(&data, 6), Some(BracketPair { bracket: 6, partner: Some(1) }));
+        assert_eq!(bracket_pair_at(&data, 9), Some(BracketPair { bracket: 8, partner: None }));
+        assert_eq!(bracket_pair_at(&data, 0), None);
+    }
+}
@@ -0,0 +1,69 @@
+use super::{
+    metadata::Index,
+    operations::{Operation, OperationParameter},
+};
+use std::collections::BTreeMap;
+
+/// Positions in a buffer, named by a character, that follow the text they were set at when text is inserted or deleted before them.
+/// They're kept by the buffer, which moves them along with every edit, whether it's recorded in the history or not
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Marks {
+    marks: BTreeMap<char, Index>,
+}
+
+impl Marks {
+    pub fn set(&mut self, name: char, at: Index) {
+        self.marks.insert(name, at);
+    }
+
+    pub fn get(&self, name: char) -> Option<Index> {
+        self.marks.get(&name).copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (char, Index)> + '_ {
+        self.marks.iter().map(|(name, at)| (*name, *at))
+    }
+
+    pub fn clear(&mut self) {
+        self.marks.clear();
+    }
+
+    /// Moves the marks along with operation, which has just been performed. A mark in deleted text ends up where the text was
+    pub fn follow(&mut self, operation: &Operation) {
+        match operation {
+            Operation::Insert(i, parameter) => self.inserted(*i, length_of(parameter)),
+            Operation::Delete(i, parameter) => self.deleted(*i, length_of(parameter)),
+            Operation::Group(operations) => operations.iter().for_each(|operation| self.follow(operation)),
+        }
+    }
+
+    /// Moves the marks back, along with operation, which has just been undone
+    pub fn follow_undone(&mut self, operation: &Operation) {
+        match operation {
+            Operation::Insert(i, parameter) => self.deleted(*i, length_of(parameter)),
+            Operation::Delete(i, parameter) => self.inserted(*i, length_of(parameter)),
+            Operation::Group(operations) => operations.iter().rev().for_each(|operation| self.follow_undone(operation)),
+        }
+    }
+
+    /// Moves the marks along with length characters inserted at at
+    pub fn inserted(&mut self, at: Index, length: usize) {
+        for mark in self.marks.values_mut().filter(|mark| **mark >= at) {
+            *mark = mark.offset(length as _);
+        }
+    }
+
+    /// Moves the marks along with length characters deleted at at
+    pub fn deleted(&mut self, at: Index, length: usize) {
+        for mark in self.marks.values_mut().filter(|mark| **mark > at) {
+            *mark = Index(std::cmp::max(*at, (**mark).saturating_sub(length)));
+        }
+    }
+}
+
+fn length_of(parameter: &OperationParameter) -> usize {
+    match parameter {
+        OperationParameter::Char(_) => 1,
+        OperationParameter::Range(text) => text.chars().count(),
+    }
+}
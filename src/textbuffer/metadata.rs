@@ -27,11 +27,16 @@ impl Length {
 #[derive(Debug)]
 pub struct MetaData {
     pub file_name: Option<PathBuf>,
-    pub line_begin_indices: Vec<Index>,
+    /// Buffer indices of where each line begins. Never empty; an empty buffer still has one (empty) line, beginning at 0.
+    /// That invariant is why this isn't public. All mutation goes through the methods below, which uphold it
+    line_begin_indices: Vec<Index>,
     pub buffer_size: usize,
     /// real simple approach to checking file changes
     buf_hash: u64,
     hash_on_open: u64,
+    /// Bumped by every edit, which all end by setting the buffer size. Lets what's derived from the contents, like the lines of
+    /// search matches, be kept until the buffer changes
+    revision: u64,
 }
 
 impl std::fmt::Display for MetaData {
@@ -48,14 +53,26 @@ impl MetaData {
             buffer_size: 0,
             buf_hash: 0,
             hash_on_open: 0,
+            revision: 0,
         }
     }
 
     /// Guaranteed to always be at least 1, no matter what.
     pub fn line_count(&self) -> usize {
+        debugger_catch!(!self.line_begin_indices.is_empty(), "meta data has no lines. An empty buffer must have 1 line");
         self.line_begin_indices.len()
     }
 
+    /// The last line in the buffer. Since there's always at least one line, this is always valid
+    pub fn last_line(&self) -> Line {
+        Line(self.line_count() - 1)
+    }
+
+    /// Buffer index of where the last line begins
+    pub fn last_line_start_index(&self) -> Index {
+        self.line_begin_indices.last().cloned().unwrap_or_default()
+    }
+
     pub fn line_length(&self, line: Line) -> Option<Length> {
         self.get(line).and_then(|a| {
             self.get(line.offset(1))
@@ -81,26 +98,49 @@ impl MetaData {
         self.line_begin_indices.get(*line_number).cloned()
     }
 
-    /// Returns the buffer indices of the beginning of line a and b. If *either* line does not exist in buffer, function will return None
-    pub fn get_byte_indices_of_lines(&self, line_a: Line, line_b: Line) -> (Option<Index>, Option<Index>) {
-        let a = self.get_line_start_index(line_a);
-        let b = self.get_line_start_index(line_b);
-        (a, b)
+    /// Returns the buffer index range, from the beginning of line a, up to the beginning of line b. Lines are clamped to the buffer,
+    /// so a line_a past the last line, begins at the last line, and a line_b past the last line, ends at the end of the buffer.
+    /// The returned start is therefore always <= end
+    pub fn get_byte_indices_of_lines(&self, line_a: Line, line_b: Line) -> (Index, Index) {
+        let a = self.get_line_start_index(line_a).unwrap_or_else(|| self.last_line_start_index());
+        let b = self.get_line_start_index(line_b).unwrap_or(Index(self.buffer_size));
+        (a, std::cmp::max(a, b))
     }
 
     /// Finds what line in buffer, the absolute cursor position buffer_index points into
     /// * `buffer_index` - the buffer index, we want to find the line that it lives on for
+    /// The line begin indices are sorted in ascending order, so this is a binary search
     pub fn get_line_number_of_buffer_index(&self, buffer_index: Index) -> Option<usize> {
-        self.line_begin_indices
-            .windows(2)
-            .enumerate()
-            .find(|(_, slice)| {
-                // This is safe. Slice will *always* be a Some([a, b]) or a None, at which point this loop exits already
-                let (a, b) = (slice[0], slice[1]);
-                a <= buffer_index && buffer_index < b
-            })
-            .map(|(index, _)| index)
-            .or_else(|| if *buffer_index <= self.buffer_size { Some(self.line_begin_indices.len() - 1) } else { None })
+        // first line always begins at 0, so this is at least 1
+        let lines_beginning_before = self.line_begin_indices.partition_point(|&begin| begin <= buffer_index);
+        let line = lines_beginning_before.saturating_sub(1);
+        if line < self.line_begin_indices.len() - 1 || *buffer_index <= self.buffer_size {
+            Some(line)
+        } else {
+            None
+        }
+    }
+
+    /// The lines touched by a selection from begin to end, end being the last selected index, as selections include the character
+    /// under the cursor. By convention, a selection that ends on the first character of a line, having begun on a line above it,
+    /// doesn't touch that line; selecting lines by moving the cursor down from the beginning of the first of them, leaves the
+    /// cursor at the beginning of the line after them. Indices past the end of the buffer are on the last line
+    pub fn lines_spanning(&self, begin: Index, end: Index) -> std::ops::Range<usize> {
+        let line_of = |idx: Index| self.get_line_number_of_buffer_index(idx).unwrap_or_else(|| *self.last_line());
+        let (first, last) = (line_of(begin), line_of(end));
+        if last > first && self.get_line_start_index(Line(last)) == Some(end) {
+            first..last
+        } else {
+            first..last + 1
+        }
+    }
+
+    /// Finds the line and the column that buffer_index points to, in one go. Returns None if buffer_index lies outside of the buffer
+    pub fn position_of(&self, buffer_index: Index) -> Option<(Line, Column)> {
+        self.get_line_number_of_buffer_index(buffer_index).and_then(|line| {
+            self.get_line_start_index(Line(line))
+                .map(|line_begin| (Line(line), Column(*buffer_index - *line_begin)))
+        })
     }
 
     pub fn get(&self, line: Line) -> Option<Index> {
@@ -140,6 +180,16 @@ impl MetaData {
         self.line_begin_indices.push(Index(0));
     }
 
+    /// Updates the line begins for length characters having been inserted at buffer index at; the lines after it are moved along,
+    /// and new_line_begins, where the lines begun by new lines in the inserted text begin, are added
+    pub fn text_inserted(&mut self, at: Index, length: usize, new_line_begins: &[Index]) {
+        let after = self.line_begin_indices.partition_point(|&begin| begin <= at);
+        self.line_begin_indices[after..]
+            .iter_mut()
+            .for_each(|begin| *begin = Index(**begin + length));
+        self.line_begin_indices.splice(after..after, new_line_begins.iter().copied());
+    }
+
     pub fn update_line_metadata_after_line(&mut self, line: Line, shift_amount: i64) {
         self.line_begin_indices.iter_mut().skip(*line + 1).for_each(|l| {
             *l = l.offset_mut(shift_amount as _);
@@ -154,6 +204,11 @@ impl MetaData {
 
     pub fn set_buffer_size(&mut self, size: usize) {
         self.buffer_size = size;
+        self.revision = self.revision.wrapping_add(1);
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
     }
 
     pub fn set_checksum(&mut self, sum: u64) {
@@ -183,3 +238,108 @@ pub fn calculate_hash<'a, T: CharBuffer<'a> + Hash + Sized>(buf: &T) -> u64 {
     l.hash(&mut s);
     s.finish()
 }
+
+#[cfg(test)]
+mod metadata_tests {
+    use super::{Column, Index, Line, MetaData};
+
+    /// Metadata for the contents "foo\nbar\nbaz"
+    fn three_lines() -> MetaData {
+        let mut md = MetaData::new(None);
+        md.push_new_line_begin(Index(4));
+        md.push_new_line_begin(Index(8));
+        md.set_buffer_size(11);
+        md
+    }
+
+    #[test]
+    fn always_at_least_one_line() {
+        let mut md = MetaData::new(None);
+        assert_eq!(md.line_count(), 1);
+        assert_eq!(md.last_line(), Line(0));
+        assert_eq!(md.last_line_start_index(), Index(0));
+        md.push_new_line_begin(Index(4));
+        assert_eq!(md.line_count(), 2);
+        assert_eq!(md.last_line_start_index(), Index(4));
+        md.clear_line_index_metadata();
+        assert_eq!(md.line_count(), 1);
+        assert_eq!(md.last_line(), Line(0));
+    }
+
+    #[test]
+    fn position_of_line_starts() {
+        let md = three_lines();
+        assert_eq!(md.position_of(Index(0)), Some((Line(0), Column(0))));
+        assert_eq!(md.position_of(Index(4)), Some((Line(1), Column(0))));
+        assert_eq!(md.position_of(Index(8)), Some((Line(2), Column(0))));
+    }
+
+    #[test]
+    fn position_of_mid_line() {
+        let md = three_lines();
+        assert_eq!(md.position_of(Index(2)), Some((Line(0), Column(2))));
+        // the newline character belongs to the line it terminates
+        assert_eq!(md.position_of(Index(7)), Some((Line(1), Column(3))));
+        assert_eq!(md.position_of(Index(9)), Some((Line(2), Column(1))));
+    }
+
+    #[test]
+    fn position_of_buffer_end() {
+        let md = three_lines();
+        assert_eq!(md.position_of(Index(11)), Some((Line(2), Column(3))));
+        assert_eq!(md.position_of(Index(12)), None);
+        assert_eq!(MetaData::new(None).position_of(Index(0)), Some((Line(0), Column(0))));
+    }
+
+    #[test]
+    fn line_number_lookup_matches_linear_search() {
+        // the previous, linear, implementation of get_line_number_of_buffer_index
+        fn linear(md: &MetaData, buffer_index: Index) -> Option<usize> {
+            md.line_begin_indices
+                .windows(2)
+                .position(|w| w[0] <= buffer_index && buffer_index < w[1])
+                .or_else(|| if *buffer_index <= md.buffer_size { Some(md.line_begin_indices.len() - 1) } else { None })
+        }
+        let mut md = MetaData::new(None);
+        let mut begin = 0;
+        for line_length in (0..1000).map(|n| 1 + (n * 7) % 13) {
+            begin += line_length;
+            md.push_new_line_begin(Index(begin));
+        }
+        md.set_buffer_size(begin + 5);
+        for i in 0..=begin + 10 {
+            assert_eq!(md.get_line_number_of_buffer_index(Index(i)), linear(&md, Index(i)), "at buffer index {}", i);
+        }
+        // exactly on line boundaries
+        for (line, begin) in md.line_begin_indices.iter().enumerate() {
+            assert_eq!(md.get_line_number_of_buffer_index(*begin), Some(line));
+        }
+    }
+
+    #[test]
+    fn byte_indices_of_lines_within_buffer() {
+        let md = three_lines();
+        assert_eq!(md.get_byte_indices_of_lines(Line(0), Line(1)), (Index(0), Index(4)));
+        assert_eq!(md.get_byte_indices_of_lines(Line(1), Line(2)), (Index(4), Index(8)));
+        assert_eq!(md.get_byte_indices_of_lines(Line(1), Line(3)), (Index(4), Index(11)));
+    }
+
+    #[test]
+    fn byte_indices_of_lines_beyond_buffer() {
+        let md = three_lines();
+        assert_eq!(md.get_byte_indices_of_lines(Line(2), Line(40)), (Index(8), Index(11)));
+        assert_eq!(md.get_byte_indices_of_lines(Line(10), Line(40)), (Index(8), Index(11)));
+        assert_eq!(MetaData::new(None).get_byte_indices_of_lines(Line(3), Line(5)), (Index(0), Index(0)));
+    }
+
+    #[test]
+    fn lines_spanned_by_selections() {
+        let md = three_lines();
+        assert_eq!(md.lines_spanning(Index(5), Index(6)), 1..2, "within one line");
+        assert_eq!(md.lines_spanning(Index(4), Index(4)), 1..2);
+        assert_eq!(md.lines_spanning(Index(1), Index(5)), 0..2, "spanning two");
+        assert_eq!(md.lines_spanning(Index(1), Index(7)), 0..2, "up to and including the new line");
+        assert_eq!(md.lines_spanning(Index(0), Index(8)), 0..2, "ending on the first character of a line leaves it out");
+        assert_eq!(md.lines_spanning(Index(8), Index(20)), 2..3);
+    }
+}
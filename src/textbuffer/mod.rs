@@ -16,10 +16,18 @@ pub mod contiguous;
 pub mod cursor;
 /// GapBuffer module
 pub mod gb;
+/// Indentation of typed text
+pub mod indent;
+/// Named positions in buffers, that follow the text they were set at
+pub mod marks;
 /// Buffer metadata module
 pub mod metadata;
 // Definitions of abstractions of operations on buffers
 pub mod operations;
+/// Rewrapping paragraphs of text and comments to a column width
+pub mod reflow;
+/// Definitions (functions, types and so on) found in source text
+pub mod symbols;
 
 #[derive(Debug, Hash, PartialEq, PartialOrd, Eq, Ord, Clone, Copy, Deserialize, Serialize)]
 pub enum TextKind {
@@ -27,6 +35,8 @@ pub enum TextKind {
     Word,
     Line,
     Block,
+    /// Lines separated by empty (or all whitespace) lines
+    Paragraph,
     Page,
     File,
 }
@@ -39,6 +49,7 @@ impl std::str::FromStr for TextKind {
             "Word" => Ok(TextKind::Word),
             "Line" => Ok(TextKind::Line),
             "Block" => Ok(TextKind::Block),
+            "Paragraph" => Ok(TextKind::Paragraph),
             "Page" => Ok(TextKind::Page),
             "File" => Ok(TextKind::File),
             _ => Err("Unknown Text Kind type"),
@@ -208,28 +219,20 @@ pub trait CharBuffer<'a>: std::hash::Hash {
     fn cursor_from_metadata(&self, absolute_position: metadata::Index) -> Option<BufferCursor> {
         use metadata::Column as Col;
         use metadata::Index as Idx;
-        use metadata::Line;
         let absolute_position = *absolute_position;
         debugger_catch!(absolute_position <= self.len(), "absolute position is outside of the buffer");
         if absolute_position == self.len() {
+            // the metadata always holds at least one line, so this is valid for empty buffers as well
+            let md = self.meta_data();
             Some(BufferCursor {
                 pos: Idx(absolute_position),
-                row: Line(self.meta_data().line_count() - 1),
-                col: Col(self
-                    .meta_data()
-                    .line_begin_indices
-                    .last()
-                    .map(|v| absolute_position - **v as usize)
-                    .unwrap()),
+                row: md.last_line(),
+                col: Col(absolute_position.saturating_sub(*md.last_line_start_index())),
             })
         } else {
             self.meta_data()
-                .get_line_number_of_buffer_index(Idx(absolute_position))
-                .and_then(|line| {
-                    self.meta_data()
-                        .get_line_start_index(Line(line))
-                        .map(|line_begin| (absolute_position, line, absolute_position - *line_begin).into())
-                })
+                .position_of(Idx(absolute_position))
+                .map(|(row, col)| BufferCursor { pos: Idx(absolute_position), row, col })
         }
     }
 
@@ -248,7 +251,9 @@ pub trait CharBuffer<'a>: std::hash::Hash {
     /// Moves cursor to absolute buffer index
     fn cursor_goto(&mut self, buffer_index: metadata::Index) {
         if self.is_valid_index(buffer_index) {
-            self.set_cursor(self.cursor_from_metadata(buffer_index).unwrap());
+            if let Some(cursor) = self.cursor_from_metadata(buffer_index) {
+                self.set_cursor(cursor);
+            }
         }
     }
 
@@ -1,5 +1,6 @@
 use super::metadata;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 // Todo: Implement serialization of the History data, to be used in the file caching/backup scheme
 
@@ -13,6 +14,8 @@ pub enum OperationParameter {
 pub enum Operation {
     Insert(metadata::Index, OperationParameter),
     Delete(metadata::Index, OperationParameter),
+    /// Several operations that are undone & redone together, in one step. They are stored in the order they were performed
+    Group(Vec<Operation>),
 }
 
 impl Operation {
@@ -20,24 +23,175 @@ impl Operation {
         match self {
             Operation::Insert(i, ..) => *i,
             Operation::Delete(i, ..) => *i,
+            Operation::Group(ops) => ops.first().map(|op| op.index()).unwrap_or_default(),
         }
     }
 }
 
 type Operations = Vec<Operation>;
 
+/// How many operations the history holds on to by default, before the oldest are evicted
+pub const DEFAULT_MAX_OPERATIONS: usize = 10_000;
+
+/// How consecutive deletes (with backspace or the delete key) are grouped together, into one undo step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CoalescePolicy {
+    /// Every deleted character is undone on its own
+    PerChar,
+    /// Deletes are grouped up until whitespace, so that one undo restores a word
+    PerWord,
+    /// Deletes are grouped up until a new line, so that one undo restores (the deleted part of) a line
+    PerLine,
+}
+
+impl Default for CoalescePolicy {
+    fn default() -> Self {
+        CoalescePolicy::PerWord
+    }
+}
+
+impl CoalescePolicy {
+    /// Whether deleting ch can join the run of deletes, that neighbour was deleted in
+    fn joins(&self, ch: char, neighbour: char) -> bool {
+        match self {
+            CoalescePolicy::PerChar => false,
+            CoalescePolicy::PerWord => !ch.is_whitespace() && !neighbour.is_whitespace(),
+            CoalescePolicy::PerLine => ch != '\n' && neighbour != '\n',
+        }
+    }
+}
+
+/// A suggested interval for grouping operations by time, see History::set_group_interval
+pub const DEFAULT_GROUP_INTERVAL_MS: u64 = 400;
+
+/// Where the history gets the time of operations from, for grouping them by time
+#[derive(Debug, Clone, Copy)]
+pub enum Clock {
+    /// Milliseconds passed since the instant
+    System(std::time::Instant),
+    /// A time set by hand, in milliseconds
+    Manual(u64),
+}
+
+impl Clock {
+    fn now(&self) -> u64 {
+        match self {
+            Clock::System(start) => start.elapsed().as_millis() as u64,
+            Clock::Manual(ms) => *ms,
+        }
+    }
+}
+
+/// When an entry of the history was first made, and when it was last coalesced with, in milliseconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Span {
+    start: u64,
+    end: u64,
+}
+
 #[derive(Debug)]
 pub struct History {
     history_stack: Operations,
     /// the undo stack are just for operation which we want to redo
     /// so if we undo an operation, it gets put here. Every time the user types something, it
     /// invalidates the undo stack, since the user has created a new time line (which still exists in the history stack, but the undone operations are now purged)
-    undo_stack: Operations,
+    undo_stack: Vec<(Operation, Span)>,
+    /// When each of the operations in the history stack was made
+    times: Vec<Span>,
+    /// How many operations there were in the history stack, when the buffer was last saved
+    saved_at: usize,
+    /// The most operations the history stack holds. When exceeded, the oldest operations are evicted and can no longer be undone
+    max_operations: usize,
+    /// How consecutive deletes are grouped into undo steps
+    coalesce_policy: CoalescePolicy,
+    clock: Clock,
+    /// Operations made within this many milliseconds of each other are undone together. None undoes every entry on its own
+    group_interval: Option<u64>,
 }
 
 impl History {
     pub fn new() -> History {
-        History { history_stack: Vec::with_capacity(1024), undo_stack: vec![] }
+        History::with_max_operations(DEFAULT_MAX_OPERATIONS)
+    }
+
+    pub fn with_max_operations(max_operations: usize) -> History {
+        History {
+            history_stack: Vec::with_capacity(std::cmp::min(1024, max_operations)),
+            undo_stack: vec![],
+            times: Vec::with_capacity(std::cmp::min(1024, max_operations)),
+            saved_at: 0,
+            max_operations,
+            coalesce_policy: CoalescePolicy::default(),
+            clock: Clock::System(std::time::Instant::now()),
+            group_interval: None,
+        }
+    }
+
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = clock;
+    }
+
+    pub fn group_interval(&self) -> Option<u64> {
+        self.group_interval
+    }
+
+    /// Sets the interval (in milliseconds) that operations made within of each other, are undone and redone together. This applies
+    /// to operations already in the history as well
+    pub fn set_group_interval(&mut self, interval_ms: Option<u64>) {
+        self.group_interval = interval_ms;
+    }
+
+    pub fn coalesce_policy(&self) -> CoalescePolicy {
+        self.coalesce_policy
+    }
+
+    /// Sets how deletes made from now on are grouped. Deletes already in the history keep their grouping
+    pub fn set_coalesce_policy(&mut self, policy: CoalescePolicy) {
+        self.coalesce_policy = policy;
+    }
+
+    pub fn max_operations(&self) -> usize {
+        self.max_operations
+    }
+
+    /// Sets how many operations the history holds on to, evicting the oldest ones right away if there are more than that
+    pub fn set_max_operations(&mut self, max_operations: usize) {
+        self.max_operations = max_operations;
+        self.evict_oldest();
+        // the undone operations can be redone onto the history stack, so they count towards the limit as well
+        let redoable = self.max_operations.saturating_sub(self.history_stack.len());
+        if self.undo_stack.len() > redoable {
+            // the undo stack is popped from the back, so the operations furthest away from being redone are at the front
+            let excess = self.undo_stack.len() - redoable;
+            self.undo_stack.drain(..excess);
+        }
+    }
+
+    /// Evicts the oldest operations, until the history stack is within its limit
+    fn evict_oldest(&mut self) {
+        if self.history_stack.len() > self.max_operations {
+            let excess = self.history_stack.len() - self.max_operations;
+            self.history_stack.drain(..excess);
+            self.times.drain(..excess);
+            // if the save point itself was evicted, every remaining operation was made after the save
+            self.saved_at = self.saved_at.saturating_sub(excess);
+        }
+    }
+
+    /// Marks the current state as saved. Operations made after this, are the ones returned by operations_since_save
+    pub fn mark_saved(&mut self) {
+        self.saved_at = self.history_stack.len();
+    }
+
+    /// The operations made since the buffer was last saved, oldest first
+    pub fn operations_since_save(&self) -> &[Operation] {
+        &self.history_stack[std::cmp::min(self.saved_at, self.history_stack.len())..]
+    }
+
+    /// Operations that were saved must not be coalesced with new ones, or the new edits would go missing from operations_since_save
+    #[inline(always)]
+    fn may_coalesce(&self) -> bool {
+        self.history_stack.len() > self.saved_at
     }
 
     #[inline(always)]
@@ -45,20 +199,42 @@ impl History {
         self.undo_stack.clear();
     }
 
+    /// Pushes a new entry onto the history stack, made now
+    fn record(&mut self, operation: Operation) {
+        let now = self.clock.now();
+        self.history_stack.push(operation);
+        self.times.push(Span { start: now, end: now });
+        self.evict_oldest();
+    }
+
+    /// Marks the latest entry of the history stack as changed now, when an operation has been coalesced into it
+    fn touch_latest(&mut self) {
+        let now = self.clock.now();
+        if let Some(span) = self.times.last_mut() {
+            span.end = now;
+        }
+    }
+
     pub fn push_insert_range(&mut self, index: metadata::Index, data: String) {
-        self.history_stack.push(Operation::Insert(index, OperationParameter::Range(data)));
+        self.record(Operation::Insert(index, OperationParameter::Range(data)));
         self.invalidate_undo_stack();
     }
 
     pub fn push_delete_range(&mut self, index: metadata::Index, data: String) {
-        self.history_stack.push(Operation::Delete(index, OperationParameter::Range(data)));
+        self.record(Operation::Delete(index, OperationParameter::Range(data)));
+        self.invalidate_undo_stack();
+    }
+
+    /// Pushes operations that should be undone/redone in one step, as a single entry in the history
+    pub fn push_group(&mut self, operations: Vec<Operation>) {
+        self.record(Operation::Group(operations));
         self.invalidate_undo_stack();
     }
 
     pub fn push_insert(&mut self, index: metadata::Index, ch: char) {
         self.invalidate_undo_stack();
         let mut coalesced = false;
-        if !ch.is_whitespace() {
+        if !ch.is_whitespace() && self.may_coalesce() {
             if let Some(Operation::Insert(i, o)) = self.history_stack.last_mut() {
                 coalesced = match o {
                     OperationParameter::Char(c) if !c.is_whitespace() && i.offset(1) == index => {
@@ -80,18 +256,21 @@ impl History {
                 };
             }
         }
-        if !coalesced {
-            self.history_stack.push(Operation::Insert(index, OperationParameter::Char(ch)));
+        if coalesced {
+            self.touch_latest();
+        } else {
+            self.record(Operation::Insert(index, OperationParameter::Char(ch)));
         }
     }
 
     pub fn push_delete(&mut self, index: metadata::Index, ch: char) {
         self.invalidate_undo_stack();
         let mut coalesced = false;
-        if !ch.is_whitespace() {
+        let policy = self.coalesce_policy;
+        if self.may_coalesce() {
             if let Some(Operation::Delete(i, o)) = self.history_stack.last_mut() {
                 coalesced = match o {
-                    OperationParameter::Char(c) if !c.is_whitespace() => {
+                    OperationParameter::Char(c) if policy.joins(ch, *c) => {
                         if index.offset(1) == *i {
                             // we've deleted backwards (backspace)
                             // thus, ch is going to be the first character in this range (from left to right)
@@ -115,11 +294,11 @@ impl History {
                         }
                     }
                     OperationParameter::Range(d) => {
-                        if *i == index {
+                        if *i == index && d.chars().last().map_or(false, |c| policy.joins(ch, c)) {
                             // we've deleted forwards
                             d.push(ch);
                             true
-                        } else if index.offset(1) == *i {
+                        } else if index.offset(1) == *i && d.chars().next().map_or(false, |c| policy.joins(ch, c)) {
                             //we've deleted backwards
                             d.insert(0, ch);
                             *i = index;
@@ -132,37 +311,162 @@ impl History {
                 };
             }
         }
-        if !coalesced {
-            self.history_stack.push(Operation::Delete(index, OperationParameter::Char(ch)));
+        if coalesced {
+            self.touch_latest();
+        } else {
+            self.record(Operation::Delete(index, OperationParameter::Char(ch)));
         }
     }
 
-    fn pop(&mut self) -> Option<Operation> {
-        self.history_stack.pop()
+    /// The operations currently in the history, oldest first. Undone operations are not among them
+    pub fn operations(&self) -> &[Operation] {
+        &self.history_stack
+    }
+
+    fn pop(&mut self) -> Option<(Operation, Span)> {
+        let popped = self.history_stack.pop().zip(self.times.pop());
+        // an undone saved operation can't be told apart from a new one, so the save point moves back with it
+        self.saved_at = std::cmp::min(self.saved_at, self.history_stack.len());
+        popped
     }
 
     /// Pops the latest operation from the history stack and pushes it onto the undo stack.
     /// It takes the operation and inverses it. So if when you hit "undo", it will take whatever's top of the history stack
-    /// inverse it (from a delete->insert and vice versa) and push that onto the undo stack. This is how one can achieve undo / redo
+    /// inverse it (from a delete->insert and vice versa) and push that onto the undo stack. This is how one can achieve undo / redo.
+    /// With a group interval set, the operations made within that interval of each other are popped as well, and undone as one group
     pub fn undo(&mut self) -> Option<&Operation> {
-        let popped = self.pop();
-        if let Some(op) = popped {
-            self.undo_stack.push(op);
-            self.undo_stack.last()
-        } else {
-            None
+        let (op, mut span) = self.pop()?;
+        let mut operations = vec![op];
+        if let Some(interval) = self.group_interval {
+            while let Some(&previous) = self.times.last() {
+                if span.start.saturating_sub(previous.end) > interval {
+                    break;
+                }
+                operations.extend(self.pop().map(|(op, _)| op));
+                span.start = previous.start;
+            }
         }
+        let op = if operations.len() == 1 {
+            operations.pop().unwrap()
+        } else {
+            operations.reverse();
+            Operation::Group(operations)
+        };
+        self.undo_stack.push((op, span));
+        self.undo_stack.last().map(|(op, _)| op)
     }
 
     pub fn redo(&mut self) -> Option<&Operation> {
-        let popped = self.undo_stack.pop();
-        if let Some(op) = popped {
-            self.history_stack.push(op);
-            self.history_stack.last()
-        } else {
-            None
+        let (op, span) = self.undo_stack.pop()?;
+        self.history_stack.push(op);
+        self.times.push(span);
+        self.history_stack.last()
+    }
+}
+
+/// The lines touched by operations, found by mapping the indices of the operations through meta_data. Indices of older operations
+/// can have been shifted by later edits, which makes them a bit off; good enough for finding where edits were made. Sorted and unique
+pub fn modified_lines(operations: &[Operation], meta_data: &metadata::MetaData) -> Vec<usize> {
+    fn touched_lines(operation: &Operation, meta_data: &metadata::MetaData, lines: &mut Vec<usize>) {
+        let line_of = |index: metadata::Index| {
+            meta_data
+                .get_line_number_of_buffer_index(index)
+                .unwrap_or_else(|| *meta_data.last_line())
+        };
+        match operation {
+            Operation::Insert(index, parameter) => {
+                let inserted = match parameter {
+                    OperationParameter::Char(_) => 1,
+                    OperationParameter::Range(data) => data.chars().count().max(1),
+                };
+                lines.extend(line_of(*index)..=line_of(index.offset(inserted as isize - 1)));
+            }
+            Operation::Delete(index, ..) => lines.push(line_of(*index)),
+            Operation::Group(operations) => {
+                for operation in operations {
+                    touched_lines(operation, meta_data, lines);
+                }
+            }
+        }
+    }
+    let mut lines = vec![];
+    for operation in operations {
+        touched_lines(operation, meta_data, &mut lines);
+    }
+    lines.sort_unstable();
+    lines.dedup();
+    lines
+}
+
+/// How a line has changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LineChange {
+    Modified,
+    /// Every character on the line was inserted
+    Added,
+}
+
+/// Classifies the lines touched by operations as added or modified, mapping the indices of the operations through meta_data, just like
+/// modified_lines does. meta_data is expected to describe the buffer after the operations were made. Sorted by line
+pub fn line_changes(operations: &[Operation], meta_data: &metadata::MetaData) -> Vec<(usize, LineChange)> {
+    fn classify(operation: &Operation, meta_data: &metadata::MetaData, changes: &mut BTreeMap<usize, LineChange>) {
+        let mut change = |line: usize, change: LineChange| {
+            let entry = changes.entry(line).or_insert(change);
+            *entry = std::cmp::max(*entry, change);
+        };
+        match operation {
+            Operation::Insert(index, parameter) => {
+                let (newlines, begins_with_newline, ends_with_newline) = match parameter {
+                    OperationParameter::Char(c) => ((*c == '\n') as usize, *c == '\n', *c == '\n'),
+                    OperationParameter::Range(data) => (data.matches('\n').count(), data.starts_with('\n'), data.ends_with('\n')),
+                };
+                let (line, col) = meta_data
+                    .position_of(*index)
+                    .map_or((*meta_data.last_line(), 0), |(line, col)| (*line, *col));
+                let at_end_of_line = meta_data
+                    .line_length(metadata::Line(line))
+                    .map_or(false, |length| *length == col + 1);
+                if begins_with_newline && at_end_of_line {
+                    // a new line typed at the end of a line, leaves that line as it was
+                    (line + 1..=line + newlines).for_each(|line| change(line, LineChange::Added));
+                } else if col == 0 && newlines > 0 {
+                    (line..line + newlines).for_each(|line| change(line, LineChange::Added));
+                    if !ends_with_newline {
+                        change(line + newlines, LineChange::Modified);
+                    }
+                } else {
+                    change(line, LineChange::Modified);
+                    (line + 1..=line + newlines).for_each(|line| change(line, LineChange::Added));
+                }
+            }
+            Operation::Delete(index, ..) => {
+                let line = meta_data
+                    .get_line_number_of_buffer_index(*index)
+                    .unwrap_or_else(|| *meta_data.last_line());
+                change(line, LineChange::Modified);
+            }
+            Operation::Group(operations) => {
+                for operation in operations {
+                    classify(operation, meta_data, changes);
+                }
+            }
         }
     }
+    let mut changes = BTreeMap::new();
+    for operation in operations {
+        classify(operation, meta_data, &mut changes);
+    }
+    changes.into_iter().filter(|(line, _)| *line < meta_data.line_count()).collect()
+}
+
+/// The first line of each run of consecutive lines in (sorted) lines
+pub fn region_starts(lines: &[usize]) -> Vec<usize> {
+    lines
+        .iter()
+        .enumerate()
+        .filter(|&(i, &line)| i == 0 || lines[i - 1] + 1 != line)
+        .map(|(_, &line)| line)
+        .collect()
 }
 
 #[derive(Debug, Hash, PartialEq, PartialOrd, Eq, Ord, Clone, Deserialize, Serialize)]
@@ -172,11 +476,45 @@ pub enum LineOperation {
     PasteAt { insertion: char },
 }
 
+/// How the case of text is changed, see ContiguousBuffer::transform_selection_case
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
+pub enum CaseMode {
+    Upper,
+    Lower,
+    /// The first letter of each word upper case, the rest lower case
+    Title,
+    /// Upper case letters become lower case, and lower case letters upper case
+    Toggle,
+}
+
+impl CaseMode {
+    /// Maps the case of each char in text. Some chars map to more than one char (like 'ß' to "SS"), so the result may be longer than text
+    pub fn apply(self, text: &[char]) -> Vec<char> {
+        let mut result = Vec::with_capacity(text.len());
+        let mut previous: Option<char> = None;
+        for &c in text {
+            let upper = match self {
+                CaseMode::Upper => true,
+                CaseMode::Lower => false,
+                CaseMode::Title => previous.map_or(true, |p| !p.is_alphanumeric() && p != '\''),
+                CaseMode::Toggle => !c.is_uppercase(),
+            };
+            if upper {
+                result.extend(c.to_uppercase());
+            } else {
+                result.extend(c.to_lowercase());
+            }
+            previous = Some(c);
+        }
+        result
+    }
+}
+
 #[cfg(test)]
 pub mod history_tests {
     use crate::textbuffer::{contiguous::contiguous::ContiguousBuffer, metadata, operations::OperationParameter, CharBuffer, Movement, TextKind};
 
-    use super::{History, Operation};
+    use super::{line_changes, modified_lines, region_starts, Clock, CoalescePolicy, History, LineChange, Operation};
 
     #[test]
     fn test_invalidate_undo_stack_after_insert() {
@@ -395,10 +733,184 @@ pub mod history_tests {
                     OperationParameter::Range(d) => sb.delete_range(*i, i.offset(d.len() as _)),
                 },
                 Operation::Delete(i, op) => {}
+                Operation::Group(..) => {}
             }
         }
 
         println!("{:#?}", history);
         println!("{:?}. Cursor: p{:?}", sb.data, sb.cursor());
     }
+
+    #[test]
+    fn modified_lines_of_operations() {
+        let mut b = ContiguousBuffer::new(0, 1024);
+        b.insert_slice(&"zero\none\ntwo\nthree\nfour\nfive".chars().collect::<Vec<_>>());
+        let mut history = History::new();
+        // on line 1
+        history.push_insert(metadata::Index(5), 'X');
+        // spanning line 3 and 4
+        history.push_insert_range(metadata::Index(14), "ab\ncd\nef".into());
+        history.push_group(vec![
+            Operation::Delete(metadata::Index(25), OperationParameter::Char('i')),
+            Operation::Insert(metadata::Index(25), OperationParameter::Char('I')),
+        ]);
+        // undone operations are no longer part of the history
+        history.push_delete(metadata::Index(0), 'z');
+        let _ = history.undo();
+
+        let lines = modified_lines(history.operations(), b.meta_data());
+        assert_eq!(lines, vec![1, 3, 4, 5]);
+        assert_eq!(region_starts(&lines), vec![1, 3]);
+        assert_eq!(region_starts(&[]), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn inserted_line_is_added_and_changed_line_modified() {
+        // "one\ntwo\nthree" after typing a new line "new" below "one" and capitalizing "three"
+        let mut b = ContiguousBuffer::new(0, 1024);
+        b.insert_slice(&"one\nnew\ntwo\nThree".chars().collect::<Vec<_>>());
+        let mut history = History::new();
+        history.push_insert(metadata::Index(3), '\n');
+        history.push_insert_range(metadata::Index(4), "new".into());
+        history.mark_saved();
+        history.push_delete(metadata::Index(12), 't');
+        history.push_insert(metadata::Index(12), 'T');
+
+        assert_eq!(line_changes(history.operations(), b.meta_data()), vec![(1, LineChange::Added), (3, LineChange::Modified)]);
+        assert_eq!(line_changes(history.operations_since_save(), b.meta_data()), vec![(3, LineChange::Modified)]);
+        history.mark_saved();
+        assert!(history.operations_since_save().is_empty());
+    }
+
+    #[test]
+    fn history_beyond_limit_evicts_oldest_first() {
+        let mut history = History::with_max_operations(3);
+        // whitespace is never coalesced, so every insert is an operation of its own
+        for i in 0..5 {
+            history.push_insert(metadata::Index(i), ' ');
+        }
+        let indices: Vec<usize> = history.operations().iter().map(|op| *op.index()).collect();
+        assert_eq!(indices, vec![2, 3, 4]);
+
+        // the retained operations can still be undone and redone, but no further back than that
+        assert_eq!(history.undo().map(|op| *op.index()), Some(4));
+        assert_eq!(history.undo().map(|op| *op.index()), Some(3));
+        assert_eq!(history.redo().map(|op| *op.index()), Some(3));
+        assert_eq!(history.undo().map(|op| *op.index()), Some(3));
+        assert_eq!(history.undo().map(|op| *op.index()), Some(2));
+        assert!(history.undo().is_none());
+        for _ in 0..3 {
+            assert!(history.redo().is_some());
+        }
+        assert!(history.redo().is_none());
+        assert_eq!(history.operations().len(), 3);
+    }
+
+    #[test]
+    fn buffer_undo_works_within_retained_history() {
+        let mut b = ContiguousBuffer::new(0, 1024);
+        b.set_history_limit(2);
+        for c in "a b c".chars() {
+            b.insert(c, true);
+        }
+        let _ = b.undo();
+        let _ = b.undo();
+        assert_eq!(b.data.iter().collect::<String>(), "a b");
+        let _ = b.undo();
+        assert_eq!(b.data.iter().collect::<String>(), "a b");
+        let _ = b.redo();
+        assert_eq!(b.data.iter().collect::<String>(), "a b ");
+    }
+
+    /// Backspaces over "ab cd\nef" from its end, with policy, and returns what each undo step restores
+    fn backspace_run(policy: CoalescePolicy) -> Vec<String> {
+        let text = "ab cd\nef";
+        let mut history = History::new();
+        history.set_coalesce_policy(policy);
+        for (index, ch) in text.chars().enumerate().collect::<Vec<_>>().into_iter().rev() {
+            history.push_delete(metadata::Index(index), ch);
+        }
+        let mut steps = vec![];
+        while let Some(op) = history.undo() {
+            steps.push(match op {
+                Operation::Delete(_, OperationParameter::Char(c)) => c.to_string(),
+                Operation::Delete(_, OperationParameter::Range(d)) => d.clone(),
+                _ => unreachable!(),
+            });
+        }
+        steps
+    }
+
+    #[test]
+    fn delete_coalescing_per_policy() {
+        assert_eq!(backspace_run(CoalescePolicy::PerChar), vec!["a", "b", " ", "c", "d", "\n", "e", "f"]);
+        assert_eq!(backspace_run(CoalescePolicy::PerWord), vec!["ab", " ", "cd", "\n", "ef"]);
+        assert_eq!(backspace_run(CoalescePolicy::PerLine), vec!["ab cd", "\n", "ef"]);
+        assert_eq!(CoalescePolicy::default(), CoalescePolicy::PerWord);
+    }
+
+    #[test]
+    fn delete_coalescing_breaks_on_cursor_jump_for_every_policy() {
+        let policies = [
+            CoalescePolicy::PerChar,
+            CoalescePolicy::PerWord,
+            CoalescePolicy::PerLine,
+        ];
+        for policy in policies.iter() {
+            let mut history = History::new();
+            history.set_coalesce_policy(*policy);
+            history.push_delete(metadata::Index(30), 'a');
+            history.push_delete(metadata::Index(30), 'b');
+            history.push_delete(metadata::Index(10), 'c');
+            assert_eq!(history.history_stack.last(), Some(&Operation::Delete(metadata::Index(10), OperationParameter::Char('c'))));
+        }
+    }
+
+    fn indices_of(operation: &Operation) -> Vec<usize> {
+        match operation {
+            Operation::Group(operations) => operations.iter().map(|op| *op.index()).collect(),
+            op => vec![*op.index()],
+        }
+    }
+
+    #[test]
+    fn operations_within_interval_undo_together() {
+        let mut history = History::new();
+        history.set_group_interval(Some(400));
+        // whitespace is never coalesced, so every insert is an entry of its own. The gaps are 100, 350, 550, 300 and 400 ms
+        let times = [0, 100, 450, 1000, 1300, 1700];
+        for (index, time) in times.iter().enumerate() {
+            history.set_clock(Clock::Manual(*time));
+            history.push_insert(metadata::Index(index), ' ');
+        }
+        assert_eq!(history.operations().len(), 6);
+        assert_eq!(history.undo().map(indices_of), Some(vec![3, 4, 5]));
+        assert_eq!(history.undo().map(indices_of), Some(vec![0, 1, 2]));
+        assert!(history.undo().is_none());
+        assert_eq!(history.redo().map(indices_of), Some(vec![0, 1, 2]));
+        assert_eq!(history.redo().map(indices_of), Some(vec![3, 4, 5]));
+        assert!(history.redo().is_none());
+
+        // without an interval, entries are undone one by one, but a redone group stays one entry
+        history.set_group_interval(None);
+        assert_eq!(history.undo().map(indices_of), Some(vec![3, 4, 5]));
+    }
+
+    #[test]
+    fn interval_is_measured_from_last_coalesced_edit() {
+        let mut history = History::new();
+        history.set_group_interval(Some(400));
+        history.set_clock(Clock::Manual(0));
+        history.push_insert(metadata::Index(0), ' ');
+        // "abc" is coalesced into one entry, typed from 1000 to 2000 ms
+        for (offset, (ch, time)) in [('a', 1000), ('b', 1500), ('c', 2000)].iter().enumerate() {
+            history.set_clock(Clock::Manual(*time));
+            history.push_insert(metadata::Index(1 + offset), *ch);
+        }
+        history.set_clock(Clock::Manual(2300));
+        history.push_insert(metadata::Index(4), ' ');
+        assert_eq!(history.operations().len(), 3);
+        assert_eq!(history.undo().map(indices_of), Some(vec![1, 4]));
+        assert_eq!(history.undo().map(indices_of), Some(vec![0]));
+    }
 }
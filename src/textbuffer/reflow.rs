@@ -0,0 +1,42 @@
+/// Comment markers kept at the beginning of every line of a rewrapped paragraph. Longer markers go first, so that `///` isn't taken for `//`
+const COMMENT_MARKERS: &[&str] = &["///", "//!", "//", "#", "--", ";", ">", "*"];
+
+/// What each line of a paragraph that starts like line, begins with: the indentation of line, and the comment marker after it (and
+/// the whitespace after the marker), if there is one
+pub fn line_prefix(line: &str) -> &str {
+    let indent = line.len() - line.trim_start().len();
+    let rest = &line[indent..];
+    match COMMENT_MARKERS.iter().find(|marker| rest.starts_with(**marker)) {
+        Some(marker) => {
+            let after = &rest[marker.len()..];
+            &line[..indent + marker.len() + after.len() - after.trim_start().len()]
+        }
+        None => &line[..indent],
+    }
+}
+
+/// Fills words into lines that begin with prefix, and are no longer than width characters. A word that doesn't fit on a line of
+/// its own is put on one anyway
+pub fn fill(words: &[&str], prefix: &str, width: usize) -> Vec<String> {
+    let prefix_len = prefix.chars().count();
+    let mut lines = vec![];
+    let mut line = prefix.to_string();
+    let mut len = prefix_len;
+    for word in words {
+        let word_len = word.chars().count();
+        if len > prefix_len && len + 1 + word_len > width {
+            lines.push(std::mem::replace(&mut line, prefix.to_string()));
+            len = prefix_len;
+        }
+        if len > prefix_len {
+            line.push(' ');
+            len += 1;
+        }
+        line.push_str(word);
+        len += word_len;
+    }
+    if len > prefix_len {
+        lines.push(line);
+    }
+    lines
+}
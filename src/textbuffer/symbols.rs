@@ -0,0 +1,144 @@
+use super::contiguous::contiguous::is_word_char;
+
+/// What a symbol is defined as, by the keyword that introduces it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    Struct,
+    Enum,
+    Union,
+    Trait,
+    Module,
+    Type,
+    Constant,
+    Static,
+    Macro,
+}
+
+const KEYWORDS: &[(&str, SymbolKind)] = &[
+    ("fn", SymbolKind::Function),
+    ("struct", SymbolKind::Struct),
+    ("enum", SymbolKind::Enum),
+    ("union", SymbolKind::Union),
+    ("trait", SymbolKind::Trait),
+    ("mod", SymbolKind::Module),
+    ("type", SymbolKind::Type),
+    ("const", SymbolKind::Constant),
+    ("static", SymbolKind::Static),
+    ("macro_rules!", SymbolKind::Macro),
+];
+
+impl SymbolKind {
+    pub fn keyword(self) -> &'static str {
+        KEYWORDS.iter().find(|(_, kind)| *kind == self).map(|(keyword, _)| *keyword).unwrap()
+    }
+
+    fn from_keyword(word: &str) -> Option<SymbolKind> {
+        KEYWORDS.iter().find(|(keyword, _)| *keyword == word).map(|(_, kind)| *kind)
+    }
+}
+
+/// A definition found by scan_symbols
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// The line (0-based) the symbol is defined on
+    pub line: usize,
+}
+
+impl Symbol {
+    /// How the symbol is listed in the input box, for the user to choose from. Lines are shown 1-based, like everywhere else
+    pub fn list_item(&self) -> String {
+        format!("{}: {} {}", self.line + 1, self.kind.keyword(), self.name)
+    }
+}
+
+/// The line (0-based) of a symbol, listed with Symbol::list_item
+pub fn line_of_list_item(item: &str) -> Option<usize> {
+    item.split(':')
+        .next()
+        .and_then(|line| line.trim().parse::<usize>().ok())
+        .and_then(|line| line.checked_sub(1))
+}
+
+/// Words that may come before the keyword of a definition, like in `pub(crate) async unsafe fn`
+fn is_qualifier(word: &str) -> bool {
+    matches!(word, "pub" | "async" | "unsafe" | "extern" | "default") || word.starts_with("pub(") || word.starts_with('"')
+}
+
+/// Finds the definitions of (Rust) functions, types, modules, constants and macros in data, one per line at most. This is a line by line
+/// scan and not a parser, so definitions split over several lines, or in comments and strings, may be missed or found where there are none
+pub fn scan_symbols(data: &[char]) -> Vec<Symbol> {
+    let text: String = data.iter().collect();
+    let mut symbols = vec![];
+    for (line, text) in text.lines().enumerate() {
+        let mut words = text.split_whitespace().peekable();
+        while let Some(word) = words.next() {
+            let next = match words.peek() {
+                Some(next) => *next,
+                None => break,
+            };
+            match SymbolKind::from_keyword(word) {
+                // `const fn` and the like, where the last keyword is what's being defined
+                Some(_) if is_qualifier(next) || SymbolKind::from_keyword(next).is_some() => continue,
+                Some(kind) => {
+                    let name_word = if next == "mut" { words.nth(1) } else { Some(next) };
+                    let name: String = name_word.map_or(String::new(), |w| w.chars().take_while(|&c| is_word_char(c)).collect());
+                    if name.chars().next().map_or(false, |c| !c.is_numeric()) {
+                        symbols.push(Symbol { name, kind, line });
+                    }
+                    break;
+                }
+                None if is_qualifier(word) => continue,
+                None => break,
+            }
+        }
+    }
+    symbols
+}
+
+/// The symbols named name
+pub fn definitions_of(symbols: Vec<Symbol>, name: &str) -> Vec<Symbol> {
+    symbols.into_iter().filter(|symbol| symbol.name == name).collect()
+}
+
+#[cfg(test)]
+mod symbols_tests {
+    use super::{definitions_of, line_of_list_item, scan_symbols, Symbol, SymbolKind};
+
+    #[test]
+    fn scan_definitions() {
+        let source = "use std::fmt;\n\
+                      pub(crate) struct Point<T> {\n\
+                      \x20   x: T,\n\
+                      }\n\
+                      // fn commented_out() {}\n\
+                      pub const fn origin() -> Point<i32> {\n\
+                      \x20   let fn_like = 1;\n\
+                      }\n\
+                      static mut COUNTER: usize = 0;\n\
+                      impl fmt::Display for Point<i32> {}\n\
+                      macro_rules! point {\n";
+        let symbols = scan_symbols(&source.chars().collect::<Vec<_>>());
+        let found: Vec<(&str, SymbolKind, usize)> = symbols.iter().map(|s| (s.name.as_str(), s.kind, s.line)).collect();
+        assert_eq!(
+            found,
+            vec![
+                ("Point", SymbolKind::Struct, 1),
+                ("origin", SymbolKind::Function, 5),
+                ("COUNTER", SymbolKind::Static, 8),
+                ("point", SymbolKind::Macro, 10),
+            ]
+        );
+        assert_eq!(definitions_of(symbols, "origin").len(), 1);
+    }
+
+    #[test]
+    fn list_items_carry_the_line() {
+        let symbol = Symbol { name: "main".into(), kind: SymbolKind::Function, line: 41 };
+        assert_eq!(symbol.list_item(), "42: fn main");
+        assert_eq!(line_of_list_item(&symbol.list_item()), Some(41));
+        assert_eq!(line_of_list_item("main"), None);
+    }
+}
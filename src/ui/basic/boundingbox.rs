@@ -2,9 +2,9 @@ use super::{
     coordinate::{Margin, Size},
     frame::Frame,
 };
-use crate::datastructure::generic::{Vec2f, Vec2i};
+use crate::datastructure::generic::{Vec2d, Vec2f, Vec2i};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BoundingBox {
     /// Bottom left corner
     pub min: Vec2i,
@@ -28,6 +28,13 @@ impl BoundingBox {
         BoundingBox::from((anchor, size))
     }
 
+    /// The box with its corners at min and max, which may be fractional (after scaling, say), each edge rounded to the nearest pixel.
+    /// Rounding the edges, rather than a position and a size, keeps boxes that shared an edge before snapping, sharing it after
+    pub fn snap_to_pixel(min: Vec2d, max: Vec2d) -> BoundingBox {
+        let snap = |v: Vec2d| Vec2i::new(v.x.round() as i32, v.y.round() as i32);
+        BoundingBox::new(snap(min), snap(max))
+    }
+
     #[inline(always)]
     pub fn from_frame(frame: &Frame) -> BoundingBox {
         let (Vec2i { x, y }, Size { width, height }) = (frame.anchor, frame.size);
@@ -141,3 +148,31 @@ impl From<(Vec2i, Size)> for BoundingBox {
         BoundingBox::new(Vec2i::new(x, y - size.height), Vec2i::new(x + size.width, y))
     }
 }
+
+#[cfg(test)]
+mod boundingbox_tests {
+    use super::BoundingBox;
+    use crate::datastructure::generic::{Vec2d, Vec2i};
+
+    #[test]
+    fn snapping_rounds_each_edge() {
+        let snapped = BoundingBox::snap_to_pixel(Vec2d::new(10.4, 20.5), Vec2d::new(300.6, 199.49));
+        assert_eq!(snapped, BoundingBox::new(Vec2i::new(10, 21), Vec2i::new(301, 199)));
+    }
+
+    #[test]
+    fn snapped_neighbours_share_their_edge() {
+        let scale = |x: i32, factor: f64| Vec2d::new(x as f64 * factor, 0.0);
+        for factor in [0.77, 1.2345, 1.5, 2.0 / 3.0].iter() {
+            let edges = [0, 333, 667, 1001];
+            let boxes: Vec<BoundingBox> = edges
+                .windows(2)
+                .map(|w| BoundingBox::snap_to_pixel(scale(w[0], *factor), scale(w[1], *factor)))
+                .collect();
+            for pair in boxes.windows(2) {
+                assert_eq!(pair[0].max.x, pair[1].min.x, "a gap or an overlap, scaled by {}", factor);
+            }
+            assert_eq!(boxes.iter().map(|b| b.width()).sum::<i32>(), (1001.0 * factor).round() as i32);
+        }
+    }
+}
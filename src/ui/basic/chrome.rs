@@ -0,0 +1,83 @@
+use crate::datastructure::generic::Vec2i;
+
+use super::{
+    coordinate::{Coordinate, Size},
+    frame::Frame,
+};
+
+/// The parts of a view around its text: a title bar above it, a gutter to its left, a scroll bar to its right and a footer below
+/// it, if there is one. The text gets whatever space is left over
+#[derive(Clone, Copy, Debug)]
+pub struct Chrome {
+    pub title_height: i32,
+    pub gutter_width: i32,
+    pub scroll_bar_width: i32,
+    pub footer_height: Option<i32>,
+}
+
+/// Where each part of a view goes, see Chrome::layout
+#[derive(Clone, Copy, Debug)]
+pub struct ChromeLayout {
+    pub title: Frame,
+    pub gutter: Frame,
+    pub content: Frame,
+    pub scroll_bar: Frame,
+    pub footer: Option<Frame>,
+}
+
+impl Chrome {
+    /// Lays out the parts of a view, with its top left corner at anchor, that takes up size in total
+    pub fn layout(&self, anchor: Vec2i, size: Size) -> ChromeLayout {
+        let footer_height = self.footer_height.unwrap_or(0);
+        let body_height = size.height - self.title_height - footer_height;
+        let body_top = anchor.y - self.title_height;
+        let content_width = size.width - self.gutter_width - self.scroll_bar_width;
+        ChromeLayout {
+            title: Frame::new(anchor, Size::new(size.width, self.title_height)),
+            gutter: Frame::new(Vec2i::new(anchor.x, body_top), Size::new(self.gutter_width, body_height)),
+            content: Frame::new(Vec2i::new(anchor.x + self.gutter_width, body_top), Size::new(content_width, body_height)),
+            scroll_bar: Frame::new(Vec2i::new(anchor.x + size.width - self.scroll_bar_width, body_top), Size::new(self.scroll_bar_width, body_height)),
+            footer: self
+                .footer_height
+                .map(|height| Frame::new(Vec2i::new(anchor.x, body_top - body_height), Size::new(size.width, height))),
+        }
+    }
+
+    /// The total size of a view, whose content is of size content. The opposite of layout
+    pub fn outer_size(&self, content: Size) -> Size {
+        Size::new(content.width + self.gutter_width + self.scroll_bar_width, content.height + self.title_height + self.footer_height.unwrap_or(0))
+    }
+}
+
+#[cfg(test)]
+mod chrome_tests {
+    use super::Chrome;
+    use crate::datastructure::generic::Vec2i;
+    use crate::ui::basic::{coordinate::Size, frame::Frame};
+
+    /// The left, top, width and height of frame
+    fn rect(frame: Frame) -> (i32, i32, i32, i32) {
+        (frame.anchor.x, frame.anchor.y, frame.size.width, frame.size.height)
+    }
+
+    #[test]
+    fn content_gets_what_the_chrome_leaves() {
+        let size = Size { width: 500, height: 400 };
+        let plain = Chrome { title_height: 20, gutter_width: 0, scroll_bar_width: 15, footer_height: None };
+        let layout = plain.layout(Vec2i::new(100, 700), size);
+        assert_eq!(rect(layout.title), (100, 700, 500, 20));
+        assert_eq!(rect(layout.content), (100, 680, 485, 380));
+        assert_eq!(rect(layout.scroll_bar), (585, 680, 15, 380));
+        assert!(layout.footer.is_none());
+
+        let full = Chrome { gutter_width: 30, footer_height: Some(18), ..plain };
+        let layout = full.layout(Vec2i::new(100, 700), size);
+        assert_eq!(rect(layout.gutter), (100, 680, 30, 362));
+        assert_eq!(rect(layout.content), (130, 680, 455, 362));
+        assert_eq!(rect(layout.scroll_bar), (585, 680, 15, 362));
+        assert_eq!(rect(layout.footer.unwrap()), (100, 318, 500, 18));
+
+        let outer = full.outer_size(layout.content.size);
+        assert_eq!((outer.width, outer.height), (size.width, size.height));
+    }
+}
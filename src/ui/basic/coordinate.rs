@@ -99,35 +99,33 @@ impl Size {
 
     pub fn divide(&self, divisor: u32, margin: i32, layout: Layout) -> Vec<Size> {
         assert_ne!(divisor, 0);
-
-        let divisor = divisor as i32;
+        self.divide_weighted(&vec![1; divisor as usize], margin, layout)
+    }
+
+    /// Divides the size along the layout axis, giving each element a share proportional to its weight
+    pub fn divide_weighted(&self, weights: &[u32], margin: i32, layout: Layout) -> Vec<Size> {
+        assert!(!weights.is_empty());
+        let divisor = weights.len() as i32;
+        let total_weight = weights.iter().map(|&w| w as i64).sum::<i64>().max(1);
+        let spread = |total: i32| -> Vec<i32> {
+            let mut result: Vec<i32> = weights.iter().map(|&w| (total as i64 * w as i64 / total_weight) as i32).collect();
+            // we're dealing with integers... so we need all elements to actually cover, so one element might get a bit larger
+            let diff = total - result.iter().sum::<i32>();
+            *result.last_mut().unwrap() += diff;
+            result
+        };
         match layout {
             Layout::Horizontal(Spacing(space)) => {
                 let total_width = self.width - (margin * 2) - space as i32 * (divisor - 1);
                 assert!(total_width > 0, "Margin & spacing taking up more space than dimension can handle");
-                let element_width = total_width / divisor;
-                // we're dealing with integers... so we need all elements to actually cover, so one element might get a bit larger
-                let diff_width = total_width - (divisor * element_width);
-                let mut result = vec![];
-                for _ in 0..(divisor - 1) {
-                    result.push(Size { width: element_width, height: self.height - margin * 2 })
-                }
-                result.push(Size { width: element_width + diff_width, height: self.height - margin * 2 });
-                result
+                let height = self.height - margin * 2;
+                spread(total_width).into_iter().map(|width| Size { width, height }).collect()
             }
             Layout::Vertical(Spacing(space)) => {
                 let width = self.width - (margin * 2);
                 let total_height = self.height - (margin * 2) - space as i32 * (divisor - 1);
                 assert!(total_height > 0, "Margin & spacing taking up more space than dimension can handle");
-                let element_height = total_height / divisor;
-                // we're dealing with integers... so we need all elements to actually cover, so one element might get a bit larger
-                let diff_height = total_height - (divisor * element_height);
-                let mut result = vec![];
-                for _ in 0..(divisor - 1) {
-                    result.push(Size { width, height: element_height })
-                }
-                result.push(Size { width, height: element_height + diff_height });
-                result
+                spread(total_height).into_iter().map(|height| Size { width, height }).collect()
             }
         }
     }
@@ -6,3 +6,6 @@ pub mod boundingbox;
 
 /// A frame is a struct containing the anchor point of a UI element (it's most top left position) and it's size in pixels
 pub mod frame;
+
+/// The title bar, gutter, scroll bar and footer around the text of a view, and the layout of them
+pub mod chrome;
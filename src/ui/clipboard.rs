@@ -1,35 +1,258 @@
+use crate::textbuffer::{contiguous::contiguous::ContiguousBuffer, cursor::MetaCursor, CharBuffer};
+use std::path::Path;
+
+/// What was copied, which decides how it gets pasted
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ClipKind {
+    /// A selection, pasted at the cursor
+    Char,
+    /// Whole lines, copied without a selection, pasted as new lines above the cursor's line
+    Line,
+    /// A rectangular block, pasted line by line at the cursor's column
+    Block,
+}
+
+/// How the Copy with line numbers and Copy as code block commands dress up the copied text, for pasting it outside the editor
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CopyFormat {
+    /// Each line begins with its line number, like in a gutter
+    LineNumbers,
+    /// Wrapped in a markdown code fence, tagged with the language of the file
+    CodeBlock,
+}
+
+/// The language tags of markdown code blocks, for files with each extension
+pub const FENCE_LANGUAGES: &[(&str, &str)] = &[
+    ("rs", "rust"),
+    ("c", "c"),
+    ("h", "c"),
+    ("cpp", "cpp"),
+    ("cc", "cpp"),
+    ("hpp", "cpp"),
+    ("go", "go"),
+    ("py", "python"),
+    ("js", "javascript"),
+    ("ts", "typescript"),
+    ("glsl", "glsl"),
+    ("sh", "sh"),
+    ("toml", "toml"),
+    ("json", "json"),
+    ("md", "markdown"),
+];
+
+/// The language tag of a code block holding text from file, going by its extension. Empty if it's unknown
+pub fn fence_language(file: Option<&Path>) -> &'static str {
+    file.and_then(|file| file.extension())
+        .and_then(|extension| extension.to_str())
+        .and_then(|extension| FENCE_LANGUAGES.iter().find(|(ext, _)| *ext == extension))
+        .map_or("", |(_, language)| *language)
+}
+
+/// Prefixes the lines of text with their numbers, counting from first_line, right aligned to the widest of them
+pub fn with_line_numbers(text: &str, first_line: usize) -> String {
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    let width = (first_line + lines.len().saturating_sub(1)).to_string().len();
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| format!("{:>width$} | {}", first_line + i, line, width = width))
+        .collect()
+}
+
+/// Wraps text in a markdown code block tagged with language. The fence is made longer than any run of backticks in text, so that
+/// those don't end the block
+pub fn fenced(text: &str, language: &str) -> String {
+    let longest_run = text.split(|c: char| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(std::cmp::max(3, longest_run + 1));
+    let line_end = if text.ends_with('\n') { "" } else { "\n" };
+    format!("{}{}\n{}{}{}\n", fence, language, text, line_end, fence)
+}
+
+#[derive(Clone, Debug)]
+pub struct ClipEntry {
+    pub text: String,
+    pub kind: ClipKind,
+}
+
+impl ClipEntry {
+    pub fn new(text: String, kind: ClipKind) -> ClipEntry {
+        ClipEntry { text, kind }
+    }
+
+    fn kind_of_copy(buffer: &ContiguousBuffer) -> ClipKind {
+        if buffer.meta_cursor.is_some() {
+            ClipKind::Char
+        } else {
+            ClipKind::Line
+        }
+    }
+
+    /// Copies the selection, or the line of the cursor if nothing is selected
+    pub fn copied_from(buffer: &ContiguousBuffer) -> Option<ClipEntry> {
+        let kind = ClipEntry::kind_of_copy(buffer);
+        buffer.copy_range_or_line().map(|text| ClipEntry::new(text, kind))
+    }
+
+    /// Copies the selection, or the line of the cursor if nothing is selected, in format. Numbered lines are numbered like in the
+    /// gutter, from 1
+    pub fn copied_as(buffer: &ContiguousBuffer, format: CopyFormat) -> Option<ClipEntry> {
+        let kind = ClipEntry::kind_of_copy(buffer);
+        let text = buffer.copy_range_or_line()?;
+        let text = match format {
+            CopyFormat::LineNumbers => {
+                let begin = match buffer.meta_cursor {
+                    Some(MetaCursor::Absolute(meta_cursor)) => std::cmp::min(meta_cursor, buffer.cursor_abs()),
+                    _ => buffer.cursor_abs(),
+                };
+                let first_line = buffer.meta_data().get_line_number_of_buffer_index(begin).unwrap_or(0);
+                with_line_numbers(&text, first_line + 1)
+            }
+            CopyFormat::CodeBlock => fenced(&text, fence_language(buffer.file_name())),
+        };
+        Some(ClipEntry::new(text, kind))
+    }
+
+    /// Cuts the selection, or the line of the cursor if nothing is selected
+    pub fn cut_from(buffer: &mut ContiguousBuffer) -> Option<ClipEntry> {
+        let kind = ClipEntry::kind_of_copy(buffer);
+        buffer.cut_range_or_line().map(|text| ClipEntry::new(text, kind))
+    }
+
+    /// Pastes the entry into buffer. Lines and blocks pasted over a selection, are pasted like any other text, replacing the selection
+    pub fn paste_into(&self, buffer: &mut ContiguousBuffer) {
+        match self.kind {
+            ClipKind::Line if buffer.meta_cursor.is_none() => buffer.insert_lines_above_cursor(&self.text),
+            ClipKind::Block if buffer.meta_cursor.is_none() => buffer.insert_block(&self.text),
+            _ => {
+                let data: Vec<char> = self.text.chars().collect();
+                buffer.insert_slice(&data);
+            }
+        }
+    }
+}
+
 pub struct ClipBoard {
-    data: String,
+    data: Option<ClipEntry>,
 }
 
 impl ClipBoard {
     pub fn new() -> ClipBoard {
-        ClipBoard { data: String::new() }
+        ClipBoard { data: None }
     }
 
     pub fn copy(&mut self, data: &str) {
-        self.data = data.to_owned();
+        self.take(data.to_owned());
     }
 
     pub fn take(&mut self, data: String) {
-        self.data = data;
+        self.take_entry(ClipEntry::new(data, ClipKind::Char));
+    }
+
+    pub fn take_entry(&mut self, entry: ClipEntry) {
+        self.data = Some(entry).filter(|entry| !entry.text.is_empty());
     }
 
     pub fn give(&self) -> Option<&String> {
-        if self.data.is_empty() {
-            None
-        } else {
-            Some(&self.data)
-        }
+        self.give_entry().map(|entry| &entry.text)
+    }
+
+    pub fn give_entry(&self) -> Option<&ClipEntry> {
+        self.data.as_ref()
     }
 
     pub fn release(&mut self) -> Option<String> {
-        if self.data.is_empty() {
-            None
-        } else {
-            let mut res = String::with_capacity(self.data.len());
-            std::mem::swap(&mut res, &mut self.data);
-            Some(res)
+        self.data.take().map(|entry| entry.text)
+    }
+}
+
+#[cfg(test)]
+mod clipboard_tests {
+    use super::{fence_language, fenced, with_line_numbers, ClipBoard, ClipEntry, ClipKind, CopyFormat};
+    use crate::textbuffer::{contiguous::contiguous::ContiguousBuffer, metadata as md, CharBuffer, Movement, TextKind};
+    use std::path::Path;
+
+    fn buffer_with(text: &str) -> ContiguousBuffer {
+        let mut b = ContiguousBuffer::new(0, 1024);
+        for c in text.chars() {
+            b.insert(c, true);
         }
+        b
+    }
+
+    #[test]
+    fn line_copy_pastes_as_new_line() {
+        let mut b = buffer_with("foo\nbar");
+        b.cursor_goto(md::Index(1));
+        let mut clipboard = ClipBoard::new();
+        clipboard.take_entry(ClipEntry::copied_from(&b).unwrap());
+        assert_eq!(clipboard.give_entry().unwrap().kind, ClipKind::Line);
+
+        // pasted above the cursor's line, no matter the column
+        b.cursor_goto(md::Index(6));
+        clipboard.give_entry().unwrap().paste_into(&mut b);
+        assert_eq!(b.data.iter().collect::<String>(), "foo\nfoo\nbar");
+        assert_eq!(b.cursor_abs(), md::Index(10));
+        b.undo();
+        assert_eq!(b.data.iter().collect::<String>(), "foo\nbar");
+
+        // the last line has no new line of its own, but still pastes as a whole line
+        b.cursor_goto(md::Index(5));
+        let last_line = ClipEntry::copied_from(&b).unwrap();
+        b.cursor_goto(md::Index(0));
+        last_line.paste_into(&mut b);
+        assert_eq!(b.data.iter().collect::<String>(), "bar\nfoo\nbar");
+    }
+
+    #[test]
+    fn char_copy_pastes_inline() {
+        let mut b = buffer_with("foo\nbar");
+        b.cursor_goto(md::Index(0));
+        b.select_move_cursor_absolute(Movement::Forward(TextKind::Char, 1));
+        let entry = ClipEntry::copied_from(&b).unwrap();
+        assert_eq!(entry.kind, ClipKind::Char);
+        assert_eq!(entry.text, "fo");
+
+        b.meta_cursor = None;
+        b.cursor_goto(md::Index(5));
+        entry.paste_into(&mut b);
+        assert_eq!(b.data.iter().collect::<String>(), "foo\nbfoar");
+    }
+
+    #[test]
+    fn block_pastes_at_column() {
+        let mut b = buffer_with("ab\n\ncd");
+        b.cursor_goto(md::Index(1));
+        ClipEntry::new("1\n2\n3\n4".into(), ClipKind::Block).paste_into(&mut b);
+        assert_eq!(b.data.iter().collect::<String>(), "a1b\n 2\nc3d\n 4");
+        assert_eq!(b.cursor_abs(), md::Index(1));
+        b.undo();
+        assert_eq!(b.data.iter().collect::<String>(), "ab\n\ncd");
+    }
+
+    #[test]
+    fn copying_with_line_numbers() {
+        let mut b = buffer_with("fn main() {\n    let a = 1;\n}\n");
+        b.cursor_goto(md::Index(28));
+        b.set_absolute_meta_cursor(md::Index(16));
+        let entry = ClipEntry::copied_as(&b, CopyFormat::LineNumbers).unwrap();
+        assert_eq!(entry.text, "2 | let a = 1;\n3 | }\n");
+        assert_eq!(entry.kind, ClipKind::Char);
+        b.meta_cursor = None;
+        assert_eq!(ClipEntry::copied_as(&b, CopyFormat::LineNumbers).unwrap().text, "3 | }\n", "the line of the cursor");
+        assert_eq!(with_line_numbers("a\nb\nc", 8), " 8 | a\n 9 | b\n10 | c");
+    }
+
+    #[test]
+    fn copying_as_code_block() {
+        let mut b = buffer_with("fn main() {\n    let a = 1;\n}\n");
+        b.cursor_goto(md::Index(28));
+        b.set_absolute_meta_cursor(md::Index(0));
+        assert_eq!(ClipEntry::copied_as(&b, CopyFormat::CodeBlock).unwrap().text, "```\nfn main() {\n    let a = 1;\n}\n```\n");
+        assert_eq!(fenced("let a = 1;", "rust"), "```rust\nlet a = 1;\n```\n");
+        assert_eq!(fenced("``` and ````", ""), "`````\n``` and ````\n`````\n");
+        assert_eq!(fence_language(Some(Path::new("src/app.rs"))), "rust");
+        assert_eq!(fence_language(Some(Path::new("list.hpp"))), "cpp");
+        assert_eq!(fence_language(Some(Path::new("Makefile"))), "");
+        assert_eq!(fence_language(None), "");
     }
 }
@@ -0,0 +1,132 @@
+use super::{boundingbox::BoundingBox, coordinate::Layout};
+use crate::datastructure::generic::Vec2i;
+use glfw::StandardCursor;
+
+/// What lies under the mouse cursor, which decides the shape of the cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorRegion {
+    /// The title bar of a view, by which the view is dragged
+    Title,
+    ScrollBar,
+    Text,
+    /// The space between two views laid out side by side
+    VerticalDivider,
+    /// The space between two views stacked on top of each other
+    HorizontalDivider,
+    Elsewhere,
+}
+
+impl CursorRegion {
+    pub fn standard_cursor(self) -> StandardCursor {
+        match self {
+            CursorRegion::Title | CursorRegion::ScrollBar => StandardCursor::Hand,
+            CursorRegion::Text => StandardCursor::IBeam,
+            CursorRegion::VerticalDivider => StandardCursor::HResize,
+            CursorRegion::HorizontalDivider => StandardCursor::VResize,
+            CursorRegion::Elsewhere => StandardCursor::Arrow,
+        }
+    }
+}
+
+/// The parts of a view that the cursor changes shape over
+#[derive(Debug, Clone)]
+pub struct ViewRegions {
+    pub title: BoundingBox,
+    pub scroll_bar: BoundingBox,
+    pub text: BoundingBox,
+}
+
+impl ViewRegions {
+    /// The box spanning all of the view's regions
+    fn extent(&self) -> BoundingBox {
+        let boxes = [&self.title, &self.scroll_bar, &self.text];
+        BoundingBox::new(
+            Vec2i::new(boxes.iter().map(|b| b.min.x).min().unwrap(), boxes.iter().map(|b| b.min.y).min().unwrap()),
+            Vec2i::new(boxes.iter().map(|b| b.max.x).max().unwrap(), boxes.iter().map(|b| b.max.y).max().unwrap()),
+        )
+    }
+}
+
+/// A panel, and the regions of its visible views, in the order they're laid out
+pub struct PanelRegions {
+    pub bounding_box: BoundingBox,
+    pub layout: Layout,
+    pub views: Vec<ViewRegions>,
+}
+
+/// Classifies what's at pos. Panels are given in the order they're drawn, so the last one containing pos is the one on top
+pub fn cursor_region_at(pos: Vec2i, panels: &[PanelRegions]) -> CursorRegion {
+    let panel = match panels.iter().rev().find(|p| p.bounding_box.box_hit_check(pos)) {
+        Some(panel) => panel,
+        None => return CursorRegion::Elsewhere,
+    };
+    for view in panel.views.iter() {
+        if view.title.box_hit_check(pos) {
+            return CursorRegion::Title;
+        } else if view.scroll_bar.box_hit_check(pos) {
+            return CursorRegion::ScrollBar;
+        } else if view.text.box_hit_check(pos) {
+            return CursorRegion::Text;
+        }
+    }
+    let between = panel.views.windows(2).any(|pair| {
+        let (a, b) = (pair[0].extent(), pair[1].extent());
+        match panel.layout {
+            // laid out left to right
+            Layout::Horizontal(_) => pos.x > a.max.x && pos.x < b.min.x,
+            // laid out top to bottom, and y grows upwards
+            Layout::Vertical(_) => pos.y < a.min.y && pos.y > b.max.y,
+        }
+    });
+    match (between, panel.layout) {
+        (true, Layout::Horizontal(_)) => CursorRegion::VerticalDivider,
+        (true, Layout::Vertical(_)) => CursorRegion::HorizontalDivider,
+        (false, _) => CursorRegion::Elsewhere,
+    }
+}
+
+#[cfg(test)]
+mod cursor_region_tests {
+    use super::{cursor_region_at, CursorRegion, PanelRegions, ViewRegions};
+    use crate::datastructure::generic::Vec2i;
+    use crate::ui::boundingbox::BoundingBox;
+    use crate::ui::coordinate::{Layout, Spacing};
+
+    /// A view spanning x from left to right, and y from bottom to top, with a title bar 20 high and a scroll bar 10 wide
+    fn view(left: i32, right: i32, bottom: i32, top: i32) -> ViewRegions {
+        ViewRegions {
+            title: BoundingBox::new(Vec2i::new(left, top - 20), Vec2i::new(right, top)),
+            scroll_bar: BoundingBox::new(Vec2i::new(right - 10, bottom), Vec2i::new(right, top - 21)),
+            text: BoundingBox::new(Vec2i::new(left, bottom), Vec2i::new(right - 11, top - 21)),
+        }
+    }
+
+    #[test]
+    fn classify_regions_of_side_by_side_views() {
+        let panels = [PanelRegions {
+            bounding_box: BoundingBox::new(Vec2i::new(0, 0), Vec2i::new(1000, 600)),
+            layout: Layout::Horizontal(Spacing(10)),
+            views: vec![view(5, 495, 5, 595), view(505, 995, 5, 595)],
+        }];
+        assert_eq!(cursor_region_at(Vec2i::new(100, 590), &panels), CursorRegion::Title);
+        assert_eq!(cursor_region_at(Vec2i::new(490, 300), &panels), CursorRegion::ScrollBar);
+        assert_eq!(cursor_region_at(Vec2i::new(600, 300), &panels), CursorRegion::Text);
+        assert_eq!(cursor_region_at(Vec2i::new(500, 300), &panels), CursorRegion::VerticalDivider);
+        // the panel's margin is not between views
+        assert_eq!(cursor_region_at(Vec2i::new(2, 300), &panels), CursorRegion::Elsewhere);
+        assert_eq!(cursor_region_at(Vec2i::new(1200, 300), &panels), CursorRegion::Elsewhere);
+    }
+
+    #[test]
+    fn classify_regions_of_stacked_views() {
+        let panels = [PanelRegions {
+            bounding_box: BoundingBox::new(Vec2i::new(0, 0), Vec2i::new(1000, 600)),
+            layout: Layout::Vertical(Spacing(10)),
+            views: vec![view(5, 995, 305, 595), view(5, 995, 5, 295)],
+        }];
+        assert_eq!(cursor_region_at(Vec2i::new(500, 300), &panels), CursorRegion::HorizontalDivider);
+        assert_eq!(cursor_region_at(Vec2i::new(500, 290), &panels), CursorRegion::Title);
+        assert_eq!(cursor_region_at(Vec2i::new(500, 100), &panels), CursorRegion::Text);
+        assert_eq!(cursor_region_at(Vec2i::new(500, 2), &panels), CursorRegion::Elsewhere);
+    }
+}
@@ -7,7 +7,7 @@ use crate::{
     },
 };
 
-use crate::opengl::text_renderer as gltxt;
+use crate::opengl::text_renderer::{self as gltxt, Align};
 
 use super::{
     basic::{
@@ -22,12 +22,12 @@ pub struct DebugView {
     pub view: View,
     pub visibile: bool,
     debug_info: DebugInfo,
-    pub bg_texture: Texture,
+    pub bg_texture: Option<Texture>,
     pub handle_key_time: u128,
 }
 
 impl DebugView {
-    pub fn new(view: View, debug_info: DebugInfo, bg_texture: Texture) -> DebugView {
+    pub fn new(view: View, debug_info: DebugInfo, bg_texture: Option<Texture>) -> DebugView {
         DebugView { view, visibile: false, debug_info, bg_texture, handle_key_time: 0 }
     }
 
@@ -51,12 +51,14 @@ impl DebugView {
         self.view
             .window_renderer
             .make_bordered_rect(view_bb, bg_color, (2, bg_color.uniform_scale(-1.0)), PolygonType::Undecorated);
-        let image_bb = BoundingBox::shrink(&self.view.view_frame.to_bb(), Margin::Perpendicular { h: 20, v: 20 });
-        let mut see_through_bg = bg_color;
-        see_through_bg.a = 0.1;
-        self.view
-            .window_renderer
-            .push_draw_command(image_bb, see_through_bg, PolygonType::Decorated { texture: self.bg_texture });
+        if let Some(texture) = self.bg_texture {
+            let image_bb = BoundingBox::shrink(&self.view.view_frame.to_bb(), Margin::Perpendicular { h: 20, v: 20 });
+            let mut see_through_bg = bg_color;
+            see_through_bg.a = 0.1;
+            self.view
+                .window_renderer
+                .push_draw_command(image_bb, see_through_bg, PolygonType::Decorated { texture });
+        }
     }
 
     pub fn do_update_view(&mut self, fps: f64, frame_time: f64) {
@@ -98,15 +100,10 @@ impl DebugView {
             self.resize(size);
             self.update();
 
-            let Vec2i { x: tx, y: ty } = self.view.title_frame.anchor;
-            let text_title_rect = gltxt::calculate_text_dimensions_iter(title, &self.view.title_font);
-            let half = text_title_rect.width / 2;
-            let title_frame_half = self.view.title_frame.width() / 2;
-            let start_x = title_frame_half - half;
-
+            let title_rect = self.view.title_frame.to_bb();
             self.view
                 .text_renderer
-                .push_draw_command(title.chars(), RGBColor::black(), tx + start_x, ty, self.view.title_font.clone());
+                .push_aligned(title, RGBColor::black(), title_rect, Align::Center, self.view.title_font.clone());
             let color = RGBColor::white();
             self.view
                 .text_renderer
@@ -0,0 +1,82 @@
+use crate::textbuffer::{contiguous::contiguous::ContiguousBuffer, CharBuffer};
+
+/// How large a delete may be, before it has to be confirmed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeleteLimit {
+    Chars(usize),
+    Lines(usize),
+}
+
+/// Holds back deleting (or typing over) a selection larger than a limit, until the user confirms it, so that selecting everything
+/// and hitting a key by accident doesn't wipe out a file. It is off until a limit is set
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DeleteGuard {
+    limit: Option<DeleteLimit>,
+}
+
+impl DeleteGuard {
+    /// Sets the limit from what the user typed; a number of characters, or a number of lines followed by "lines". "off", or nothing
+    /// at all, turns the guard off. Anything else leaves the limit be, and returns false
+    pub fn configure(&mut self, input: &str) -> bool {
+        let input = input.trim();
+        if input.is_empty() || input.eq_ignore_ascii_case("off") {
+            self.limit = None;
+            return true;
+        }
+        let (count, lines) = match input.strip_suffix("lines") {
+            Some(count) => (count.trim(), true),
+            None => (input, false),
+        };
+        match count.parse::<usize>() {
+            Ok(count) if count > 0 => {
+                self.limit = Some(if lines { DeleteLimit::Lines(count) } else { DeleteLimit::Chars(count) });
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The size of the selection in buffer, as (characters, lines), if deleting it would go over the limit
+    pub fn held_back(&self, buffer: &ContiguousBuffer) -> Option<(usize, usize)> {
+        let limit = self.limit?;
+        let (begin, end) = buffer.get_selection()?;
+        let chars = std::cmp::min(*end + 1, buffer.len()) - *begin;
+        let md = buffer.meta_data();
+        let lines = md.get_line_number_of_buffer_index(end)? - md.get_line_number_of_buffer_index(begin)? + 1;
+        let over = match limit {
+            DeleteLimit::Chars(limit) => chars > limit,
+            DeleteLimit::Lines(limit) => lines > limit,
+        };
+        Some((chars, lines)).filter(|_| over)
+    }
+}
+
+#[cfg(test)]
+mod delete_guard_tests {
+    use super::DeleteGuard;
+    use crate::textbuffer::{contiguous::contiguous::ContiguousBuffer, metadata::Index, CharBuffer};
+
+    #[test]
+    fn only_large_selections_are_held_back() {
+        let mut buffer = ContiguousBuffer::new(0, 4096);
+        buffer.insert_slice(&"let answer = 42;\n".repeat(50).chars().collect::<Vec<char>>());
+        let mut select = |begin: usize, end: usize, guard: &DeleteGuard| {
+            buffer.set_absolute_meta_cursor(Index(begin));
+            buffer.cursor_goto(Index(end));
+            guard.held_back(&buffer)
+        };
+
+        let mut guard = DeleteGuard::default();
+        assert_eq!(select(0, 849, &guard), None, "the guard is opt-in");
+        assert!(guard.configure("100"));
+        assert_eq!(select(4, 9, &guard), None);
+        assert_eq!(select(0, 849, &guard), Some((850, 50)));
+
+        assert!(guard.configure("3 lines"));
+        assert_eq!(select(0, 40, &guard), None);
+        assert_eq!(select(0, 60, &guard), Some((61, 4)));
+        assert!(!guard.configure("lots"));
+        assert!(guard.configure("off"));
+        assert_eq!(select(0, 849, &guard), None);
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{
     cmd::CommandTag,
     textbuffer::{operations::LineOperation, Movement},
-    ui::UID,
+    ui::{panel::Direction, UID},
 };
 use serde::{Deserialize, Serialize};
 use std::{fmt::Display, path::PathBuf};
@@ -50,6 +50,12 @@ pub enum ViewAction {
     Movement(Movement),
     TextSelect(Movement),
     Find,
+    /// Repeat the last search, forward from the cursor
+    FindNext,
+    /// Repeat the last search, backward from the cursor
+    FindPrevious,
+    /// Jump to where the word at the cursor is defined, in the same file
+    GotoDefinition,
     Goto,
     Delete(Movement),
     ChangeValueOfAssignment,
@@ -62,6 +68,15 @@ pub enum ViewAction {
     LineOperation(LineOperation),
     Debug,
     InputNewline,
+    DuplicateSelection,
+    /// Grow the selection to the word, line or block around it
+    ExpandSelection,
+    /// Step back to the selection before the last ExpandSelection
+    ShrinkSelection,
+    /// Select what's inside the {...} block around the cursor
+    SelectInsideBlock,
+    /// Select the {...} block around the cursor, along with its braces
+    SelectAroundBlock,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -72,6 +87,10 @@ pub enum AppAction {
     SearchInFiles,
     GotoLineInFile,
     CycleFocus,
+    /// Focus the n:th visible view, counting from 1
+    FocusView(usize),
+    /// Focus the view focused before the active one, going further back each time it's repeated while Control is held
+    CycleRecentViews,
     HideFocused,
     ShowAll,
     ShowDebugInterface,
@@ -79,6 +98,18 @@ pub enum AppAction {
     Quit,
     OpenNewView,
     ListCommands,
+    ToggleReadOnly,
+    RevealInFileFinder,
+    /// Move the active view one step among the views of its panel
+    MoveActiveView(Direction),
+    /// Grow (Right/Down) or shrink (Left/Up) the active view, along the layout of its panel
+    ResizeActiveView(Direction),
+    /// Move the active view out of its panel, into a new panel beside it
+    DetachActiveView,
+    /// Go back to where the cursor was before the last jump (to a line, a search match, a definition or a mark)
+    JumpBack,
+    /// Go forward again, after going back with JumpBack
+    JumpForward,
 }
 
 impl Display for AppAction {
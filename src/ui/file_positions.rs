@@ -0,0 +1,148 @@
+use crate::textbuffer::cursor::BufferCursor;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// Where the positions are kept between sessions, in the state directory of the user. None if the user has no such directory
+pub fn file_positions_path() -> Option<PathBuf> {
+    state_directory(|var| std::env::var_os(var)).map(|dir| dir.join("file_positions.json"))
+}
+
+/// The directory state kept between sessions goes in: $XDG_STATE_HOME/cxgledit, or ~/.local/state/cxgledit when that isn't set,
+/// and %APPDATA%\cxgledit on Windows. var looks up environment variables
+fn state_directory(var: impl Fn(&str) -> Option<OsString>) -> Option<PathBuf> {
+    let non_empty = |name: &str| var(name).filter(|value| !value.is_empty()).map(PathBuf::from);
+    let base = if cfg!(target_os = "windows") {
+        non_empty("APPDATA")
+    } else {
+        non_empty("XDG_STATE_HOME").or_else(|| non_empty("HOME").map(|home| home.join(".local/state")))
+    };
+    base.map(|dir| dir.join("cxgledit"))
+}
+
+/// Where the cursor, and the top of the view, were in a file when it was last saved or closed
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FilePosition {
+    /// Absolute index of the cursor in the buffer
+    pub cursor: usize,
+    /// The line shown at the top of the view
+    pub top_line: usize,
+}
+
+impl FilePosition {
+    /// Keeps the position within a file of len characters and line_count lines, which it might not be, if the file has been
+    /// changed (by some other program) since the position was remembered
+    pub fn clamped(self, len: usize, line_count: usize) -> FilePosition {
+        FilePosition { cursor: self.cursor.min(len), top_line: self.top_line.min(line_count.saturating_sub(1)) }
+    }
+}
+
+/// The last known positions in files, and the marks set in them, keyed by their path
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct FilePositions {
+    positions: HashMap<PathBuf, FilePosition>,
+    #[serde(default)]
+    marks: HashMap<PathBuf, Vec<(char, BufferCursor)>>,
+}
+
+impl FilePositions {
+    /// Reads the positions stored at path. A missing or unreadable file gives no positions
+    pub fn load(path: &Path) -> FilePositions {
+        match std::fs::read_to_string(path) {
+            Ok(data) => serde_json::from_str(&data).unwrap_or_else(|e| {
+                println!("Failed to read file positions from {}: {}", path.display(), e); // todo: UI representation
+                FilePositions::default()
+            }),
+            Err(_) => FilePositions::default(),
+        }
+    }
+
+    /// Writes the positions to path, creating the directory it's in, if it doesn't exist yet
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let data = serde_json::to_string(self).map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, data)
+    }
+
+    pub fn remember(&mut self, file: &Path, position: FilePosition) {
+        self.positions.insert(key_of(file), position);
+    }
+
+    pub fn recall(&self, file: &Path) -> Option<FilePosition> {
+        self.positions.get(&key_of(file)).copied()
+    }
+
+    /// Remembers the marks set in file, forgetting the ones remembered before. No marks forgets the file altogether
+    pub fn remember_marks(&mut self, file: &Path, marks: Vec<(char, BufferCursor)>) {
+        if marks.is_empty() {
+            self.marks.remove(&key_of(file));
+        } else {
+            self.marks.insert(key_of(file), marks);
+        }
+    }
+
+    pub fn recall_marks(&self, file: &Path) -> &[(char, BufferCursor)] {
+        self.marks.get(&key_of(file)).map(Vec::as_slice).unwrap_or(&[])
+    }
+}
+
+/// The same file may be opened by different relative paths, so positions are keyed by the absolute path, when there is one
+fn key_of(file: &Path) -> PathBuf {
+    std::fs::canonicalize(file).unwrap_or_else(|_| file.to_path_buf())
+}
+
+#[cfg(test)]
+mod file_positions_tests {
+    use super::{state_directory, FilePosition, FilePositions};
+    use crate::utils::temp_dir::TempDir;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn positions_survive_a_round_trip() {
+        let directory = TempDir::new("positions");
+        let stored = directory.join("positions.json");
+        let mut positions = FilePositions::default();
+        positions.remember(Path::new("src/main.rs"), FilePosition { cursor: 120, top_line: 3 });
+        positions.remember(Path::new("does/not/exist.rs"), FilePosition { cursor: 7, top_line: 0 });
+        positions.remember_marks(Path::new("src/main.rs"), vec![('a', (120, 3, 8).into())]);
+        positions.save(&stored).unwrap();
+
+        let restored = FilePositions::load(&stored);
+        std::fs::remove_file(&stored).unwrap();
+        assert_eq!(restored.recall(Path::new("src/main.rs")), Some(FilePosition { cursor: 120, top_line: 3 }));
+        assert_eq!(restored.recall(Path::new("does/not/exist.rs")), Some(FilePosition { cursor: 7, top_line: 0 }));
+        assert_eq!(restored.recall(Path::new("src/app.rs")), None);
+        let marks = restored.recall_marks(Path::new("src/main.rs"));
+        assert_eq!(
+            marks
+                .iter()
+                .map(|(name, cursor)| (*name, *cursor.pos, *cursor.row, *cursor.col))
+                .collect::<Vec<_>>(),
+            vec![('a', 120, 3, 8)]
+        );
+        assert!(restored.recall_marks(Path::new("does/not/exist.rs")).is_empty());
+        assert!(FilePositions::load(&stored).recall(Path::new("src/main.rs")).is_none());
+    }
+
+    #[test]
+    fn positions_are_clamped_to_a_shrunk_file() {
+        let position = FilePosition { cursor: 500, top_line: 40 };
+        assert_eq!(position.clamped(100, 10), FilePosition { cursor: 100, top_line: 9 });
+        assert_eq!(position.clamped(1000, 100), position);
+        assert_eq!(position.clamped(0, 0), FilePosition { cursor: 0, top_line: 0 });
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn state_is_kept_in_the_state_directory_of_the_user() {
+        let env = |vars: &'static [(&'static str, &'static str)]| move |name: &str| vars.iter().find(|(var, _)| *var == name).map(|(_, value)| value.into());
+        let expected = Some(PathBuf::from("/home/user/.local/state/cxgledit"));
+        assert_eq!(state_directory(env(&[("HOME", "/home/user")])), expected);
+        assert_eq!(state_directory(env(&[("HOME", "/home/user"), ("XDG_STATE_HOME", "")])), expected);
+        assert_eq!(state_directory(env(&[("HOME", "/home/user"), ("XDG_STATE_HOME", "/state")])), Some(PathBuf::from("/state/cxgledit")));
+        assert_eq!(state_directory(env(&[])), None);
+    }
+}
@@ -0,0 +1,89 @@
+use super::view::ViewId;
+
+/// The views, most recently focused first, to switch between like Ctrl+Tab does in IDEs; pressing the binding again while still
+/// holding its modifier goes further back, and letting go of the modifier settles on the view cycled to
+#[derive(Debug, Default)]
+pub struct FocusHistory {
+    views: Vec<ViewId>,
+    /// How far back into views the cycle going on has gone. None when not cycling
+    cycling: Option<usize>,
+}
+
+impl FocusHistory {
+    /// Makes view the most recently focused. Ignored while cycling, so that the order stays put until the cycle is settled
+    pub fn focused(&mut self, view: ViewId) {
+        if self.cycling.is_some() || self.views.first() == Some(&view) {
+            return;
+        }
+        self.views.retain(|&v| v != view);
+        self.views.insert(0, view);
+    }
+
+    /// Forgets the views that exists says no longer exist
+    pub fn retain(&mut self, exists: impl Fn(ViewId) -> bool) {
+        self.views.retain(|&v| exists(v));
+        if self.cycling.map_or(false, |depth| depth >= self.views.len()) {
+            self.cycling = None;
+        }
+    }
+
+    /// The view one step further back than the last step of the cycle; the first step goes to the view focused before the current
+    /// one. Going past the least recently focused view wraps around. None if there's no other view to go to
+    pub fn cycle(&mut self) -> Option<ViewId> {
+        if self.views.len() < 2 {
+            return None;
+        }
+        let depth = self.cycling.map_or(1, |depth| (depth + 1) % self.views.len());
+        self.cycling = Some(depth);
+        Some(self.views[depth])
+    }
+
+    /// Ends the cycle going on, making the view it ended on the most recently focused
+    pub fn settle(&mut self) {
+        if let Some(depth) = self.cycling.take() {
+            let view = self.views.remove(depth);
+            self.views.insert(0, view);
+        }
+    }
+}
+
+#[cfg(test)]
+mod focus_history_tests {
+    use super::FocusHistory;
+    use crate::ui::view::ViewId;
+
+    #[test]
+    fn focusing_moves_a_view_to_the_front() {
+        let (a, b, c) = (ViewId(1), ViewId(2), ViewId(3));
+        let mut history = FocusHistory::default();
+        assert_eq!(history.cycle(), None);
+        history.focused(a);
+        history.focused(b);
+        history.focused(c);
+        history.focused(a);
+        assert_eq!(history.views, vec![a, c, b]);
+        history.retain(|v| v != c);
+        assert_eq!(history.views, vec![a, b]);
+    }
+
+    #[test]
+    fn cycling_visits_views_most_recently_focused_first() {
+        let (a, b, c, d) = (ViewId(1), ViewId(2), ViewId(3), ViewId(4));
+        let mut history = FocusHistory::default();
+        for view in [a, b, c, d].iter() {
+            history.focused(*view);
+        }
+        assert_eq!(history.cycle(), Some(c));
+        assert_eq!(history.cycle(), Some(b));
+        history.focused(b);
+        assert_eq!(history.cycle(), Some(a), "focus changes made by the cycle, don't reorder it");
+        assert_eq!(history.cycle(), Some(d), "wraps around to where the cycle began");
+        assert_eq!(history.cycle(), Some(c));
+        history.settle();
+        assert_eq!(history.views, vec![c, d, b, a]);
+        // a single step back and forth, switches between the latest two
+        assert_eq!(history.cycle(), Some(d));
+        history.settle();
+        assert_eq!(history.cycle(), Some(c));
+    }
+}
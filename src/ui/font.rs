@@ -1,9 +1,14 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::datastructure::generic::Vec2i;
 use crate::debugger_catch;
 
+/// The fonts the editor uses, embedded in the binary so that it doesn't depend on being run from the repository's directory
+pub const SOURCE_CODE_PRO_REGULAR: &[u8] = include_bytes!("../../fonts/SourceCodePro-Regular.ttf");
+pub const SOURCE_CODE_PRO_LIGHT: &[u8] = include_bytes!("../../fonts/SourceCodePro-Light.ttf");
+
 /// Contains the texture coordinates & related glyph info about size & dimension
 pub struct GlyphInfo {
     pub x0: i32,
@@ -32,9 +37,26 @@ pub struct Font {
     glyph_cache: HashMap<char, GlyphInfo>,
     texture_id: gl::types::GLuint,
     texture_dimensions: Vec2i,
+    /// Kept around for looking up kerning pairs, which are looked up lazily, since most pairs of characters never end up next to each other
+    face: ft::Face,
+    kerning_cache: RefCell<HashMap<(char, char), i32>>,
+}
+
+/// Looks up the kerning, in pixels, between prev and next. Only fonts with a (legacy) kern table have kerning pairs,
+/// which FreeType can read; for all others this returns 0
+fn lookup_kerning(face: &ft::Face, prev: char, next: char) -> i32 {
+    if !face.has_kerning() {
+        return 0;
+    }
+    match (face.get_char_index(prev as usize), face.get_char_index(next as usize)) {
+        (Some(left), Some(right)) => face
+            .get_kerning(left, right, ft::face::KerningMode::KerningDefault)
+            .map_or(0, |kerning| (kerning.x >> 6) as i32),
+        _ => 0,
+    }
 }
 
-fn debug_write_font_texture_to_file(font_path: &Path, pixels: &Vec<u8>, pixel_size: i32, tex_width: u32, tex_height: u32) {
+fn debug_write_font_texture_to_file(font_name: &str, pixels: &Vec<u8>, pixel_size: i32, tex_width: u32, tex_height: u32) {
     use std::fs::File;
     use std::io::BufWriter;
     let mut png_data: Vec<u8> = Vec::with_capacity(pixels.len() * 4);
@@ -44,7 +66,7 @@ fn debug_write_font_texture_to_file(font_path: &Path, pixels: &Vec<u8>, pixel_si
         png_data.extend_from_slice(&[p, p, p, 0xff]);
     }
 
-    let font_file_name = format!("{}_{}", font_path.file_stem().unwrap().to_str().unwrap(), pixel_size);
+    let font_file_name = format!("{}_{}", font_name, pixel_size);
     let mut output_file = std::path::PathBuf::new();
 
     output_file.push("./");
@@ -65,81 +87,213 @@ fn debug_write_font_texture_to_file(font_path: &Path, pixels: &Vec<u8>, pixel_si
     writer.write_image_data(&png_data).unwrap(); // Save
 }
 
-// fn debug_write_font_texture_to_file(_font_path: &Path, _pixels: &Vec<u8>, _pixel_size: i32, _tex_width: u32, _tex_height: u32) {}
+// fn debug_write_font_texture_to_file(_font_name: &str, _pixels: &Vec<u8>, _pixel_size: i32, _tex_width: u32, _tex_height: u32) {}
+
+/// Directories where fonts are installed, on Linux, macOS and Windows. The ones that don't exist on this system are skipped
+fn system_font_directories() -> Vec<PathBuf> {
+    let mut directories: Vec<PathBuf> = [
+        "/usr/share/fonts",
+        "/usr/local/share/fonts",
+        "/Library/Fonts",
+        "/System/Library/Fonts",
+        "C:\\Windows\\Fonts",
+    ]
+    .iter()
+    .map(PathBuf::from)
+    .collect();
+    if let Some(home) = std::env::var_os("HOME") {
+        directories.push(Path::new(&home).join(".local/share/fonts"));
+        directories.push(Path::new(&home).join(".fonts"));
+        directories.push(Path::new(&home).join("Library/Fonts"));
+    }
+    directories
+}
+
+/// A font name, or file name, without case, spaces, dashes or underscores, so that "Source Code Pro" matches SourceCodePro-Regular.ttf
+fn normalized_font_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '_'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Searches directories (and their sub directories) for a TrueType or OpenType file of the font family name. Files named
+/// like the family with some style (Bold, Italic, ...) match as well, but the regular style, or the family name alone, is preferred
+pub fn find_font_file(name: &str, directories: &[PathBuf]) -> Option<PathBuf> {
+    let family = normalized_font_name(name);
+    let mut candidates = vec![];
+    let mut pending: Vec<PathBuf> = directories.to_vec();
+    while let Some(directory) = pending.pop() {
+        let entries = match std::fs::read_dir(&directory) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let is_font = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map_or(false, |ext| ext.eq_ignore_ascii_case("ttf") || ext.eq_ignore_ascii_case("otf"));
+            let stem = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .map(normalized_font_name)
+                .unwrap_or_default();
+            if is_font && stem.starts_with(&family) {
+                candidates.push((stem[family.len()..].to_string(), path));
+            }
+        }
+    }
+    let preference = |style: &str| match style {
+        "regular" | "r" => 0,
+        "" => 1,
+        _ => 2,
+    };
+    candidates.sort_by(|(a, a_path), (b, b_path)| {
+        preference(a)
+            .cmp(&preference(b))
+            .then(a.len().cmp(&b.len()))
+            .then(a_path.cmp(b_path))
+    });
+    candidates.into_iter().next().map(|(_, path)| path)
+}
+
+/// The glyphs of a face rendered into one texture (of single byte pixels), before it's uploaded to the GPU
+struct GlyphAtlas {
+    pixels: Vec<u8>,
+    dimensions: Vec2i,
+    glyph_cache: HashMap<char, GlyphInfo>,
+    max_glyph_height: i32,
+}
+
+fn rasterize(face: &ft::Face, pixel_size: i32, characters: &[char]) -> Result<GlyphAtlas, ft::Error> {
+    face.set_pixel_sizes(pixel_size as u32, pixel_size as u32)?;
+    let glyph_count = characters.len() as f64;
+    let max_dim = ((1 + face.size_metrics().unwrap().height >> 6) as f64 * glyph_count.sqrt().ceil()) as i32;
+
+    let mut texture_dimension = Vec2i { x: 1, y: 1 };
+    while texture_dimension.x < max_dim {
+        texture_dimension.x = texture_dimension.x << 1;
+    }
+    texture_dimension.y = texture_dimension.x;
+    let mut pixels = Vec::new();
+    pixels.resize((texture_dimension.x * texture_dimension.y) as usize, 0);
+
+    let mut pen_x = 0;
+    let mut pen_y = 0;
+    let mut max_glyph_dimensions = Vec2i { x: 0, y: 0 };
+    let mut max_bearing_size_diff = 0;
+    let mut glyph_cache: HashMap<char, GlyphInfo> = HashMap::new();
+
+    for &c in characters {
+        face.load_char(
+            c as usize,
+            ft::face::LoadFlag::RENDER | ft::face::LoadFlag::FORCE_AUTOHINT | ft::face::LoadFlag::TARGET_LIGHT | ft::face::LoadFlag::COLOR,
+        )?;
+        let glyph = face.glyph();
+        let bitmap = glyph.bitmap();
+        max_glyph_dimensions.y = std::cmp::max(bitmap.rows(), max_glyph_dimensions.x);
+        max_glyph_dimensions.x = std::cmp::max(bitmap.width(), max_glyph_dimensions.x);
+
+        if pen_x + bitmap.width() >= texture_dimension.x {
+            pen_x = 0;
+            pen_y += (face.size_metrics().unwrap().height >> 6) as i32 + 1;
+        }
+
+        for row in 0..bitmap.rows() {
+            for col in 0..bitmap.width() {
+                let x = pen_x + col;
+                let y = pen_y + row;
+                let mut pixel_index = (y * texture_dimension.x + x) as usize;
+                let bitmap_index = (row * bitmap.pitch() + col) as usize;
+                if pixel_index >= pixels.len() {
+                    debugger_catch!(!(pixel_index >= 262144), crate::DebuggerCatch::Handle("Pixel index must remaing below 262144".into()));
+                    pixel_index = pixels.len() - 1;
+                }
+                pixels[pixel_index] = bitmap.buffer()[bitmap_index];
+            }
+        }
+
+        let glyph_info = GlyphInfo {
+            x0: pen_x,
+            x1: pen_x + bitmap.width(),
+            y0: pen_y,
+            y1: pen_y + bitmap.rows(),
+            advance: glyph.advance().x as i32 >> 6,
+            offsets: Vec2i { x: glyph.bitmap_left(), y: glyph.bitmap_top() },
+            size: Vec2i { x: bitmap.width(), y: bitmap.rows() },
+            bearing: Vec2i { x: glyph.bitmap_left(), y: glyph.bitmap_top() },
+        };
+        max_bearing_size_diff = std::cmp::max((glyph_info.size.y - glyph_info.bearing.y).abs(), max_bearing_size_diff);
+        glyph_cache.insert(c, glyph_info);
+        pen_x += bitmap.width() + 1;
+    }
+    Ok(GlyphAtlas { pixels, dimensions: texture_dimension, glyph_cache, max_glyph_height: max_glyph_dimensions.y })
+}
 
 impl Font {
     pub fn new(font_path: &Path, pixel_size: i32, characters: &Vec<char>) -> Result<Font, ft::Error> {
         let lib = ft::Library::init()?;
         let face = lib.new_face(font_path, 0)?;
-        face.set_pixel_sizes(pixel_size as u32, pixel_size as u32)?;
-        let glyph_count = characters.len() as f64;
-        let max_dim = ((1 + face.size_metrics().unwrap().height >> 6) as f64 * glyph_count.sqrt().ceil()) as i32;
+        let name = font_path.file_stem().unwrap().to_str().unwrap().to_string();
+        Font::from_face(face, &name, pixel_size, characters)
+    }
 
-        let mut texture_dimension = Vec2i { x: 1, y: 1 };
-        while texture_dimension.x < max_dim {
-            texture_dimension.x = texture_dimension.x << 1;
+    /// Creates a font from the contents of a font file, for instance one embedded in the binary with include_bytes!
+    pub fn from_bytes(data: &[u8], pixel_size: i32, characters: &Vec<char>) -> Result<Font, ft::Error> {
+        let lib = ft::Library::init()?;
+        let face = lib.new_memory_face(data.to_vec(), 0)?;
+        let family = face.family_name().unwrap_or_else(|| "embedded".to_string());
+        let name = format!("{}-{}", family, face.style_name().unwrap_or_default()).replace(' ', "");
+        Font::from_face(face, &name, pixel_size, characters)
+    }
+
+    /// Creates a font of the family name (like "Ubuntu Mono"), installed on the system. This only looks through the common font
+    /// directories for a file named like the family, it doesn't ask the system (through fontconfig or the like) where fonts are
+    pub fn from_system_family(name: &str, pixel_size: i32, characters: &Vec<char>) -> Result<Font, ft::Error> {
+        match find_font_file(name, &system_font_directories()) {
+            Some(font_path) => Font::new(&font_path, pixel_size, characters),
+            None => Err(ft::Error::CannotOpenResource),
         }
-        texture_dimension.y = texture_dimension.x;
-        let mut pixels = Vec::new();
-        pixels.resize((texture_dimension.x * texture_dimension.y) as usize, 0);
-
-        let mut pen_x = 0;
-        let mut pen_y = 0;
-        let mut max_glyph_dimensions = Vec2i { x: 0, y: 0 };
-        let mut max_bearing_size_diff = 0;
-        let mut glyph_cache: HashMap<char, GlyphInfo> = HashMap::new();
-
-        for &c in characters {
-            face.load_char(
-                c as usize,
-                ft::face::LoadFlag::RENDER | ft::face::LoadFlag::FORCE_AUTOHINT | ft::face::LoadFlag::TARGET_LIGHT | ft::face::LoadFlag::COLOR,
-            )?;
-            let glyph = face.glyph();
-            let bitmap = glyph.bitmap();
-            max_glyph_dimensions.y = std::cmp::max(bitmap.rows(), max_glyph_dimensions.x);
-            max_glyph_dimensions.x = std::cmp::max(bitmap.width(), max_glyph_dimensions.x);
-
-            if pen_x + bitmap.width() >= texture_dimension.x {
-                pen_x = 0;
-                pen_y += (face.size_metrics().unwrap().height >> 6) as i32 + 1;
-            }
+    }
 
-            for row in 0..bitmap.rows() {
-                for col in 0..bitmap.width() {
-                    let x = pen_x + col;
-                    let y = pen_y + row;
-                    let mut pixel_index = (y * texture_dimension.x + x) as usize;
-                    let bitmap_index = (row * bitmap.pitch() + col) as usize;
-                    if pixel_index >= pixels.len() {
-                        debugger_catch!(!(pixel_index >= 262144), crate::DebuggerCatch::Handle("Pixel index must remaing below 262144".into()));
-                        pixel_index = pixels.len() - 1;
-                    }
-                    pixels[pixel_index] = bitmap.buffer()[bitmap_index];
-                }
-            }
+    fn from_face(face: ft::Face, name: &str, pixel_size: i32, characters: &Vec<char>) -> Result<Font, ft::Error> {
+        let atlas = rasterize(&face, pixel_size, characters)?;
+        let row_advance = atlas.max_glyph_height + 7;
 
-            let glyph_info = GlyphInfo {
-                x0: pen_x,
-                x1: pen_x + bitmap.width(),
-                y0: pen_y,
-                y1: pen_y + bitmap.rows(),
-                advance: glyph.advance().x as i32 >> 6,
-                offsets: Vec2i { x: glyph.bitmap_left(), y: glyph.bitmap_top() },
-                size: Vec2i { x: bitmap.width(), y: bitmap.rows() },
-                bearing: Vec2i { x: glyph.bitmap_left(), y: glyph.bitmap_top() },
-            };
-            max_bearing_size_diff = std::cmp::max((glyph_info.size.y - glyph_info.bearing.y).abs(), max_bearing_size_diff);
-            glyph_cache.insert(c, glyph_info);
-            pen_x += bitmap.width() + 1;
-        }
-        let max_adv_y = max_glyph_dimensions.y + 7;
-        let row_advance = max_adv_y;
+        let texture_id = unsafe { Font::upload_texture(&atlas.pixels, atlas.dimensions.x, atlas.dimensions.y) };
 
-        let texture_id = unsafe { Font::upload_texture(&pixels, texture_dimension.x, texture_dimension.y) };
+        debug_write_font_texture_to_file(name, &atlas.pixels, pixel_size, atlas.dimensions.x as u32, atlas.dimensions.y as u32);
 
-        debug_write_font_texture_to_file(font_path, &pixels, pixel_size, texture_dimension.x as u32, texture_dimension.y as u32);
+        Ok(Font {
+            row_height: row_advance,
+            texture_id,
+            pixel_size,
+            glyph_cache: atlas.glyph_cache,
+            texture_dimensions: atlas.dimensions,
+            face,
+            kerning_cache: RefCell::new(HashMap::new()),
+        })
+    }
 
-        Ok(Font { row_height: row_advance, texture_id, pixel_size, glyph_cache, texture_dimensions: texture_dimension })
+    /// A font with the glyphs and metrics of data, but no texture, since tests have no OpenGL context to upload it to
+    #[cfg(test)]
+    pub fn without_texture(data: &[u8], pixel_size: i32, characters: &Vec<char>) -> Font {
+        let lib = ft::Library::init().unwrap();
+        let face = lib.new_memory_face(data.to_vec(), 0).unwrap();
+        let atlas = rasterize(&face, pixel_size, characters).unwrap();
+        Font {
+            row_height: atlas.max_glyph_height + 7,
+            texture_id: 0,
+            pixel_size,
+            glyph_cache: atlas.glyph_cache,
+            texture_dimensions: atlas.dimensions,
+            face,
+            kerning_cache: RefCell::new(HashMap::new()),
+        }
     }
 
     unsafe fn upload_texture(data: &Vec<u8>, width: i32, height: i32) -> gl::types::GLuint {
@@ -155,71 +309,14 @@ impl Font {
     pub fn texture_overwrite(&mut self, font_path: &Path, pixel_size: i32, characters: &Vec<char>) -> Result<(), ft::Error> {
         let lib = ft::Library::init()?;
         let face = lib.new_face(font_path, 0)?;
-        face.set_pixel_sizes(pixel_size as u32, pixel_size as u32)?;
-        let glyph_count = characters.len() as f64;
-        let max_dim = ((1 + face.size_metrics().unwrap().height >> 6) as f64 * glyph_count.sqrt().ceil()) as i32;
-
-        let mut texture_dimension = Vec2i { x: 1, y: 1 };
-        while texture_dimension.x < max_dim {
-            texture_dimension.x = texture_dimension.x << 1;
-        }
-        texture_dimension.y = texture_dimension.x;
-        let mut pixels = Vec::new();
-        pixels.resize((texture_dimension.x * texture_dimension.y) as usize, 0);
-
-        let mut pen_x = 0;
-        let mut pen_y = 0;
-        let mut max_glyph_dimensions = Vec2i { x: 0, y: 0 };
-        let mut max_bearing_size_diff = 0;
-        let mut glyph_cache: HashMap<char, GlyphInfo> = HashMap::new();
-
-        for &c in characters {
-            face.load_char(
-                c as usize,
-                ft::face::LoadFlag::RENDER | ft::face::LoadFlag::FORCE_AUTOHINT | ft::face::LoadFlag::TARGET_LIGHT | ft::face::LoadFlag::COLOR,
-            )?;
-            let glyph = face.glyph();
-            let bitmap = glyph.bitmap();
-            max_glyph_dimensions.y = std::cmp::max(bitmap.rows(), max_glyph_dimensions.x);
-            max_glyph_dimensions.x = std::cmp::max(bitmap.width(), max_glyph_dimensions.x);
-
-            if pen_x + bitmap.width() >= texture_dimension.x {
-                pen_x = 0;
-                pen_y += (face.size_metrics().unwrap().height >> 6) as i32 + 1;
-            }
-
-            for row in 0..bitmap.rows() {
-                for col in 0..bitmap.width() {
-                    let x = pen_x + col;
-                    let y = pen_y + row;
-                    let mut pixel_index = (y * texture_dimension.x + x) as usize;
-                    let bitmap_index = (row * bitmap.pitch() + col) as usize;
-                    if pixel_index >= pixels.len() {
-                        debugger_catch!(!(pixel_index >= 262144), crate::DebuggerCatch::Handle("Pixel index must remaing below 262144".into()));
-                        pixel_index = pixels.len() - 1;
-                    }
-                    pixels[pixel_index] = bitmap.buffer()[bitmap_index];
-                }
-            }
-
-            let glyph_info = GlyphInfo {
-                x0: pen_x,
-                x1: pen_x + bitmap.width(),
-                y0: pen_y,
-                y1: pen_y + bitmap.rows(),
-                advance: glyph.advance().x as i32 >> 6,
-                offsets: Vec2i { x: glyph.bitmap_left(), y: glyph.bitmap_top() },
-                size: Vec2i { x: bitmap.width(), y: bitmap.rows() },
-                bearing: Vec2i { x: glyph.bitmap_left(), y: glyph.bitmap_top() },
-            };
-            max_bearing_size_diff = std::cmp::max((glyph_info.size.y - glyph_info.bearing.y).abs(), max_bearing_size_diff);
-            glyph_cache.insert(c, glyph_info);
-            pen_x += bitmap.width() + 1;
-        }
-        let max_adv_y = max_glyph_dimensions.y + 5;
+        let GlyphAtlas { pixels, dimensions: texture_dimension, glyph_cache, max_glyph_height } = rasterize(&face, pixel_size, characters)?;
+        let max_adv_y = max_glyph_height + 5;
         let row_advance = max_adv_y;
         self.texture_dimensions = texture_dimension;
         self.row_height = row_advance;
+        self.glyph_cache = glyph_cache;
+        self.face = face;
+        self.kerning_cache.borrow_mut().clear();
         // gl::GenTextures(1, &mut id);
         unsafe {
             gl::BindTexture(gl::TEXTURE_2D, self.texture_id);
@@ -251,6 +348,16 @@ impl Font {
         self.glyph_cache.get(&character)
     }
 
+    /// The adjustment in pixels, to the advance of prev, when it is followed by next. Negative values pull the glyphs closer together
+    pub fn kerning(&self, prev: char, next: char) -> i32 {
+        if let Some(kerning) = self.kerning_cache.borrow().get(&(prev, next)) {
+            return *kerning;
+        }
+        let kerning = lookup_kerning(&self.face, prev, next);
+        self.kerning_cache.borrow_mut().insert((prev, next), kerning);
+        kerning
+    }
+
     #[inline(always)]
     pub fn texture_width(&self) -> i32 {
         self.texture_dimensions.x
@@ -274,3 +381,48 @@ impl Font {
         w
     }
 }
+
+#[cfg(test)]
+mod font_tests {
+    use super::{find_font_file, lookup_kerning, rasterize, SOURCE_CODE_PRO_REGULAR};
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn kerning_pair_is_narrower_than_its_advances() {
+        let lib = ft::Library::init().unwrap();
+        // Source Code Pro, the font used by the editor, is monospaced and has no kern table, Ubuntu does
+        let face = lib.new_face("fonts/Ubuntu-L.ttf", 0).unwrap();
+        face.set_pixel_sizes(24, 24).unwrap();
+        let advance = |c: char| {
+            face.load_char(c as usize, ft::face::LoadFlag::DEFAULT).unwrap();
+            face.glyph().advance().x as i32 >> 6
+        };
+        let advances = advance('A') + advance('V');
+        assert!(advances + lookup_kerning(&face, 'A', 'V') < advances);
+        assert_eq!(lookup_kerning(&face, 'A', '\u{10FFFF}'), 0);
+    }
+
+    #[test]
+    fn embedded_font_has_metrics() {
+        let lib = ft::Library::init().unwrap();
+        let face = lib.new_memory_face(SOURCE_CODE_PRO_REGULAR.to_vec(), 0).unwrap();
+        assert_eq!(face.family_name().as_deref(), Some("Source Code Pro"));
+        let characters: Vec<char> = (' '..='~').collect();
+        let atlas = rasterize(&face, 14, &characters).unwrap();
+        assert!(atlas.max_glyph_height > 0);
+        assert!(atlas.dimensions.x > 0 && atlas.dimensions.y > 0);
+        assert!(atlas.pixels.iter().any(|&p| p != 0));
+        let glyph = &atlas.glyph_cache[&'M'];
+        assert!(glyph.advance > 0 && glyph.size.x > 0 && glyph.size.y > 0);
+        assert_eq!(atlas.glyph_cache.len(), characters.len());
+    }
+
+    #[test]
+    fn find_font_file_by_family_name() {
+        let directories = [PathBuf::from("fonts")];
+        assert_eq!(find_font_file("Source Code Pro", &directories).as_deref(), Some(Path::new("fonts/SourceCodePro-Regular.ttf")));
+        assert_eq!(find_font_file("ubuntu mono", &directories).as_deref(), Some(Path::new("fonts/UbuntuMono-R.ttf")));
+        assert_eq!(find_font_file("Ubuntu", &directories).as_deref(), Some(Path::new("fonts/Ubuntu-R.ttf")));
+        assert_eq!(find_font_file("Comic Sans", &directories), None);
+    }
+}
@@ -0,0 +1,77 @@
+/// Decides when buffers with unsaved changes are saved without being asked to: once there has been no input for a while, or when
+/// the window loses focus. It is off until a delay is set
+#[derive(Debug, Default)]
+pub struct IdleSave {
+    /// Seconds without input, after which changes are saved. None when saving on idle is turned off
+    delay: Option<f64>,
+    /// When input was last given, if nothing has been saved since
+    last_input: Option<f64>,
+}
+
+impl IdleSave {
+    /// Sets the delay from what the user typed; a number of seconds turns saving on, "off" or nothing at all turns it off.
+    /// Anything else leaves the settings be, and returns false
+    pub fn configure(&mut self, input: &str) -> bool {
+        let input = input.trim();
+        if input.is_empty() || input.eq_ignore_ascii_case("off") {
+            self.delay = None;
+            self.last_input = None;
+            return true;
+        }
+        match input.parse::<f64>() {
+            Ok(delay) if delay.is_finite() && delay > 0.0 => {
+                self.delay = Some(delay);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Restarts the quiet period. time is in seconds, like glfw's
+    pub fn input_at(&mut self, time: f64) {
+        self.last_input = Some(time);
+    }
+
+    /// Whether changes should be saved at time, which they should once, after each quiet period that followed input
+    pub fn due(&mut self, time: f64) -> bool {
+        match (self.delay, self.last_input) {
+            (Some(delay), Some(last_input)) if time - last_input >= delay => {
+                self.last_input = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether changes should be saved, now that the window lost focus
+    pub fn focus_lost(&mut self) -> bool {
+        self.last_input = None;
+        self.delay.is_some()
+    }
+}
+
+#[cfg(test)]
+mod idle_save_tests {
+    use super::IdleSave;
+
+    #[test]
+    fn saves_only_after_the_quiet_period() {
+        let mut idle = IdleSave::default();
+        idle.input_at(1.0);
+        assert!(!idle.due(10.0), "saving on idle is opt-in");
+
+        assert!(idle.configure("2"));
+        idle.input_at(1.0);
+        assert!(!idle.due(2.5));
+        idle.input_at(2.5);
+        assert!(!idle.due(4.0), "input restarts the quiet period");
+        assert!(idle.due(4.5));
+        assert!(!idle.due(10.0), "nothing to save without new input");
+
+        assert!(!idle.configure("soon"));
+        idle.input_at(5.0);
+        assert!(idle.due(7.0), "bad input leaves the delay be");
+        assert!(idle.configure("off"));
+        assert!(!idle.focus_lost());
+    }
+}
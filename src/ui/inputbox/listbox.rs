@@ -54,3 +54,37 @@ impl ListBox {
         self.selection = self.selection.map(|f| if f + 1 >= self.data.len() { 0 } else { f + 1 }).or(Some(0));
     }
 }
+
+/// Splits a file path list item into its file name and its parent path, for display purposes. A leading "./" is
+/// not part of the parent, since all paths in the list are relative to the same root anyway
+pub fn split_file_name(item: &[char]) -> (&[char], &[char]) {
+    let (name, parent) = match item.iter().rposition(|c| std::path::is_separator(*c)) {
+        Some(sep) if sep + 1 < item.len() => (&item[sep + 1..], &item[..sep]),
+        _ => (item, &item[..0]),
+    };
+    match parent {
+        ['.'] => (name, &parent[..0]),
+        ['.', sep, rest @ ..] if std::path::is_separator(*sep) => (name, rest),
+        _ => (name, parent),
+    }
+}
+
+#[cfg(test)]
+mod listbox_tests {
+    use super::split_file_name;
+
+    fn split(path: &str) -> (String, String) {
+        let chars: Vec<char> = path.chars().collect();
+        let (name, parent) = split_file_name(&chars);
+        (name.iter().collect(), parent.iter().collect())
+    }
+
+    #[test]
+    fn split_name_from_parent() {
+        assert_eq!(split("./src/ui/view.rs"), ("view.rs".into(), "src/ui".into()));
+        assert_eq!(split("/home/user/main.rs"), ("main.rs".into(), "/home/user".into()));
+        assert_eq!(split("./README.md"), ("README.md".into(), "".into()));
+        assert_eq!(split("Cargo.toml"), ("Cargo.toml".into(), "".into()));
+        assert_eq!(split("."), (".".into(), "".into()));
+    }
+}
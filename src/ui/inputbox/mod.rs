@@ -4,6 +4,7 @@ pub mod listbox;
 use line_text_box::LineTextBox;
 use listbox::ListBox;
 
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 use walkdir::WalkDir;
@@ -17,7 +18,7 @@ use super::{
     Viewable, ACTIVE_VIEW_BACKGROUND,
 };
 use crate::{
-    cmd::{commands_matching, get_command, CommandTag},
+    cmd::{commands_matching, get_command, snippets::snippets_matching, CommandTag},
     datastructure::generic::Vec2i,
     opengl::{
         rectangle_renderer::RectRenderer,
@@ -25,7 +26,9 @@ use crate::{
         text_renderer::{self, TextRenderer},
         types::{RGBAColor, RGBColor},
     },
+    textbuffer::metadata::Index,
     ui::eventhandling::event::CommandOutput,
+    utils::smart_case_sensitive,
 };
 
 pub struct TextRenderSetting {
@@ -66,12 +69,21 @@ pub struct InputBox {
     pub mode: Mode,
     pub needs_update: bool,
     font: Rc<Font>,
+    /// Directory the file finder walks from
+    search_root: PathBuf,
+    /// What to choose between, in the modes where the application lists the choices, like symbols (as listed by Symbol::list_item)
+    choices: Vec<String>,
+    /// Shown at the right end of the input, like the number of matches of a search
+    status: Option<String>,
 }
 
 impl InputBox {
     pub fn new(frame: Frame, font: Rc<Font>, font_shader: &TextShader, rect_shader: &RectShader) -> InputBox {
         let (text_renderer, rect_renderer) = (TextRenderer::create(font_shader.clone(), 1024 * 10), RectRenderer::create(rect_shader.clone(), 8 * 60));
+        InputBox::with_renderers(frame, font, text_renderer, rect_renderer)
+    }
 
+    fn with_renderers(frame: Frame, font: Rc<Font>, text_renderer: TextRenderer, rect_renderer: RectRenderer) -> InputBox {
         let margin = 2;
         let input_box_frame = Frame { anchor: frame.anchor, size: Size::new(frame.size.width, font.row_height() + margin * 4) };
         let input_inner_frame = make_inner_frame(&input_box_frame, margin);
@@ -93,36 +105,85 @@ impl InputBox {
             mode: Mode::CommandInput(CommandTag::Goto),
             needs_update: true,
             font,
+            search_root: PathBuf::from("."),
+            choices: vec![],
+            status: None,
         }
     }
 
-    pub fn open(&mut self, mode: Mode) {
+    /// Opens the input box in mode. If start_dir is a directory, the file finder walks from there, and the input is pre-filled with it,
+    /// otherwise it walks from the current working directory
+    pub fn open(&mut self, mode: Mode, start_dir: Option<&Path>) {
         self.mode = mode;
+        match start_dir.filter(|dir| dir.is_dir()) {
+            Some(dir) => {
+                self.search_root = dir.to_path_buf();
+                self.input_box.data = format!("{}{}", dir.display(), std::path::MAIN_SEPARATOR).chars().collect();
+                self.input_box.cursor = self.input_box.data.len();
+                self.update_list_of_files();
+            }
+            None => self.search_root = PathBuf::from("."),
+        }
         self.needs_update = true;
         self.draw();
     }
 
+    /// Goes from the command list to the input of cmd's parameter. Commands that take none are run by the application, and leave
+    /// the box as is. Returns whether the box now takes the input of cmd
+    pub fn select_command(&mut self, cmd: CommandTag) -> bool {
+        if !cmd.takes_input() {
+            return false;
+        }
+        self.clear();
+        self.open(Mode::CommandInput(cmd), None);
+        true
+    }
+
     /// updates the list of possible selections that contains what the user has input into the
     /// input box.
     pub fn update_list_of_files(&mut self) {
         let name = &self.input_box.data.iter().collect::<String>();
-        self.selection_list.data = WalkDir::new(".")
-            .sort_by_file_name()
-            .into_iter()
-            .filter_map(|e| {
-                let e = e.unwrap();
-                // this is *odd* behavior. When we pass in a slice to contains(...)
-                // it will return true if *any* of the elements in that slice, exists in the string
-                if e.path().to_str().unwrap().to_ascii_uppercase().contains(&name.to_uppercase()) {
-                    Some(e)
-                } else {
-                    None
-                }
-            })
-            .map(|de| de.path().display().to_string().chars().collect())
+        self.selection_list.data = files_matching(&self.search_root, name);
+    }
+
+    /// Goes on with picking a file inside of dir, which was picked from the list of files
+    pub fn descend_into(&mut self, dir: Vec<char>) {
+        self.input_box.data = dir;
+        self.input_box.data.push('/');
+        self.input_box.cursor = self.input_box.data.len();
+        self.selection_list.selection = None;
+        self.update_list_of_files();
+        self.needs_update = true;
+    }
+
+    pub fn update_list_of_snippets(&mut self) {
+        let name = &self.input_box.data.iter().collect::<String>();
+        self.selection_list.data = snippets_matching(name).iter().map(|s| s.chars().collect()).collect();
+    }
+
+    pub fn update_list_of_choices(&mut self) {
+        let name = &self.input_box.data.iter().collect::<String>();
+        self.selection_list.data = self
+            .choices
+            .iter()
+            .filter(|s| s.contains(name.as_str()))
+            .map(|s| s.chars().collect())
             .collect();
     }
 
+    /// Lists what to choose from, in the GotoSymbol, SwitchBuffer and ToggleRelated modes. The first choice is selected
+    pub fn set_choices(&mut self, choices: Vec<String>) {
+        self.choices = choices;
+        self.update_list_of_choices();
+        self.selection_list.selection = if self.selection_list.data.is_empty() { None } else { Some(0) };
+        self.needs_update = true;
+    }
+
+    pub fn set_status(&mut self, status: Option<String>) {
+        self.status = status;
+        self.needs_update = true;
+    }
+
     pub fn update_list_of_commands(&mut self) {
         let name = &self.input_box.data.iter().collect::<String>();
         if let Some(matches) = commands_matching(name) {
@@ -148,21 +209,12 @@ impl InputBox {
             self.rect_renderer.clear_data();
 
             match self.mode {
-                Mode::CommandInput(cmd) => match cmd {
-                    CommandTag::Goto => {
-                        self.draw_without_list(cmd);
-                    }
-                    CommandTag::GotoInFile => todo!(),
-                    CommandTag::Find => {
-                        self.draw_without_list(cmd);
-                    }
-                    CommandTag::OpenFile => {
-                        self.draw_with_list();
-                    }
-                    CommandTag::SaveFile => {
-                        self.draw_without_list(cmd);
-                    }
-                },
+                Mode::CommandInput(cmd) if parameter_list(cmd).is_none() => {
+                    self.draw_without_list(cmd);
+                }
+                Mode::CommandInput(_) => {
+                    self.draw_with_list();
+                }
                 Mode::CommandList => {
                     self.draw_with_list();
                 }
@@ -215,17 +267,18 @@ impl InputBox {
 
         let text_top_left_anchor = Vec2i::new(text_area.min.x, text_area.max.y);
         if !self.input_box.data.is_empty() {
-            self.text_renderer.push_draw_command(
-                self.input_box.data.iter().map(|c| *c),
-                color,
-                text_top_left_anchor.x,
-                text_top_left_anchor.y,
-                self.font.clone(),
-            );
+            self.text_renderer
+                .push_chars(&self.input_box.data, color, text_top_left_anchor.x, text_top_left_anchor.y, self.font.clone());
         } else {
             let msg: &'static str = CommandTag::description(cmd);
             self.text_renderer
-                .push_draw_command(msg.chars(), color, text_top_left_anchor.x, text_top_left_anchor.y, self.font.clone());
+                .push_str(msg, color, text_top_left_anchor.x, text_top_left_anchor.y, self.font.clone());
+        }
+        if let Some(status) = &self.status {
+            let status_chars: Vec<char> = status.chars().collect();
+            let width = text_renderer::calculate_text_dimensions(&status_chars, &self.font).width;
+            self.text_renderer
+                .push_str(status, RGBColor::gray(), text_area.max.x - width - MARGIN, text_top_left_anchor.y, self.font.clone());
         }
     }
 
@@ -265,7 +318,7 @@ impl InputBox {
         let color = self.input_box.text_render_settings.text_color;
         if !self.input_box.data.is_empty() {
             self.text_renderer
-                .push_draw_command(self.input_box.data.iter().map(|c| *c), color, t.min.x, t.max.y, self.font.clone());
+                .push_chars(&self.input_box.data, color, t.min.x, t.max.y, self.font.clone());
             let color = self.selection_list.text_render_settings.text_color;
 
             // the bottom edge of each list item in the list box. Decreases with font.row_height() per list item
@@ -283,6 +336,16 @@ impl InputBox {
                 })
                 .collect();
 
+            // file paths are displayed in two columns; the file name, followed by its (dimmed) parent path
+            let show_as_files = matches!(self.mode, Mode::CommandInput(cmd) if parameter_list(cmd) == Some(ParameterList::Files));
+            let name_column_width = items
+                .iter()
+                .map(|item| text_renderer::calculate_text_dimensions(listbox::split_file_name(item).0, &self.font).width)
+                .max()
+                .unwrap_or(0)
+                + self.font.get_max_glyph_width() * 2;
+            let parent_color = RGBColor { r: 0.6, g: 0.6, b: 0.6 };
+
             let selected = self.selection_list.selection.unwrap_or(0);
             for (index, item) in items.into_iter().enumerate() {
                 if selected == index {
@@ -300,39 +363,35 @@ impl InputBox {
                     self.rect_renderer.add_rect(selection_box, RGBAColor::new(0.0, 0.65, 0.5, 1.0));
                 }
 
-                self.text_renderer
-                    .push_draw_command(item.iter().map(|c| *c), color, t.min.x, list_item_y_anchor, self.font.clone());
+                if show_as_files {
+                    let (name, parent) = listbox::split_file_name(item);
+                    self.text_renderer
+                        .push_chars(name, color, t.min.x, list_item_y_anchor, self.font.clone());
+                    self.text_renderer
+                        .push_chars(parent, parent_color, t.min.x + name_column_width, list_item_y_anchor, self.font.clone());
+                } else {
+                    self.text_renderer
+                        .push_chars(item, color, t.min.x, list_item_y_anchor, self.font.clone());
+                }
                 list_item_y_anchor -= self.selection_list.item_height;
             }
         } else {
             let color = RGBColor { r: 0.5, g: 0.5, b: 0.5 };
             self.text_renderer
-                .push_draw_command(INPUT_BOX_MSG.chars(), color, t.min.x, t.max.y, self.font.clone());
+                .push_str(INPUT_BOX_MSG, color, t.min.x, t.max.y, self.font.clone());
         }
     }
 
     pub fn clear(&mut self) {
         self.selection_list.clear();
         self.input_box.clear();
+        self.status = None;
         self.needs_update = true;
     }
 
     fn process_input(&mut self) -> CommandOutput {
         match self.mode {
-            Mode::CommandInput(cmd) => match cmd {
-                CommandTag::Goto => self
-                    .input_box
-                    .data
-                    .iter()
-                    .collect::<String>()
-                    .parse()
-                    .map(|v| CommandOutput::Goto(v))
-                    .unwrap_or(CommandOutput::None),
-                CommandTag::Find => CommandOutput::Find(self.input_box.data.iter().collect::<String>()),
-                CommandTag::GotoInFile => todo!(),
-                CommandTag::OpenFile => todo!(),
-                CommandTag::SaveFile => todo!(),
-            },
+            Mode::CommandInput(cmd) => parameter_output(cmd, &self.input_box.data),
             Mode::CommandList => {
                 if let Some(item) = self.selection_list.pop_selected() {
                     get_command(&item.iter().collect::<String>())
@@ -345,23 +404,48 @@ impl InputBox {
         }
     }
 
-    pub fn update(&mut self) {
+    /// Updates the list below the input to what matches it, in the modes that have one
+    fn update_list(&mut self) {
         match self.mode {
-            Mode::CommandInput(_c) => match _c {
-                // these need no interactive updating
-                CommandTag::Goto | CommandTag::GotoInFile | CommandTag::Find | CommandTag::SaveFile => {}
-                // these need interactive updating
-                CommandTag::OpenFile => self.update_list_of_files(),
+            Mode::CommandInput(cmd) => match parameter_list(cmd) {
+                Some(ParameterList::Files) => self.update_list_of_files(),
+                Some(ParameterList::Snippets) => self.update_list_of_snippets(),
+                Some(ParameterList::Choices) => self.update_list_of_choices(),
+                None => {}
             },
-            Mode::CommandList => {
-                self.update_list_of_commands();
-            }
+            Mode::CommandList => self.update_list_of_commands(),
         }
+    }
+
+    pub fn update(&mut self) {
+        self.update_list();
         self.input_box.cursor = self.input_box.cursor.clamp(0, self.input_box.data.len());
         self.needs_update = true;
     }
 }
 
+/// Walks the directory tree from root, returning the paths that contain name. Matched using smart case
+fn files_matching(root: &Path, name: &str) -> Vec<Vec<char>> {
+    let case_sensitive = smart_case_sensitive(name, true);
+    let name = if case_sensitive { name.to_string() } else { name.to_uppercase() };
+    WalkDir::new(root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|e| {
+            let e = e.unwrap();
+            // this is *odd* behavior. When we pass in a slice to contains(...)
+            // it will return true if *any* of the elements in that slice, exists in the string
+            let path = e.path().to_str().unwrap();
+            if case_sensitive && path.contains(&name) || !case_sensitive && path.to_uppercase().contains(&name) {
+                Some(e)
+            } else {
+                None
+            }
+        })
+        .map(|de| de.path().display().to_string().chars().collect())
+        .collect()
+}
+
 impl InputBehavior for InputBox {
     fn handle_key(&mut self, key: glfw::Key, action: glfw::Action, _modifier: glfw::Modifiers) -> CommandOutput {
         self.selection_list.selection = self.selection_list.selection.or_else(|| Some(0));
@@ -397,17 +481,8 @@ impl InputBehavior for InputBox {
         self.input_box.data.insert(self.input_box.cursor, ch);
         self.input_box.cursor += 1;
         self.selection_list.selection = None;
-        match self.mode {
-            Mode::CommandInput(_cmd) => match _cmd {
-                // these do not need interactive updating of the list
-                CommandTag::SaveFile | CommandTag::Goto | CommandTag::GotoInFile | CommandTag::Find => {}
-                // these need interactive updating the of the list
-                CommandTag::OpenFile => self.update_list_of_files(),
-            },
-            Mode::CommandList => {
-                self.update_list_of_commands();
-            }
-        }
+        self.status = None;
+        self.update_list();
         if !self.selection_list.data.is_empty() {
             self.selection_list.selection = Some(0);
         }
@@ -531,7 +606,113 @@ impl Viewable for InputBox {
         todo!()
     }
 
-    fn mouse_dragged(&mut self, _begin_coordinate: Vec2i, _current_coordinated: Vec2i) {
+    fn mouse_dragged(&mut self, _begin_coordinate: Vec2i, _current_coordinated: Vec2i, _anchor: Option<Index>) {
         todo!()
     }
 }
+
+/// What entering input, as the parameter of cmd, results in
+fn parameter_output(cmd: CommandTag, input: &[char]) -> CommandOutput {
+    match cmd {
+        CommandTag::Goto => input
+            .iter()
+            .collect::<String>()
+            .parse()
+            .map(|v| CommandOutput::Goto(v))
+            .unwrap_or(CommandOutput::None),
+        CommandTag::Find => CommandOutput::Find(input.iter().collect::<String>()),
+        CommandTag::GotoInFile => todo!(),
+        CommandTag::OpenFile => todo!(),
+        CommandTag::SaveFile => todo!(),
+        // the rest are carried out by the application, which owns what they act on, like the active view, its buffer or the timer
+        _ => CommandOutput::None,
+    }
+}
+
+/// What's listed below the input of a parameter, to choose from
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ParameterList {
+    Files,
+    Snippets,
+    /// Set by the application, like symbols or buffers
+    Choices,
+}
+
+/// What's listed below the input of cmd's parameter, if anything
+fn parameter_list(cmd: CommandTag) -> Option<ParameterList> {
+    match cmd {
+        CommandTag::OpenFile | CommandTag::InsertFile => Some(ParameterList::Files),
+        CommandTag::InsertSnippet => Some(ParameterList::Snippets),
+        CommandTag::GotoSymbol | CommandTag::SwitchBuffer | CommandTag::ToggleRelated => Some(ParameterList::Choices),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod inputbox_tests {
+    use super::{files_matching, parameter_output, InputBox, Mode};
+    use crate::cmd::{get_command, CommandTag};
+    use crate::datastructure::generic::Vec2i;
+    use crate::opengl::{
+        rectangle_renderer::RectRenderer,
+        shaders::{RectShader, TextShader},
+        text_renderer::TextRenderer,
+    };
+    use crate::ui::basic::coordinate::{Coordinate, Size};
+    use crate::ui::basic::frame::Frame;
+    use crate::ui::eventhandling::event::{CommandOutput, InputBehavior};
+    use crate::ui::font::{Font, SOURCE_CODE_PRO_REGULAR};
+    use crate::utils::temp_dir::TempDir;
+    use std::rc::Rc;
+
+    #[test]
+    fn file_list_is_populated_from_start_dir() {
+        let root = TempDir::new("finder");
+        let sibling = root.join("sibling");
+        std::fs::create_dir_all(&sibling).unwrap();
+        std::fs::write(sibling.join("a.rs"), "").unwrap();
+        std::fs::write(sibling.join("b.rs"), "").unwrap();
+        std::fs::write(root.join("outside.rs"), "").unwrap();
+
+        let files: Vec<String> = files_matching(&sibling, "").iter().map(|f| f.iter().collect()).collect();
+
+        assert!(files.iter().all(|f| f.starts_with(sibling.to_str().unwrap())));
+        assert!(files.iter().any(|f| f.ends_with("a.rs")));
+        assert!(files.iter().any(|f| f.ends_with("b.rs")));
+        assert!(!files.iter().any(|f| f.ends_with("outside.rs")));
+    }
+
+    fn input_box() -> InputBox {
+        let font = Rc::new(Font::without_texture(SOURCE_CODE_PRO_REGULAR, 14, &(' '..='~').collect()));
+        let frame = Frame { anchor: Vec2i::new(0, 400), size: Size::new(400, 300) };
+        InputBox::with_renderers(frame, font, TextRenderer::without_buffers(TextShader::unlinked()), RectRenderer::without_buffers(RectShader::unlinked()))
+    }
+
+    #[test]
+    fn selecting_go_to_takes_the_line_as_input() {
+        let goto = *get_command("Go to").unwrap();
+        assert!(goto == CommandTag::Goto && goto.takes_input());
+        assert!(matches!(parameter_output(goto, &['4', '2']), CommandOutput::Goto(42)));
+        assert!(matches!(parameter_output(goto, &['x']), CommandOutput::None));
+        // and commands without parameters are run as soon as they're selected
+        assert!(!get_command("Sort lines").unwrap().takes_input());
+    }
+
+    #[test]
+    fn go_to_selected_from_the_command_list_outputs_the_line_on_enter() {
+        let mut input_box = input_box();
+        input_box.open(Mode::CommandList, None);
+        assert!(input_box.select_command(*get_command("Go to").unwrap()));
+        assert!(input_box.mode == Mode::CommandInput(CommandTag::Goto));
+        assert!(input_box.input_box.data.is_empty());
+
+        input_box.handle_char('1');
+        input_box.handle_char('2');
+        let enter = input_box.handle_key(glfw::Key::Enter, glfw::Action::Press, glfw::Modifiers::empty());
+        assert!(matches!(enter, CommandOutput::Goto(12)));
+
+        // a command that takes no input leaves the box as it is
+        assert!(!input_box.select_command(*get_command("Sort lines").unwrap()));
+        assert!(input_box.mode == Mode::CommandInput(CommandTag::Goto));
+    }
+}
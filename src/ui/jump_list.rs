@@ -0,0 +1,105 @@
+use super::view::ViewId;
+use crate::textbuffer::metadata::Index;
+
+/// How many jumps are remembered, before the oldest are forgotten
+pub const MAX_JUMPS: usize = 100;
+
+/// Where the cursor was before it jumped (to a line, a search match, a definition and so on), to go back and forth between, like
+/// the back and forward buttons of a browser
+#[derive(Debug)]
+pub struct JumpList {
+    jumps: Vec<(ViewId, Index)>,
+    /// Where in jumps going forward leads to. The length of jumps, unless going back
+    current: usize,
+    max_jumps: usize,
+}
+
+impl Default for JumpList {
+    fn default() -> Self {
+        JumpList::with_max_jumps(MAX_JUMPS)
+    }
+}
+
+impl JumpList {
+    pub fn with_max_jumps(max_jumps: usize) -> JumpList {
+        JumpList { jumps: Vec::new(), current: 0, max_jumps }
+    }
+
+    /// Remembers from, the position the cursor is about to jump from. Jumping after going back forgets the jumps that could be
+    /// gone forward to
+    pub fn push(&mut self, view: ViewId, from: Index) {
+        self.jumps.truncate(self.current);
+        if self.jumps.last() != Some(&(view, from)) {
+            self.jumps.push((view, from));
+        }
+        if self.jumps.len() > self.max_jumps {
+            let excess = self.jumps.len() - self.max_jumps;
+            self.jumps.drain(..excess);
+        }
+        self.current = self.jumps.len();
+    }
+
+    /// Where to go back to, from here. Going back from the latest jump remembers here, so that going forward returns to it
+    pub fn back(&mut self, view: ViewId, here: Index) -> Option<(ViewId, Index)> {
+        if self.current == 0 {
+            return None;
+        }
+        if self.current == self.jumps.len() {
+            self.jumps.push((view, here));
+        }
+        self.current -= 1;
+        Some(self.jumps[self.current])
+    }
+
+    /// Where to go forward to, after going back. None if there's nowhere to go back from
+    pub fn forward(&mut self) -> Option<(ViewId, Index)> {
+        if self.current + 1 >= self.jumps.len() {
+            return None;
+        }
+        self.current += 1;
+        Some(self.jumps[self.current])
+    }
+}
+
+#[cfg(test)]
+mod jump_list_tests {
+    use super::JumpList;
+    use crate::textbuffer::metadata::Index;
+    use crate::ui::view::ViewId;
+
+    #[test]
+    fn back_and_forward_through_jumps() {
+        let (a, b) = (ViewId(1), ViewId(2));
+        let mut jumps = JumpList::default();
+        assert_eq!(jumps.back(a, Index(0)), None);
+        jumps.push(a, Index(10));
+        jumps.push(b, Index(20));
+        assert_eq!(jumps.back(b, Index(300)), Some((b, Index(20))));
+        assert_eq!(jumps.back(b, Index(20)), Some((a, Index(10))));
+        assert_eq!(jumps.back(a, Index(10)), None);
+        assert_eq!(jumps.forward(), Some((b, Index(20))));
+        assert_eq!(jumps.forward(), Some((b, Index(300))), "going back from the latest jump, remembers where it was made to");
+        assert_eq!(jumps.forward(), None);
+    }
+
+    #[test]
+    fn jumping_after_going_back_forgets_the_way_forward() {
+        let view = ViewId(1);
+        let mut jumps = JumpList::with_max_jumps(3);
+        jumps.push(view, Index(1));
+        jumps.push(view, Index(2));
+        assert_eq!(jumps.back(view, Index(3)), Some((view, Index(2))));
+        jumps.push(view, Index(2));
+        assert_eq!(jumps.forward(), None);
+        assert_eq!(jumps.back(view, Index(4)), Some((view, Index(2))));
+        assert_eq!(jumps.back(view, Index(2)), Some((view, Index(1))));
+
+        for at in 5..10 {
+            jumps.push(view, Index(at));
+        }
+        assert_eq!(jumps.back(view, Index(10)), Some((view, Index(9))));
+        assert_eq!(jumps.back(view, Index(9)), Some((view, Index(8))));
+        assert_eq!(jumps.back(view, Index(8)), Some((view, Index(7))));
+        assert_eq!(jumps.back(view, Index(7)), None, "only the latest jumps are kept");
+    }
+}
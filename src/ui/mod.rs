@@ -5,6 +5,7 @@ use basic::{boundingbox, coordinate, frame};
 use crate::{
     datastructure::generic::{Vec2d, Vec2i},
     opengl::types::RGBAColor,
+    textbuffer::metadata::Index,
 };
 use glfw::{Action, Key, Modifiers};
 
@@ -18,8 +19,16 @@ pub mod panel;
 pub mod view;
 
 pub mod clipboard;
+pub mod cursor_region;
 pub mod debug_view;
+pub mod delete_guard;
+pub mod file_positions;
+pub mod focus_history;
+pub mod idle_save;
+pub mod jump_list;
+pub mod peek;
 pub mod scrollbar;
+pub mod view_options;
 
 #[derive(Clone, Copy, Debug)]
 pub enum UID {
@@ -37,16 +46,16 @@ pub enum MouseState {
     /// Mouse state that immediately gets translated to, when a mouse click is registered
     Click(glfw::MouseButton, Vec2d),
     /// Represents the mouse state when a UI element has been clicked and when Application has verified that MouseState::Click
-    /// was inside a UI Element
-    UIElementClicked(ViewId, glfw::MouseButton, Vec2d),
+    /// was inside a UI Element. The index is where in the buffer the click landed, if it was on text, which a drag that follows selects from
+    UIElementClicked(ViewId, glfw::MouseButton, Vec2d, Option<Index>),
     /// Mouse state representing a mouse drag action, involving the layout of an Element in the
     /// window. Thus, the behavior manager of this state, is the Application itself and not the individual UI element.
     UIElementDrag(ViewId, glfw::MouseButton, Vec2d),
     /// UIElementDragAction is a mouse state that represents a mouse click and drag
     /// that the UI element should register itself, and handle what decision to take.
     /// In contrast with UIElementDrag, which is a MouseState that Application<'app> should handle
-    /// Since it involves how the Application lays element out in the UI.
-    UIElementDragAction(ViewId, glfw::MouseButton, Vec2d, Vec2d),
+    /// Since it involves how the Application lays element out in the UI. The index is the one clicked, in UIElementClicked, the drag started from
+    UIElementDragAction(ViewId, glfw::MouseButton, Vec2d, Vec2d, Option<Index>),
     /// Mouse state for when/where the mouse button was released
     Released(glfw::MouseButton, Vec2d),
     None,
@@ -57,9 +66,9 @@ impl MouseState {
         match self {
             MouseState::Click(.., pos) => Some(pos.to_i32()),
             MouseState::UIElementDrag(_, _, pos) => Some(pos.to_i32()),
-            MouseState::UIElementDragAction(_, _, _, current) => Some(current.to_i32()),
+            MouseState::UIElementDragAction(_, _, _, current, _) => Some(current.to_i32()),
             MouseState::Released(_, pos) => Some(pos.to_i32()),
-            MouseState::UIElementClicked(.., pos) => Some(pos.to_i32()),
+            MouseState::UIElementClicked(_, _, pos, _) => Some(pos.to_i32()),
             MouseState::None => None,
         }
     }
@@ -80,5 +89,14 @@ pub trait Viewable {
     fn set_anchor(&mut self, anchor: Vec2i);
     fn bounding_box(&self) -> BoundingBox;
     fn mouse_clicked(&mut self, screen_coordinate: Vec2i);
-    fn mouse_dragged(&mut self, begin_coordinate: Vec2i, current_coordinated: Vec2i);
+    /// The mouse was dragged from begin_coordinate to current_coordinate. anchor is the index of the buffer the drag started on, if any
+    fn mouse_dragged(&mut self, begin_coordinate: Vec2i, current_coordinated: Vec2i, anchor: Option<Index>);
+    /// The index in the buffer of the character at screen_coordinate, if this shows a buffer and the coordinate is on its text
+    fn coordinate_to_index(&self, _screen_coordinate: Vec2i) -> Option<Index> {
+        None
+    }
+    /// Whether screen_coordinate is on this element
+    fn contains_point(&self, screen_coordinate: Vec2i) -> bool {
+        self.bounding_box().box_hit_check(screen_coordinate)
+    }
 }
@@ -1,11 +1,31 @@
 use super::boundingbox::BoundingBox;
 use super::coordinate::{Coordinate, Layout, Size};
+use super::cursor_region::PanelRegions;
 use super::view::{View, ViewId};
 use super::Viewable;
+use crate::textbuffer::metadata::Index;
 use crate::ui::Vec2i;
 
+use serde::{Deserialize, Serialize};
 use std::fmt::Formatter;
 
+/// The share of a panel's space each view gets when added. Weights are relative to each other, so a view with
+/// weight 200 next to a view with weight 100, takes up two thirds of the panel
+pub const DEFAULT_VIEW_WEIGHT: u32 = 100;
+/// How much weight a keyboard resize moves from one view to its neighbour
+pub const RESIZE_WEIGHT_STEP: u32 = 10;
+/// Views are never shrunk by keyboard resizing below this weight
+pub const MIN_VIEW_WEIGHT: u32 = 20;
+
+/// Direction in which to move or resize a view, within its panel
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
 #[derive(PartialEq, Clone, Copy, Eq, Hash, PartialOrd, Ord, Debug)]
 pub struct PanelId(pub u32);
 
@@ -34,6 +54,8 @@ pub struct Panel {
     pub size: Size,
     pub anchor: Vec2i,
     pub children: Vec<View>,
+    /// The relative share of the panel's space, for each child, in the same order as children
+    pub weights: Vec<u32>,
 }
 
 impl std::fmt::Debug for Panel {
@@ -72,6 +94,86 @@ pub fn divide_scatter(number: i32, spread_count: usize) -> Vec<i32> {
     r
 }
 
+/// The index of the child next to index, in direction. Only directions along the layout axis have neighbours;
+/// Left/Right in a horizontal layout and Up/Down in a vertical one
+pub fn neighbour_index(index: usize, len: usize, direction: Direction, layout: Layout) -> Option<usize> {
+    match (layout, direction) {
+        (Layout::Horizontal(_), Direction::Left) | (Layout::Vertical(_), Direction::Up) => index.checked_sub(1),
+        (Layout::Horizontal(_), Direction::Right) | (Layout::Vertical(_), Direction::Down) => Some(index + 1).filter(|&i| i < len),
+        _ => None,
+    }
+}
+
+/// Grows (or shrinks) the weight at index, by taking weight from (or giving it to) the next child, or the previous child, if
+/// index is the last one. Returns false if no weight could be moved
+pub fn transfer_weight(weights: &mut [u32], index: usize, grow: bool) -> bool {
+    if weights.len() < 2 || index >= weights.len() {
+        return false;
+    }
+    let other = if index + 1 < weights.len() { index + 1 } else { index - 1 };
+    let (to, from) = if grow { (index, other) } else { (other, index) };
+    if weights[from] < MIN_VIEW_WEIGHT + RESIZE_WEIGHT_STEP {
+        return false;
+    }
+    weights[from] -= RESIZE_WEIGHT_STEP;
+    weights[to] += RESIZE_WEIGHT_STEP;
+    true
+}
+
+/// The topmost of the candidates, given as (what was hit, its bounding box, whether it's visible), that pos lies within.
+/// Candidates come back to front, in the order they're drawn, so the last one hit is the one on top. Invisible ones can't be hit
+pub fn topmost_hit<T>(candidates: impl IntoIterator<Item = (T, BoundingBox, bool)>, pos: Vec2i) -> Option<T> {
+    candidates
+        .into_iter()
+        .filter(|(_, bounding_box, visible)| *visible && bounding_box.box_hit_check(pos))
+        .last()
+        .map(|(hit, ..)| hit)
+}
+
+/// How thick the bar is, that marks where a dragged view lands
+pub const INSERTION_BAR_WIDTH: i32 = 4;
+
+/// Where the dragged view lands if dropped at pos, among the views (given like the candidates of topmost_hit) of a panel with
+/// layout. It swaps places with the view it's dropped on, which is returned along with the bar marking that slot, along the
+/// leading edge of the view. Dropping it on itself, or on no view, does nothing
+pub fn drop_slot<T: PartialEq>(dragged: T, pos: Vec2i, layout: Layout, views: impl IntoIterator<Item = (T, BoundingBox, bool)>) -> Option<(T, BoundingBox)> {
+    let (target, bounding_box) = topmost_hit(views.into_iter().map(|(view, bb, visible)| ((view, bb.clone()), bb, visible)), pos)?;
+    if target == dragged {
+        return None;
+    }
+    let BoundingBox { min, max } = bounding_box;
+    let bar = match layout {
+        Layout::Horizontal(_) => BoundingBox::new(min, Vec2i::new(min.x + INSERTION_BAR_WIDTH, max.y)),
+        // y grows upwards, so the top edge is at max.y
+        Layout::Vertical(_) => BoundingBox::new(Vec2i::new(min.x, max.y - INSERTION_BAR_WIDTH), max),
+    };
+    Some((target, bar))
+}
+
+/// Splits the space of a panel, with its top left corner at anchor, in two halves side by side. The left half comes first, and gets
+/// the extra pixel of an odd width
+pub fn split_side_by_side(anchor: Vec2i, size: Size) -> [(Vec2i, Size); 2] {
+    let left = size.width - size.width / 2;
+    [
+        (anchor, Size::new(left, size.height)),
+        (anchor + Vec2i::new(left, 0), Size::new(size.width - left, size.height)),
+    ]
+}
+
+/// The anchors of children with sizes, laid out one after the other, starting at the top left corner of a panel at anchor
+pub fn child_anchors(anchor: Vec2i, margin: i32, layout: Layout, sizes: &[Size]) -> Vec<Vec2i> {
+    let mut anchor_iter = anchor + Vec2i::new(margin, -margin);
+    let mut anchors = Vec::with_capacity(sizes.len());
+    for size in sizes {
+        anchors.push(anchor_iter);
+        anchor_iter += match layout {
+            Layout::Vertical(space) => Vec2i::new(0, -size.height - *space as i32),
+            Layout::Horizontal(space) => Vec2i::new(size.width + *space as i32, 0),
+        };
+    }
+    anchors
+}
+
 impl Panel {
     pub fn new(id: u32, layout: Layout, margin: Option<i32>, border: Option<i32>, width: i32, height: i32, anchor: Vec2i) -> Panel {
         Panel {
@@ -82,6 +184,7 @@ impl Panel {
             size: Size::new(width, height),
             anchor: anchor,
             children: vec![],
+            weights: vec![],
         }
     }
 
@@ -102,26 +205,24 @@ impl Panel {
             view.resize(Size::shrink_by_margin(self.size, self.margin.unwrap_or(0)));
             view.set_anchor(adjusted_anchor);
         } else {
-            let sub_space_count = self.children.iter().filter(|v| v.visible).count();
             let margin = self.margin.unwrap_or(0);
-            let child_sizes = self.size.divide(sub_space_count as _, margin, self.layout);
-            match self.layout {
-                Layout::Vertical(space) => {
-                    let mut anchor_iter = self.anchor + Vec2i::new(margin, -margin);
-                    for (c, size) in self.children.iter_mut().filter(|v| v.visible).zip(child_sizes.into_iter()) {
-                        c.resize(size);
-                        c.set_anchor(anchor_iter);
-                        anchor_iter += Vec2i::new(0, -size.height - *space as i32);
-                    }
-                }
-                Layout::Horizontal(space) => {
-                    let mut anchor = self.anchor + Vec2i::new(margin, -margin);
-                    for (c, size) in self.children.iter_mut().filter(|v| v.visible).zip(child_sizes.iter()) {
-                        c.set_anchor(anchor);
-                        c.resize(*size);
-                        anchor += Vec2i::new(size.width + *space as i32, 0);
-                    }
-                }
+            let visible_weights: Vec<u32> = self
+                .children
+                .iter()
+                .zip(self.weights.iter())
+                .filter(|(v, _)| v.visible)
+                .map(|(_, w)| *w)
+                .collect();
+            let child_sizes = self.size.divide_weighted(&visible_weights, margin, self.layout);
+            let anchors = child_anchors(self.anchor, margin, self.layout, &child_sizes);
+            for (c, (size, anchor)) in self
+                .children
+                .iter_mut()
+                .filter(|v| v.visible)
+                .zip(child_sizes.into_iter().zip(anchors))
+            {
+                c.resize(size);
+                c.set_anchor(anchor);
             }
         }
         for v in self.children.iter_mut().filter(|v| v.visible) {
@@ -132,18 +233,66 @@ impl Panel {
     pub fn add_view(&mut self, mut view: View) {
         view.set_manager_panel(self.id);
         self.children.push(view);
+        self.weights.push(DEFAULT_VIEW_WEIGHT);
         self.layout();
     }
 
     pub fn remove_view(&mut self, view_id: ViewId) -> Option<View> {
         if let Some(pos) = self.children.iter().position(|v| v.id == view_id) {
             let v = self.children.remove(pos);
+            self.weights.remove(pos);
             Some(v)
         } else {
             None
         }
     }
 
+    /// Swaps the position of two children, and their share of the panel's space, along with them
+    pub fn swap_children(&mut self, a: usize, b: usize) {
+        self.children.swap(a, b);
+        self.weights.swap(a, b);
+        self.layout();
+    }
+
+    /// Moves the child at index one step in direction, among its siblings. Returns the new index of the child, if it moved
+    pub fn move_child(&mut self, index: usize, direction: Direction) -> Option<usize> {
+        let neighbour = neighbour_index(index, self.children.len(), direction, self.layout)?;
+        self.swap_children(index, neighbour);
+        Some(neighbour)
+    }
+
+    /// Grows the child at index when direction is Right or Down and shrinks it when it is Left or Up, along the layout axis
+    pub fn resize_child(&mut self, index: usize, direction: Direction) -> bool {
+        let grow = match (self.layout, direction) {
+            (Layout::Horizontal(_), Direction::Right) | (Layout::Vertical(_), Direction::Down) => true,
+            (Layout::Horizontal(_), Direction::Left) | (Layout::Vertical(_), Direction::Up) => false,
+            _ => return false,
+        };
+        let resized = transfer_weight(&mut self.weights, index, grow);
+        if resized {
+            self.layout();
+        }
+        resized
+    }
+
+    /// The visible view of this panel at pos, if any
+    pub fn view_at(&self, pos: Vec2i) -> Option<ViewId> {
+        topmost_hit(self.children.iter().map(|v| (v.id, v.bounding_box(), v.visible)), pos)
+    }
+
+    /// Where view_id lands, if it's dragged and dropped at pos, and the bar marking that slot
+    pub fn drop_slot(&self, view_id: ViewId, pos: Vec2i) -> Option<(ViewId, BoundingBox)> {
+        drop_slot(view_id, pos, self.layout, self.children.iter().map(|v| (v.id, v.bounding_box(), v.visible)))
+    }
+
+    pub fn regions(&self) -> PanelRegions {
+        PanelRegions {
+            bounding_box: self.bounding_box(),
+            layout: self.layout,
+            views: self.children.iter().filter(|v| v.visible).map(|v| v.regions()).collect(),
+        }
+    }
+
     pub fn get_view(&mut self, view_id: ViewId) -> Option<*mut View> {
         for v in self.children.iter_mut() {
             if *v.id() == *view_id {
@@ -221,7 +370,110 @@ impl Viewable for Panel {
         todo!()
     }
 
-    fn mouse_dragged(&mut self, _begin_coordinate: Vec2i, _current_coordinated: Vec2i) {
+    fn mouse_dragged(&mut self, _begin_coordinate: Vec2i, _current_coordinated: Vec2i, _anchor: Option<Index>) {
         todo!()
     }
 }
+
+#[cfg(test)]
+mod panel_tests {
+    use super::{child_anchors, drop_slot, neighbour_index, split_side_by_side, topmost_hit, transfer_weight, Direction, DEFAULT_VIEW_WEIGHT};
+    use crate::ui::boundingbox::BoundingBox;
+    use crate::ui::coordinate::{Layout, Size, Spacing};
+    use crate::ui::Vec2i;
+
+    #[test]
+    fn move_right_resequences_and_relays_out() {
+        let layout = Layout::Horizontal(Spacing(10));
+        let panel_size = Size { width: 1000, height: 600 };
+        let mut views = vec!['a', 'b', 'c'];
+        let mut weights = vec![
+            2 * DEFAULT_VIEW_WEIGHT,
+            DEFAULT_VIEW_WEIGHT,
+            DEFAULT_VIEW_WEIGHT,
+        ];
+
+        let target = neighbour_index(0, views.len(), Direction::Right, layout).unwrap();
+        views.swap(0, target);
+        weights.swap(0, target);
+        assert_eq!(views, vec!['b', 'a', 'c']);
+
+        let sizes = panel_size.divide_weighted(&weights, 5, layout);
+        let anchors = child_anchors(Vec2i::new(0, 600), 5, layout, &sizes);
+        // the moved view keeps its share of the space, and now sits after 'b'
+        assert_eq!(sizes[1].width, 2 * sizes[0].width + 1);
+        assert_eq!(anchors[0], Vec2i::new(5, 595));
+        assert_eq!(anchors[1], Vec2i::new(5 + sizes[0].width + 10, 595));
+        assert_eq!(anchors[2].x, anchors[1].x + sizes[1].width + 10);
+        assert_eq!(sizes.iter().map(|s| s.width).sum::<i32>(), 1000 - 5 * 2 - 10 * 2);
+
+        assert_eq!(neighbour_index(2, views.len(), Direction::Right, layout), None);
+        assert_eq!(neighbour_index(1, views.len(), Direction::Up, layout), None);
+        assert_eq!(neighbour_index(1, views.len(), Direction::Up, Layout::Vertical(Spacing(0))), Some(0));
+    }
+
+    #[test]
+    fn resize_moves_weight_between_neighbours() {
+        let mut weights = vec![DEFAULT_VIEW_WEIGHT, DEFAULT_VIEW_WEIGHT];
+        assert!(transfer_weight(&mut weights, 1, true));
+        assert_eq!(weights, vec![90, 110]);
+        while transfer_weight(&mut weights, 0, false) {}
+        assert_eq!(weights, vec![20, 180]);
+        assert!(!transfer_weight(&mut vec![DEFAULT_VIEW_WEIGHT], 0, true));
+    }
+
+    #[test]
+    fn clicks_land_on_topmost_visible_view() {
+        let left = BoundingBox::new(Vec2i::new(0, 0), Vec2i::new(500, 600));
+        let right = BoundingBox::new(Vec2i::new(510, 0), Vec2i::new(1000, 600));
+        let floating = BoundingBox::new(Vec2i::new(250, 150), Vec2i::new(750, 450));
+        let views = |floating_visible| {
+            vec![
+                ('l', left.clone(), true),
+                ('r', right.clone(), true),
+                ('f', floating.clone(), floating_visible),
+            ]
+        };
+
+        assert_eq!(topmost_hit(views(true), Vec2i::new(100, 100)), Some('l'));
+        assert_eq!(topmost_hit(views(true), Vec2i::new(900, 100)), Some('r'));
+        assert_eq!(topmost_hit(views(true), Vec2i::new(505, 100)), None);
+        // the view drawn last covers the ones beneath it
+        assert_eq!(topmost_hit(views(true), Vec2i::new(300, 300)), Some('f'));
+        assert_eq!(topmost_hit(views(true), Vec2i::new(700, 300)), Some('f'));
+        // unless it's hidden
+        assert_eq!(topmost_hit(views(false), Vec2i::new(300, 300)), Some('l'));
+        assert_eq!(topmost_hit(views(false), Vec2i::new(700, 300)), Some('r'));
+        assert_eq!(topmost_hit(vec![('l', left, false)], Vec2i::new(100, 100)), None);
+    }
+
+    #[test]
+    fn dragged_view_lands_on_view_beneath_it() {
+        let layout = Layout::Horizontal(Spacing(10));
+        let views = vec![
+            ('a', BoundingBox::new(Vec2i::new(0, 0), Vec2i::new(300, 600)), true),
+            ('b', BoundingBox::new(Vec2i::new(310, 0), Vec2i::new(600, 600)), true),
+            ('c', BoundingBox::new(Vec2i::new(610, 0), Vec2i::new(900, 600)), true),
+        ];
+        let bar_before_b = BoundingBox::new(Vec2i::new(310, 0), Vec2i::new(314, 600));
+        assert_eq!(drop_slot('a', Vec2i::new(400, 300), layout, views.clone()), Some(('b', bar_before_b)));
+        assert_eq!(drop_slot('c', Vec2i::new(50, 300), layout, views.clone()).map(|(v, _)| v), Some('a'));
+        // dropped on itself, or between views, it stays where it is
+        assert_eq!(drop_slot('a', Vec2i::new(50, 300), layout, views.clone()), None);
+        assert_eq!(drop_slot('a', Vec2i::new(305, 300), layout, views), None);
+
+        let stacked = vec![
+            ('a', BoundingBox::new(Vec2i::new(0, 310), Vec2i::new(900, 600)), true),
+            ('b', BoundingBox::new(Vec2i::new(0, 0), Vec2i::new(900, 300)), true),
+        ];
+        let bar_above_b = BoundingBox::new(Vec2i::new(0, 296), Vec2i::new(900, 300));
+        assert_eq!(drop_slot('a', Vec2i::new(450, 100), Layout::Vertical(Spacing(10)), stacked), Some(('b', bar_above_b)));
+    }
+
+    #[test]
+    fn detached_panels_split_the_space_of_the_panel() {
+        let [(kept_anchor, kept), (detached_anchor, detached)] = split_side_by_side(Vec2i::new(0, 768), Size { width: 1025, height: 768 });
+        assert_eq!((kept_anchor, kept.width, kept.height), (Vec2i::new(0, 768), 513, 768));
+        assert_eq!((detached_anchor, detached.width, detached.height), (Vec2i::new(513, 768), 512, 768));
+    }
+}
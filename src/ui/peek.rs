@@ -0,0 +1,60 @@
+use super::view::ViewId;
+use crate::textbuffer::{
+    contiguous::contiguous::ContiguousBuffer,
+    metadata::{Index, Line},
+    CharBuffer,
+};
+use std::ops::Range;
+
+/// How many lines before, and after, the line of a match are shown when peeking at it
+pub const PEEK_CONTEXT_LINES: usize = 2;
+
+/// A match of a search, shown in the popup without the cursor moving to it. The cursor is moved there only if the search is accepted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Peek {
+    pub view: ViewId,
+    pub find: String,
+    pub at: Index,
+}
+
+/// The lines shown around a match on line, of a buffer with line_count lines; context lines before it and after it, or fewer where
+/// the buffer begins or ends
+pub fn context_lines(line: usize, line_count: usize, context: usize) -> Range<usize> {
+    let line = std::cmp::min(line, line_count.saturating_sub(1));
+    line.saturating_sub(context)..std::cmp::min(line + context + 1, line_count)
+}
+
+/// The text of the lines around the match at, and where in that text the match begins. None if at is outside of the buffer
+pub fn peek_text(buffer: &ContiguousBuffer, at: Index, context: usize) -> Option<(String, usize)> {
+    let md = buffer.meta_data();
+    let line = md.get_line_number_of_buffer_index(at)?;
+    let lines = context_lines(line, md.line_count(), context);
+    let begin = md.get_line_start_index(Line(lines.start))?;
+    let end = md.get_line_start_index(Line(lines.end)).map_or(buffer.len(), |end| *end);
+    let text: String = buffer.get_slice(*begin..end).iter().collect();
+    let text = text.strip_suffix('\n').unwrap_or(&text).to_string();
+    Some((text, *at - *begin))
+}
+
+#[cfg(test)]
+mod peek_tests {
+    use super::{context_lines, peek_text};
+    use crate::textbuffer::{contiguous::contiguous::ContiguousBuffer, metadata::Index};
+
+    #[test]
+    fn context_is_cut_short_at_the_ends_of_the_buffer() {
+        assert_eq!(context_lines(10, 100, 2), 8..13);
+        assert_eq!(context_lines(1, 100, 2), 0..4);
+        assert_eq!(context_lines(99, 100, 2), 97..100);
+        assert_eq!(context_lines(0, 1, 2), 0..1);
+    }
+
+    #[test]
+    fn peeking_shows_the_lines_around_a_match() {
+        let mut buffer = ContiguousBuffer::new(0, 1024);
+        buffer.insert_slice(&"one\ntwo\nthree\nfour\nfive\nsix\n".chars().collect::<Vec<char>>());
+        assert_eq!(peek_text(&buffer, Index(15), 1), Some(("three\nfour\nfive".to_string(), 7)));
+        assert_eq!(peek_text(&buffer, Index(1), 1), Some(("one\ntwo".to_string(), 1)));
+        assert_eq!(peek_text(&buffer, Index(100), 1), None);
+    }
+}
@@ -16,32 +16,106 @@ pub struct ScrollBar {
     pub slider: Frame,
     /// The range of values this slider slides beween
     pub max: usize,
+    /// How many of the values that are visible at once, i.e. the page size. The slider's size is proportional to this
+    pub visible: usize,
     /// The layout of the slider/scroll bar
     pub layout: ScrollBarLayout,
-
+    /// The first visible value. Between 0 and max - visible
     pub scroll_value: usize,
 }
 
 impl ScrollBar {
+    pub const MIN_SLIDER_SIZE: i32 = 35;
+    /// The width of the scroll bar of a view, unless set with the "Scroll bar width" command
+    pub const WIDTH: i32 = 15;
+    /// The height of the ticks that mark positions along the track, like the lines of search matches
+    pub const MARKER_HEIGHT: i32 = 2;
+
     pub fn ui_update(&mut self) {
         match self.layout {
             ScrollBarLayout::Horizontal => todo!(),
             ScrollBarLayout::Vertical => {
-                self.slider.size.height = std::cmp::max(35, self.frame.size.height / self.max as i32);
+                self.slider.size.height = self.slider_size();
+                self.slider.size.width = self.frame.size.width;
                 self.slider.anchor.x = self.frame.anchor.x;
             }
         }
     }
 
-    pub fn new(frame: Frame, end: usize, layout: ScrollBarLayout, scroll_value: usize) -> ScrollBar {
-        let mut slider = frame.clone();
-        match layout {
+    pub fn new(frame: Frame, end: usize, visible: usize, layout: ScrollBarLayout, scroll_value: usize) -> ScrollBar {
+        let slider = frame.clone();
+        let mut sb = ScrollBar { frame, slider, max: end, visible, layout, scroll_value };
+        sb.ui_update();
+        sb
+    }
+
+    fn track_size(&self) -> i32 {
+        match self.layout {
             ScrollBarLayout::Horizontal => todo!(),
-            ScrollBarLayout::Vertical => {
-                slider.size.height = frame.size.height / end as i32;
-            }
+            ScrollBarLayout::Vertical => self.frame.size.height,
+        }
+    }
+
+    /// The slider is as large, relative to the track, as the visible part is, relative to the whole range. Never smaller than MIN_SLIDER_SIZE though
+    fn slider_size(&self) -> i32 {
+        let track = self.track_size();
+        if self.max <= self.visible {
+            track
+        } else {
+            let proportional = (track as f64 * self.visible as f64 / self.max as f64) as i32;
+            proportional.clamp(std::cmp::min(ScrollBar::MIN_SLIDER_SIZE, track), track)
+        }
+    }
+
+    /// The largest value scroll_value can take
+    pub fn scrollable_range(&self) -> usize {
+        self.max.saturating_sub(self.visible)
+    }
+
+    /// How far the slider can move along the track, in pixels
+    fn slider_travel(&self) -> i32 {
+        self.track_size() - self.slider_size()
+    }
+
+    /// Maps the slider's offset from the top of the track, to a scroll value
+    pub fn value_at_slider_offset(&self, offset: i32) -> usize {
+        let travel = self.slider_travel();
+        if travel <= 0 {
+            0
+        } else {
+            let fraction = offset.clamp(0, travel) as f64 / travel as f64;
+            (fraction * self.scrollable_range() as f64).round() as usize
+        }
+    }
+
+    /// Maps a scroll value, to the slider's offset from the top of the track. This is the inverse of value_at_slider_offset
+    pub fn slider_offset_of_value(&self, value: usize) -> i32 {
+        let range = self.scrollable_range();
+        if range == 0 {
+            0
+        } else {
+            let fraction = std::cmp::min(value, range) as f64 / range as f64;
+            (fraction * self.slider_travel() as f64).round() as i32
+        }
+    }
+
+    /// The y, at which the tick for value (a line) is drawn downwards from. Unlike the slider, which moves over the scrollable range,
+    /// ticks are placed by where value is in the whole range, so that a tick at the top of the track is the first line, and one
+    /// at the bottom the last
+    pub fn marker_y(&self, value: usize) -> i32 {
+        let track = self.track_size();
+        let offset = if self.max == 0 { 0 } else { (track as f64 * value as f64 / self.max as f64).round() as i32 };
+        self.frame.anchor.y - offset.clamp(0, std::cmp::max(track - ScrollBar::MARKER_HEIGHT, 0))
+    }
+
+    /// The width a scroll bar of width takes up next to the text of lines lines, of which visible fit in view. An auto hidden
+    /// scroll bar takes up none, leaving it to the text, while there's nothing to scroll; all lines fit and the view isn't scrolled
+    pub fn reserved_width(width: i32, auto_hide: bool, lines: usize, visible: usize, scrolled: bool) -> i32 {
+        if auto_hide && lines <= visible && !scrolled {
+            0
+        } else {
+            width
         }
-        ScrollBar { frame, slider, max: end, layout, scroll_value }
     }
 
     // Only use this function when we've validated that pos is inside this objects frame. otherwise, blame yourself
@@ -49,10 +123,10 @@ impl ScrollBar {
         match self.layout {
             ScrollBarLayout::Horizontal => todo!(),
             ScrollBarLayout::Vertical => {
-                let percent = (self.frame.anchor.y - pos.y) as f64 / self.frame.size.height as f64;
-                self.slider.anchor.y = pos.y.clamp(0 + self.slider.size.height, self.frame.anchor.y);
-                self.scroll_value = ((self.max as f64 * percent).floor() as usize).clamp(0, self.max);
                 self.ui_update();
+                let offset = (self.frame.anchor.y - pos.y).clamp(0, self.slider_travel());
+                self.scroll_value = self.value_at_slider_offset(offset);
+                self.slider.anchor.y = self.frame.anchor.y - offset;
             }
         }
     }
@@ -61,9 +135,89 @@ impl ScrollBar {
         match self.layout {
             ScrollBarLayout::Horizontal => todo!(),
             ScrollBarLayout::Vertical => {
-                let percent = self.scroll_value as f64 / self.max as f64;
-                self.slider.anchor.y = self.frame.anchor.y - (percent * self.frame.height() as f64) as i32;
+                self.ui_update();
+                self.scroll_value = std::cmp::min(self.scroll_value, self.scrollable_range());
+                self.slider.anchor.y = self.frame.anchor.y - self.slider_offset_of_value(self.scroll_value);
             }
         }
     }
 }
+
+#[cfg(test)]
+mod scrollbar_tests {
+    use super::{ScrollBar, ScrollBarLayout};
+    use crate::datastructure::generic::Vec2i;
+    use crate::ui::basic::{coordinate::Size, frame::Frame};
+
+    fn vertical(lines: usize, visible: usize) -> ScrollBar {
+        ScrollBar::new(Frame::new(Vec2i::new(0, 800), Size { width: 15, height: 800 }), lines, visible, ScrollBarLayout::Vertical, 0)
+    }
+
+    #[test]
+    fn slider_maps_both_ways_on_large_buffer() {
+        let sb = vertical(100_000, 40);
+        assert_eq!(sb.slider_size(), ScrollBar::MIN_SLIDER_SIZE);
+        let travel = sb.slider_travel();
+        assert_eq!(sb.value_at_slider_offset(0), 0);
+        assert_eq!(sb.value_at_slider_offset(travel), sb.scrollable_range());
+        assert_eq!(sb.slider_offset_of_value(sb.scrollable_range()), travel);
+        // every pixel maps to a value, which maps back to that same pixel
+        for offset in 0..=travel {
+            assert_eq!(sb.slider_offset_of_value(sb.value_at_slider_offset(offset)), offset);
+        }
+        // and every value maps to a pixel, which maps back to within one pixel's worth of values
+        let lines_per_pixel = sb.scrollable_range() as f64 / travel as f64;
+        for value in (0..=sb.scrollable_range()).step_by(97) {
+            let roundtrip = sb.value_at_slider_offset(sb.slider_offset_of_value(value));
+            assert!((roundtrip as f64 - value as f64).abs() <= lines_per_pixel / 2.0 + 1.0);
+        }
+    }
+
+    #[test]
+    fn dragging_and_setting_value_agree() {
+        let mut sb = vertical(100_000, 40);
+        sb.scroll_to_ui_pos(Vec2i::new(0, 800 - 300));
+        let (dragged_value, dragged_anchor) = (sb.scroll_value, sb.slider.anchor.y);
+        sb.scroll_value = 0;
+        sb.update_ui_position_by_value();
+        sb.scroll_value = dragged_value;
+        sb.update_ui_position_by_value();
+        assert_eq!(sb.slider.anchor.y, dragged_anchor);
+    }
+
+    #[test]
+    fn slider_is_proportional_on_small_buffer() {
+        let sb = vertical(100, 40);
+        assert_eq!(sb.slider_size(), 320);
+        for value in 0..=sb.scrollable_range() {
+            assert_eq!(sb.value_at_slider_offset(sb.slider_offset_of_value(value)), value);
+        }
+        // everything fits, the slider fills the track
+        assert_eq!(vertical(20, 40).slider_size(), 800);
+        assert_eq!(vertical(20, 40).value_at_slider_offset(100), 0);
+    }
+
+    #[test]
+    fn auto_hidden_scroll_bar_reserves_no_width_when_everything_fits() {
+        assert_eq!(ScrollBar::reserved_width(15, true, 20, 40, false), 0);
+        assert_eq!(ScrollBar::reserved_width(15, true, 40, 40, false), 0);
+        assert_eq!(ScrollBar::reserved_width(15, true, 41, 40, false), 15);
+        assert_eq!(ScrollBar::reserved_width(15, true, 20, 40, true), 15, "scrolled down, there's still something to scroll back");
+        assert_eq!(ScrollBar::reserved_width(15, false, 20, 40, false), 15);
+        assert_eq!(ScrollBar::reserved_width(8, false, 2000, 40, true), 8);
+    }
+
+    #[test]
+    fn markers_are_placed_by_line() {
+        let sb = vertical(1000, 40);
+        assert_eq!(sb.marker_y(0), 800);
+        assert_eq!(sb.marker_y(500), 400);
+        assert_eq!(sb.marker_y(250), 600);
+        // the tick of the last line stays on the track
+        assert_eq!(sb.marker_y(999), ScrollBar::MARKER_HEIGHT);
+        // and regardless of whether everything fits, or where the slider is
+        let mut sb = vertical(10, 40);
+        sb.scroll_value = 5;
+        assert_eq!(sb.marker_y(5), 400);
+    }
+}
@@ -1,28 +1,44 @@
 use glfw::{Action, Key, Modifiers};
 
 use super::boundingbox::BoundingBox;
+use super::clipboard::ClipEntry;
+use super::cursor_region::ViewRegions;
 use super::eventhandling::event::{key_press, key_press_repeat, CommandOutput, InputBehavior};
 use super::eventhandling::input::KeyboardInputContext;
 use super::panel::PanelId;
 use super::scrollbar::{ScrollBar, ScrollBarLayout};
 use super::Viewable;
 use super::{
-    basic::{coordinate::Size, frame::Frame},
+    basic::{
+        chrome::{Chrome, ChromeLayout},
+        coordinate::Size,
+        frame::Frame,
+    },
     font::Font,
 };
+use crate::cmd::format::format_buffer;
+use crate::cmd::shell::{append_output, ShellOutput};
+use crate::cmd::snippets::resolve_cursor_placeholder;
 use crate::datastructure::generic::Vec2i;
 use crate::debugger_catch;
 use crate::opengl::polygon_renderer::{PolygonRenderer, PolygonType, Texture};
+use crate::opengl::scissor::{ClipRect, ScissorGuard};
 use crate::opengl::{rectangle_renderer::RectRenderer, text_renderer::TextRenderer, types::RGBAColor};
 use crate::textbuffer::cursor::MetaCursor;
-use crate::textbuffer::operations::LineOperation;
+use crate::textbuffer::operations::{CaseMode, LineChange, LineOperation};
+use crate::textbuffer::symbols::Symbol;
 use crate::ui::basic::coordinate::Margin;
+use crate::ui::delete_guard::DeleteGuard;
+use crate::ui::file_positions::FilePosition;
+use crate::ui::view_options::{ViewOption, ViewOptions};
+use crate::utils::smart_case_sensitive;
 use crate::{app::TEST_DATA, opengl::types::RGBColor};
 
 use crate::textbuffer::{
     contiguous::contiguous::ContiguousBuffer,
     cursor::BufferCursor,
-    metadata::{Index, Line},
+    indent::{bracket_pair_at, IndentPolicy},
+    metadata::{Column, Index, Line, MetaData},
     CharBuffer, Movement, TextKind,
 };
 
@@ -54,8 +70,12 @@ pub struct View {
     pub title_font: Rc<Font>,
     pub edit_font: Rc<Font>,
     pub text_renderer: TextRenderer,
+    /// The title is drawn by a renderer of its own, since it shows the cursor position, and changes far more often than the text
+    title_renderer: TextRenderer,
     pub window_renderer: PolygonRenderer,
     pub cursor_renderer: RectRenderer,
+    /// What's around the text, which title_frame, view_frame and the frame of the scroll bar are laid out from
+    chrome: Chrome,
     pub title_frame: Frame,
     pub view_frame: Frame,
     pub topmost_line_in_buffer: i32,
@@ -66,10 +86,116 @@ pub struct View {
     buffer_in_view: std::ops::Range<usize>,
     pub view_changed: bool,
     pub bg_color: RGBAColor,
+    /// A background color set for this view in particular, see set_bg_color. Decorating the view as active or inactive leaves it be
+    bg_override: Option<RGBAColor>,
+    /// Whether this is the active view, the one keyboard input goes to. Set when the view is decorated
+    focused: bool,
     pub visible: bool,
-    background_image: Texture,
+    /// Drawn in the middle of the view while its buffer is empty, see empty_background
+    background_image: Option<Texture>,
     text_margin_left: i32,
     scroll_bar: ScrollBar,
+    /// How typed newlines and closing braces get indented
+    pub indent_policy: IndentPolicy,
+    /// Whitespace markers, indent guides, rulers and the like
+    pub options: ViewOptions,
+    /// Pinned views are left open by "close other views" and "close all views", and files are never opened into them
+    pub pinned: bool,
+    /// Keep the last line in view as shell output is appended, for as long as the cursor is at the end and the last line is shown.
+    /// Scrolling up stops following, scrolling back down to the bottom resumes it
+    pub follow_tail: bool,
+    /// Home goes to the first non-blank character of the line, and pressing it again goes to the beginning of the line
+    pub smart_home: bool,
+    /// Where the last press of Home put the cursor, at the first non-blank character of a line. Pressing Home again, without
+    /// having moved the cursor since, toggles to the beginning of the line
+    home_toggle: Option<Index>,
+    /// A range of the buffer that is briefly highlighted, for instance where a goto or search landed
+    flash: Option<Flash>,
+    /// The time (in seconds, as reported by glfw) of the last call to draw
+    frame_time: f64,
+    /// The search, whose matches are marked along the scroll bar
+    search_markers: Option<SearchMarkers>,
+    /// The occurrences of the word at the cursor, highlighted when options.highlight_word_matches is set
+    word_matches: WordMatches,
+    /// What the text_renderer's data was last built from. None if it hasn't been built yet
+    text_built_from: Option<TextSnapshot>,
+    /// Deleting selections larger than this has to be confirmed
+    pub delete_guard: DeleteGuard,
+    /// The delete waiting to be confirmed, and its size in characters and lines
+    held_delete: Option<(HeldDelete, (usize, usize))>,
+}
+
+/// What the text of a view is laid out from. The text is only rebuilt, and uploaded, when this has changed, and not when only the
+/// cursor (or selection) has moved, which is what most redraws are for
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TextSnapshot {
+    anchor: Vec2i,
+    top_line: i32,
+    rows: i32,
+    render_whitespace: bool,
+    buffer: u32,
+    /// The revision of the buffer's meta data, which every edit bumps, so that the characters in view don't have to be compared
+    revision: u64,
+    in_view: (usize, usize),
+}
+
+impl TextSnapshot {
+    pub fn of(buffer: &ContiguousBuffer, in_view: &std::ops::Range<usize>, top_line: i32, rows: i32, anchor: Vec2i, render_whitespace: bool) -> TextSnapshot {
+        TextSnapshot {
+            anchor,
+            top_line,
+            rows,
+            render_whitespace,
+            buffer: buffer.id,
+            revision: buffer.meta_data().revision(),
+            in_view: (in_view.start, in_view.end),
+        }
+    }
+}
+
+/// How long a goto or search result is highlighted for
+pub const FLASH_DURATION_MS: u64 = 400;
+/// The alpha a flash highlight starts out with, before fading
+const FLASH_ALPHA: f32 = 0.6;
+
+#[derive(Clone, Copy, Debug)]
+struct Flash {
+    begin: Index,
+    end: Index,
+    /// The frame time the flash started at, in seconds
+    started: f64,
+    /// In seconds
+    duration: f64,
+}
+
+/// The lines of the matches of a search, which are only searched for again when the term or the buffer changes
+struct SearchMarkers {
+    term: String,
+    /// The buffer revision the lines were found in, None if they haven't been looked for yet
+    revision: Option<u64>,
+    lines: Vec<usize>,
+}
+
+/// How long the cursor has to rest on a word, in seconds, before the other occurrences of it are highlighted
+const WORD_MATCHES_DELAY: f64 = 0.4;
+
+/// The other occurrences in view, of the word the cursor rests on. They're looked for once the cursor has stayed put for a moment,
+/// so not on every keystroke while typing
+#[derive(Default)]
+struct WordMatches {
+    /// The cursor position, the buffer revision and the part of the buffer in view, the matches are for, and the frame time the
+    /// cursor came to rest there
+    rest: Option<(Index, u64, std::ops::Range<usize>, f64)>,
+    /// Where the matches begin, and the length of the word. None until the cursor has rested long enough
+    found: Option<(Vec<Index>, usize)>,
+}
+
+/// An edit that would delete a selection larger than the delete guard allows, held back until the user confirms it
+#[derive(Clone, Copy, Debug)]
+enum HeldDelete {
+    Delete(Movement),
+    /// Typing ch over the selection
+    Replace(char),
 }
 
 pub struct Popup {
@@ -80,6 +206,7 @@ pub struct Popup {
 impl Popup {
     pub fn reset(&mut self) {
         self.view.buffer.clear();
+        self.view.flash = None;
         self.view.set_need_redraw();
     }
 }
@@ -109,15 +236,11 @@ impl InputBehavior for View {
         match key {
             Key::Tab if key_press(action) => {
                 if let Some((begin, end)) = self.buffer.get_selection() {
-                    let md = self.buffer.meta_data();
-                    let a = unsafe { md.get_line_number_of_buffer_index(begin).unwrap_unchecked() };
-                    let b_inclusive = unsafe { md.get_line_number_of_buffer_index(end).unwrap_unchecked() };
+                    let lines = self.buffer.meta_data().lines_spanning(begin, end);
                     if modifier == Modifiers::Shift {
-                        self.buffer
-                            .line_operation(a..b_inclusive + 1, &LineOperation::ShiftLeft { shift_by: 4 });
+                        self.buffer.line_operation(lines, &LineOperation::ShiftLeft { shift_by: 4 });
                     } else {
-                        self.buffer
-                            .line_operation(a..b_inclusive + 1, &LineOperation::ShiftRight { shift_by: 4 });
+                        self.buffer.line_operation(lines, &LineOperation::ShiftRight { shift_by: 4 });
                     }
                 } else {
                     self.insert_slice(&[' ', ' ', ' ', ' ']);
@@ -125,10 +248,12 @@ impl InputBehavior for View {
             }
             Key::Home | Key::Kp7 if key_press(action) => match modifier {
                 Modifiers::Control => self.cursor_goto(crate::textbuffer::metadata::Index(0)),
-                _ => self.move_cursor(Movement::Begin(TextKind::Line)),
+                m if m == Modifiers::Control | Modifiers::Shift => self.select_to(crate::textbuffer::metadata::Index(0)),
+                _ => self.home(),
             },
             Key::End | Key::Kp1 if key_press(action) => match modifier {
                 Modifiers::Control => self.cursor_goto(crate::textbuffer::metadata::Index(self.buffer.len())),
+                m if m == Modifiers::Control | Modifiers::Shift => self.select_to(crate::textbuffer::metadata::Index(self.buffer.len())),
                 Modifiers::Shift => {
                     self.buffer.select_move_cursor_absolute(Movement::End(TextKind::Line));
                 }
@@ -264,25 +389,45 @@ impl InputBehavior for View {
 }
 
 impl View {
-    const SCROLL_BAR_WIDTH: i32 = 15;
     pub fn new(
-        name: &str, view_id: ViewId, text_renderer: TextRenderer, mut cursor_renderer: RectRenderer, window_renderer: PolygonRenderer, width: i32, height: i32,
-        bg_color: RGBAColor, mut buffer: Box<ContiguousBuffer>, edit_font: Rc<Font>, title_font: Rc<Font>, background_image: Texture,
+        name: &str, view_id: ViewId, text_renderer: TextRenderer, cursor_renderer: RectRenderer, window_renderer: PolygonRenderer, width: i32, height: i32,
+        bg_color: RGBAColor, buffer: Box<ContiguousBuffer>, edit_font: Rc<Font>, title_font: Rc<Font>, background_image: Option<Texture>,
     ) -> View {
-        let title_height = title_font.row_height() + 5;
-
-        let tmp_anchor = Vec2i::new(0, height);
-        let title_size = Size::new(width, title_height);
-        let title_frame = Frame::new(tmp_anchor, title_size);
-        let view_anchor = Vec2i::new(0, height - title_height);
-        let view_size = Size::new(width - View::SCROLL_BAR_WIDTH, height - title_height);
-        let view_frame = Frame::new(view_anchor, view_size);
-        buffer.rebuild_metadata();
+        let title_renderer = TextRenderer::create(text_renderer.shader.clone(), 128);
+        View::with_renderers(
+            name,
+            view_id,
+            text_renderer,
+            title_renderer,
+            cursor_renderer,
+            window_renderer,
+            width,
+            height,
+            bg_color,
+            buffer,
+            edit_font,
+            title_font,
+            background_image,
+        )
+    }
 
-        let scroll_bar_frame =
-            Frame::new(view_frame.anchor + Vec2i::new(width - View::SCROLL_BAR_WIDTH, 0), Size::new(View::SCROLL_BAR_WIDTH, height - title_height));
+    fn with_renderers(
+        name: &str, view_id: ViewId, text_renderer: TextRenderer, title_renderer: TextRenderer, mut cursor_renderer: RectRenderer,
+        window_renderer: PolygonRenderer, width: i32, height: i32, bg_color: RGBAColor, mut buffer: Box<ContiguousBuffer>, edit_font: Rc<Font>,
+        title_font: Rc<Font>, background_image: Option<Texture>,
+    ) -> View {
+        let chrome = Chrome {
+            title_height: title_font.row_height() + 5,
+            gutter_width: 0,
+            scroll_bar_width: ScrollBar::WIDTH,
+            footer_height: None,
+        };
+        let ChromeLayout { title: title_frame, content: view_frame, scroll_bar: scroll_bar_frame, .. } =
+            chrome.layout(Vec2i::new(0, height), Size::new(width, height));
+        buffer.rebuild_metadata();
 
-        let sb = ScrollBar::new(scroll_bar_frame, buffer.meta_data().line_count(), ScrollBarLayout::Vertical, 0);
+        let rows_displayable = (view_frame.size.height / edit_font.row_height()) as usize;
+        let sb = ScrollBar::new(scroll_bar_frame, buffer.meta_data().line_count(), rows_displayable, ScrollBarLayout::Vertical, 0);
 
         cursor_renderer.set_color(RGBAColor { r: 0.5, g: 0.5, b: 0.5, a: 0.5 });
         let mut v = View {
@@ -291,8 +436,10 @@ impl View {
             name: name.to_string(),
             id: view_id,
             text_renderer,
+            title_renderer,
             window_renderer,
             cursor_renderer,
+            chrome,
             title_frame,
             view_frame,
             topmost_line_in_buffer: 0,
@@ -301,10 +448,25 @@ impl View {
             buffer_in_view: 0..0,
             view_changed: true,
             bg_color,
+            bg_override: None,
+            focused: true,
             visible: true,
             background_image,
             text_margin_left: 4,
             scroll_bar: sb,
+            indent_policy: IndentPolicy::default(),
+            options: ViewOptions::default(),
+            pinned: false,
+            follow_tail: false,
+            smart_home: true,
+            home_toggle: None,
+            flash: None,
+            frame_time: 0.0,
+            search_markers: None,
+            word_matches: WordMatches::default(),
+            text_built_from: None,
+            delete_guard: DeleteGuard::default(),
+            held_delete: None,
         };
 
         v.update(None);
@@ -323,30 +485,10 @@ impl View {
         } else {
             let Vec2i { x: ax, y: ay } = self.view_frame.anchor;
             let Vec2i { x: mx, y: my } = mouse_pos;
-
-            let md = self.buffer.meta_data();
-            let view_line = ((ay - my) as f64 / self.get_text_font().row_height() as f64).floor() as isize;
-            let line_clicked = Line(self.topmost_line_in_buffer as usize).offset(view_line);
-
-            let start_index = md
-                .get_line_start_index(line_clicked)
-                .unwrap_or(md.get_line_start_index(Line(md.line_count() - 1)).unwrap());
-
-            let end_index = md.get_line_start_index(line_clicked.offset(1)).unwrap_or(Index(self.buffer.len()));
-
-            let line_contents = self.buffer.get_slice(*start_index..*end_index);
-            let mut rel_x = mx - ax;
             let text_font = self.get_text_font();
-            let final_index_pos = line_contents
-                .iter()
-                .enumerate()
-                .find(|(_, ch)| {
-                    rel_x -= text_font.get_glyph(**ch).unwrap().advance;
-                    rel_x <= 0
-                })
-                .map(|(i, _)| start_index.offset(i as isize))
-                .unwrap_or(end_index.offset(-1));
-            Some(final_index_pos)
+            Some(index_at_point(&self.buffer, self.topmost_line_in_buffer as usize, mx - ax, ay - my, text_font.row_height(), |ch| {
+                text_font.get_glyph(ch).unwrap().advance
+            }))
         }
     }
 
@@ -378,90 +520,134 @@ impl View {
         );
 
         let bg_color = self.bg_color;
+        // read only views get a differently colored border, so that it's obvious that typing in it won't do anything
+        let border_color = if self.buffer.read_only() { RGBAColor::new(0.75, 0.25, 0.25, 1.0) } else { RGBAColor::black() };
         if let Some(texture) = bg_texture {
             self.window_renderer.make_bordered_rect(
                 self.view_frame.to_bb(),
                 bg_color,
-                (2, RGBAColor::black()),
+                (2, border_color),
                 PolygonType::RoundedDecorated { corner_radius: 3.5, texture },
             );
         } else {
             self.window_renderer.make_bordered_rect(
                 self.view_frame.to_bb(),
                 bg_color,
-                (2, RGBAColor::black()),
+                (2, border_color),
                 PolygonType::RoundedUndecorated { corner_radius: 3.5 },
             );
         }
 
-        if self.buffer.empty() {
+        if let Some(texture) = empty_background(&self.options, self.buffer.empty(), self.background_image) {
             let Size { width, height } = self.view_frame.size;
             let image_bb = BoundingBox::shrink(&self.view_frame.to_bb(), Margin::Perpendicular { h: width / 4, v: height / 4 });
             self.window_renderer
-                .push_draw_command(image_bb, bg_color, PolygonType::Decorated { texture: self.background_image });
+                .push_draw_command(image_bb, bg_color, PolygonType::Decorated { texture });
         }
 
         self.set_need_redraw();
     }
 
-    pub fn draw(&mut self) {
+    /// Draws the view. time is the time of this frame, in seconds, used for animating highlights
+    pub fn draw(&mut self, time: f64) {
         if !self.visible {
             return;
         }
+        self.frame_time = time;
+        if self.flash.is_some() {
+            // the flash fades every frame, until it has run its course
+            self.view_changed = true;
+        }
+        if self.update_word_matches(time) {
+            self.view_changed = true;
+        }
+        self.fit_scroll_bar();
         let total_size = self.total_size();
         if self.view_changed {
             self.scroll_bar.max = self.buffer.meta_data().line_count();
-            self.text_renderer.clear_data();
+            self.title_renderer.clear_data();
             self.cursor_renderer.clear_data();
             self.update(None);
-            // create the scroll bar
-            self.window_renderer
-                .push_draw_command(self.scroll_bar.frame.to_bb(), self.bg_color.uniform_scale(-0.05), PolygonType::Undecorated);
-            assert_eq!(self.scroll_bar.slider.width(), self.scroll_bar.frame.width());
-            self.window_renderer.make_bordered_rect(
-                self.scroll_bar.slider.to_bb(),
-                self.bg_color.uniform_scale(0.2),
-                (1, RGBAColor::white()),
-                PolygonType::RoundedUndecorated { corner_radius: 7.5 },
-            );
+            // create the scroll bar, unless it's hidden
+            if self.chrome.scroll_bar_width > 0 {
+                self.window_renderer
+                    .push_draw_command(self.scroll_bar.frame.to_bb(), self.bg_color.uniform_scale(-0.05), PolygonType::Undecorated);
+                assert_eq!(self.scroll_bar.slider.width(), self.scroll_bar.frame.width());
+                self.window_renderer.make_bordered_rect(
+                    self.scroll_bar.slider.to_bb(),
+                    self.bg_color.uniform_scale(0.2),
+                    (1, RGBAColor::white()),
+                    PolygonType::RoundedUndecorated { corner_radius: 7.5 },
+                );
+                self.render_search_markers();
+            }
 
             // self.menu_text_renderer.clear_data();
             let BufferCursor { row, col, .. } = self.buffer.cursor();
             let title = format!(
-                "{}:{}:{}",
+                "{}:{}:{}{}{}",
                 self.buffer
                     .file_name()
                     .map(|p| p.display().to_string())
                     .unwrap_or("unnamed_file".into()),
                 *row,
-                *col
+                *col,
+                if self.buffer.read_only() { " [read only]" } else { "" },
+                if self.pinned { " [pinned]" } else { "" }
             );
 
             self.draw_title(&title);
 
-            unsafe {
-                let Vec2i { x: top_x, y: top_y } = self.title_frame.anchor;
-                gl::Enable(gl::SCISSOR_TEST);
-                gl::Scissor(top_x, top_y - total_size.height, total_size.width, total_size.height);
-            }
-
             // draw text view
             let Vec2i { x: top_x, y: top_y } = self.view_frame.anchor;
             let top_x = top_x + self.text_margin_left;
 
-            // render text contents
-            self.text_renderer.push_draw_command(
-                self.buffer
-                    .iter()
-                    .skip(self.buffer_in_view.start)
-                    .take(self.buffer_in_view.len() + 100)
-                    .map(|c| *c),
-                RGBColor::white(),
-                top_x,
-                top_y,
-                self.get_text_font(),
+            // render text contents, unless it's what's already been rendered
+            let snapshot = TextSnapshot::of(
+                &self.buffer,
+                &self.buffer_in_view,
+                self.topmost_line_in_buffer,
+                self.rows_displayable(),
+                self.view_frame.anchor,
+                self.options.render_whitespace,
             );
+            let rebuild_text = self.text_built_from != Some(snapshot);
+            if rebuild_text {
+                self.text_renderer.clear_data();
+                self.text_renderer.push_draw_command(
+                    self.buffer
+                        .iter()
+                        .skip(self.buffer_in_view.start)
+                        .take(self.buffer_in_view.len() + 100)
+                        .map(|c| *c),
+                    RGBColor::white(),
+                    top_x,
+                    top_y,
+                    self.get_text_font(),
+                );
+                self.text_built_from = Some(snapshot);
+            }
             self.cursor_renderer.clear_data();
+            if self.options.render_whitespace {
+                self.render_whitespace_markers(rebuild_text);
+            }
+            if self.options.show_indent_guides {
+                self.render_indent_guides();
+            }
+            if self.options.show_change_markers {
+                self.render_change_markers();
+            }
+            if !self.options.rulers.is_empty() {
+                self.render_rulers();
+            }
+            if let Some(flash) = self.flash {
+                match flash_alpha(time - flash.started, flash.duration, FLASH_ALPHA) {
+                    Some(alpha) => self.render_flash(flash.begin, flash.end, alpha),
+                    None => self.flash = None,
+                }
+            }
+            self.render_word_matches();
+            self.render_bracket_match();
             if let Some(marker) = self.buffer.meta_cursor {
                 match marker {
                     crate::textbuffer::cursor::MetaCursor::Absolute(ref abs_pos) => {
@@ -482,22 +668,20 @@ impl View {
         // Remember to draw in correct Z-order! We manage our own "layers". Therefore, draw cursor last
         self.window_renderer.execute_draw_list();
         let Vec2i { x: top_x, y: top_y } = self.title_frame.anchor;
-        unsafe {
-            gl::Enable(gl::SCISSOR_TEST);
-            gl::Scissor(top_x + 2, top_y - total_size.height, self.view_frame.width() - self.text_margin_left, total_size.height);
+        let text_width = self.view_frame.width() - self.text_margin_left;
+        {
+            let _clip = ScissorGuard::new(ClipRect::new(top_x + 2, top_y - total_size.height, text_width, total_size.height));
+            self.text_renderer.execute_draw_list();
+        }
+        {
+            let _clip = ScissorGuard::new(ClipRect::from_frame(&self.title_frame));
+            self.title_renderer.execute_draw_list();
         }
-        self.text_renderer.execute_draw_list();
 
         // we clip here as well, because otherwise the cursor might show up "on top" of the title bar, which is undesirable
-        unsafe {
-            gl::Scissor(top_x + 2, top_y - total_size.height, self.view_frame.width() - self.text_margin_left, self.view_frame.height());
-        }
+        let _clip = ScissorGuard::new(ClipRect::new(top_x + 2, top_y - total_size.height, text_width, self.view_frame.height()));
         self.cursor_renderer.draw();
         //self.menu_text_renderer.draw();
-
-        unsafe {
-            gl::Disable(gl::SCISSOR_TEST);
-        }
     }
 
     fn render_absolute_selection(&mut self, absolute_metacursor_position: Index) {
@@ -507,18 +691,17 @@ impl View {
         let top_x = top_x + self.text_margin_left;
         if absolute_metacursor_position < self.buffer.cursor_abs() {
             // means we have drag-selected downwards/forwards
-            let first_line = self
+            let (first_line, begin_selection) = self
                 .buffer
                 .meta_data()
-                .get_line_number_of_buffer_index(absolute_metacursor_position)
-                .map_or(Line(0), |l| Line(l));
+                .position_of(absolute_metacursor_position)
+                .unwrap_or((Line(0), Column(0)));
             let last_line = self.buffer.cursor_row();
             if first_line == last_line {
                 let rows_down_in_view: i32 = *first_line as i32 - self.topmost_line_in_buffer;
-                let line_begin = self.buffer.meta_data().get_line_start_index(self.buffer.cursor_row()).unwrap();
-                let begin_selection = absolute_metacursor_position - line_begin;
+                let line_begin = *self.buffer.cursor_abs() - *self.buffer.cursor_col();
                 let end_selection = self.buffer.cursor_col();
-                let slice = self.buffer.get_slice(*line_begin..*self.buffer.cursor_abs());
+                let slice = self.buffer.get_slice(line_begin..*self.buffer.cursor_abs());
                 let begin_x = gltxt::calculate_text_dimensions(&slice[0..*begin_selection], self.edit_font.as_ref()).x();
                 let end_x = gltxt::calculate_text_dimensions(&slice[0..*end_selection], self.edit_font.as_ref()).x();
 
@@ -541,19 +724,17 @@ impl View {
             // means we drag-selected upwards/backwards
             let md = self.buffer.meta_data();
             let first_line = self.buffer.cursor_row();
-            let last_line = md
-                .get_line_number_of_buffer_index(absolute_metacursor_position)
-                .map_or(Line(md.line_count()).offset(-1), |l| Line(l));
+            let (last_line, end_selection) = md
+                .position_of(absolute_metacursor_position)
+                .unwrap_or_else(|| (md.last_line(), Column(*absolute_metacursor_position - *md.last_line_start_index())));
 
             if first_line == last_line {
                 let rows_down_in_view: i32 = *first_line as i32 - self.topmost_line_in_buffer;
-                let line_begin = self.buffer.meta_data().get_line_start_index(self.buffer.cursor_row()).unwrap();
-                // let begin_selection = marker - line_begin;
-                let begin_selection = Index(*self.buffer.cursor_col());
-                let end_selection = *absolute_metacursor_position - *line_begin;
-                let slice = self.buffer.get_slice(*line_begin..*absolute_metacursor_position);
+                let begin_selection = self.buffer.cursor_col();
+                let line_begin = *absolute_metacursor_position - *end_selection;
+                let slice = self.buffer.get_slice(line_begin..*absolute_metacursor_position);
                 let begin_x = gltxt::calculate_text_dimensions(&slice[0..*begin_selection], self.edit_font.as_ref()).x();
-                let end_x = gltxt::calculate_text_dimensions(&slice[0..end_selection], self.edit_font.as_ref()).x();
+                let end_x = gltxt::calculate_text_dimensions(&slice[0..*end_selection], self.edit_font.as_ref()).x();
 
                 let min = Vec2i::new(top_x + begin_x, top_y - (rows_down_in_view + 1) * self.get_text_font().row_height());
                 let max =
@@ -575,27 +756,298 @@ impl View {
         }
     }
 
-    fn render_normal_cursor(&mut self) {
-        // Rendering the "normal" cursor stuff, i.e. the block cursor, and the line highlighter
-        let rows_down: i32 = *self.buffer.cursor_row() as i32 - self.topmost_line_in_buffer;
-        let cols_in = *self.buffer.cursor_col() as i32;
+    /// Highlights trailing whitespace, and when rebuild_text is set, pushes the markers along with the text they mark
+    fn render_whitespace_markers(&mut self, rebuild_text: bool) {
+        let marker_color = RGBColor { r: 0.35, g: 0.35, b: 0.4 };
+        let trailing_color = RGBAColor { r: 0.9, g: 0.3, b: 0.3, a: 0.35 };
+        let Vec2i { x: top_x, y: top_y } = self.view_frame.anchor;
+        let top_x = top_x + self.text_margin_left;
+        let font = self.edit_font.clone();
+        let row_height = font.row_height();
+        let visible_lines = self.visible_lines();
+        let md = self.buffer.meta_data();
+        for (rows_down, line) in visible_lines.enumerate() {
+            let (begin, length) = md.get_line_info(Line(line)).unwrap();
+            let line_contents = self.buffer.get_slice(*begin..*begin + *length);
+            let x_of = |col: usize| top_x + gltxt::calculate_text_dimensions(&line_contents[..col], font.as_ref()).width;
+            let y = top_y - rows_down as i32 * row_height;
+            for run in whitespace_runs(line_contents) {
+                if run.kind == WhitespaceKind::Trailing {
+                    let rect = BoundingBox::new(Vec2i::new(x_of(run.begin), y - row_height), Vec2i::new(x_of(run.begin + run.len), y));
+                    self.cursor_renderer.add_rect(rect.translate(Vec2i::new(0, -3)), trailing_color);
+                }
+                if rebuild_text {
+                    for col in run.begin..run.begin + run.len {
+                        let marker = match line_contents[col] {
+                            '\t' => '\u{2192}',
+                            '\n' => '\u{ac}',
+                            _ => '\u{b7}',
+                        };
+                        self.text_renderer
+                            .push_draw_command(std::iter::once(marker), marker_color, x_of(col), y, font.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    fn render_indent_guides(&mut self) {
+        let guide_color = RGBAColor { r: 0.5, g: 0.5, b: 0.55, a: 0.25 };
+        let Vec2i { x: top_x, y: top_y } = self.view_frame.anchor;
+        let top_x = top_x + self.text_margin_left;
+        let font = self.edit_font.clone();
+        let row_height = font.row_height();
+        let space_advance = font.get_glyph(' ').map_or(0, |g| g.advance);
+        let visible_lines = self.visible_lines();
+        let md = self.buffer.meta_data();
+        let lines: Vec<&[char]> = visible_lines
+            .map(|line| {
+                let (begin, length) = md.get_line_info(Line(line)).unwrap();
+                self.buffer.get_slice(*begin..*begin + *length)
+            })
+            .collect();
+        for (rows_down, columns) in indent_guide_columns(&lines, self.indent_policy.tab_width).into_iter().enumerate() {
+            let y = top_y - rows_down as i32 * row_height;
+            for col in columns {
+                let x = top_x + col as i32 * space_advance;
+                let rect = BoundingBox::new(Vec2i::new(x, y - row_height), Vec2i::new(x + 1, y));
+                self.cursor_renderer.add_rect(rect.translate(Vec2i::new(0, -3)), guide_color);
+            }
+        }
+    }
+
+    fn render_change_markers(&mut self) {
+        let Vec2i { x: left_x, y: top_y } = self.view_frame.anchor;
+        let row_height = self.edit_font.row_height();
+        let visible_lines = self.visible_lines();
+        let first_line = visible_lines.start;
+        for (line, change) in self.buffer.line_changes_since_save() {
+            if !visible_lines.contains(&line) {
+                continue;
+            }
+            let color = match change {
+                LineChange::Added => RGBAColor { r: 0.3, g: 0.8, b: 0.3, a: 0.8 },
+                LineChange::Modified => RGBAColor { r: 0.9, g: 0.8, b: 0.2, a: 0.8 },
+            };
+            let y = top_y - (line - first_line) as i32 * row_height;
+            let rect = BoundingBox::new(Vec2i::new(left_x + 1, y - row_height), Vec2i::new(left_x + 3, y));
+            self.cursor_renderer.add_rect(rect.translate(Vec2i::new(0, -3)), color);
+        }
+    }
+
+    fn render_rulers(&mut self) {
+        let ruler_color = RGBAColor { r: 0.6, g: 0.6, b: 0.65, a: 0.2 };
+        let Vec2i { x: left_x, y: top_y } = self.view_frame.anchor;
+        let Size { width, height } = self.view_frame.size;
+        let space_advance = self.edit_font.get_glyph(' ').map_or(0, |g| g.advance);
+        for &column in self.options.rulers.iter() {
+            let x = ruler_x(left_x + self.text_margin_left, column, space_advance);
+            if x < left_x + width {
+                let rect = BoundingBox::new(Vec2i::new(x, top_y - height), Vec2i::new(x + 1, top_y));
+                self.cursor_renderer.add_rect(rect, ruler_color);
+            }
+        }
+    }
+
+    fn render_flash(&mut self, begin: Index, end: Index, alpha: f32) {
+        self.render_highlight(begin, end, RGBAColor { r: 1.0, g: 0.85, b: 0.3, a: alpha });
+    }
+
+    /// Looks for the other occurrences in view of the word at the cursor, once the cursor has rested for WORD_MATCHES_DELAY.
+    /// Returns whether what's to be highlighted changed
+    fn update_word_matches(&mut self, time: f64) -> bool {
+        let (cursor, revision) = (self.buffer.cursor_abs(), self.buffer.meta_data().revision());
+        match &self.word_matches.rest {
+            Some((at, at_revision, in_view, since)) if *at == cursor && *at_revision == revision && *in_view == self.buffer_in_view => {
+                let since = *since;
+                if self.word_matches.found.is_some() || time - since < WORD_MATCHES_DELAY {
+                    return false;
+                }
+                let found = match self.buffer.word_at_cursor() {
+                    Some((word, begin, _)) if self.options.highlight_word_matches && self.search_markers.is_none() => {
+                        let in_view = self.buffer_in_view.clone();
+                        let matches: Vec<Index> = self
+                            .buffer
+                            .whole_word_matches(&word, in_view)
+                            .into_iter()
+                            .filter(|&idx| idx != begin)
+                            .collect();
+                        (matches, word.chars().count())
+                    }
+                    _ => (vec![], 0),
+                };
+                let changed = !found.0.is_empty();
+                self.word_matches.found = Some(found);
+                changed
+            }
+            _ => {
+                self.word_matches.rest = Some((cursor, revision, self.buffer_in_view.clone(), time));
+                self.word_matches.found.take().map_or(false, |(matches, _)| !matches.is_empty())
+            }
+        }
+    }
+
+    fn render_word_matches(&mut self) {
+        let matches = match &self.word_matches.found {
+            Some((matches, length)) if !matches.is_empty() => matches.iter().map(|&idx| (idx, idx.offset(*length as _))).collect::<Vec<_>>(),
+            _ => return,
+        };
+        let color = RGBAColor { r: 0.6, g: 0.7, b: 1.0, a: 0.18 };
+        for (begin, end) in matches {
+            self.render_highlight(begin, end, color);
+        }
+    }
+
+    /// Highlights the bracket at, or right before, the cursor along with the one it matches. A bracket without a match is
+    /// highlighted on its own, in a color that tells it apart
+    fn render_bracket_match(&mut self) {
+        if let Some(pair) = bracket_pair_at(&self.buffer.data, *self.buffer.cursor_abs()) {
+            match pair.partner {
+                Some(partner) => {
+                    let color = RGBAColor { r: 0.4, g: 0.9, b: 0.5, a: 0.35 };
+                    self.render_highlight(Index(pair.bracket), Index(pair.bracket + 1), color);
+                    if self.is_index_visible(Index(partner)) {
+                        self.render_highlight(Index(partner), Index(partner + 1), color);
+                    }
+                }
+                None => {
+                    let color = RGBAColor { r: 1.0, g: 0.3, b: 0.3, a: 0.45 };
+                    self.render_highlight(Index(pair.bracket), Index(pair.bracket + 1), color);
+                }
+            }
+        }
+    }
+
+    /// Draws color over the text of begin..end, on the lines of it that are in view
+    fn render_highlight(&mut self, begin: Index, end: Index, color: RGBAColor) {
+        let visible_lines = self.visible_lines();
+        let Vec2i { x: top_x, y: top_y } = self.view_frame.anchor;
+        let top_x = top_x + self.text_margin_left;
+        let font = self.edit_font.clone();
+        let row_height = font.row_height();
+        let md = self.buffer.meta_data();
+        let first_visible = visible_lines.start;
+        let first_line = md.position_of(begin).map_or(*md.last_line(), |(line, _)| *line);
+        let last_line = md.position_of(end).map_or(*md.last_line(), |(line, _)| *line);
+        let mut rects = vec![];
+        for line in std::cmp::max(first_line, first_visible)..std::cmp::min(last_line + 1, visible_lines.end) {
+            if let Some((line_begin, length)) = md.get_line_info(Line(line)) {
+                let slice = self.buffer.get_slice(*line_begin..*line_begin + *length);
+                let from = std::cmp::max(*begin, *line_begin) - *line_begin;
+                let to = std::cmp::max(std::cmp::min(*end, *line_begin + *length) - *line_begin, from);
+                let begin_x = gltxt::calculate_text_dimensions(&slice[..from], &font).x();
+                // an empty range still gets highlighted, one glyph wide
+                let end_x = if to > from {
+                    gltxt::calculate_text_dimensions(&slice[..to], &font).x()
+                } else {
+                    begin_x + font.get_max_glyph_width()
+                };
+                let y = top_y - (line - first_visible) as i32 * row_height;
+                rects.push(BoundingBox::new(Vec2i::new(top_x + begin_x, y - row_height), Vec2i::new(top_x + end_x, y)));
+            }
+        }
+        for rect in rects {
+            self.cursor_renderer.add_rect(rect.translate(Vec2i::new(0, -3)), color);
+        }
+    }
+
+    /// Briefly highlights the range begin..end of the buffer, fading it out over duration_ms
+    pub fn flash_range(&mut self, begin: Index, end: Index, duration_ms: u64) {
+        self.flash = Some(Flash {
+            begin: std::cmp::min(begin, end),
+            end: std::cmp::max(begin, end),
+            started: self.frame_time,
+            duration: duration_ms as f64 / 1000.0,
+        });
+        self.view_changed = true;
+    }
+
+    /// Briefly highlights the line the cursor is on
+    pub fn flash_cursor_line(&mut self) {
+        let md = self.buffer.meta_data();
+        if let Some((begin, length)) = md.get_line_info(self.buffer.cursor_row()) {
+            self.flash_range(begin, Index(*begin + *length), FLASH_DURATION_MS);
+        }
+    }
+
+    /// Marks where term occurs along the scroll bar. None, or an empty term, removes the marks
+    pub fn set_search_markers(&mut self, term: Option<&str>) {
+        let term = term.filter(|term| !term.is_empty());
+        if self.search_markers.as_ref().map(|markers| markers.term.as_str()) != term {
+            self.search_markers = term.map(|term| SearchMarkers { term: term.to_string(), revision: None, lines: vec![] });
+            self.set_need_redraw();
+        }
+    }
 
-        let nl_buf_idx = *self.buffer.meta_data().get_line_start_index(self.buffer.cursor_row()).unwrap();
-        let line_contents = self.buffer.get_slice(nl_buf_idx..(nl_buf_idx + cols_in as usize));
+    /// Draws a tick on the scroll bar track for every line with a match of the search, finding them again if the buffer has changed
+    fn render_search_markers(&mut self) {
+        let buffer = &self.buffer;
+        let markers = match self.search_markers.as_mut() {
+            Some(markers) => markers,
+            None => return,
+        };
+        let revision = buffer.meta_data().revision();
+        if markers.revision != Some(revision) {
+            let case_sensitive = smart_case_sensitive(&markers.term, buffer.smart_case);
+            let md = buffer.meta_data();
+            markers.lines = buffer
+                .find_all(&markers.term, case_sensitive)
+                .into_iter()
+                .filter_map(|idx| md.get_line_number_of_buffer_index(idx))
+                .collect();
+            markers.lines.dedup();
+            markers.revision = Some(revision);
+        }
+        let track = self.scroll_bar.frame.to_bb();
+        let color = RGBAColor { r: 1.0, g: 0.75, b: 0.0, a: 0.9 };
+        let mut last_y = None;
+        self.window_renderer.reserve_for(markers.lines.len());
+        for &line in markers.lines.iter() {
+            let y = self.scroll_bar.marker_y(line);
+            // lines close together end up on the same pixel, only one tick is needed for them
+            if last_y != Some(y) {
+                let tick = BoundingBox::new(Vec2i::new(track.min.x + 2, y - ScrollBar::MARKER_HEIGHT), Vec2i::new(track.max.x - 2, y));
+                self.window_renderer.push_draw_command(tick, color, PolygonType::Undecorated);
+                last_y = Some(y);
+            }
+        }
+    }
 
-        let min_x = gltxt::calculate_text_dimensions(line_contents, self.edit_font.as_ref()).x();
-        let min = Vec2i::new(min_x, 0 - (rows_down + 1) * self.get_text_font().row_height());
-        let max = Vec2i::new(min_x + self.get_text_font().get_max_glyph_width() - 2, 0 - (rows_down * self.get_text_font().row_height()));
+    /// Briefly highlights len characters from the cursor, like a search result
+    pub fn flash_at_cursor(&mut self, len: usize) {
+        let cursor = self.buffer.cursor_abs();
+        self.flash_range(cursor, Index(*cursor + len), FLASH_DURATION_MS);
+    }
 
-        let cursor_bound_box = BoundingBox::new(min, max)
-            .translate(Vec2i::new(self.text_margin_left, -3))
-            .translate(self.view_frame.anchor);
+    /// The rectangle, on screen, of the glyph at idx. None if idx is not on a line in view
+    pub fn index_to_screen_rect(&self, idx: Index) -> Option<BoundingBox> {
+        let font = self.get_text_font();
+        glyph_rect(&self.buffer, self.visible_lines(), idx, font.row_height(), font.get_max_glyph_width(), |text| {
+            gltxt::calculate_text_dimensions(text, &font).x()
+        })
+        .map(|rect| {
+            rect.translate(Vec2i::new(self.text_margin_left, -3))
+                .translate(self.view_frame.anchor)
+        })
+    }
+
+    fn render_normal_cursor(&mut self) {
+        // Rendering the "normal" cursor stuff, i.e. the block cursor, and the line highlighter. Both are left out when the cursor is
+        // scrolled out of view; the line highlight goes with the window, which isn't clipped
+        let mut cursor_bound_box = match self.index_to_screen_rect(self.buffer.cursor_abs()) {
+            Some(rect) => rect,
+            None => return,
+        };
+        cursor_bound_box.max.x = cursor_bound_box.min.x + self.get_text_font().get_max_glyph_width() - 2;
         let mut line_bounding_box = cursor_bound_box.clone();
         line_bounding_box.min.x = self.view_frame.anchor.x + 2;
-        line_bounding_box.max.x = self.view_frame.anchor.x + 2 + self.view_frame.width();
+        line_bounding_box.max.x = self.view_frame.anchor.x + self.view_frame.width() - 2;
 
-        self.cursor_renderer
-            .add_rect(line_bounding_box, RGBAColor { r: 0.75, g: 0.75, b: 0.75, a: 0.2 });
+        // the line highlight goes with the window, which is drawn before, and thus under, the text
+        if let Some(color) = current_line_highlight(self.focused, self.options.highlight_current_line) {
+            self.window_renderer
+                .push_draw_command(line_bounding_box, color, PolygonType::Undecorated);
+        }
         self.cursor_renderer
             .add_rect(cursor_bound_box, RGBAColor { r: 0.95, g: 0.75, b: 0.75, a: 0.5 });
     }
@@ -608,15 +1060,13 @@ impl View {
         debug_assert!(begin < end);
 
         let md = self.buffer.meta_data();
-        let first_line = md.get_line_number_of_buffer_index(begin).map_or(Line(0), |l| Line(l));
-        let last_line = md
-            .get_line_number_of_buffer_index(end)
-            .map_or(Line(md.line_count()).offset(-1), |l| Line(l));
+        let (first_line, first_selected_col_position) = md.position_of(begin).map_or((Line(0), 0), |(l, c)| (l, *c));
+        let (last_line, last_selected_col_position) = md
+            .position_of(end)
+            .map_or((md.last_line(), *end - *md.last_line_start_index()), |(l, c)| (l, *c));
         let mut render_infos = Vec::with_capacity(*last_line - *first_line);
         let mut lines_contents = self.buffer.get_lines_as_slices(first_line, last_line);
         let mut rows_down_in_view: i32 = 0;
-        let first_selected_col_position = *begin - *md.get_line_start_index(first_line).unwrap();
-        let last_selected_col_position = *end - *md.get_line_start_index(last_line).unwrap();
 
         let cursor_start_x = gltxt::calculate_text_dimensions(&lines_contents[0][0..first_selected_col_position], self.edit_font.as_ref()).x();
         let remaining_line_width = gltxt::calculate_text_dimensions(&lines_contents[0][first_selected_col_position..], self.edit_font.as_ref()).x();
@@ -654,8 +1104,8 @@ impl View {
 
     pub fn draw_title(&mut self, title: &str) {
         let Vec2i { x: tx, y: ty } = self.title_frame.anchor;
-        self.text_renderer
-            .push_draw_command(title.chars().map(|c| c), RGBColor::white(), tx + 3, ty, self.get_title_font());
+        self.title_renderer
+            .push_str(title, RGBColor::white(), tx + 3, ty, self.get_title_font());
     }
 
     pub fn load_file(&mut self, path: &Path) {
@@ -667,8 +1117,37 @@ impl View {
         self.scroll_bar.max = self.buffer.meta_data().line_count();
     }
 
+    /// Where the cursor and the top of the view are, to be restored when the file is opened again
+    pub fn file_position(&self) -> FilePosition {
+        FilePosition { cursor: *self.buffer.cursor_abs(), top_line: std::cmp::max(self.topmost_line_in_buffer, 0) as usize }
+    }
+
+    pub fn restore_file_position(&mut self, position: FilePosition) {
+        let position = position.clamped(self.buffer.len(), self.buffer.meta_data().line_count());
+        self.buffer.cursor_goto(Index(position.cursor));
+        self.topmost_line_in_buffer = position.top_line as i32;
+        self.set_view_on_buffer_cursor();
+    }
+
     pub fn insert_ch(&mut self, ch: char) {
-        if input_not_valid(ch) {
+        if input_not_valid(ch) || self.buffer.read_only() {
+            return;
+        }
+
+        if let Some(close) = wrapping_pair_of(ch) {
+            if self.buffer.wrap_selection(ch, close) {
+                self.set_view_on_buffer_cursor();
+                return;
+            }
+        }
+
+        if self.hold_back_delete(HeldDelete::Replace(ch)) {
+            return;
+        }
+
+        if self.buffer.insert_indented(ch, self.indent_policy) {
+            self.set_view_on_buffer_cursor();
+            self.scroll_bar.max = self.buffer.meta_data().line_count();
             return;
         }
 
@@ -690,28 +1169,16 @@ impl View {
         if self.buffer.cursor_row() >= Line((self.topmost_line_in_buffer + self.rows_displayable()) as _) {
             let diff = std::cmp::max((*self.buffer.cursor_row() as i32) - (self.topmost_line_in_buffer + self.rows_displayable()) as i32, 1);
             self.topmost_line_in_buffer += diff;
-            if let (Some(a), end) =
-                md.get_byte_indices_of_lines(Line(self.topmost_line_in_buffer as _), Line((self.topmost_line_in_buffer + self.rows_displayable()) as _))
-            {
-                self.buffer_in_view = *a..*end.unwrap_or(Index(self.buffer.len()));
-            }
+            self.buffer_in_view = buffer_range_in_view(md, self.topmost_line_in_buffer, self.rows_displayable());
 
             self.view_changed = true;
         } else if self.buffer.cursor_row() < Line(self.topmost_line_in_buffer as _) {
             self.topmost_line_in_buffer = *self.buffer.cursor_row() as _;
-            if let (Some(a), end) =
-                md.get_byte_indices_of_lines(Line(self.topmost_line_in_buffer as _), Line((self.topmost_line_in_buffer + self.rows_displayable()) as _))
-            {
-                self.buffer_in_view = *a..*end.unwrap_or(Index(self.buffer.len()));
-            }
+            self.buffer_in_view = buffer_range_in_view(md, self.topmost_line_in_buffer, self.rows_displayable());
         } else {
-            if let (Some(a), end) =
-                md.get_byte_indices_of_lines(Line(self.topmost_line_in_buffer as _), Line((self.topmost_line_in_buffer + self.rows_displayable()) as _))
-            {
-                self.buffer_in_view = *a..*end.unwrap_or(Index(self.buffer.len()));
-            }
+            self.buffer_in_view = buffer_range_in_view(md, self.topmost_line_in_buffer, self.rows_displayable());
         }
-        self.scroll_bar.scroll_value = *self.buffer.cursor_row();
+        self.scroll_bar.scroll_value = std::cmp::max(self.topmost_line_in_buffer, 0) as usize;
         self.scroll_bar.update_ui_position_by_value();
         self.view_changed = true;
     }
@@ -731,10 +1198,258 @@ impl View {
         self.set_view_on_buffer_cursor();
     }
 
+    /// Inserts the contents of the file at path at the cursor, see ContiguousBuffer::insert_file
+    pub fn insert_file(&mut self, path: &Path) -> std::io::Result<usize> {
+        let inserted = self.buffer.insert_file(path)?;
+        self.text_renderer.pristine = false;
+        self.set_view_on_buffer_cursor();
+        self.set_need_redraw();
+        Ok(inserted)
+    }
+
+    /// Replaces the text with what formatter (a shell command line) makes of it. If formatter fails, the text is left as it was, and
+    /// what the formatter had to say is returned
+    pub fn format_document(&mut self, formatter: &str) -> Result<(), String> {
+        if format_buffer(&mut self.buffer, formatter)? {
+            self.text_renderer.pristine = false;
+            self.scroll_bar.max = self.buffer.meta_data().line_count();
+            self.set_view_on_buffer_cursor();
+            self.set_need_redraw();
+        }
+        Ok(())
+    }
+
+    /// Inserts an expanded snippet at the cursor, placing the cursor where the snippet's cursor placeholder was, if it had one
+    pub fn insert_snippet(&mut self, expansion: &str) {
+        if self.buffer.read_only() {
+            return;
+        }
+        let (text, cursor_offset) = resolve_cursor_placeholder(expansion);
+        self.insert_str(&text);
+        if let Some(offset) = cursor_offset {
+            let inserted_at = *self.buffer.cursor_abs() - text.chars().count();
+            self.buffer.cursor_goto(Index(inserted_at + offset));
+            self.set_view_on_buffer_cursor();
+        }
+        self.scroll_bar.max = self.buffer.meta_data().line_count();
+    }
+
+    /// Appends the output of a shell command, to the end of this view's buffer. A view following the tail scrolls along with
+    /// the output, any other view stays where it is
+    pub fn append_shell_output(&mut self, output: &[ShellOutput]) {
+        if output.is_empty() {
+            return;
+        }
+        let cursor = self.buffer.cursor_abs();
+        let line_count_before = self.buffer.meta_data().line_count();
+        let following = self.follow_tail && *cursor == self.buffer.len();
+        append_output(&mut self.buffer, output);
+        self.text_renderer.pristine = false;
+        if !following {
+            self.buffer.cursor_goto(cursor);
+        }
+        self.topmost_line_in_buffer =
+            top_line_after_append(self.topmost_line_in_buffer, self.rows_displayable(), line_count_before, self.buffer.meta_data().line_count(), following);
+        self.buffer_in_view = buffer_range_in_view(self.buffer.meta_data(), self.topmost_line_in_buffer, self.rows_displayable());
+        self.scroll_bar.max = self.buffer.meta_data().line_count();
+        self.scroll_bar.scroll_value = std::cmp::max(self.topmost_line_in_buffer, 0) as usize;
+        self.scroll_bar.update_ui_position_by_value();
+        self.view_changed = true;
+    }
+
+    /// Pastes a clip board entry, as whole lines, a block or at the cursor, depending on how it was copied
+    pub fn paste(&mut self, entry: &ClipEntry) {
+        if self.buffer.read_only() {
+            return;
+        }
+        entry.paste_into(&mut self.buffer);
+        self.text_renderer.pristine = false;
+        self.validate_range();
+        self.set_view_on_buffer_cursor();
+        self.scroll_bar.max = self.buffer.meta_data().line_count();
+    }
+
+    /// Sorts the selected lines, or all lines if nothing is selected, in ascending order
+    pub fn sort_lines(&mut self) {
+        let lines = self.buffer.selected_lines_or_all();
+        self.buffer.sort_lines(lines, false, false);
+        self.set_view_on_buffer_cursor();
+    }
+
+    /// Removes lines that repeat an earlier line, from the selected lines, or all lines if nothing is selected
+    pub fn remove_duplicate_lines(&mut self) {
+        let lines = self.buffer.selected_lines_or_all();
+        let removed = self.buffer.dedup_lines(lines, false);
+        println!("Removed {} duplicate lines", removed); // todo: UI representation
+        self.scroll_bar.max = self.buffer.meta_data().line_count();
+        self.set_view_on_buffer_cursor();
+    }
+
+    /// Jumps to the next (or previous) region of lines, edited since the file was loaded
+    pub fn goto_modified_region(&mut self, forward: bool) {
+        if self.buffer.goto_modified_region(forward) {
+            self.set_view_on_buffer_cursor();
+        } else {
+            println!("No {} change in buffer", if forward { "next" } else { "previous" });
+            // todo: UI representation
+        }
+    }
+
+    /// Jumps to where the word at the cursor is defined in this view. If it's defined in more than one place, the cursor stays put,
+    /// and the definitions are returned for the user to choose from
+    pub fn goto_definition_of_word(&mut self) -> Vec<Symbol> {
+        let definitions = self.buffer.goto_definition_of_word();
+        match definitions.len() {
+            0 => println!("Found no definition of the word at the cursor"), // todo: UI representation
+            1 => {
+                self.set_view_on_buffer_cursor();
+                self.flash_cursor_line();
+            }
+            _ => {}
+        }
+        definitions
+    }
+
+    /// Shrinks the selection to leave out whitespace at its beginning and end
+    pub fn trim_selection(&mut self) {
+        if self.buffer.trim_selection() {
+            self.set_view_on_buffer_cursor();
+        }
+    }
+
+    /// Names the cursor position name, to jump back to with goto_mark
+    pub fn set_mark(&mut self, name: char) {
+        self.buffer.set_mark(name);
+    }
+
+    /// Moves the cursor to mark name, wherever edits have moved it since it was set. Returns false if there is no such mark
+    pub fn goto_mark(&mut self, name: char) -> bool {
+        match self.buffer.mark(name) {
+            Some(cursor) => {
+                self.buffer.cursor_goto_deselect(cursor.pos);
+                self.set_view_on_buffer_cursor();
+                self.flash_cursor_line();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Rewraps the paragraph the cursor is in, to width columns
+    pub fn reflow_paragraph(&mut self, width: usize) {
+        if self.buffer.reflow_paragraph(self.buffer.cursor_row(), width) {
+            self.scroll_bar.max = self.buffer.meta_data().line_count();
+            self.set_view_on_buffer_cursor();
+        }
+    }
+
+    /// Changes the case of the selection, or of the word at the cursor
+    pub fn transform_case(&mut self, mode: CaseMode) {
+        if self.buffer.transform_selection_case(mode) {
+            self.set_view_on_buffer_cursor();
+        }
+    }
+
+    /// Duplicates the current selection, or the current line if nothing is selected
+    pub fn duplicate_selection(&mut self) {
+        self.buffer.duplicate_selection_or_line();
+        self.scroll_bar.max = self.buffer.meta_data().line_count();
+        self.set_view_on_buffer_cursor();
+    }
+
+    /// Grows the selection (or the cursor) to the word, the line, the enclosing block and so on, one step at a time
+    pub fn expand_selection(&mut self) {
+        if self.buffer.expand_selection() {
+            self.set_view_on_buffer_cursor();
+        }
+    }
+
+    /// Selects what's inside the {...} block the cursor is in, without the braces
+    pub fn select_inside_block(&mut self) {
+        if self.buffer.select_block(true) {
+            self.set_view_on_buffer_cursor();
+        }
+    }
+
+    /// Selects the {...} block the cursor is in, braces and all
+    pub fn select_around_block(&mut self) {
+        if self.buffer.select_block(false) {
+            self.set_view_on_buffer_cursor();
+        }
+    }
+
+    /// Undoes the last expand_selection step
+    pub fn shrink_selection(&mut self) {
+        if self.buffer.shrink_selection() {
+            self.set_view_on_buffer_cursor();
+        }
+    }
+
+    /// Flips the read only state of the buffer in this view. A read only buffer ignores all edits, which is useful
+    /// when keeping a file open for reference without wanting to accidentally change it
+    pub fn toggle_read_only(&mut self) {
+        let read_only = !self.buffer.read_only();
+        self.buffer.set_read_only(read_only);
+        self.update(None);
+    }
+
+    /// Turns a display option of this view on or off
+    pub fn toggle_option(&mut self, option: ViewOption) {
+        self.options.toggle(option);
+        // looked for again, with or without the option
+        self.word_matches = WordMatches::default();
+        self.set_need_redraw();
+    }
+
+    pub fn toggle_pinned(&mut self) {
+        self.pinned = !self.pinned;
+        self.update(None);
+    }
+
+    /// Tints the background of this view with color, whether it's the active view or not, to tell it apart from the others
+    pub fn set_bg_color(&mut self, color: RGBAColor) {
+        self.bg_override = Some(color);
+        self.decorate(color, self.focused);
+    }
+
+    /// Goes back to the background of active and inactive views. Takes effect the next time the view is decorated
+    pub fn clear_bg_color(&mut self) {
+        self.bg_override = None;
+    }
+
+    /// Colors the background with decoration, the color of the active or of inactive views, unless the view has a color of its own.
+    /// focused is whether the view is the active one
+    pub fn decorate(&mut self, decoration: RGBAColor, focused: bool) {
+        self.focused = focused;
+        let color = background_color(self.bg_override, decoration);
+        self.bg_color = color;
+        self.window_renderer.set_color(color);
+        self.update(None);
+    }
+
+    /// Moves the cursor to pos, dropping the selection, if there is one
     pub fn cursor_goto(&mut self, pos: Index) {
-        self.buffer.cursor_goto(pos);
+        self.buffer.cursor_goto_deselect(pos);
         self.set_view_on_buffer_cursor();
     }
+
+    /// Moves the cursor to pos, selecting from where it was, or extending the selection
+    pub fn select_to(&mut self, pos: Index) {
+        self.buffer.select_to(pos);
+        self.set_view_on_buffer_cursor();
+    }
+    /// Moves the cursor to the beginning of the line, or with smart home, toggles between the first non-blank character of the
+    /// line and the beginning of it
+    pub fn home(&mut self) {
+        let toggled = self.home_toggle.take() == Some(self.buffer.cursor_abs());
+        if !self.smart_home || toggled || !self.buffer.cursor_to_first_non_blank() {
+            self.move_cursor(Movement::Begin(TextKind::Line));
+        } else {
+            self.home_toggle = Some(self.buffer.cursor_abs());
+            self.set_view_on_buffer_cursor();
+        }
+    }
+
     pub fn move_cursor(&mut self, dir: Movement) {
         let translated = dir.transform_page_param(self.rows_displayable() as _);
         self.buffer.move_cursor(translated);
@@ -742,12 +1457,49 @@ impl View {
     }
 
     pub fn delete(&mut self, dir: Movement) {
+        if self.hold_back_delete(HeldDelete::Delete(dir)) {
+            return;
+        }
         self.buffer.delete(dir);
         self.view_changed = true;
         self.validate_range();
         self.set_view_on_buffer_cursor();
     }
 
+    /// Holds back delete, if the selection it would delete is larger than the delete guard allows, until settle_held_delete is
+    /// called. Returns whether it was held back
+    fn hold_back_delete(&mut self, delete: HeldDelete) -> bool {
+        if self.buffer.read_only() {
+            return false;
+        }
+        match self.delete_guard.held_back(&self.buffer) {
+            Some(size) => {
+                self.held_delete = Some((delete, size));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The size, in characters and lines, of the delete waiting to be confirmed, if there is one
+    pub fn held_delete_size(&self) -> Option<(usize, usize)> {
+        self.held_delete.map(|(_, size)| size)
+    }
+
+    /// Makes the delete that was held back, if confirmed, or forgets about it
+    pub fn settle_held_delete(&mut self, confirmed: bool) {
+        if let Some((delete, _)) = self.held_delete.take() {
+            if confirmed {
+                let guard = std::mem::take(&mut self.delete_guard);
+                match delete {
+                    HeldDelete::Delete(dir) => self.delete(dir),
+                    HeldDelete::Replace(ch) => self.insert_ch(ch),
+                }
+                self.delete_guard = guard;
+            }
+        }
+    }
+
     pub fn backspace_handle(&mut self, kind: TextKind) {
         match kind {
             TextKind::Char => self.buffer.delete(Movement::Backward(TextKind::Char, 1)),
@@ -773,6 +1525,11 @@ impl View {
         self.id
     }
 
+    /// The text currently visible in this view
+    pub fn get_visible_text(&self) -> String {
+        self.buffer.text_in_range(self.buffer_in_view.clone())
+    }
+
     pub fn get_file_info(&self) -> (Option<&Path>, BufferCursor) {
         self.buffer.buffer_info()
     }
@@ -781,18 +1538,283 @@ impl View {
         self.view_frame.size.height / self.get_text_font().row_height()
     }
 
+    /// The lines of the buffer that are in view. Empty if the view has been scrolled past the end of the buffer
+    fn visible_lines(&self) -> std::ops::Range<usize> {
+        visible_lines(self.topmost_line_in_buffer, self.rows_displayable(), self.buffer.meta_data().line_count())
+    }
+
+    /// The line at the top of the view
+    pub fn first_visible_line(&self) -> Line {
+        Line(self.visible_lines().start)
+    }
+
+    /// The line furthest down in view, that the buffer has. The first visible line, if there are none
+    pub fn last_visible_line(&self) -> Line {
+        let lines = self.visible_lines();
+        Line(std::cmp::max(lines.end, lines.start + 1) - 1)
+    }
+
+    /// Whether idx is on screen. The view doesn't scroll sideways, so every column of the lines in view is on screen
+    pub fn is_index_visible(&self, idx: Index) -> bool {
+        index_in_view(&self.buffer_in_view, idx, self.buffer.len())
+    }
+
     pub fn total_boundingbox(&self) -> BoundingBox {
         let title_bb = BoundingBox::from_frame(&self.title_frame);
         let view_bb = BoundingBox::from_frame(&self.view_frame);
         BoundingBox::new(Vec2i::new(view_bb.min.x, view_bb.min.y), Vec2i::new(title_bb.max.x, title_bb.max.y))
     }
 
+    /// The parts of the view the mouse cursor changes shape over
+    pub fn regions(&self) -> ViewRegions {
+        ViewRegions {
+            title: BoundingBox::from_frame(&self.title_frame),
+            scroll_bar: self.scroll_bar.frame.to_bb(),
+            text: BoundingBox::from_frame(&self.view_frame),
+        }
+    }
+
     pub fn total_size(&self) -> Size {
-        Size {
-            width: self.title_frame.size.width,
-            height: self.view_frame.size.height + self.title_frame.size.height,
+        self.chrome.outer_size(self.view_frame.size)
+    }
+
+    /// Gives the scroll bar the width of the options, or none, when it's auto hidden and there's nothing to scroll. The text takes
+    /// up the rest of the width of the view
+    fn fit_scroll_bar(&mut self) {
+        let width = ScrollBar::reserved_width(
+            std::cmp::max(self.options.scroll_bar_width, 0),
+            self.options.auto_hide_scroll_bar,
+            self.buffer.meta_data().line_count(),
+            self.rows_displayable() as usize,
+            self.topmost_line_in_buffer > 0,
+        );
+        if width != self.chrome.scroll_bar_width {
+            let (anchor, size) = (self.title_frame.anchor, self.total_size());
+            self.chrome.scroll_bar_width = width;
+            self.lay_out_chrome(anchor, size);
+            self.scroll_bar.ui_update();
+            self.text_renderer.pristine = false;
+            self.view_changed = true;
         }
     }
+
+    /// Places the title bar, the text and the scroll bar, within the space of the view at anchor, of size
+    fn lay_out_chrome(&mut self, anchor: Vec2i, size: Size) {
+        let layout = self.chrome.layout(anchor, size);
+        self.title_frame = layout.title;
+        self.view_frame = layout.content;
+        self.scroll_bar.frame = layout.scroll_bar;
+    }
+}
+
+/// Returns the closing character, for characters that wrap a selection when typed, instead of replacing it
+fn wrapping_pair_of(ch: char) -> Option<char> {
+    match ch {
+        '(' => Some(')'),
+        '[' => Some(']'),
+        '{' => Some('}'),
+        '"' => Some('"'),
+        '\'' => Some('\''),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WhitespaceKind {
+    Space,
+    Tab,
+    Newline,
+    /// Spaces and/or tabs at the end of a line
+    Trailing,
+}
+
+/// A run of whitespace on a line, that shows up when rendering whitespace
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WhitespaceRun {
+    pub begin: usize,
+    pub len: usize,
+    pub kind: WhitespaceKind,
+}
+
+/// Finds the runs of whitespace in line, which is expected to hold at most one newline, at its end
+pub fn whitespace_runs(line: &[char]) -> Vec<WhitespaceRun> {
+    let content_len = if line.last() == Some(&'\n') { line.len() - 1 } else { line.len() };
+    let trailing_begin = line[..content_len]
+        .iter()
+        .rposition(|c| *c != ' ' && *c != '\t')
+        .map_or(0, |last_visible| last_visible + 1);
+    let mut runs: Vec<WhitespaceRun> = vec![];
+    for (col, ch) in line.iter().enumerate() {
+        let kind = match ch {
+            _ if col >= trailing_begin && col < content_len => WhitespaceKind::Trailing,
+            ' ' => WhitespaceKind::Space,
+            '\t' => WhitespaceKind::Tab,
+            '\n' => WhitespaceKind::Newline,
+            _ => continue,
+        };
+        match runs.last_mut() {
+            Some(run) if run.kind == kind && kind != WhitespaceKind::Newline && run.begin + run.len == col => run.len += 1,
+            _ => runs.push(WhitespaceRun { begin: col, len: 1, kind }),
+        }
+    }
+    runs
+}
+
+/// The width of the indentation of line in columns, where a tab counts as tab_width columns. None if the line is blank
+fn indentation_width(line: &[char], tab_width: usize) -> Option<usize> {
+    let mut width = 0;
+    for c in line {
+        match c {
+            ' ' => width += 1,
+            '\t' => width += tab_width,
+            '\n' => return None,
+            _ => return Some(width),
+        }
+    }
+    None
+}
+
+/// The index of the character at a point of a view showing top_line at its top, where the point is given relative to the top
+/// left of the text (x to the right and y downwards), rows are row_height high and advance gives the width of a character
+pub fn index_at_point(buffer: &ContiguousBuffer, top_line: usize, rel_x: i32, rel_y: i32, row_height: i32, advance: impl Fn(char) -> i32) -> Index {
+    let md = buffer.meta_data();
+    let view_line = (rel_y as f64 / row_height as f64).floor() as isize;
+    let line_clicked = Line(top_line).offset(view_line);
+
+    let start_index = md.get_line_start_index(line_clicked).unwrap_or_else(|| md.last_line_start_index());
+
+    let end_index = md.get_line_start_index(line_clicked.offset(1)).unwrap_or(Index(buffer.len()));
+
+    let line_contents = buffer.get_slice(*start_index..*end_index);
+    let mut rel_x = rel_x;
+    line_contents
+        .iter()
+        .enumerate()
+        .find(|(_, ch)| {
+            rel_x -= advance(**ch);
+            rel_x <= 0
+        })
+        .map(|(i, _)| start_index.offset(i as isize))
+        // clicking past the end of a line, puts the cursor on it's new line character. The last line has none, so there we use the end of the buffer
+        .unwrap_or(if line_clicked >= md.last_line() { end_index } else { end_index.offset(-1) })
+}
+
+/// The rectangle of the glyph at idx, relative to the top left of the text of a view showing the lines in view. y goes upwards, as
+/// on screen, so the rows of the view go downwards from 0. width gives how wide a run of text is drawn, tabs and all, and a new line
+/// (or the end of the buffer) is glyph_width wide. None if idx is not on a line in view
+pub fn glyph_rect(
+    buffer: &ContiguousBuffer, in_view: std::ops::Range<usize>, idx: Index, row_height: i32, glyph_width: i32, width: impl Fn(&[char]) -> i32,
+) -> Option<BoundingBox> {
+    if *idx > buffer.len() {
+        return None;
+    }
+    let md = buffer.meta_data();
+    let line = md.position_of(idx).map_or(md.last_line(), |(line, _)| line);
+    if !in_view.contains(&*line) {
+        return None;
+    }
+    let line_begin = *md.get_line_start_index(line)?;
+    let text = buffer.get_slice(line_begin..*idx);
+    let begin_x = width(text);
+    let end_x = match buffer.data.get(*idx) {
+        Some(&c) if c != '\n' => width(buffer.get_slice(line_begin..*idx + 1)),
+        _ => begin_x + glyph_width,
+    };
+    let rows_down = (*line - in_view.start) as i32;
+    Some(BoundingBox::new(Vec2i::new(begin_x, -(rows_down + 1) * row_height), Vec2i::new(end_x, -rows_down * row_height)))
+}
+
+/// The image drawn in the middle of a view, if any. It's only drawn while the buffer is empty, if the options of the view say so,
+/// and if the view has one
+pub fn empty_background(options: &ViewOptions, buffer_empty: bool, image: Option<Texture>) -> Option<Texture> {
+    image.filter(|_| buffer_empty && options.show_empty_background)
+}
+
+/// The lines a view with its top at top_line, rows_displayable rows high, shows of a buffer with line_count lines
+pub fn visible_lines(top_line: i32, rows_displayable: i32, line_count: usize) -> std::ops::Range<usize> {
+    let first = std::cmp::max(top_line, 0) as usize;
+    first..std::cmp::min(first + std::cmp::max(rows_displayable, 0) as usize, line_count)
+}
+
+/// The range of the buffer that a view with its top at top_line, rows_displayable rows high, has in view
+pub fn buffer_range_in_view(md: &MetaData, top_line: i32, rows_displayable: i32) -> std::ops::Range<usize> {
+    let (begin, end) = md.get_byte_indices_of_lines(Line(std::cmp::max(top_line, 0) as _), Line(std::cmp::max(top_line + rows_displayable, 0) as _));
+    *begin..*end
+}
+
+/// Whether idx is in in_view, the range a view has in view of a buffer buffer_len long. The end of the buffer is in view along
+/// with the last line, even though it holds no character
+pub fn index_in_view(in_view: &std::ops::Range<usize>, idx: Index, buffer_len: usize) -> bool {
+    in_view.contains(&*idx) || (*idx == buffer_len && in_view.end == buffer_len)
+}
+
+/// The x coordinate of the ruler at column, for text beginning at text_left and a font where each column is advance wide
+pub fn ruler_x(text_left: i32, column: usize, advance: i32) -> i32 {
+    text_left + column as i32 * advance
+}
+
+/// The columns at which indentation guides are drawn, for each of lines. Guides go every tab_width columns, but never
+/// where (or after) the text of a line begins. Blank lines get the guides of the deeper of the lines around them
+pub fn indent_guide_columns(lines: &[&[char]], tab_width: usize) -> Vec<Vec<usize>> {
+    if tab_width == 0 {
+        return vec![vec![]; lines.len()];
+    }
+    let widths: Vec<Option<usize>> = lines.iter().map(|line| indentation_width(line, tab_width)).collect();
+    (0..lines.len())
+        .map(|i| {
+            let width = widths[i].unwrap_or_else(|| {
+                let before = widths[..i].iter().rev().find_map(|w| *w).unwrap_or(0);
+                let after = widths[i + 1..].iter().find_map(|w| *w).unwrap_or(0);
+                before.max(after)
+            });
+            (0..width).step_by(tab_width).collect()
+        })
+        .collect()
+}
+
+/// The alpha of a highlight that started at initial_alpha, elapsed seconds into its duration (also in seconds). It fades linearly
+/// to nothing, and None means that it has run its course
+pub fn flash_alpha(elapsed: f64, duration: f64, initial_alpha: f32) -> Option<f32> {
+    if elapsed >= duration || duration <= 0.0 {
+        None
+    } else {
+        let remaining = 1.0 - elapsed.max(0.0) / duration;
+        Some(initial_alpha * remaining as f32)
+    }
+}
+
+/// Where the top of a view, rows_displayable rows high, goes when its buffer grows from line_count_before to line_count lines.
+/// A view following the tail, that showed the last line, is scrolled to keep showing it. Any other view stays where it is
+pub fn top_line_after_append(top_line: i32, rows_displayable: i32, line_count_before: usize, line_count: usize, following: bool) -> i32 {
+    let showed_last_line = top_line + rows_displayable >= line_count_before as i32;
+    if following && showed_last_line {
+        std::cmp::max(top_line, line_count as i32 - rows_displayable)
+    } else {
+        top_line
+    }
+}
+
+/// Selects from anchor, where a drag started, to target, where the mouse is now and where the cursor goes. The anchor stays put
+/// however the cursor has moved since the click, for instance by the click itself
+pub fn drag_select(buffer: &mut ContiguousBuffer, anchor: Index, target: Index) {
+    buffer.cursor_goto(target);
+    buffer.meta_cursor = Some(MetaCursor::Absolute(anchor));
+}
+
+/// The color the line of the cursor is highlighted with. Inactive views only highlight it with highlight_current_line set
+pub fn current_line_highlight(focused: bool, highlight_current_line: bool) -> Option<RGBAColor> {
+    if focused {
+        Some(RGBAColor { r: 0.75, g: 0.75, b: 0.75, a: 0.2 })
+    } else if highlight_current_line {
+        Some(RGBAColor { r: 0.75, g: 0.75, b: 0.75, a: 0.08 })
+    } else {
+        None
+    }
+}
+
+/// The background of a view decorated with decoration, which a color set for the view in particular takes precedence over
+pub fn background_color(bg_override: Option<RGBAColor>, decoration: RGBAColor) -> RGBAColor {
+    bg_override.unwrap_or(decoration)
 }
 
 fn input_not_valid(ch: char) -> bool {
@@ -807,26 +1829,16 @@ fn input_not_valid(ch: char) -> bool {
 }
 
 impl Viewable for View {
-    fn resize(&mut self, mut size: Size) {
+    fn resize(&mut self, size: Size) {
         debug_assert!(size.height > 20, "resize size invalid. Must be larger than 20");
-        size.height -= self.get_title_font().row_height() + 5;
-        self.title_frame.size.width = size.width;
-        size.width -= View::SCROLL_BAR_WIDTH;
-        self.view_frame.anchor.y = self.title_frame.anchor.y - self.title_frame.size.height;
-        // self.view_frame.anchor = self.title_frame.anchor + Vec2i::new(0, -self.row_height - 5);
-        self.view_frame.size = size;
-        assert_eq!(self.view_frame.anchor, self.title_frame.anchor + Vec2i::new(0, -self.get_title_font().row_height() - 5));
-        let sb_frame =
-            Frame::new(self.view_frame.anchor + Vec2i::new(self.view_frame.size.width, 0), Size::new(View::SCROLL_BAR_WIDTH, self.view_frame.size.height));
-        self.scroll_bar.frame = sb_frame;
-        self.scroll_bar.ui_update();
+        self.lay_out_chrome(self.title_frame.anchor, size);
         self.scroll_bar.max = self.buffer.meta_data().line_count();
+        self.scroll_bar.visible = self.rows_displayable() as usize;
+        self.scroll_bar.update_ui_position_by_value();
     }
 
     fn set_anchor(&mut self, anchor: Vec2i) {
-        self.title_frame.anchor = anchor;
-        self.view_frame.anchor = self.title_frame.anchor + Vec2i::new(0, -self.title_frame.size.height);
-        self.scroll_bar.frame.anchor = self.view_frame.anchor + Vec2i::new(self.view_frame.width(), 0);
+        self.lay_out_chrome(anchor, self.total_size());
         self.scroll_bar.ui_update();
     }
 
@@ -834,6 +1846,10 @@ impl Viewable for View {
         self.total_boundingbox()
     }
 
+    fn coordinate_to_index(&self, screen_coordinate: Vec2i) -> Option<Index> {
+        self.mouse_to_buffer_position(screen_coordinate)
+    }
+
     fn mouse_clicked(&mut self, validated_inside_pos: Vec2i) {
         debugger_catch!(
             self.bounding_box().box_hit_check(validated_inside_pos),
@@ -847,7 +1863,7 @@ impl Viewable for View {
             let buf_view_begin = *self
                 .buffer
                 .meta_data()
-                .get_line_start_index(Line(self.scroll_bar.scroll_value.clamp(0, md.line_count() - 1)))
+                .get_line_start_index(Line(self.scroll_bar.scroll_value.clamp(0, *md.last_line())))
                 .unwrap();
             let buf_view_end = self
                 .buffer
@@ -867,20 +1883,9 @@ impl Viewable for View {
         }
     }
 
-    fn mouse_dragged(&mut self, begin_coordinate: Vec2i, current_coordinate: Vec2i) {
-        if let Some((begin_coord_idx, target_coord_idx)) = self
-            .mouse_to_buffer_position(begin_coordinate)
-            .zip(self.mouse_to_buffer_position(current_coordinate))
-        {
-            match self.buffer.meta_cursor {
-                Some(MetaCursor::Absolute(..)) => {
-                    self.buffer.cursor_goto(target_coord_idx);
-                }
-                _ => {
-                    self.buffer.cursor_goto(target_coord_idx);
-                    self.buffer.meta_cursor = Some(MetaCursor::Absolute(begin_coord_idx));
-                }
-            }
+    fn mouse_dragged(&mut self, begin_coordinate: Vec2i, current_coordinate: Vec2i, anchor: Option<Index>) {
+        if let Some((anchor, target_coord_idx)) = anchor.zip(self.mouse_to_buffer_position(current_coordinate)) {
+            drag_select(&mut self.buffer, anchor, target_coord_idx);
             self.set_view_on_buffer_cursor();
         } else if self.scroll_bar.frame.to_bb().box_hit_check(begin_coordinate) {
             match self.scroll_bar.layout {
@@ -892,7 +1897,7 @@ impl Viewable for View {
                     let buf_view_begin = *self
                         .buffer
                         .meta_data()
-                        .get_line_start_index(Line(self.scroll_bar.scroll_value.clamp(0, md.line_count() - 1)))
+                        .get_line_start_index(Line(self.scroll_bar.scroll_value.clamp(0, *md.last_line())))
                         .unwrap();
                     let buf_view_end = self
                         .buffer
@@ -910,3 +1915,296 @@ impl Viewable for View {
         }
     }
 }
+
+#[cfg(test)]
+mod view_tests {
+    use super::{
+        background_color, buffer_range_in_view, current_line_highlight, drag_select, empty_background, flash_alpha, glyph_rect, indent_guide_columns,
+        index_at_point, index_in_view, ruler_x, top_line_after_append, visible_lines, whitespace_runs, TextSnapshot, View, ViewId, WhitespaceKind as W,
+        WhitespaceRun,
+    };
+    use crate::datastructure::generic::Vec2i;
+    use crate::opengl::{
+        polygon_renderer::{PolygonRenderer, Texture},
+        rectangle_renderer::RectRenderer,
+        shaders::{RectShader, TextShader},
+        text_renderer::TextRenderer,
+        types::RGBAColor,
+    };
+    use crate::textbuffer::contiguous::contiguous::ContiguousBuffer;
+    use crate::textbuffer::metadata::Index;
+    use crate::textbuffer::{CharBuffer, Movement, TextKind};
+    use crate::ui::font::{Font, SOURCE_CODE_PRO_REGULAR};
+    use crate::ui::view_options::ViewOptions;
+    use std::rc::Rc;
+
+    fn run(begin: usize, len: usize, kind: W) -> WhitespaceRun {
+        WhitespaceRun { begin, len, kind }
+    }
+
+    #[test]
+    fn whitespace_runs_of_line() {
+        let line: Vec<char> = "\t\tlet  a = 1;  \t\n".chars().collect();
+        assert_eq!(
+            whitespace_runs(&line),
+            vec![
+                run(0, 2, W::Tab),
+                run(5, 2, W::Space),
+                run(8, 1, W::Space),
+                run(10, 1, W::Space),
+                run(13, 3, W::Trailing),
+                run(16, 1, W::Newline)
+            ]
+        );
+    }
+
+    #[test]
+    fn whitespace_only_line_is_trailing() {
+        let line: Vec<char> = "    ".chars().collect();
+        assert_eq!(whitespace_runs(&line), vec![run(0, 4, W::Trailing)]);
+        assert_eq!(whitespace_runs(&['\n']), vec![run(0, 1, W::Newline)]);
+        assert_eq!(whitespace_runs(&[]), vec![]);
+    }
+
+    #[test]
+    fn ruler_position() {
+        // a monospace font 9 pixels wide, with text beginning 4 pixels into a view at x = 100
+        assert_eq!(ruler_x(104, 80, 9), 824);
+        assert_eq!(ruler_x(104, 0, 9), 104);
+        assert_eq!(ruler_x(4, 100, 7), 704);
+    }
+
+    #[test]
+    fn indent_guides_of_lines() {
+        let text = [
+            "fn main() {\n",
+            "    if x {\n",
+            "        y();\n",
+            "\n",
+            "\tz();\n",
+            "    }\n",
+            "}",
+        ];
+        let lines: Vec<Vec<char>> = text.iter().map(|l| l.chars().collect()).collect();
+        let lines: Vec<&[char]> = lines.iter().map(|l| &l[..]).collect();
+        assert_eq!(
+            indent_guide_columns(&lines, 4),
+            vec![
+                vec![],
+                vec![0],
+                vec![0, 4],
+                vec![0, 4],
+                vec![0],
+                vec![0],
+                vec![]
+            ]
+        );
+    }
+
+    #[test]
+    fn flash_fades_out_over_its_duration() {
+        assert_eq!(flash_alpha(0.0, 0.4, 0.6), Some(0.6));
+        assert!((flash_alpha(0.1, 0.4, 0.6).unwrap() - 0.45).abs() < 1e-6);
+        assert!((flash_alpha(0.3, 0.4, 0.6).unwrap() - 0.15).abs() < 1e-6);
+        let alphas: Vec<f32> = (0..40).map(|ms| flash_alpha(ms as f64 / 100.0, 0.4, 0.6).unwrap()).collect();
+        assert!(alphas.windows(2).all(|pair| pair[1] < pair[0]));
+        assert_eq!(flash_alpha(0.4, 0.4, 0.6), None);
+        assert_eq!(flash_alpha(1.0, 0.4, 0.6), None);
+        assert_eq!(flash_alpha(0.0, 0.0, 0.6), None);
+    }
+
+    #[test]
+    fn following_the_tail_keeps_last_line_in_view() {
+        // a view of 10 rows, over output that grows by 4 lines at a time
+        let mut top = 0;
+        let mut lines = 1;
+        for _ in 0..10 {
+            top = top_line_after_append(top, 10, lines, lines + 4, true);
+            lines += 4;
+            assert!(top + 10 >= lines as i32);
+        }
+        assert_eq!(top, 31);
+        // scrolled up, the view stays put, until it's scrolled back down to the bottom
+        assert_eq!(top_line_after_append(20, 10, 41, 45, true), 20);
+        assert_eq!(top_line_after_append(31, 10, 41, 45, true), 35);
+        // short output doesn't scroll, and a view that doesn't follow never does
+        assert_eq!(top_line_after_append(0, 10, 3, 5, true), 0);
+        assert_eq!(top_line_after_append(31, 10, 41, 45, false), 31);
+    }
+
+    #[test]
+    fn points_map_to_buffer_indices() {
+        let mut buffer = ContiguousBuffer::new(0, 1024);
+        buffer.insert_slice(&"ab\ncd\nlast".chars().collect::<Vec<char>>());
+        // a monospace font 10 pixels wide, with rows 20 pixels high
+        let index_at = |top_line, x, y| *index_at_point(&buffer, top_line, x, y, 20, |_| 10);
+        assert_eq!(index_at(0, 5, 5), 0);
+        assert_eq!(index_at(0, 15, 19), 1);
+        assert_eq!(index_at(0, 15, 25), 4);
+        assert_eq!(index_at(1, 5, 5), 3);
+        // past the end of a line lands on its new line, and on the last line, at the end of the buffer
+        assert_eq!(index_at(0, 100, 5), 2);
+        assert_eq!(index_at(0, 100, 45), 10);
+        // below the last line lands on the last line
+        assert_eq!(index_at(0, 5, 200), 6);
+    }
+
+    #[test]
+    fn buffer_indices_map_to_glyph_rects() {
+        let mut buffer = ContiguousBuffer::new(0, 1024);
+        buffer.insert_slice(&"fn main() {\n\tlet a = 1;\n}".chars().collect::<Vec<char>>());
+        // a monospace font 10 pixels wide, where a tab is drawn 4 columns wide, with rows 20 pixels high
+        let width = |text: &[char]| -> i32 { text.iter().map(|&c| if c == '\t' { 40 } else { 10 }).sum() };
+        let rect = |in_view, idx| glyph_rect(&buffer, in_view, Index(idx), 20, 10, width).map(|rect| (rect.min, rect.max));
+
+        assert_eq!(rect(0..2, 3), Some((Vec2i::new(30, -20), Vec2i::new(40, 0))));
+        assert_eq!(rect(1..3, 12), Some((Vec2i::new(0, -20), Vec2i::new(40, 0))), "the tab, at the top of the scrolled view");
+        assert_eq!(rect(1..3, 13), Some((Vec2i::new(40, -20), Vec2i::new(50, 0))));
+        assert_eq!(rect(0..2, 24), None, "the last line is below the view");
+        assert_eq!(rect(2..4, 11), None, "scrolled away");
+        assert_eq!(rect(0..3, 25), Some((Vec2i::new(10, -60), Vec2i::new(20, -40))), "the end of the buffer");
+        assert_eq!(rect(0..3, 26), None);
+    }
+
+    #[test]
+    fn moving_the_cursor_does_not_rebuild_the_text() {
+        let mut buffer = ContiguousBuffer::new(0, 1024);
+        buffer.insert_slice(&"fn main() {\n    let a = 1;\n}\n".chars().collect::<Vec<char>>());
+        let in_view = 0..buffer.len();
+        let anchor = Vec2i::new(0, 600);
+        let built_from = TextSnapshot::of(&buffer, &in_view, 0, 30, anchor, false);
+
+        buffer.cursor_goto(Index(4));
+        assert_eq!(TextSnapshot::of(&buffer, &in_view, 0, 30, anchor, false), built_from);
+
+        // scrolling, moving the view, or showing whitespace, all do
+        assert_ne!(TextSnapshot::of(&buffer, &in_view, 1, 30, anchor, false), built_from);
+        assert_ne!(TextSnapshot::of(&buffer, &in_view, 0, 30, Vec2i::new(10, 600), false), built_from);
+        assert_ne!(TextSnapshot::of(&buffer, &in_view, 0, 30, anchor, true), built_from);
+        // as does editing, even when it leaves the characters in view as they were
+        buffer.insert_slice(&['x']);
+        let edited = TextSnapshot::of(&buffer, &in_view, 0, 30, anchor, false);
+        assert_ne!(edited, built_from);
+        buffer.cursor_goto(Index(buffer.len()));
+        buffer.delete(Movement::Backward(TextKind::Char, 1));
+        assert_ne!(TextSnapshot::of(&buffer, &in_view, 0, 30, anchor, false), edited);
+        // and showing another buffer
+        let other = ContiguousBuffer::new(1, 1024);
+        let empty = ContiguousBuffer::new(0, 1024);
+        assert_ne!(TextSnapshot::of(&other, &(0..0), 0, 30, anchor, false), TextSnapshot::of(&empty, &(0..0), 0, 30, anchor, false));
+    }
+
+    #[test]
+    fn background_override_survives_redecoration() {
+        let active = RGBAColor::new(0.071, 0.202, 0.3242123, 1.0);
+        let inactive = RGBAColor::new(0.021, 0.62, 0.742123, 1.0);
+        let scratch = RGBAColor::new(64.0 / 255.0, 32.0 / 255.0, 32.0 / 255.0, 1.0);
+        // cycling the focus decorates the view as inactive, and then, back around, as active again
+        for &decoration in [inactive, active, inactive].iter() {
+            assert_eq!(background_color(Some(scratch), decoration), scratch);
+            assert_eq!(background_color(None, decoration), decoration);
+        }
+    }
+
+    #[test]
+    fn dragging_selects_from_the_click() {
+        let mut buffer = ContiguousBuffer::new(0, 1024);
+        buffer.insert_slice(&"hello world\nsecond line".chars().collect::<Vec<char>>());
+        // the click puts the cursor where it landed, and clears any selection
+        let clicked = Index(3);
+        buffer.cursor_goto(clicked);
+        buffer.meta_cursor = None;
+        // the first drag event finds the cursor already moved on
+        buffer.cursor_goto(Index(5));
+        drag_select(&mut buffer, clicked, Index(15));
+        assert_eq!(buffer.get_selection(), Some((Index(3), Index(15))));
+        // dragging back past the click point selects the other way
+        drag_select(&mut buffer, clicked, Index(1));
+        assert_eq!(buffer.get_selection(), Some((Index(1), Index(3))));
+        assert_eq!(buffer.cursor_abs(), Index(1));
+    }
+
+    #[test]
+    fn inactive_views_highlight_the_current_line_when_asked_to() {
+        let active = current_line_highlight(true, false).unwrap();
+        let inactive = current_line_highlight(false, true).unwrap();
+        assert!(inactive.a > 0.0 && inactive.a < active.a);
+        assert_eq!(current_line_highlight(true, true), Some(active));
+        assert_eq!(current_line_highlight(false, false), None);
+    }
+
+    #[test]
+    fn indices_scroll_in_and_out_of_view() {
+        let mut buffer = ContiguousBuffer::new(0, 1024);
+        let text: String = (0..10).map(|line| format!("line {}\n", line)).collect();
+        buffer.insert_slice(&text.chars().collect::<Vec<char>>());
+        // each line is 7 long, the 10th ends with the last character, and an 11th, empty line follows
+        let visible = |top_line: i32, idx: usize| index_in_view(&buffer_range_in_view(buffer.meta_data(), top_line, 3), Index(idx), buffer.len());
+        assert!(visible(0, 0) && visible(0, 20), "the new line ending the last line in view is in view");
+        assert!(!visible(0, 21));
+        assert!(!visible(4, 27) && visible(4, 28) && visible(4, 48) && !visible(4, 49), "scrolled down 4 lines");
+        assert!(!visible(4, buffer.len()));
+        assert!(visible(8, 56) && visible(8, buffer.len()), "the end of the buffer is in view with the last line");
+        assert_eq!(visible_lines(4, 3, buffer.meta_data().line_count()), 4..7);
+        assert_eq!(visible_lines(9, 3, 11), 9..11);
+        assert_eq!(visible_lines(-2, 3, 11), 0..3);
+        assert!(visible_lines(12, 3, 11).is_empty());
+    }
+
+    #[test]
+    fn empty_views_draw_the_background_unless_turned_off() {
+        let logo = Some(Texture { id: 7, dimensions: Vec2i::new(256, 256) });
+        let mut options = ViewOptions::default();
+        assert_eq!(empty_background(&options, true, logo).map(|t| t.id), Some(7));
+        assert!(empty_background(&options, false, logo).is_none(), "only empty views show it");
+        assert!(empty_background(&options, true, None).is_none(), "a view without one");
+        options.show_empty_background = false;
+        assert!(empty_background(&options, true, logo).is_none());
+    }
+
+    fn view_of(text: &str) -> View {
+        let font = Rc::new(Font::without_texture(SOURCE_CODE_PRO_REGULAR, 14, &(' '..='~').collect()));
+        let mut buffer = Box::new(ContiguousBuffer::new(0, 4096));
+        buffer.insert_slice(&text.chars().collect::<Vec<char>>());
+        View::with_renderers(
+            "view",
+            ViewId(0),
+            TextRenderer::without_buffers(TextShader::unlinked()),
+            TextRenderer::without_buffers(TextShader::unlinked()),
+            RectRenderer::without_buffers(RectShader::unlinked()),
+            PolygonRenderer::without_buffers(RectShader::unlinked()),
+            800,
+            600,
+            RGBAColor::black(),
+            buffer,
+            font.clone(),
+            font,
+            None,
+        )
+    }
+
+    #[test]
+    fn large_deletes_wait_for_confirmation() {
+        let mut view = view_of(&"let answer = 42;\n".repeat(50));
+        assert!(view.delete_guard.configure("100"));
+        let delete = Movement::Forward(TextKind::Char, 1);
+
+        view.buffer.set_absolute_meta_cursor(Index(4));
+        view.buffer.cursor_goto(Index(9));
+        view.delete(delete);
+        assert_eq!(view.held_delete_size(), None);
+        assert_eq!(view.buffer.len(), 850 - 6, "small deletes go through");
+
+        view.buffer.set_absolute_meta_cursor(Index(0));
+        view.buffer.cursor_goto(Index(843));
+        view.delete(delete);
+        assert_eq!(view.held_delete_size(), Some((844, 50)));
+        assert_eq!(view.buffer.len(), 844, "until it's confirmed");
+        view.settle_held_delete(false);
+        assert_eq!((view.held_delete_size(), view.buffer.len()), (None, 844));
+
+        view.delete(delete);
+        view.settle_held_delete(true);
+        assert_eq!((view.held_delete_size(), view.buffer.len()), (None, 0));
+    }
+}
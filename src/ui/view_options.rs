@@ -0,0 +1,96 @@
+use super::scrollbar::ScrollBar;
+
+/// How a view displays its text. New views get the application's defaults, which can then be changed for each view
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ViewOptions {
+    /// Draw faint markers for spaces, tabs and newlines, and highlight trailing whitespace
+    pub render_whitespace: bool,
+    /// Draw faint vertical lines at each level of indentation
+    pub show_indent_guides: bool,
+    /// Mark lines added or modified since the last save, with a colored strip to the left of the text
+    pub show_change_markers: bool,
+    /// Highlight the line the cursor is on, in views that aren't active as well, dimmer than in the active view
+    pub highlight_current_line: bool,
+    /// Highlight the other occurrences in view of the word the cursor rests on, unless there's a search going on
+    pub highlight_word_matches: bool,
+    /// The width of the scroll bar, in pixels
+    pub scroll_bar_width: i32,
+    /// Hide the scroll bar, giving its space to the text, while the whole buffer fits in view
+    pub auto_hide_scroll_bar: bool,
+    /// Columns (for instance 80 or 100) at which a faint vertical line is drawn, to help keep lines within a length
+    pub rulers: Vec<usize>,
+    /// Draw the logo in the middle of the view, while its buffer is empty
+    pub show_empty_background: bool,
+}
+
+impl Default for ViewOptions {
+    fn default() -> Self {
+        ViewOptions {
+            render_whitespace: false,
+            show_indent_guides: false,
+            show_change_markers: true,
+            highlight_current_line: true,
+            highlight_word_matches: true,
+            scroll_bar_width: ScrollBar::WIDTH,
+            auto_hide_scroll_bar: false,
+            rulers: vec![],
+            show_empty_background: true,
+        }
+    }
+}
+
+/// The options of ViewOptions that are on or off, and can be toggled from the command list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViewOption {
+    Whitespace,
+    IndentGuides,
+    ChangeMarkers,
+    CurrentLine,
+    WordMatches,
+    AutoHideScrollBar,
+    EmptyBackground,
+}
+
+impl ViewOptions {
+    /// Turns option on if it's off, and off if it's on. Returns whether it's on now
+    pub fn toggle(&mut self, option: ViewOption) -> bool {
+        let flag = match option {
+            ViewOption::Whitespace => &mut self.render_whitespace,
+            ViewOption::IndentGuides => &mut self.show_indent_guides,
+            ViewOption::ChangeMarkers => &mut self.show_change_markers,
+            ViewOption::CurrentLine => &mut self.highlight_current_line,
+            ViewOption::WordMatches => &mut self.highlight_word_matches,
+            ViewOption::AutoHideScrollBar => &mut self.auto_hide_scroll_bar,
+            ViewOption::EmptyBackground => &mut self.show_empty_background,
+        };
+        *flag = !*flag;
+        *flag
+    }
+}
+
+#[cfg(test)]
+mod view_options_tests {
+    use super::{ViewOption, ViewOptions};
+    use crate::datastructure::generic::Vec2i;
+    use crate::textbuffer::contiguous::contiguous::ContiguousBuffer;
+    use crate::textbuffer::CharBuffer;
+    use crate::ui::view::TextSnapshot;
+
+    #[test]
+    fn toggling_flips_only_that_option_and_rebuilds_the_text() {
+        let mut buffer = ContiguousBuffer::new(0, 1024);
+        buffer.insert_slice(&"let a = 1;  \n".chars().collect::<Vec<char>>());
+        let in_view = 0..buffer.len();
+        let snapshot = |options: &ViewOptions| TextSnapshot::of(&buffer, &in_view, 0, 30, Vec2i::new(0, 600), options.render_whitespace);
+
+        let mut options = ViewOptions::default();
+        let built_from = snapshot(&options);
+        assert!(options.toggle(ViewOption::Whitespace));
+        assert_eq!(options, ViewOptions { render_whitespace: true, ..ViewOptions::default() });
+        assert_ne!(snapshot(&options), built_from);
+
+        assert!(!options.toggle(ViewOption::ChangeMarkers));
+        assert!(!options.toggle(ViewOption::Whitespace));
+        assert_eq!(snapshot(&options), built_from);
+    }
+}
@@ -2,6 +2,8 @@ use std::ffi::CString;
 
 #[macro_use]
 pub mod macros;
+#[cfg(test)]
+pub mod temp_dir;
 
 /// Copies slice to memory pointed at by dst.
 #[inline(always)]
@@ -52,6 +54,17 @@ impl CountDigits for usize {
     }
 }
 
+/// Smart case; a needle without upper case characters is matched case insensitively, but as soon as it contains
+/// an upper case character, it is matched case sensitively. With smart case turned off, matching is always case sensitive
+pub fn smart_case_sensitive(needle: &str, smart_case: bool) -> bool {
+    !smart_case || needle.chars().any(char::is_uppercase)
+}
+
+/// Compares a and b, ignoring case unless case_sensitive is set
+pub fn chars_match(a: char, b: char, case_sensitive: bool) -> bool {
+    a == b || (!case_sensitive && a.to_lowercase().eq(b.to_lowercase()))
+}
+
 /// Converts a vec of u32 to Vec<char>, unsafely. If you fuck up the code points, it's on you.
 pub fn convert_vec_of_u32_utf(data: &[u32]) -> Vec<char> {
     unsafe { data.iter().map(|&c| std::char::from_u32_unchecked(c)).collect() }
@@ -0,0 +1,36 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static CREATED: AtomicUsize = AtomicUsize::new(0);
+
+/// A directory of its own, for a test to write files in. It's removed, with everything in it, when dropped; also when the test
+/// fails on the way
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Makes a new, empty directory in the temporary directory of the system. name tells apart what's left behind, if removing it
+    /// fails
+    pub fn new(name: &str) -> TempDir {
+        let count = CREATED.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("cxgledit_{}_{}_{}", name, std::process::id(), count));
+        std::fs::create_dir_all(&path).unwrap();
+        TempDir { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The path of file in the directory
+    pub fn join(&self, file: impl AsRef<Path>) -> PathBuf {
+        self.path.join(file)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}